@@ -2,3 +2,12 @@
 /// 3 billion entries ≈ 300GB (100 bytes per entry) - Theoretical limit
 /// In practice, we'll cap it lower for safety, say 1M for now during dev/test
 pub const MAX_ENTRIES: u64 = 1_000_000;
+
+/// Maximum number of sequences that can be requested in one range query
+pub const MAX_RANGE_QUERY: u64 = 100;
+
+/// Maximum number of entries moved per rekey_user call
+pub const MAX_REKEY_BATCH: usize = 1_000;
+
+/// Metadata stored in place of the original after a user's data is erased
+pub const REDACTED_METADATA: &str = "[redacted]";
@@ -38,6 +38,50 @@ fn receive_archive_batch(user: Principal, transactions: Vec<TransactionToArchive
     receive_archive_batch_internal(user, transactions)
 }
 
+/// Store a batch of the parent shard's audit log
+#[update]
+fn receive_audit_batch(entries: Vec<AuditEntryToArchive>) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    let parent_id = PARENT_SHARD_ID.with(|id| *id.borrow().get());
+    
+    // Authorization check
+    if caller != parent_id {
+        return Err("Unauthorized: Only parent shard can archive data".to_string());
+    }
+    
+    receive_audit_batch_internal(entries)
+}
+
+/// Move a user's archived entries to a new principal (account recovery)
+/// Processes one batch per call; returns the number of entries moved
+#[update]
+fn rekey_user(old_user: Principal, new_user: Principal) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    let parent_id = PARENT_SHARD_ID.with(|id| *id.borrow().get());
+    
+    // Authorization check
+    if caller != parent_id {
+        return Err("Unauthorized: Only parent shard can rekey data".to_string());
+    }
+    
+    Ok(rekey_user_internal(old_user, new_user))
+}
+
+/// Redact metadata of a user's archived entries (right to erasure)
+/// Processes one batch per call; returns the number of entries redacted
+#[update]
+fn redact_user(user: Principal) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    let parent_id = PARENT_SHARD_ID.with(|id| *id.borrow().get());
+    
+    // Authorization check
+    if caller != parent_id {
+        return Err("Unauthorized: Only parent shard can redact data".to_string());
+    }
+    
+    Ok(redact_user_internal(user))
+}
+
 #[update]
 fn set_next_archive(next_id: Principal) -> Result<(), String> {
     let caller = ic_cdk::caller();
@@ -64,6 +108,18 @@ fn get_archived_transactions(user: Principal, start_sequence: Option<u64>, limit
     get_user_history_archived_internal(user, start_sequence, limit)
 }
 
+/// Get a user's archived transactions by sequence range (end exclusive, max 100)
+#[query]
+fn get_archived_range(user: Principal, start_sequence: u64, end_sequence: u64) -> ArchivedRange {
+    get_archived_range_internal(user, start_sequence, end_sequence)
+}
+
+/// Get archived audit entries by id range (end exclusive, max 100)
+#[query]
+fn get_audit_range(start_id: u64, end_id: u64) -> ArchivedAuditRange {
+    get_audit_range_internal(start_id, end_id)
+}
+
 #[query]
 fn get_stats() -> ArchiveStats {
     get_archive_stats_internal()
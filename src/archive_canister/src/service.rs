@@ -49,6 +49,85 @@ pub fn receive_archive_batch_internal(user: Principal, transactions: Vec<Transac
     Ok(count)
 }
 
+/// Store a batch of the parent shard's audit entries (ids already held are skipped)
+pub fn receive_audit_batch_internal(entries: Vec<AuditEntryToArchive>) -> Result<u64, String> {
+    let now = ic_cdk::api::time();
+    let mut count = 0;
+
+    AUDIT_STORAGE.with(|storage| {
+        let mut map = storage.borrow_mut();
+
+        for entry in entries {
+            if map.contains_key(&entry.id) {
+                continue;
+            }
+
+            map.insert(entry.id, ArchivedAuditEntry {
+                id: entry.id,
+                timestamp: entry.timestamp,
+                caller: entry.caller,
+                method: entry.method,
+                target: entry.target,
+                before: entry.before,
+                after: entry.after,
+                archived_at: now,
+            });
+            count += 1;
+        }
+    });
+
+    TOTAL_ENTRY_COUNT.with(|c| {
+        let mut cell = c.borrow_mut();
+        let new_count = *cell.get() + count;
+        cell.set(new_count).expect("Failed to update entry count");
+    });
+
+    Ok(count)
+}
+
+/// Move up to MAX_REKEY_BATCH of `old_user`'s entries to `new_user`
+///
+/// Returns the number moved; callers repeat until it returns 0.
+pub fn rekey_user_internal(old_user: Principal, new_user: Principal) -> u64 {
+    ARCHIVE_STORAGE.with(|s| {
+        let mut map = s.borrow_mut();
+        let start_key = ArchiveKey { user: old_user, sequence: 0 };
+        let end_key = ArchiveKey { user: old_user, sequence: u64::MAX };
+
+        let batch: Vec<(ArchiveKey, ArchivedTransaction)> = map.range(start_key..=end_key)
+            .take(MAX_REKEY_BATCH)
+            .collect();
+
+        for (key, record) in batch.iter() {
+            map.remove(key);
+            map.insert(ArchiveKey { user: new_user, sequence: key.sequence }, record.clone());
+        }
+        batch.len() as u64
+    })
+}
+
+/// Replace the metadata of a user's archived entries (right to erasure)
+/// Amounts and timestamps are kept for supply accounting.
+/// Processes one batch per call; returns the number of entries redacted
+pub fn redact_user_internal(user: Principal) -> u64 {
+    ARCHIVE_STORAGE.with(|s| {
+        let mut map = s.borrow_mut();
+        let start_key = ArchiveKey { user, sequence: 0 };
+        let end_key = ArchiveKey { user, sequence: u64::MAX };
+
+        let batch: Vec<(ArchiveKey, ArchivedTransaction)> = map.range(start_key..=end_key)
+            .filter(|(_, record)| record.metadata != REDACTED_METADATA)
+            .take(MAX_REKEY_BATCH)
+            .collect();
+
+        for (key, mut record) in batch.iter().cloned() {
+            record.metadata = REDACTED_METADATA.to_string();
+            map.insert(key, record);
+        }
+        batch.len() as u64
+    })
+}
+
 // ============================================================================
 // READ OPERATIONS
 // ============================================================================
@@ -71,6 +150,40 @@ pub fn get_user_history_archived_internal(user: Principal, start_sequence: Optio
     })
 }
 
+/// Look up a user's transactions with sequence in [start_sequence, end_sequence)
+///
+/// Uses point lookups rather than a range scan so results do not depend on
+/// the byte ordering of encoded keys. Sequences not stored here are omitted.
+pub fn get_archived_range_internal(user: Principal, start_sequence: u64, end_sequence: u64) -> ArchivedRange {
+    let end = end_sequence.min(start_sequence.saturating_add(MAX_RANGE_QUERY));
+
+    let transactions = ARCHIVE_STORAGE.with(|s| {
+        let map = s.borrow();
+        (start_sequence..end)
+            .filter_map(|sequence| map.get(&ArchiveKey { user, sequence }))
+            .collect()
+    });
+
+    ArchivedRange {
+        transactions,
+        next_archive: NEXT_ARCHIVE.with(|n| *n.borrow().get()),
+    }
+}
+
+/// Look up audit entries with id in [start_id, end_id) (max MAX_RANGE_QUERY)
+pub fn get_audit_range_internal(start_id: u64, end_id: u64) -> ArchivedAuditRange {
+    let end = end_id.min(start_id.saturating_add(MAX_RANGE_QUERY));
+
+    let entries = AUDIT_STORAGE.with(|s| {
+        s.borrow().range(start_id..end).map(|(_, entry)| entry).collect()
+    });
+
+    ArchivedAuditRange {
+        entries,
+        next_archive: NEXT_ARCHIVE.with(|n| *n.borrow().get()),
+    }
+}
+
 pub fn get_archive_stats_internal() -> ArchiveStats {
     let parent_shard = PARENT_SHARD_ID.with(|id| *id.borrow().get());
     let entry_count = TOTAL_ENTRY_COUNT.with(|c| *c.borrow().get());
@@ -45,4 +45,13 @@ thread_local! {
             None
         ).unwrap()
     );
+
+    /// Archived audit log of the parent shard
+    /// Key: audit entry id (assigned sequentially by the shard)
+    /// Value: ArchivedAuditEntry
+    pub static AUDIT_STORAGE: RefCell<StableBTreeMap<u64, ArchivedAuditEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(5)))
+        )
+    );
 }
@@ -80,3 +80,59 @@ pub struct ArchiveStats {
     pub is_full: bool,
     pub next_archive: Option<Principal>,
 }
+
+/// Slice of a user's archived transactions by sequence, with a pointer to
+/// the next archive in the chain for sequences not held here
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArchivedRange {
+    pub transactions: Vec<ArchivedTransaction>,
+    pub next_archive: Option<Principal>,
+}
+
+/// Audit entry received from the parent shard for archiving
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AuditEntryToArchive {
+    pub id: u64,
+    pub timestamp: u64,
+    pub caller: Principal,
+    pub method: String,
+    pub target: Principal,
+    pub before: String,
+    pub after: String,
+}
+
+/// Archived audit entry (includes archive metadata)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArchivedAuditEntry {
+    pub id: u64,
+    pub timestamp: u64,
+    pub caller: Principal,
+    pub method: String,
+    pub target: Principal,
+    pub before: String,
+    pub after: String,
+    pub archived_at: u64,
+}
+
+impl Storable for ArchivedAuditEntry {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode ArchivedAuditEntry")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 2048,
+        is_fixed_size: false,
+    };
+}
+
+/// Slice of the parent shard's archived audit log by id, with a pointer to
+/// the next archive in the chain for ids not held here
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArchivedAuditRange {
+    pub entries: Vec<ArchivedAuditEntry>,
+    pub next_archive: Option<Principal>,
+}
@@ -0,0 +1,33 @@
+// ============================================================================
+// COLLECTION METADATA
+// ============================================================================
+
+pub const COLLECTION_NAME: &str = "GreenHero Learning Credentials";
+pub const COLLECTION_SYMBOL: &str = "GHCRED";
+pub const COLLECTION_DESCRIPTION: &str = "Soulbound achievement badges and course completion certificates earned on GreenHero. Tokens cannot be transferred or approved.";
+
+// ============================================================================
+// ICRC-7 LIMITS
+// ============================================================================
+
+/// Maximum token ids / accounts accepted by batch queries
+pub const MAX_QUERY_BATCH_SIZE: u64 = 100;
+/// Maximum entries accepted by batch updates (all of which are rejected)
+pub const MAX_UPDATE_BATCH_SIZE: u64 = 20;
+/// Page size of icrc7_tokens / icrc7_tokens_of when `take` is omitted
+pub const DEFAULT_TAKE_VALUE: u64 = 100;
+/// Largest accepted `take`
+pub const MAX_TAKE_VALUE: u64 = 500;
+pub const MAX_MEMO_SIZE: u64 = 32;
+
+/// error_code of the GenericError returned for transfers and approvals
+pub const SOULBOUND_ERROR_CODE: u64 = 1;
+pub const SOULBOUND_ERROR_MESSAGE: &str = "Soulbound token: transfers and approvals are disabled";
+
+// ============================================================================
+// MINTING LIMITS
+// ============================================================================
+
+pub const MAX_CREDENTIALS_PER_OWNER: usize = 1_000;
+pub const MAX_CREDENTIAL_ATTRIBUTES: usize = 20;
+pub const MAX_CREDENTIAL_TEXT_LEN: usize = 500;
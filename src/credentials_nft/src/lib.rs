@@ -0,0 +1,300 @@
+mod types;
+mod state;
+mod constants;
+mod service;
+
+use ic_cdk::{init, query, update};
+use candid::{Nat, Principal};
+
+use types::*;
+use state::*;
+use constants::*;
+use service::*;
+
+// ============================================================================
+// INITIALIZATION
+// ============================================================================
+
+#[init]
+fn init(args: InitArgs) {
+    STAKING_HUB_ID.with(|id| {
+        id.borrow_mut().set(args.staking_hub_id).expect("Failed to set staking hub ID");
+    });
+}
+
+// ============================================================================
+// MINTING (Only registered user_profile shards can call)
+// ============================================================================
+
+/// Mint a soulbound credential for a learner.
+/// Idempotent: minting the same (owner, kind, reference) again returns the existing token id.
+#[update]
+async fn mint_credential(args: MintCredentialArgs) -> Result<Nat, String> {
+    let caller = ic_cdk::caller();
+    ensure_registered_shard(caller).await?;
+
+    mint_credential_internal(caller, args).map(Nat::from)
+}
+
+/// Burn all credentials of `owner` that were minted by the calling shard
+/// (right to erasure, account recovery). Returns the number of tokens burned.
+#[update]
+async fn revoke_credentials(owner: Principal) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    ensure_registered_shard(caller).await?;
+
+    Ok(revoke_credentials_internal(caller, owner))
+}
+
+#[query]
+fn get_staking_hub_id() -> Principal {
+    STAKING_HUB_ID.with(|id| *id.borrow().get())
+}
+
+// ============================================================================
+// ICRC-7 COLLECTION QUERIES
+// ============================================================================
+
+#[query]
+fn icrc7_collection_metadata() -> Vec<(String, Value)> {
+    collection_metadata()
+}
+
+#[query]
+fn icrc7_symbol() -> String {
+    COLLECTION_SYMBOL.to_string()
+}
+
+#[query]
+fn icrc7_name() -> String {
+    COLLECTION_NAME.to_string()
+}
+
+#[query]
+fn icrc7_description() -> Option<String> {
+    Some(COLLECTION_DESCRIPTION.to_string())
+}
+
+#[query]
+fn icrc7_logo() -> Option<String> {
+    None
+}
+
+#[query]
+fn icrc7_total_supply() -> Nat {
+    Nat::from(total_supply())
+}
+
+#[query]
+fn icrc7_supply_cap() -> Option<Nat> {
+    None
+}
+
+#[query]
+fn icrc7_max_query_batch_size() -> Option<Nat> {
+    Some(Nat::from(MAX_QUERY_BATCH_SIZE))
+}
+
+#[query]
+fn icrc7_max_update_batch_size() -> Option<Nat> {
+    Some(Nat::from(MAX_UPDATE_BATCH_SIZE))
+}
+
+#[query]
+fn icrc7_default_take_value() -> Option<Nat> {
+    Some(Nat::from(DEFAULT_TAKE_VALUE))
+}
+
+#[query]
+fn icrc7_max_take_value() -> Option<Nat> {
+    Some(Nat::from(MAX_TAKE_VALUE))
+}
+
+#[query]
+fn icrc7_max_memo_size() -> Option<Nat> {
+    Some(Nat::from(MAX_MEMO_SIZE))
+}
+
+#[query]
+fn icrc7_atomic_batch_transfers() -> Option<bool> {
+    Some(true)
+}
+
+#[query]
+fn icrc7_tx_window() -> Option<Nat> {
+    None
+}
+
+#[query]
+fn icrc7_permitted_drift() -> Option<Nat> {
+    None
+}
+
+#[query]
+fn icrc10_supported_standards() -> Vec<StandardRecord> {
+    vec![
+        StandardRecord {
+            name: "ICRC-7".to_string(),
+            url: "https://github.com/dfinity/ICRC/ICRCs/ICRC-7".to_string(),
+        },
+        StandardRecord {
+            name: "ICRC-10".to_string(),
+            url: "https://github.com/dfinity/ICRC/ICRCs/ICRC-10".to_string(),
+        },
+        StandardRecord {
+            name: "ICRC-37".to_string(),
+            url: "https://github.com/dfinity/ICRC/ICRCs/ICRC-37".to_string(),
+        },
+    ]
+}
+
+// ============================================================================
+// ICRC-7 TOKEN QUERIES
+// ============================================================================
+
+fn check_query_batch(len: usize) {
+    if len as u64 > MAX_QUERY_BATCH_SIZE {
+        ic_cdk::trap(&format!("Batch size exceeds icrc7_max_query_batch_size ({})", MAX_QUERY_BATCH_SIZE));
+    }
+}
+
+#[query]
+fn icrc7_token_metadata(token_ids: Vec<Nat>) -> Vec<Option<Vec<(String, Value)>>> {
+    check_query_batch(token_ids.len());
+    token_ids.iter()
+        .map(|id| {
+            nat_to_u64(id)
+                .and_then(|id| TOKENS.with(|m| m.borrow().get(&id)))
+                .map(|token| token_metadata(&token))
+        })
+        .collect()
+}
+
+#[query]
+fn icrc7_owner_of(token_ids: Vec<Nat>) -> Vec<Option<Account>> {
+    check_query_batch(token_ids.len());
+    token_ids.iter().map(owner_of).collect()
+}
+
+#[query]
+fn icrc7_balance_of(accounts: Vec<Account>) -> Vec<Nat> {
+    check_query_batch(accounts.len());
+    accounts.iter().map(|a| Nat::from(balance_of(a))).collect()
+}
+
+#[query]
+fn icrc7_tokens(prev: Option<Nat>, take: Option<Nat>) -> Vec<Nat> {
+    list_tokens(prev, take)
+}
+
+#[query]
+fn icrc7_tokens_of(account: Account, prev: Option<Nat>, take: Option<Nat>) -> Vec<Nat> {
+    list_tokens_of(&account, prev, take)
+}
+
+/// Credentials are soulbound: every transfer is rejected
+#[update]
+fn icrc7_transfer(args: Vec<TransferArg>) -> Vec<Option<TransferResult>> {
+    let (error_code, message) = soulbound_error();
+    if args.len() as u64 > MAX_UPDATE_BATCH_SIZE {
+        return vec![Some(Err(TransferError::GenericBatchError { error_code, message }))];
+    }
+    args.iter()
+        .map(|_| Some(Err(TransferError::GenericError { error_code: error_code.clone(), message: message.clone() })))
+        .collect()
+}
+
+// ============================================================================
+// ICRC-37 (approvals are disabled for soulbound tokens)
+// ============================================================================
+
+#[query]
+fn icrc37_metadata() -> Vec<(String, Value)> {
+    vec![
+        ("icrc37:max_approvals_per_token_or_collection".to_string(), Value::Nat(Nat::from(0u64))),
+        ("icrc37:max_revoke_approvals".to_string(), Value::Nat(Nat::from(0u64))),
+    ]
+}
+
+#[query]
+fn icrc37_max_approvals_per_token_or_collection() -> Option<Nat> {
+    Some(Nat::from(0u64))
+}
+
+#[query]
+fn icrc37_max_revoke_approvals() -> Option<Nat> {
+    Some(Nat::from(0u64))
+}
+
+#[query]
+fn icrc37_is_approved(args: Vec<IsApprovedArg>) -> Vec<bool> {
+    check_query_batch(args.len());
+    vec![false; args.len()]
+}
+
+#[query]
+fn icrc37_get_token_approvals(_token_id: Nat, _prev: Option<TokenApproval>, _take: Option<Nat>) -> Vec<TokenApproval> {
+    Vec::new()
+}
+
+#[query]
+fn icrc37_get_collection_approvals(_owner: Account, _prev: Option<CollectionApproval>, _take: Option<Nat>) -> Vec<CollectionApproval> {
+    Vec::new()
+}
+
+#[update]
+fn icrc37_approve_tokens(args: Vec<ApproveTokenArg>) -> Vec<Option<ApproveTokenResult>> {
+    let (error_code, message) = soulbound_error();
+    if args.len() as u64 > MAX_UPDATE_BATCH_SIZE {
+        return vec![Some(Err(ApproveTokenError::GenericBatchError { error_code, message }))];
+    }
+    args.iter()
+        .map(|_| Some(Err(ApproveTokenError::GenericError { error_code: error_code.clone(), message: message.clone() })))
+        .collect()
+}
+
+#[update]
+fn icrc37_approve_collection(args: Vec<ApproveCollectionArg>) -> Vec<Option<ApproveCollectionResult>> {
+    let (error_code, message) = soulbound_error();
+    if args.len() as u64 > MAX_UPDATE_BATCH_SIZE {
+        return vec![Some(Err(ApproveCollectionError::GenericBatchError { error_code, message }))];
+    }
+    args.iter()
+        .map(|_| Some(Err(ApproveCollectionError::GenericError { error_code: error_code.clone(), message: message.clone() })))
+        .collect()
+}
+
+#[update]
+fn icrc37_revoke_token_approvals(args: Vec<RevokeTokenApprovalArg>) -> Vec<Option<RevokeTokenApprovalResult>> {
+    if args.len() as u64 > MAX_UPDATE_BATCH_SIZE {
+        let (error_code, message) = soulbound_error();
+        return vec![Some(Err(RevokeTokenApprovalError::GenericBatchError { error_code, message }))];
+    }
+    args.iter()
+        .map(|_| Some(Err(RevokeTokenApprovalError::ApprovalDoesNotExist)))
+        .collect()
+}
+
+#[update]
+fn icrc37_revoke_collection_approvals(args: Vec<RevokeCollectionApprovalArg>) -> Vec<Option<RevokeCollectionApprovalResult>> {
+    if args.len() as u64 > MAX_UPDATE_BATCH_SIZE {
+        let (error_code, message) = soulbound_error();
+        return vec![Some(Err(RevokeCollectionApprovalError::GenericBatchError { error_code, message }))];
+    }
+    args.iter()
+        .map(|_| Some(Err(RevokeCollectionApprovalError::ApprovalDoesNotExist)))
+        .collect()
+}
+
+#[update]
+fn icrc37_transfer_from(args: Vec<TransferFromArg>) -> Vec<Option<TransferFromResult>> {
+    let (error_code, message) = soulbound_error();
+    if args.len() as u64 > MAX_UPDATE_BATCH_SIZE {
+        return vec![Some(Err(TransferFromError::GenericBatchError { error_code, message }))];
+    }
+    args.iter()
+        .map(|_| Some(Err(TransferFromError::GenericError { error_code: error_code.clone(), message: message.clone() })))
+        .collect()
+}
+
+ic_cdk::export_candid!();
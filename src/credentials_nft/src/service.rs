@@ -0,0 +1,265 @@
+use candid::{Nat, Principal};
+use crate::types::*;
+use crate::state::*;
+use crate::constants::*;
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+/// Only the default (absent or all-zero) subaccount can hold credentials
+pub fn is_default_account(account: &Account) -> bool {
+    account.subaccount.as_ref().is_none_or(|s| s.iter().all(|b| *b == 0))
+}
+
+pub fn nat_to_u64(n: &Nat) -> Option<u64> {
+    u64::try_from(&n.0).ok()
+}
+
+fn credential_key(kind: CredentialKind, owner: &Principal, reference: &str) -> String {
+    let kind = match kind {
+        CredentialKind::Badge => "badge",
+        CredentialKind::Certificate => "certificate",
+    };
+    format!("{}:{}:{}", kind, owner.to_text(), reference)
+}
+
+pub fn soulbound_error() -> (Nat, String) {
+    (Nat::from(SOULBOUND_ERROR_CODE), SOULBOUND_ERROR_MESSAGE.to_string())
+}
+
+/// Clamp an ICRC-7 `take` argument to the collection limits
+pub fn effective_take(take: Option<Nat>) -> usize {
+    take.and_then(|t| nat_to_u64(&t))
+        .unwrap_or(DEFAULT_TAKE_VALUE)
+        .min(MAX_TAKE_VALUE) as usize
+}
+
+// ============================================================================
+// MINTER AUTHORIZATION
+// ============================================================================
+
+/// Check that the caller is a user_profile shard registered with the staking hub.
+/// Positive answers are cached; shards are never unregistered by the hub.
+pub async fn ensure_registered_shard(caller: Principal) -> Result<(), String> {
+    if AUTHORIZED_MINTERS.with(|m| m.borrow().contains_key(&caller)) {
+        return Ok(());
+    }
+
+    let hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    let (registered,): (bool,) = ic_cdk::call(hub_id, "is_registered_shard", (caller,))
+        .await
+        .map_err(|(code, msg)| format!("Hub call failed: {:?} {}", code, msg))?;
+
+    if !registered {
+        return Err("Unauthorized: Only registered shards can mint credentials".to_string());
+    }
+
+    AUTHORIZED_MINTERS.with(|m| m.borrow_mut().insert(caller, true));
+    Ok(())
+}
+
+// ============================================================================
+// MINT / REVOKE
+// ============================================================================
+
+fn validate_mint_args(args: &MintCredentialArgs) -> Result<(), String> {
+    if args.reference.is_empty() {
+        return Err("Credential reference is required".to_string());
+    }
+    if args.name.is_empty() {
+        return Err("Credential name is required".to_string());
+    }
+    let too_long = |s: &str| s.len() > MAX_CREDENTIAL_TEXT_LEN;
+    if too_long(&args.reference) || too_long(&args.name) || too_long(&args.description)
+        || args.content_node_id.as_deref().is_some_and(too_long)
+    {
+        return Err(format!("Credential text fields are limited to {} bytes", MAX_CREDENTIAL_TEXT_LEN));
+    }
+    if args.attributes.len() > MAX_CREDENTIAL_ATTRIBUTES {
+        return Err(format!("At most {} attributes per credential", MAX_CREDENTIAL_ATTRIBUTES));
+    }
+    if args.attributes.iter().any(|(k, _)| k.is_empty() || too_long(k)) {
+        return Err("Invalid attribute key".to_string());
+    }
+    Ok(())
+}
+
+/// Mint a soulbound credential. Idempotent per (owner, kind, reference):
+/// re-minting returns the existing token id.
+pub fn mint_credential_internal(issuer: Principal, args: MintCredentialArgs) -> Result<u64, String> {
+    validate_mint_args(&args)?;
+    if args.owner == Principal::anonymous() {
+        return Err("Cannot mint to the anonymous principal".to_string());
+    }
+
+    let key = credential_key(args.kind, &args.owner, &args.reference);
+    if let Some(existing) = MINTED_CREDENTIALS.with(|m| m.borrow().get(&key)) {
+        return Ok(existing);
+    }
+
+    let mut owned = OWNER_TOKENS.with(|m| m.borrow().get(&args.owner)).unwrap_or_default();
+    if owned.0.len() >= MAX_CREDENTIALS_PER_OWNER {
+        return Err("Credential limit reached for this owner".to_string());
+    }
+
+    let token_id = TOKEN_COUNT.with(|c| {
+        let mut cell = c.borrow_mut();
+        let next = *cell.get() + 1;
+        cell.set(next).expect("Failed to increment token count");
+        next
+    });
+
+    let token = CredentialToken {
+        owner: args.owner,
+        kind: args.kind,
+        reference: args.reference,
+        name: args.name,
+        description: args.description,
+        content_node_id: args.content_node_id,
+        attributes: args.attributes,
+        issuer,
+        minted_at: ic_cdk::api::time(),
+    };
+
+    TOKENS.with(|m| m.borrow_mut().insert(token_id, token));
+    owned.0.push(token_id);
+    OWNER_TOKENS.with(|m| m.borrow_mut().insert(args.owner, owned));
+    MINTED_CREDENTIALS.with(|m| m.borrow_mut().insert(key, token_id));
+
+    Ok(token_id)
+}
+
+/// Burn every token of `owner` minted by `issuer` (erasure / account recovery).
+/// Returns the number of tokens burned.
+pub fn revoke_credentials_internal(issuer: Principal, owner: Principal) -> u64 {
+    let owned = match OWNER_TOKENS.with(|m| m.borrow().get(&owner)) {
+        Some(list) => list,
+        None => return 0,
+    };
+
+    let mut kept = Vec::new();
+    let mut burned = 0u64;
+
+    for token_id in owned.0 {
+        let token = match TOKENS.with(|m| m.borrow().get(&token_id)) {
+            Some(t) => t,
+            None => continue,
+        };
+        if token.issuer != issuer {
+            kept.push(token_id);
+            continue;
+        }
+        TOKENS.with(|m| m.borrow_mut().remove(&token_id));
+        MINTED_CREDENTIALS.with(|m| {
+            m.borrow_mut().remove(&credential_key(token.kind, &owner, &token.reference))
+        });
+        burned += 1;
+    }
+
+    OWNER_TOKENS.with(|m| {
+        let mut map = m.borrow_mut();
+        if kept.is_empty() {
+            map.remove(&owner);
+        } else {
+            map.insert(owner, TokenIdList(kept));
+        }
+    });
+
+    burned
+}
+
+// ============================================================================
+// METADATA
+// ============================================================================
+
+pub fn collection_metadata() -> Vec<(String, Value)> {
+    vec![
+        ("icrc7:symbol".to_string(), Value::Text(COLLECTION_SYMBOL.to_string())),
+        ("icrc7:name".to_string(), Value::Text(COLLECTION_NAME.to_string())),
+        ("icrc7:description".to_string(), Value::Text(COLLECTION_DESCRIPTION.to_string())),
+        ("icrc7:total_supply".to_string(), Value::Nat(Nat::from(total_supply()))),
+        ("icrc7:max_query_batch_size".to_string(), Value::Nat(Nat::from(MAX_QUERY_BATCH_SIZE))),
+        ("icrc7:max_update_batch_size".to_string(), Value::Nat(Nat::from(MAX_UPDATE_BATCH_SIZE))),
+        ("icrc7:default_take_value".to_string(), Value::Nat(Nat::from(DEFAULT_TAKE_VALUE))),
+        ("icrc7:max_take_value".to_string(), Value::Nat(Nat::from(MAX_TAKE_VALUE))),
+        ("icrc7:max_memo_size".to_string(), Value::Nat(Nat::from(MAX_MEMO_SIZE))),
+        ("icrc7:atomic_batch_transfers".to_string(), Value::Text("true".to_string())),
+    ]
+}
+
+/// ICRC-7 token metadata; GreenHero specific keys are namespaced with `gh:`
+pub fn token_metadata(token: &CredentialToken) -> Vec<(String, Value)> {
+    let kind = match token.kind {
+        CredentialKind::Badge => "badge",
+        CredentialKind::Certificate => "certificate",
+    };
+
+    let mut metadata = vec![
+        ("icrc7:name".to_string(), Value::Text(token.name.clone())),
+        ("icrc7:description".to_string(), Value::Text(token.description.clone())),
+        ("gh:kind".to_string(), Value::Text(kind.to_string())),
+        ("gh:reference".to_string(), Value::Text(token.reference.clone())),
+        ("gh:issuer".to_string(), Value::Blob(token.issuer.as_slice().to_vec())),
+        ("gh:minted_at".to_string(), Value::Nat(Nat::from(token.minted_at))),
+        ("gh:soulbound".to_string(), Value::Text("true".to_string())),
+    ];
+    if let Some(node_id) = &token.content_node_id {
+        metadata.push(("gh:content_node_id".to_string(), Value::Text(node_id.clone())));
+    }
+    for (key, value) in &token.attributes {
+        metadata.push((format!("gh:{}", key), value.clone()));
+    }
+    metadata
+}
+
+// ============================================================================
+// QUERIES
+// ============================================================================
+
+pub fn total_supply() -> u64 {
+    TOKENS.with(|m| m.borrow().len())
+}
+
+pub fn owner_of(token_id: &Nat) -> Option<Account> {
+    let id = nat_to_u64(token_id)?;
+    TOKENS.with(|m| m.borrow().get(&id)).map(|t| Account { owner: t.owner, subaccount: None })
+}
+
+pub fn balance_of(account: &Account) -> u64 {
+    if !is_default_account(account) {
+        return 0;
+    }
+    OWNER_TOKENS.with(|m| m.borrow().get(&account.owner))
+        .map(|list| list.0.len() as u64)
+        .unwrap_or(0)
+}
+
+/// Token ids in ascending order, strictly after `prev`
+pub fn list_tokens(prev: Option<Nat>, take: Option<Nat>) -> Vec<Nat> {
+    let take = effective_take(take);
+    let start = match prev {
+        Some(p) => match nat_to_u64(&p).and_then(|p| p.checked_add(1)) {
+            Some(s) => s,
+            None => return Vec::new(),
+        },
+        None => 0,
+    };
+    TOKENS.with(|m| {
+        m.borrow().range(start..).take(take).map(|(id, _)| Nat::from(id)).collect()
+    })
+}
+
+pub fn list_tokens_of(account: &Account, prev: Option<Nat>, take: Option<Nat>) -> Vec<Nat> {
+    if !is_default_account(account) {
+        return Vec::new();
+    }
+    let take = effective_take(take);
+    let prev = prev.map(|p| nat_to_u64(&p).unwrap_or(u64::MAX));
+    let owned = OWNER_TOKENS.with(|m| m.borrow().get(&account.owner)).unwrap_or_default();
+    owned.0.into_iter()
+        .filter(|id| prev.is_none_or(|p| *id > p))
+        .take(take)
+        .map(Nat::from)
+        .collect()
+}
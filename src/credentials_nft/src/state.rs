@@ -0,0 +1,59 @@
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, StableCell};
+use std::cell::RefCell;
+use candid::Principal;
+use crate::types::*;
+
+pub type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+thread_local! {
+    /// Memory manager for allocating virtual memory regions
+    pub static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
+        MemoryManager::init(DefaultMemoryImpl::default())
+    );
+
+    /// Principal ID of the staking hub (source of truth for registered shards)
+    pub static STAKING_HUB_ID: RefCell<StableCell<Principal, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(0))),
+            Principal::anonymous()
+        ).unwrap()
+    );
+
+    /// Live tokens: token id -> CredentialToken
+    /// u64 keys are stored big-endian, so iteration is in token id order
+    pub static TOKENS: RefCell<StableBTreeMap<u64, CredentialToken, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(1)))
+        )
+    );
+
+    /// Owner principal -> token ids (default subaccount only)
+    pub static OWNER_TOKENS: RefCell<StableBTreeMap<Principal, TokenIdList, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(2)))
+        )
+    );
+
+    /// "kind:owner:reference" -> token id (makes minting idempotent)
+    pub static MINTED_CREDENTIALS: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(3)))
+        )
+    );
+
+    /// Last token id issued (ids are never reused)
+    pub static TOKEN_COUNT: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(4))),
+            0u64
+        ).unwrap()
+    );
+
+    /// Shards confirmed as registered by the staking hub
+    pub static AUTHORIZED_MINTERS: RefCell<StableBTreeMap<Principal, bool, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(5)))
+        )
+    );
+}
@@ -1,3 +1,5 @@
+use rate_limit::RateLimit;
+
 // ============================================================================
 // GOVERNANCE CONSTANTS (Defaults - can be modified via admin proposals)
 // ============================================================================
@@ -43,3 +45,81 @@ pub const MIN_MEMBER_SHARE_BPS: u16 = 1;
 
 /// Maximum share for any single board member (99.00%)
 pub const MAX_MEMBER_SHARE_BPS: u16 = 9_900;
+
+/// Reward multiplier bounds for SetRewardMultiplier proposals (10,000 BPS = 1x)
+/// Must agree with the staking hub's limits
+pub const MIN_REWARD_MULTIPLIER_BPS: u32 = 5_000;
+pub const MAX_REWARD_MULTIPLIER_BPS: u32 = 50_000;
+
+/// Stored size bound of one proposal (room for an AddTranslation payload)
+pub const MAX_PROPOSAL_BYTES: u32 = 64 * 1024;
+
+/// Candid-encoded size limit of an AddTranslation proposal's translation
+/// Leaves room for the title, description and other proposal fields
+pub const MAX_TRANSLATION_PAYLOAD_BYTES: usize = 48 * 1024;
+
+/// Recipients per route_notification call to the staking hub
+pub const MAX_NOTIFICATION_RECIPIENTS: usize = 500;
+
+// ============================================================================
+// INGRESS FILTERING (canister_inspect_message)
+// ============================================================================
+
+/// Default argument size limit for ingress messages
+pub const MAX_INGRESS_ARG_BYTES: usize = 64 * 1024;
+
+/// Per-method argument size limits overriding MAX_INGRESS_ARG_BYTES
+pub const INGRESS_ARG_LIMITS: &[(&str, usize)] = &[];
+
+/// Methods that reject the anonymous principal anyway (no voting power, controller-only)
+pub const ANONYMOUS_REJECTED_METHODS: &[&str] = &[
+    "admin_expire_proposal", "admin_set_proposal_status", "clear_sentinel_member",
+    "create_add_content_proposal", "create_add_translation_proposal",
+    "create_board_member_proposal", "create_delete_content_proposal",
+    "create_remove_board_member_proposal", "create_remove_translation_proposal",
+    "create_set_reward_multiplier_proposal",
+    "create_treasury_proposal",
+    "create_update_board_member_share_proposal", "create_update_emission_schedule_proposal",
+    "create_update_governance_config_proposal", "create_update_sentinel_proposal",
+    "create_update_token_limits_proposal", "create_upsert_badge_proposal",
+    "lock_board_member_shares", "set_board_member_shares", "set_learning_engine_id",
+    "set_sentinel_member", "set_treasury_canister_id", "support_proposal",
+    "unlock_board_member_shares", "vote",
+];
+
+/// Proposal creation: burst of 3, one more per hour
+pub const PROPOSAL_RATE_LIMIT: RateLimit = RateLimit { bucket: "proposal", capacity: 3, refill_interval_ns: 60 * 60 * 1_000_000_000 };
+
+/// Supporting and voting
+pub const PARTICIPATION_RATE_LIMIT: RateLimit = RateLimit { bucket: "participation", capacity: 30, refill_interval_ns: 10 * 1_000_000_000 };
+
+/// Finalizing and executing proposals (open to anyone)
+pub const LIFECYCLE_RATE_LIMIT: RateLimit = RateLimit { bucket: "lifecycle", capacity: 10, refill_interval_ns: 30 * 1_000_000_000 };
+
+/// Voting power lookups (each is a call to the staking hub)
+pub const VOTING_POWER_RATE_LIMIT: RateLimit = RateLimit { bucket: "voting_power", capacity: 20, refill_interval_ns: 3 * 1_000_000_000 };
+
+/// Rate-limited methods and their buckets (must agree with the guards in inspect.rs)
+pub const RATE_LIMITED_METHODS: &[(&str, RateLimit)] = &[
+    ("create_add_content_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_add_translation_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_board_member_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_delete_content_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_remove_board_member_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_remove_translation_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_set_reward_multiplier_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_treasury_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_update_board_member_share_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_update_emission_schedule_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_update_governance_config_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_update_sentinel_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_update_token_limits_proposal", PROPOSAL_RATE_LIMIT),
+    ("create_upsert_badge_proposal", PROPOSAL_RATE_LIMIT),
+    ("support_proposal", PARTICIPATION_RATE_LIMIT),
+    ("vote", PARTICIPATION_RATE_LIMIT),
+    ("finalize_proposal", LIFECYCLE_RATE_LIMIT),
+    ("execute_proposal", LIFECYCLE_RATE_LIMIT),
+    ("get_user_voting_power", VOTING_POWER_RATE_LIMIT),
+    ("get_my_voting_power", VOTING_POWER_RATE_LIMIT),
+    ("get_all_board_member_voting_powers", VOTING_POWER_RATE_LIMIT),
+];
@@ -2,7 +2,7 @@ mod types;
 mod state;
 mod constants;
 
-use ic_cdk::{init, query, update, post_upgrade};
+use ic_cdk::{init, query, update, inspect_message, post_upgrade};
 use candid::Principal;
 use ic_cdk_timers::set_timer_interval;
 use std::time::Duration;
@@ -11,7 +11,9 @@ use types::*;
 use state::*;
 use constants::*;
 mod service;
+mod inspect;
 use service::*;
+use inspect::*;
 
 // ============================================================================
 // INITIALIZATION
@@ -40,18 +42,32 @@ fn start_timers() {
     });
 }
 
+// ============================================================================
+// INGRESS FILTERING
+// ============================================================================
+
+/// Drop ingress that would be rejected anyway before it costs consensus cycles
+#[inspect_message]
+fn inspect_message() {
+    let method = ic_cdk::api::call::method_name();
+    match check_ingress(&method, ic_cdk::caller(), ic_cdk::api::call::arg_data_raw_size()) {
+        Ok(()) => ic_cdk::api::call::accept_message(),
+        Err(e) => ic_cdk::trap(&e),
+    }
+}
+
 
 /// Get the voting power of a specific user
 /// 
 /// This is an update method because it may need to make inter-canister calls
 /// to the staking hub to fetch VUC or staked balances.
-#[update]
+#[update(guard = "limit_voting_power")]
 async fn get_user_voting_power(user: Principal) -> Result<u64, String> {
     fetch_voting_power(user).await
 }
 
 /// Get the voting power of the caller
-#[update]
+#[update(guard = "limit_voting_power")]
 async fn get_my_voting_power() -> Result<u64, String> {
     fetch_voting_power(ic_cdk::caller()).await
 }
@@ -61,7 +77,7 @@ async fn get_my_voting_power() -> Result<u64, String> {
 // ============================================================================
 
 /// Create a treasury spending proposal
-#[update]
+#[update(guard = "limit_proposal")]
 async fn create_treasury_proposal(input: CreateTreasuryProposalInput) -> Result<u64, String> {
     let proposer = ic_cdk::caller();
     let now = ic_cdk::api::time();
@@ -150,6 +166,11 @@ async fn create_treasury_proposal(input: CreateTreasuryProposalInput) -> Result<
         update_token_limits_payload: None,
         delete_content_payload: None,
         update_sentinel_payload: None,
+        update_emission_schedule_payload: None,
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: None,
+        add_translation_payload: None,
+        remove_translation_payload: None,
         votes_yes: 0,
         votes_no: 0,
         voter_count: 0,
@@ -173,7 +194,7 @@ async fn create_treasury_proposal(input: CreateTreasuryProposalInput) -> Result<
 /// 2. Allocate the specified BPS share to them
 /// 3. Proportionally reduce existing members' shares to accommodate the new share
 /// NOTE: The sentinel member is never affected by BPS redistribution
-#[update]
+#[update(guard = "limit_proposal")]
 async fn create_board_member_proposal(input: CreateBoardMemberProposalInput) -> Result<u64, String> {
     let proposer = ic_cdk::caller();
     let now = ic_cdk::api::time();
@@ -252,6 +273,11 @@ async fn create_board_member_proposal(input: CreateBoardMemberProposalInput) ->
         update_token_limits_payload: None,
         delete_content_payload: None,
         update_sentinel_payload: None,
+        update_emission_schedule_payload: None,
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: None,
+        add_translation_payload: None,
+        remove_translation_payload: None,
         votes_yes: 0,
         votes_no: 0,
         voter_count: 0,
@@ -278,7 +304,7 @@ async fn create_board_member_proposal(input: CreateBoardMemberProposalInput) ->
 /// Create a proposal to remove a board member
 /// Their share is redistributed proportionally among remaining members
 /// NOTE: Cannot remove the sentinel - use UpdateSentinel to change the sentinel
-#[update]
+#[update(guard = "limit_proposal")]
 async fn create_remove_board_member_proposal(input: CreateRemoveBoardMemberProposalInput) -> Result<u64, String> {
     let proposer = ic_cdk::caller();
     let now = ic_cdk::api::time();
@@ -354,6 +380,11 @@ async fn create_remove_board_member_proposal(input: CreateRemoveBoardMemberPropo
         update_token_limits_payload: None,
         delete_content_payload: None,
         update_sentinel_payload: None,
+        update_emission_schedule_payload: None,
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: None,
+        add_translation_payload: None,
+        remove_translation_payload: None,
         votes_yes: 0,
         votes_no: 0,
         voter_count: 0,
@@ -373,7 +404,7 @@ async fn create_remove_board_member_proposal(input: CreateRemoveBoardMemberPropo
 
 /// Create a proposal to update a board member's BPS share
 /// NOTE: Cannot update the sentinel's share - they always have 1 unit of VUC
-#[update]
+#[update(guard = "limit_proposal")]
 async fn create_update_board_member_share_proposal(input: CreateUpdateBoardMemberShareProposalInput) -> Result<u64, String> {
     let proposer = ic_cdk::caller();
     let now = ic_cdk::api::time();
@@ -456,6 +487,11 @@ async fn create_update_board_member_share_proposal(input: CreateUpdateBoardMembe
         update_token_limits_payload: None,
         delete_content_payload: None,
         update_sentinel_payload: None,
+        update_emission_schedule_payload: None,
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: None,
+        add_translation_payload: None,
+        remove_translation_payload: None,
         votes_yes: 0,
         votes_no: 0,
         voter_count: 0,
@@ -476,7 +512,7 @@ async fn create_update_board_member_share_proposal(input: CreateUpdateBoardMembe
 /// Create a proposal to update the sentinel member
 /// The sentinel has exactly 1 unit of VUC voting power (not BPS-based)
 /// This role is used to satisfy "requires board member vote" without affecting outcomes
-#[update]
+#[update(guard = "limit_proposal")]
 async fn create_update_sentinel_proposal(input: CreateUpdateSentinelProposalInput) -> Result<u64, String> {
     let proposer = ic_cdk::caller();
     let now = ic_cdk::api::time();
@@ -552,6 +588,11 @@ async fn create_update_sentinel_proposal(input: CreateUpdateSentinelProposalInpu
         update_sentinel_payload: Some(UpdateSentinelPayload {
             new_sentinel: input.new_sentinel,
         }),
+        update_emission_schedule_payload: None,
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: None,
+        add_translation_payload: None,
+        remove_translation_payload: None,
         votes_yes: 0,
         votes_no: 0,
         voter_count: 0,
@@ -572,7 +613,7 @@ async fn create_update_sentinel_proposal(input: CreateUpdateSentinelProposalInpu
 
 /// Create a proposal to update governance configuration
 /// (min voting power, support threshold, approval percentage, timing settings)
-#[update]
+#[update(guard = "limit_proposal")]
 async fn create_update_governance_config_proposal(input: CreateUpdateGovernanceConfigProposalInput) -> Result<u64, String> {
     let proposer = ic_cdk::caller();
     let now = ic_cdk::api::time();
@@ -680,6 +721,11 @@ async fn create_update_governance_config_proposal(input: CreateUpdateGovernanceC
         update_token_limits_payload: None,
         delete_content_payload: None,
         update_sentinel_payload: None,
+        update_emission_schedule_payload: None,
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: None,
+        add_translation_payload: None,
+        remove_translation_payload: None,
         votes_yes: 0,
         votes_no: 0,
         voter_count: 0,
@@ -702,7 +748,7 @@ async fn create_update_governance_config_proposal(input: CreateUpdateGovernanceC
 // ============================================================================
 
 /// Create a proposal to add new content from staging canister
-#[update]
+#[update(guard = "limit_proposal")]
 async fn create_add_content_proposal(input: CreateAddContentProposalInput) -> Result<u64, String> {
     let proposer = ic_cdk::caller();
     let now = ic_cdk::api::time();
@@ -776,6 +822,11 @@ async fn create_add_content_proposal(input: CreateAddContentProposalInput) -> Re
         update_token_limits_payload: None,
         delete_content_payload: None,
         update_sentinel_payload: None,
+        update_emission_schedule_payload: None,
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: None,
+        add_translation_payload: None,
+        remove_translation_payload: None,
         votes_yes: 0,
         votes_no: 0,
         voter_count: 0,
@@ -794,7 +845,7 @@ async fn create_add_content_proposal(input: CreateAddContentProposalInput) -> Re
 }
 
 /// Create a proposal to update global token limits and reward configuration
-#[update]
+#[update(guard = "limit_proposal")]
 async fn create_update_token_limits_proposal(input: CreateUpdateTokenLimitsProposalInput) -> Result<u64, String> {
     let proposer = ic_cdk::caller();
     let now = ic_cdk::api::time();
@@ -813,6 +864,7 @@ async fn create_update_token_limits_proposal(input: CreateUpdateTokenLimitsPropo
         && input.new_max_attempts.is_none()
         && input.new_regular_limits.is_none()
         && input.new_subscribed_limits.is_none()
+        && input.new_limit_mode.is_none()
     {
         return Err("At least one configuration value must be specified".to_string());
     }
@@ -877,9 +929,15 @@ async fn create_update_token_limits_proposal(input: CreateUpdateTokenLimitsPropo
             new_max_attempts: input.new_max_attempts,
             new_regular_limits: input.new_regular_limits,
             new_subscribed_limits: input.new_subscribed_limits,
+            new_limit_mode: input.new_limit_mode,
         }),
         delete_content_payload: None,
         update_sentinel_payload: None,
+        update_emission_schedule_payload: None,
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: None,
+        add_translation_payload: None,
+        remove_translation_payload: None,
         votes_yes: 0,
         votes_no: 0,
         voter_count: 0,
@@ -897,9 +955,9 @@ async fn create_update_token_limits_proposal(input: CreateUpdateTokenLimitsPropo
     Ok(id)
 }
 
-/// Create a proposal to delete a content node
-#[update]
-async fn create_delete_content_proposal(input: CreateDeleteContentProposalInput) -> Result<u64, String> {
+/// Create a proposal to replace the reward emission schedule on the staking hub
+#[update(guard = "limit_proposal")]
+async fn create_update_emission_schedule_proposal(input: CreateUpdateEmissionScheduleProposalInput) -> Result<u64, String> {
     let proposer = ic_cdk::caller();
     let now = ic_cdk::api::time();
     
@@ -910,11 +968,24 @@ async fn create_delete_content_proposal(input: CreateDeleteContentProposalInput)
     if input.description.is_empty() || input.description.len() > 5000 {
         return Err("Description must be 1-5000 characters".to_string());
     }
-    if input.content_id.is_empty() {
-        return Err("Content ID is required".to_string());
+    if input.schedule.base_reward_amount == 0 {
+        return Err("Base reward amount must be greater than 0".to_string());
     }
-    if input.reason.is_empty() {
-        return Err("Deletion reason is required".to_string());
+    if input.schedule.min_reward_amount > input.schedule.base_reward_amount {
+        return Err("Minimum reward cannot exceed the base reward".to_string());
+    }
+    match input.schedule.curve {
+        EmissionCurve::Flat => {}
+        EmissionCurve::Halving { epoch_bps } => {
+            if epoch_bps == 0 || epoch_bps > BPS_TOTAL {
+                return Err("Halving epoch must be between 1 and 10000 BPS".to_string());
+            }
+        }
+        EmissionCurve::SmoothDecay { exponent } => {
+            if exponent == 0 || exponent > 8 {
+                return Err("Decay exponent must be between 1 and 8".to_string());
+            }
+        }
     }
     
     // Check if proposer is a board member
@@ -952,7 +1023,7 @@ async fn create_delete_content_proposal(input: CreateDeleteContentProposalInput)
         proposer,
         created_at: now,
         voting_ends_at,
-        proposal_type: ProposalType::DeleteContentNode,
+        proposal_type: ProposalType::UpdateEmissionSchedule,
         title: input.title,
         description: input.description,
         external_link: input.external_link,
@@ -966,10 +1037,15 @@ async fn create_delete_content_proposal(input: CreateDeleteContentProposalInput)
         update_governance_config_payload: None,
         add_content_payload: None,
         update_token_limits_payload: None,
-        delete_content_payload: Some(DeleteContentNodePayload {
-            content_id: input.content_id,
-            reason: input.reason,
+        delete_content_payload: None,
+        update_sentinel_payload: None,
+        update_emission_schedule_payload: Some(UpdateEmissionSchedulePayload {
+            schedule: input.schedule,
         }),
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: None,
+        add_translation_payload: None,
+        remove_translation_payload: None,
         votes_yes: 0,
         votes_no: 0,
         voter_count: 0,
@@ -980,7 +1056,6 @@ async fn create_delete_content_proposal(input: CreateDeleteContentProposalInput)
         status,
         execute_method: None,
         execute_payload: None,
-        update_sentinel_payload: None,
     };
     
     PROPOSALS.with(|p| p.borrow_mut().insert(id, proposal));
@@ -988,155 +1063,678 @@ async fn create_delete_content_proposal(input: CreateDeleteContentProposalInput)
     Ok(id)
 }
 
-#[update]
-async fn support_proposal(proposal_id: u64) -> Result<(), String> {
-    let supporter = ic_cdk::caller();
+/// Create a proposal to add or replace an achievement badge
+#[update(guard = "limit_proposal")]
+async fn create_upsert_badge_proposal(input: CreateUpsertBadgeProposalInput) -> Result<u64, String> {
+    let proposer = ic_cdk::caller();
     let now = ic_cdk::api::time();
     
-    // Get proposal
-    let mut proposal = PROPOSALS.with(|p| p.borrow().get(&proposal_id))
-        .ok_or("Proposal not found")?;
-        
-    // Check status
-    if proposal.status != ProposalStatus::Proposed {
-        return Err("Proposal is not in Proposed state".to_string());
+    // Validate input
+    if input.title.is_empty() || input.title.len() > 200 {
+        return Err("Title must be 1-200 characters".to_string());
     }
-    
-    // Check if already supported
-    let vote_key = VoteKey { proposal_id, voter: supporter };
-    if SUPPORT_RECORDS.with(|r| r.borrow().contains_key(&vote_key)) {
-        return Err("Already supported this proposal".to_string());
+    if input.description.is_empty() || input.description.len() > 5000 {
+        return Err("Description must be 1-5000 characters".to_string());
+    }
+    if input.badge.id.is_empty() || input.badge.id.len() > 64 {
+        return Err("Badge id must be 1-64 characters".to_string());
+    }
+    if input.badge.name.is_empty() || input.badge.name.len() > 100 {
+        return Err("Badge name must be 1-100 characters".to_string());
+    }
+    if input.badge.description.len() > 500 {
+        return Err("Badge description must be at most 500 characters".to_string());
+    }
+    match &input.badge.rule {
+        BadgeRule::QuizzesCompleted { count } if *count == 0 => {
+            return Err("Quiz count must be greater than 0".to_string());
+        }
+        BadgeRule::StreakDays { days } if *days == 0 => {
+            return Err("Streak length must be greater than 0".to_string());
+        }
+        BadgeRule::SubtreeCompleted { root_id, unit_ids } => {
+            if root_id.is_empty() {
+                return Err("Subtree root id cannot be empty".to_string());
+            }
+            if !unit_ids.is_empty() {
+                return Err("Subtree unit ids are resolved by the staking hub; leave them empty".to_string());
+            }
+        }
+        _ => {}
     }
     
-    // Get voting power
-    let voting_power = fetch_voting_power(supporter).await?;
+    // Check if proposer is a board member
+    let proposer_is_board_member = is_board_member_local(&proposer);
+
+    // Check voting power
+    let voting_power = fetch_voting_power(proposer).await?;
     
-    if voting_power == 0 {
-        return Err("No voting power".to_string());
+    let min_power = get_min_voting_power_to_propose();
+    if voting_power < min_power {
+        return Err(format!(
+            "Insufficient voting power to propose. Required: {}, You have: {}",
+            min_power / 100_000_000,
+            voting_power / 100_000_000
+        ));
     }
     
-    // Record support
-    let record = SupportRecord {
-        supporter,
-        proposal_id,
-        support_amount: voting_power,
-        timestamp: now,
-    };
-    SUPPORT_RECORDS.with(|r| r.borrow_mut().insert(vote_key, record));
-    
-    // Update proposal
-    proposal.support_amount += voting_power;
-    proposal.supporter_count += 1;
+    // Create proposal
+    let id = PROPOSAL_COUNT.with(|c| {
+        let mut cell = c.borrow_mut();
+        let current = *cell.get();
+        cell.set(current + 1).expect("Failed to increment proposal count");
+        current
+    });
     
-    // Check threshold (configurable VP and 2 users)
-    let support_threshold = get_support_threshold();
+    let (status, voting_ends_at, required_yes_votes) = if proposer_is_board_member {
+        let threshold = calculate_approval_threshold().await?;
+        (ProposalStatus::Active, now + get_voting_period(), threshold)
+    } else {
+        (ProposalStatus::Proposed, now + get_support_period(), 0)
+    };
     
-    if proposal.support_amount >= support_threshold && proposal.supporter_count >= 2 {
-        // Transition to Active - calculate and fix the required_yes_votes at this moment
-        let required_votes = calculate_approval_threshold().await?;
-        proposal.required_yes_votes = required_votes;
-        proposal.status = ProposalStatus::Active;
-        proposal.voting_ends_at = now + get_voting_period();
-    }
+    let proposal = Proposal {
+        id,
+        proposer,
+        created_at: now,
+        voting_ends_at,
+        proposal_type: ProposalType::UpsertBadge,
+        title: input.title,
+        description: input.description,
+        external_link: input.external_link,
+        recipient: None,
+        amount: None,
+        token_type: None,
+        category: None,
+        board_member_payload: None,
+        remove_board_member_payload: None,
+        update_board_member_payload: None,
+        update_governance_config_payload: None,
+        add_content_payload: None,
+        update_token_limits_payload: None,
+        delete_content_payload: None,
+        update_sentinel_payload: None,
+        update_emission_schedule_payload: None,
+        upsert_badge_payload: Some(UpsertBadgePayload {
+            badge: input.badge,
+        }),
+        set_reward_multiplier_payload: None,
+        add_translation_payload: None,
+        remove_translation_payload: None,
+        votes_yes: 0,
+        votes_no: 0,
+        voter_count: 0,
+        board_member_yes_count: 0,
+        support_amount: 0,
+        supporter_count: 0,
+        required_yes_votes,
+        status,
+        execute_method: None,
+        execute_payload: None,
+    };
     
-    PROPOSALS.with(|p| p.borrow_mut().insert(proposal_id, proposal));
+    PROPOSALS.with(|p| p.borrow_mut().insert(id, proposal));
     
-    Ok(())
+    Ok(id)
 }
 
-// ============================================================================
-// VOTING
-// ============================================================================
-
-#[update]
-async fn vote(proposal_id: u64, approve: bool) -> Result<(), String> {
-    let voter = ic_cdk::caller();
+/// Create a proposal to override the quiz reward of a content unit
+#[update(guard = "limit_proposal")]
+async fn create_set_reward_multiplier_proposal(input: CreateSetRewardMultiplierProposalInput) -> Result<u64, String> {
+    let proposer = ic_cdk::caller();
     let now = ic_cdk::api::time();
     
-    // Get proposal
-    let mut proposal = PROPOSALS.with(|p| p.borrow().get(&proposal_id))
-        .ok_or("Proposal not found")?;
-    
-    // Check proposal is active
-    if proposal.status != ProposalStatus::Active {
-        return Err("Proposal is not active".to_string());
+    // Validate input
+    if input.title.is_empty() || input.title.len() > 200 {
+        return Err("Title must be 1-200 characters".to_string());
     }
-    
-    // Check voting period
-    if now > proposal.voting_ends_at {
-        return Err("Voting period has ended".to_string());
+    if input.description.is_empty() || input.description.len() > 5000 {
+        return Err("Description must be 1-5000 characters".to_string());
     }
-    
-    // Check if already voted
-    let vote_key = VoteKey { proposal_id, voter };
-    if VOTE_RECORDS.with(|v| v.borrow().contains_key(&vote_key)) {
-        return Err("Already voted on this proposal".to_string());
+    if input.content_id.is_empty() {
+        return Err("Content ID is required".to_string());
     }
-    
-    // Get voting power
-    let voting_power = fetch_voting_power(voter).await?;
-    
-    if voting_power == 0 {
-        return Err("No voting power".to_string());
+    if !(MIN_REWARD_MULTIPLIER_BPS..=MAX_REWARD_MULTIPLIER_BPS).contains(&input.multiplier_bps) {
+        return Err(format!(
+            "Multiplier must be between {} and {} BPS",
+            MIN_REWARD_MULTIPLIER_BPS, MAX_REWARD_MULTIPLIER_BPS
+        ));
     }
     
-    // Record vote
-    let vote_record = VoteRecord {
-        voter,
-        proposal_id,
-        vote: approve,
-        voting_power,
-        timestamp: now,
-    };
-    VOTE_RECORDS.with(|v| v.borrow_mut().insert(vote_key, vote_record));
+    // Check if proposer is a board member
+    let proposer_is_board_member = is_board_member_local(&proposer);
+
+    // Check voting power
+    let voting_power = fetch_voting_power(proposer).await?;
     
-    // Update proposal
-    if approve {
-        proposal.votes_yes += voting_power;
-        if is_board_member_local(&voter) {
-            proposal.board_member_yes_count += 1;
-        }
-    } else {
-        proposal.votes_no += voting_power;
+    let min_power = get_min_voting_power_to_propose();
+    if voting_power < min_power {
+        return Err(format!(
+            "Insufficient voting power to propose. Required: {}, You have: {}",
+            min_power / 100_000_000,
+            voting_power / 100_000_000
+        ));
     }
-    proposal.voter_count += 1;
-    
-    // Threshold check is handled in finalize_proposal, which allows early execution.
-    // We do not change status here to avoid blocking finalize_proposal (which requires Active status).
-    
-    PROPOSALS.with(|p| p.borrow_mut().insert(proposal_id, proposal));
-    
-    Ok(())
-}
-
-// ============================================================================
-// PROPOSAL FINALIZATION
-// ============================================================================
-
-/// Finalize proposals whose voting or support period has ended
-fn finalize_expired_proposals() {
-    let now = ic_cdk::api::time();
     
-    let proposals_to_finalize: Vec<u64> = PROPOSALS.with(|p| {
-        p.borrow()
-            .iter()
-            .filter(|(_, prop)| {
-                // Active proposals that have ended voting period
-                (prop.status == ProposalStatus::Active && now > prop.voting_ends_at) ||
-                // Proposed proposals that have ended support period
-                (prop.status == ProposalStatus::Proposed && now > prop.voting_ends_at)
-            })
-            .map(|(id, _)| id)
-            .collect()
+    // Create proposal
+    let id = PROPOSAL_COUNT.with(|c| {
+        let mut cell = c.borrow_mut();
+        let current = *cell.get();
+        cell.set(current + 1).expect("Failed to increment proposal count");
+        current
     });
     
-    for id in proposals_to_finalize {
-        let _ = finalize_proposal(id);
-    }
-}
-
+    let (status, voting_ends_at, required_yes_votes) = if proposer_is_board_member {
+        let threshold = calculate_approval_threshold().await?;
+        (ProposalStatus::Active, now + get_voting_period(), threshold)
+    } else {
+        (ProposalStatus::Proposed, now + get_support_period(), 0)
+    };
+    
+    let proposal = Proposal {
+        id,
+        proposer,
+        created_at: now,
+        voting_ends_at,
+        proposal_type: ProposalType::SetRewardMultiplier,
+        title: input.title,
+        description: input.description,
+        external_link: input.external_link,
+        recipient: None,
+        amount: None,
+        token_type: None,
+        category: None,
+        board_member_payload: None,
+        remove_board_member_payload: None,
+        update_board_member_payload: None,
+        update_governance_config_payload: None,
+        add_content_payload: None,
+        update_token_limits_payload: None,
+        delete_content_payload: None,
+        update_sentinel_payload: None,
+        update_emission_schedule_payload: None,
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: Some(SetRewardMultiplierPayload {
+            content_id: input.content_id,
+            multiplier_bps: input.multiplier_bps,
+        }),
+        add_translation_payload: None,
+        remove_translation_payload: None,
+        votes_yes: 0,
+        votes_no: 0,
+        voter_count: 0,
+        board_member_yes_count: 0,
+        support_amount: 0,
+        supporter_count: 0,
+        required_yes_votes,
+        status,
+        execute_method: None,
+        execute_payload: None,
+    };
+    
+    PROPOSALS.with(|p| p.borrow_mut().insert(id, proposal));
+    
+    Ok(id)
+}
+
+/// Create a proposal to delete a content node
+#[update(guard = "limit_proposal")]
+async fn create_delete_content_proposal(input: CreateDeleteContentProposalInput) -> Result<u64, String> {
+    let proposer = ic_cdk::caller();
+    let now = ic_cdk::api::time();
+    
+    // Validate input
+    if input.title.is_empty() || input.title.len() > 200 {
+        return Err("Title must be 1-200 characters".to_string());
+    }
+    if input.description.is_empty() || input.description.len() > 5000 {
+        return Err("Description must be 1-5000 characters".to_string());
+    }
+    if input.content_id.is_empty() {
+        return Err("Content ID is required".to_string());
+    }
+    if input.reason.is_empty() {
+        return Err("Deletion reason is required".to_string());
+    }
+    
+    // Check if proposer is a board member
+    let proposer_is_board_member = is_board_member_local(&proposer);
+
+    // Check voting power
+    let voting_power = fetch_voting_power(proposer).await?;
+    
+    let min_power = get_min_voting_power_to_propose();
+    if voting_power < min_power {
+        return Err(format!(
+            "Insufficient voting power to propose. Required: {}, You have: {}",
+            min_power / 100_000_000,
+            voting_power / 100_000_000
+        ));
+    }
+    
+    // Create proposal
+    let id = PROPOSAL_COUNT.with(|c| {
+        let mut cell = c.borrow_mut();
+        let current = *cell.get();
+        cell.set(current + 1).expect("Failed to increment proposal count");
+        current
+    });
+    
+    let (status, voting_ends_at, required_yes_votes) = if proposer_is_board_member {
+        let threshold = calculate_approval_threshold().await?;
+        (ProposalStatus::Active, now + get_voting_period(), threshold)
+    } else {
+        (ProposalStatus::Proposed, now + get_support_period(), 0)
+    };
+    
+    let proposal = Proposal {
+        id,
+        proposer,
+        created_at: now,
+        voting_ends_at,
+        proposal_type: ProposalType::DeleteContentNode,
+        title: input.title,
+        description: input.description,
+        external_link: input.external_link,
+        recipient: None,
+        amount: None,
+        token_type: None,
+        category: None,
+        board_member_payload: None,
+        remove_board_member_payload: None,
+        update_board_member_payload: None,
+        update_governance_config_payload: None,
+        add_content_payload: None,
+        update_token_limits_payload: None,
+        delete_content_payload: Some(DeleteContentNodePayload {
+            content_id: input.content_id,
+            reason: input.reason,
+        }),
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: None,
+        add_translation_payload: None,
+        remove_translation_payload: None,
+        votes_yes: 0,
+        votes_no: 0,
+        voter_count: 0,
+        board_member_yes_count: 0,
+        support_amount: 0,
+        supporter_count: 0,
+        required_yes_votes,
+        status,
+        execute_method: None,
+        execute_payload: None,
+        update_sentinel_payload: None,
+        update_emission_schedule_payload: None,
+    };
+    
+    PROPOSALS.with(|p| p.borrow_mut().insert(id, proposal));
+    
+    Ok(id)
+}
+
+/// Create a proposal to add or replace one locale's translation of a content node
+#[update(guard = "limit_proposal")]
+async fn create_add_translation_proposal(input: CreateAddTranslationProposalInput) -> Result<u64, String> {
+    let proposer = ic_cdk::caller();
+    let now = ic_cdk::api::time();
+    
+    // Validate input
+    if input.title.is_empty() || input.title.len() > 200 {
+        return Err("Title must be 1-200 characters".to_string());
+    }
+    if input.description.is_empty() || input.description.len() > 5000 {
+        return Err("Description must be 1-5000 characters".to_string());
+    }
+    if input.content_id.is_empty() {
+        return Err("Content ID is required".to_string());
+    }
+    if input.translation.locale.trim().is_empty() {
+        return Err("Translation locale is required".to_string());
+    }
+    if input.translation.title.trim().is_empty() {
+        return Err("Translation title is required".to_string());
+    }
+    let payload_bytes = candid::encode_one(&input.translation)
+        .map_err(|e| format!("Failed to encode translation: {}", e))?
+        .len();
+    if payload_bytes > MAX_TRANSLATION_PAYLOAD_BYTES {
+        return Err(format!(
+            "Translation is {} bytes; proposals carry at most {}",
+            payload_bytes, MAX_TRANSLATION_PAYLOAD_BYTES
+        ));
+    }
+    
+    // Check if proposer is a board member
+    let proposer_is_board_member = is_board_member_local(&proposer);
+
+    // Check voting power
+    let voting_power = fetch_voting_power(proposer).await?;
+    
+    let min_power = get_min_voting_power_to_propose();
+    if voting_power < min_power {
+        return Err(format!(
+            "Insufficient voting power to propose. Required: {}, You have: {}",
+            min_power / 100_000_000,
+            voting_power / 100_000_000
+        ));
+    }
+    
+    // Create proposal
+    let id = PROPOSAL_COUNT.with(|c| {
+        let mut cell = c.borrow_mut();
+        let current = *cell.get();
+        cell.set(current + 1).expect("Failed to increment proposal count");
+        current
+    });
+    
+    let (status, voting_ends_at, required_yes_votes) = if proposer_is_board_member {
+        let threshold = calculate_approval_threshold().await?;
+        (ProposalStatus::Active, now + get_voting_period(), threshold)
+    } else {
+        (ProposalStatus::Proposed, now + get_support_period(), 0)
+    };
+    
+    let proposal = Proposal {
+        id,
+        proposer,
+        created_at: now,
+        voting_ends_at,
+        proposal_type: ProposalType::AddTranslation,
+        title: input.title,
+        description: input.description,
+        external_link: input.external_link,
+        recipient: None,
+        amount: None,
+        token_type: None,
+        category: None,
+        board_member_payload: None,
+        remove_board_member_payload: None,
+        update_board_member_payload: None,
+        update_governance_config_payload: None,
+        add_content_payload: None,
+        update_token_limits_payload: None,
+        delete_content_payload: None,
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: None,
+        add_translation_payload: Some(AddTranslationPayload {
+            content_id: input.content_id,
+            translation: input.translation,
+        }),
+        remove_translation_payload: None,
+        votes_yes: 0,
+        votes_no: 0,
+        voter_count: 0,
+        board_member_yes_count: 0,
+        support_amount: 0,
+        supporter_count: 0,
+        required_yes_votes,
+        status,
+        execute_method: None,
+        execute_payload: None,
+        update_sentinel_payload: None,
+        update_emission_schedule_payload: None,
+    };
+    
+    PROPOSALS.with(|p| p.borrow_mut().insert(id, proposal));
+    
+    Ok(id)
+}
+
+/// Create a proposal to remove one locale's translation of a content node
+#[update(guard = "limit_proposal")]
+async fn create_remove_translation_proposal(input: CreateRemoveTranslationProposalInput) -> Result<u64, String> {
+    let proposer = ic_cdk::caller();
+    let now = ic_cdk::api::time();
+    
+    // Validate input
+    if input.title.is_empty() || input.title.len() > 200 {
+        return Err("Title must be 1-200 characters".to_string());
+    }
+    if input.description.is_empty() || input.description.len() > 5000 {
+        return Err("Description must be 1-5000 characters".to_string());
+    }
+    if input.content_id.is_empty() {
+        return Err("Content ID is required".to_string());
+    }
+    if input.locale.trim().is_empty() {
+        return Err("Locale is required".to_string());
+    }
+    if input.reason.is_empty() {
+        return Err("Removal reason is required".to_string());
+    }
+    
+    // Check if proposer is a board member
+    let proposer_is_board_member = is_board_member_local(&proposer);
+
+    // Check voting power
+    let voting_power = fetch_voting_power(proposer).await?;
+    
+    let min_power = get_min_voting_power_to_propose();
+    if voting_power < min_power {
+        return Err(format!(
+            "Insufficient voting power to propose. Required: {}, You have: {}",
+            min_power / 100_000_000,
+            voting_power / 100_000_000
+        ));
+    }
+    
+    // Create proposal
+    let id = PROPOSAL_COUNT.with(|c| {
+        let mut cell = c.borrow_mut();
+        let current = *cell.get();
+        cell.set(current + 1).expect("Failed to increment proposal count");
+        current
+    });
+    
+    let (status, voting_ends_at, required_yes_votes) = if proposer_is_board_member {
+        let threshold = calculate_approval_threshold().await?;
+        (ProposalStatus::Active, now + get_voting_period(), threshold)
+    } else {
+        (ProposalStatus::Proposed, now + get_support_period(), 0)
+    };
+    
+    let proposal = Proposal {
+        id,
+        proposer,
+        created_at: now,
+        voting_ends_at,
+        proposal_type: ProposalType::RemoveTranslation,
+        title: input.title,
+        description: input.description,
+        external_link: input.external_link,
+        recipient: None,
+        amount: None,
+        token_type: None,
+        category: None,
+        board_member_payload: None,
+        remove_board_member_payload: None,
+        update_board_member_payload: None,
+        update_governance_config_payload: None,
+        add_content_payload: None,
+        update_token_limits_payload: None,
+        delete_content_payload: None,
+        upsert_badge_payload: None,
+        set_reward_multiplier_payload: None,
+        add_translation_payload: None,
+        remove_translation_payload: Some(RemoveTranslationPayload {
+            content_id: input.content_id,
+            locale: input.locale,
+            reason: input.reason,
+        }),
+        votes_yes: 0,
+        votes_no: 0,
+        voter_count: 0,
+        board_member_yes_count: 0,
+        support_amount: 0,
+        supporter_count: 0,
+        required_yes_votes,
+        status,
+        execute_method: None,
+        execute_payload: None,
+        update_sentinel_payload: None,
+        update_emission_schedule_payload: None,
+    };
+    
+    PROPOSALS.with(|p| p.borrow_mut().insert(id, proposal));
+    
+    Ok(id)
+}
+
+#[update(guard = "limit_participation")]
+async fn support_proposal(proposal_id: u64) -> Result<(), String> {
+    let supporter = resolve_voter(ic_cdk::caller()).await?;
+    let now = ic_cdk::api::time();
+    
+    // Get proposal
+    let mut proposal = PROPOSALS.with(|p| p.borrow().get(&proposal_id))
+        .ok_or("Proposal not found")?;
+        
+    // Check status
+    if proposal.status != ProposalStatus::Proposed {
+        return Err("Proposal is not in Proposed state".to_string());
+    }
+    
+    // Check if already supported
+    let vote_key = VoteKey { proposal_id, voter: supporter };
+    if SUPPORT_RECORDS.with(|r| r.borrow().contains_key(&vote_key)) {
+        return Err("Already supported this proposal".to_string());
+    }
+    
+    // Get voting power
+    let voting_power = fetch_voting_power(supporter).await?;
+    
+    if voting_power == 0 {
+        return Err("No voting power".to_string());
+    }
+    
+    // Record support
+    let record = SupportRecord {
+        supporter,
+        proposal_id,
+        support_amount: voting_power,
+        timestamp: now,
+    };
+    SUPPORT_RECORDS.with(|r| r.borrow_mut().insert(vote_key, record));
+    
+    // Update proposal
+    proposal.support_amount += voting_power;
+    proposal.supporter_count += 1;
+    
+    // Check threshold (configurable VP and 2 users)
+    let support_threshold = get_support_threshold();
+    
+    if proposal.support_amount >= support_threshold && proposal.supporter_count >= 2 {
+        // Transition to Active - calculate and fix the required_yes_votes at this moment
+        let required_votes = calculate_approval_threshold().await?;
+        proposal.required_yes_votes = required_votes;
+        proposal.status = ProposalStatus::Active;
+        proposal.voting_ends_at = now + get_voting_period();
+
+        let (proposer, title) = (proposal.proposer, proposal.title.clone());
+        ic_cdk::spawn(async move {
+            notify_proposal_active(proposal_id, proposer, title).await;
+        });
+    }
+    
+    PROPOSALS.with(|p| p.borrow_mut().insert(proposal_id, proposal));
+    
+    Ok(())
+}
+
+// ============================================================================
+// VOTING
+// ============================================================================
+
+#[update(guard = "limit_participation")]
+async fn vote(proposal_id: u64, approve: bool) -> Result<(), String> {
+    let voter = resolve_voter(ic_cdk::caller()).await?;
+    let now = ic_cdk::api::time();
+    
+    // Get proposal
+    let mut proposal = PROPOSALS.with(|p| p.borrow().get(&proposal_id))
+        .ok_or("Proposal not found")?;
+    
+    // Check proposal is active
+    if proposal.status != ProposalStatus::Active {
+        return Err("Proposal is not active".to_string());
+    }
+    
+    // Check voting period
+    if now > proposal.voting_ends_at {
+        return Err("Voting period has ended".to_string());
+    }
+    
+    // Check if already voted
+    let vote_key = VoteKey { proposal_id, voter };
+    if VOTE_RECORDS.with(|v| v.borrow().contains_key(&vote_key)) {
+        return Err("Already voted on this proposal".to_string());
+    }
+    
+    // Get voting power
+    let voting_power = fetch_voting_power(voter).await?;
+    
+    if voting_power == 0 {
+        return Err("No voting power".to_string());
+    }
+    
+    // Record vote
+    let vote_record = VoteRecord {
+        voter,
+        proposal_id,
+        vote: approve,
+        voting_power,
+        timestamp: now,
+    };
+    VOTE_RECORDS.with(|v| v.borrow_mut().insert(vote_key, vote_record));
+    
+    // Update proposal
+    if approve {
+        proposal.votes_yes += voting_power;
+        if is_board_member_local(&voter) {
+            proposal.board_member_yes_count += 1;
+        }
+    } else {
+        proposal.votes_no += voting_power;
+    }
+    proposal.voter_count += 1;
+    
+    // Threshold check is handled in finalize_proposal, which allows early execution.
+    // We do not change status here to avoid blocking finalize_proposal (which requires Active status).
+    
+    PROPOSALS.with(|p| p.borrow_mut().insert(proposal_id, proposal));
+    
+    Ok(())
+}
+
+// ============================================================================
+// PROPOSAL FINALIZATION
+// ============================================================================
+
+/// Finalize proposals whose voting or support period has ended
+fn finalize_expired_proposals() {
+    let now = ic_cdk::api::time();
+    
+    let proposals_to_finalize: Vec<u64> = PROPOSALS.with(|p| {
+        p.borrow()
+            .iter()
+            .filter(|(_, prop)| {
+                // Active proposals that have ended voting period
+                (prop.status == ProposalStatus::Active && now > prop.voting_ends_at) ||
+                // Proposed proposals that have ended support period
+                (prop.status == ProposalStatus::Proposed && now > prop.voting_ends_at)
+            })
+            .map(|(id, _)| id)
+            .collect()
+    });
+    
+    for id in proposals_to_finalize {
+        let _ = finalize_proposal(id);
+    }
+}
+
 // finalize_proposal uses the stored required_yes_votes that was fixed at activation time
-#[update]
+#[update(guard = "limit_lifecycle")]
 fn finalize_proposal(proposal_id: u64) -> Result<ProposalStatus, String> {
     let now = ic_cdk::api::time();
     
@@ -1185,7 +1783,7 @@ fn finalize_proposal(proposal_id: u64) -> Result<ProposalStatus, String> {
     Ok(proposal.status)
 }
 
-#[update]
+#[update(guard = "limit_lifecycle")]
 async fn execute_proposal(proposal_id: u64) -> Result<(), String> {
     let proposal = PROPOSALS.with(|p| p.borrow().get(&proposal_id))
         .ok_or("Proposal not found")?;
@@ -1205,6 +1803,11 @@ async fn execute_proposal(proposal_id: u64) -> Result<(), String> {
         ProposalType::UpdateTokenLimits => execute_update_token_limits_proposal_internal(&proposal).await?,
         ProposalType::DeleteContentNode => execute_delete_content_proposal_internal(&proposal).await?,
         ProposalType::UpdateSentinel => execute_update_sentinel_proposal_internal(&proposal)?,
+        ProposalType::UpdateEmissionSchedule => execute_update_emission_schedule_proposal_internal(&proposal).await?,
+        ProposalType::UpsertBadge => execute_upsert_badge_proposal_internal(&proposal).await?,
+        ProposalType::SetRewardMultiplier => execute_set_reward_multiplier_proposal_internal(&proposal).await?,
+        ProposalType::AddTranslation => execute_add_translation_proposal_internal(&proposal).await?,
+        ProposalType::RemoveTranslation => execute_remove_translation_proposal_internal(&proposal).await?,
     }
     
     let mut proposal = proposal; // Get a mutable copy
@@ -1331,6 +1934,7 @@ async fn execute_update_token_limits_proposal_internal(proposal: &Proposal) -> R
             payload.new_max_attempts,
             payload.new_regular_limits.clone(),
             payload.new_subscribed_limits.clone(),
+            payload.new_limit_mode,
         )
     ).await;
     
@@ -1341,6 +1945,82 @@ async fn execute_update_token_limits_proposal_internal(proposal: &Proposal) -> R
 }
 
 
+/// Execute UpdateEmissionSchedule proposal
+async fn execute_update_emission_schedule_proposal_internal(proposal: &Proposal) -> Result<(), String> {
+    let payload = proposal.update_emission_schedule_payload.as_ref()
+        .ok_or("UpdateEmissionSchedule proposal missing payload")?;
+    
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    
+    if staking_hub_id == Principal::anonymous() {
+        return Err("Staking Hub ID not configured".to_string());
+    }
+    
+    // Call staking_hub.update_emission_schedule (returns the new effective reward)
+    let result: Result<(Result<u64, String>,), _> = ic_cdk::call(
+        staking_hub_id,
+        "update_emission_schedule",
+        (payload.schedule.clone(),)
+    ).await;
+    
+    match result {
+        Ok((Ok(_effective_reward),)) => Ok(()),
+        Ok((Err(e),)) => Err(e),
+        Err((code, msg)) => Err(format!("Staking Hub call failed: {:?} {}", code, msg)),
+    }
+}
+
+
+/// Execute UpsertBadge proposal
+async fn execute_upsert_badge_proposal_internal(proposal: &Proposal) -> Result<(), String> {
+    let payload = proposal.upsert_badge_payload.as_ref()
+        .ok_or("UpsertBadge proposal missing payload")?;
+    
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    
+    if staking_hub_id == Principal::anonymous() {
+        return Err("Staking Hub ID not configured".to_string());
+    }
+    
+    // Call staking_hub.upsert_badge_definition (returns the number of shards updated)
+    let result: Result<(Result<u64, String>,), _> = ic_cdk::call(
+        staking_hub_id,
+        "upsert_badge_definition",
+        (payload.badge.clone(),)
+    ).await;
+    
+    match result {
+        Ok((Ok(_shards_updated),)) => Ok(()),
+        Ok((Err(e),)) => Err(e),
+        Err((code, msg)) => Err(format!("Staking Hub call failed: {:?} {}", code, msg)),
+    }
+}
+
+
+/// Execute SetRewardMultiplier proposal
+async fn execute_set_reward_multiplier_proposal_internal(proposal: &Proposal) -> Result<(), String> {
+    let payload = proposal.set_reward_multiplier_payload.as_ref()
+        .ok_or("SetRewardMultiplier proposal missing payload")?;
+    
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    
+    if staking_hub_id == Principal::anonymous() {
+        return Err("Staking Hub ID not configured".to_string());
+    }
+    
+    // Call staking_hub.set_reward_multiplier (returns the number of shards updated)
+    let result: Result<(Result<u64, String>,), _> = ic_cdk::call(
+        staking_hub_id,
+        "set_reward_multiplier",
+        (payload.content_id.clone(), payload.multiplier_bps)
+    ).await;
+    
+    match result {
+        Ok((Ok(_shards_updated),)) => Ok(()),
+        Ok((Err(e),)) => Err(e),
+        Err((code, msg)) => Err(format!("Staking Hub call failed: {:?} {}", code, msg)),
+    }
+}
 /// Execute DeleteContentNode proposal
 async fn execute_delete_content_proposal_internal(proposal: &Proposal) -> Result<(), String> {
     let payload = proposal.delete_content_payload.as_ref()
@@ -1368,6 +2048,62 @@ async fn execute_delete_content_proposal_internal(proposal: &Proposal) -> Result
     }
 }
 
+/// Execute AddTranslation proposal
+async fn execute_add_translation_proposal_internal(proposal: &Proposal) -> Result<(), String> {
+    let payload = proposal.add_translation_payload.as_ref()
+        .ok_or("AddTranslation proposal missing payload")?;
+    
+    let learning_engine_id = LEARNING_ENGINE_ID.with(|id| *id.borrow().get());
+    
+    if learning_engine_id == Principal::anonymous() {
+        return Err("Learning engine ID not configured".to_string());
+    }
+    
+    // Call learning_engine.add_translation (validates the locale and quiz shape)
+    let result: Result<(Result<(), String>,), _> = ic_cdk::call(
+        learning_engine_id,
+        "add_translation",
+        (
+            payload.content_id.clone(),
+            payload.translation.clone(),
+            proposal.id,
+        )
+    ).await;
+    
+    match result {
+        Ok((inner_result,)) => inner_result,
+        Err((code, msg)) => Err(format!("Learning engine call failed: {:?} {}", code, msg)),
+    }
+}
+
+/// Execute RemoveTranslation proposal
+async fn execute_remove_translation_proposal_internal(proposal: &Proposal) -> Result<(), String> {
+    let payload = proposal.remove_translation_payload.as_ref()
+        .ok_or("RemoveTranslation proposal missing payload")?;
+    
+    let learning_engine_id = LEARNING_ENGINE_ID.with(|id| *id.borrow().get());
+    
+    if learning_engine_id == Principal::anonymous() {
+        return Err("Learning engine ID not configured".to_string());
+    }
+    
+    // Call learning_engine.delete_translation
+    let result: Result<(Result<(), String>,), _> = ic_cdk::call(
+        learning_engine_id,
+        "delete_translation",
+        (
+            payload.content_id.clone(),
+            payload.locale.clone(),
+            proposal.id,
+        )
+    ).await;
+    
+    match result {
+        Ok((inner_result,)) => inner_result,
+        Err((code, msg)) => Err(format!("Learning engine call failed: {:?} {}", code, msg)),
+    }
+}
+
 /// Execute a board member addition proposal
 /// 
 /// This function:
@@ -2105,7 +2841,7 @@ fn is_board_member(principal: Principal) -> bool {
 
 /// Get all board member voting powers (includes sentinel if set)
 /// Uses cumulative partitioning for zero-dust calculation
-#[update] // Async call to staking hub required
+#[update(guard = "limit_voting_power")] // Async call to staking hub required
 async fn get_all_board_member_voting_powers() -> Result<Vec<(Principal, u16, u64, bool)>, String> {
     calculate_all_board_member_powers().await
 }
@@ -1,6 +1,7 @@
 use candid::{Principal, CandidType, Deserialize};
 use crate::state::*;
 use crate::constants::*;
+use crate::types::{NotificationCategory, NotificationInput};
 
 // ============================================================================
 // BOARD MEMBER HELPERS
@@ -122,6 +123,59 @@ pub async fn calculate_approval_threshold() -> Result<u64, String> {
 // VOTING POWER CALCULATION (Cumulative Partitioning - Zero Dust)
 // ============================================================================
 
+/// Resolve the principal votes are recorded under
+///
+/// Learners can link several wallets to one profile; votes and support are
+/// keyed by the canonical principal so each account counts once.
+/// Board members and the sentinel vote with their own principal.
+pub async fn resolve_voter(caller: Principal) -> Result<Principal, String> {
+    if is_sentinel_local(&caller) || get_board_member_share_bps_local(&caller).is_some() {
+        return Ok(caller);
+    }
+
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    let result: Result<(Principal,), _> = ic_cdk::call(
+        staking_hub_id,
+        "get_canonical_principal",
+        (caller,),
+    ).await;
+
+    match result {
+        Ok((canonical,)) => Ok(canonical),
+        Err((code, msg)) => Err(format!("Failed to resolve voter: {:?} {}", code, msg)),
+    }
+}
+
+/// Tell the proposer and supporters that a proposal is open for voting
+/// Best effort: notification failures never affect the proposal.
+pub async fn notify_proposal_active(proposal_id: u64, proposer: Principal, title: String) {
+    let mut recipients: Vec<Principal> = SUPPORT_RECORDS.with(|r| {
+        r.borrow()
+            .iter()
+            .filter(|(key, _)| key.proposal_id == proposal_id)
+            .map(|(key, _)| key.voter)
+            .collect()
+    });
+    if !recipients.contains(&proposer) {
+        recipients.push(proposer);
+    }
+
+    let notification = NotificationInput {
+        category: NotificationCategory::Governance,
+        title: format!("Proposal #{} is open for voting", proposal_id),
+        body: title,
+        link: Some(format!("proposal:{}", proposal_id)),
+    };
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    for chunk in recipients.chunks(MAX_NOTIFICATION_RECIPIENTS) {
+        let _ = ic_cdk::call::<_, (Result<u64, String>,)>(
+            staking_hub_id,
+            "route_notification",
+            (chunk.to_vec(), notification.clone()),
+        ).await;
+    }
+}
+
 /// Fetch voting power for a user
 /// 
 /// - Sentinel member: returns exactly 1 unit of VUC (1 e8s)
@@ -2,6 +2,7 @@ use candid::{CandidType, Deserialize, Principal};
 use ic_stable_structures::{storable::Bound, Storable};
 use std::borrow::Cow;
 use candid::{Encode, Decode};
+use crate::constants::MAX_PROPOSAL_BYTES;
 
 /// Token types for treasury spending
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
@@ -46,7 +47,8 @@ pub enum ProposalType {
     UpdateBoardMemberShare,
     /// Update governance configuration (thresholds, approval percentage)
     UpdateGovernanceConfig,
-    /// Add new content from staging (books, courses, etc.)
+    /// Add new content from staging (books, courses, etc.), including any
+    /// per-locale translations carried by the staged nodes
     AddContentFromStaging,
     /// Update global token limits and reward configuration
     UpdateTokenLimits,
@@ -54,6 +56,16 @@ pub enum ProposalType {
     DeleteContentNode,
     /// Update the sentinel member (the member with 1 unit of voting power)
     UpdateSentinel,
+    /// Update the reward emission curve applied by the staking hub
+    UpdateEmissionSchedule,
+    /// Create or replace an achievement badge definition
+    UpsertBadge,
+    /// Override the quiz reward of one content unit
+    SetRewardMultiplier,
+    /// Add or replace one locale's translation of a content node
+    AddTranslation,
+    /// Remove one locale's translation of a content node
+    RemoveTranslation,
 }
 
 /// Payload for AddBoardMember proposals
@@ -144,6 +156,15 @@ pub struct TokenLimits {
     pub max_yearly_tokens: u64,
 }
 
+/// How the period token limits are measured
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LimitMode {
+    /// Counters reset on UTC day, week (Sunday), month and year boundaries
+    Calendar,
+    /// Usage over the last 24h, 7d, 30d and 365d
+    Rolling,
+}
+
 /// Payload for UpdateTokenLimits proposals
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct UpdateTokenLimitsPayload {
@@ -160,8 +181,135 @@ pub struct UpdateTokenLimitsPayload {
     
     pub new_regular_limits: Option<TokenLimits>,
     pub new_subscribed_limits: Option<TokenLimits>,
+    /// Switch between calendar resets and rolling windows
+    pub new_limit_mode: Option<LimitMode>,
+}
+
+
+/// Shape of the reward decay curve (mirrors staking_hub::EmissionCurve)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum EmissionCurve {
+    /// No decay: the base reward is paid until MAX_SUPPLY is reached
+    Flat,
+    /// Reward halves every time another `epoch_bps` of MAX_SUPPLY is allocated
+    Halving { epoch_bps: u16 },
+    /// Smooth decay: reward = base * (1 - allocated / MAX_SUPPLY) ^ exponent
+    SmoothDecay { exponent: u8 },
 }
 
+/// Emission schedule (mirrors staking_hub::EmissionSchedule)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EmissionSchedule {
+    /// Reward paid per quiz before any decay is applied (in e8s)
+    pub base_reward_amount: u64,
+    /// Decay curve applied to the base reward
+    pub curve: EmissionCurve,
+    /// Floor below which the effective reward never decays (in e8s)
+    pub min_reward_amount: u64,
+}
+
+/// Payload for UpdateEmissionSchedule proposals
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UpdateEmissionSchedulePayload {
+    /// Complete replacement schedule
+    pub schedule: EmissionSchedule,
+}
+
+/// Condition a user must meet to earn a badge (mirrors staking_hub::BadgeRule)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum BadgeRule {
+    /// Lifetime number of passed quizzes (1 = "first quiz")
+    QuizzesCompleted { count: u64 },
+    /// Consecutive days with at least one passed quiz
+    StreakDays { days: u64 },
+    /// Every quiz under a content node has been passed
+    /// `unit_ids` is resolved by the staking hub and must be left empty here
+    SubtreeCompleted { root_id: String, unit_ids: Vec<String> },
+}
+
+/// Achievement badge definition (mirrors staking_hub::BadgeDefinition)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct BadgeDefinition {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub rule: BadgeRule,
+    /// One-off bonus paid when earned, clamped by token limits (in e8s)
+    pub bonus_reward: u64,
+    /// Inactive badges are no longer awarded
+    pub active: bool,
+}
+
+/// Payload for UpsertBadge proposals
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UpsertBadgePayload {
+    pub badge: BadgeDefinition,
+}
+
+/// Payload for SetRewardMultiplier proposals
+/// 10,000 BPS = 1x (removes the override)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SetRewardMultiplierPayload {
+    pub content_id: String,
+    pub multiplier_bps: u32,
+}
+
+/// Correct answer of a question that is not single-choice
+/// (mirrors learning_engine::AnswerKey)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum AnswerKey {
+    MultiSelect(Vec<u8>),
+    TrueFalse(bool),
+    Numeric { value: i64, tolerance: u64, decimals: u8 },
+    Ordering(Vec<u8>),
+    ShortText(Vec<String>),
+}
+
+/// Quiz question (mirrors learning_engine::QuizQuestion)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct QuizQuestion {
+    pub question: String,
+    pub options: Vec<String>,
+    pub answer: u8,
+    pub answer_key: Option<AnswerKey>,
+}
+
+/// Quiz (mirrors learning_engine::QuizData)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct QuizData {
+    pub questions: Vec<QuizQuestion>,
+    pub draw_count: Option<u8>,
+}
+
+/// One locale's translation of a content node (mirrors learning_engine::LocalizedContent)
+/// The learning engine checks the locale tag and quiz shape when it is applied
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LocalizedContent {
+    pub locale: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub content: Option<String>,
+    pub paraphrase: Option<String>,
+    pub quiz: Option<QuizData>,
+}
+
+/// Payload for AddTranslation proposals
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AddTranslationPayload {
+    /// ID of the translated content node
+    pub content_id: String,
+    pub translation: LocalizedContent,
+}
+
+/// Payload for RemoveTranslation proposals
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RemoveTranslationPayload {
+    /// ID of the translated content node
+    pub content_id: String,
+    pub locale: String,
+    /// Reason for removal
+    pub reason: String,
+}
 
 /// Payload for DeleteContentNode proposals
 #[derive(CandidType, Deserialize, Clone, Debug)]
@@ -214,6 +362,15 @@ pub struct Proposal {
     // Sentinel payload
     pub update_sentinel_payload: Option<UpdateSentinelPayload>,
     
+    // Economy payloads
+    pub update_emission_schedule_payload: Option<UpdateEmissionSchedulePayload>,
+    pub upsert_badge_payload: Option<UpsertBadgePayload>,
+    pub set_reward_multiplier_payload: Option<SetRewardMultiplierPayload>,
+    
+    // Translation payloads
+    pub add_translation_payload: Option<AddTranslationPayload>,
+    pub remove_translation_payload: Option<RemoveTranslationPayload>,
+    
     // Voting state
     pub votes_yes: u64,
     pub votes_no: u64,
@@ -238,7 +395,7 @@ impl Storable for Proposal {
     fn from_bytes(bytes: Cow<[u8]>) -> Self {
         Decode!(bytes.as_ref(), Self).unwrap()
     }
-    const BOUND: Bound = Bound::Bounded { max_size: 10000, is_fixed_size: false };
+    const BOUND: Bound = Bound::Bounded { max_size: MAX_PROPOSAL_BYTES, is_fixed_size: false };
 }
 
 /// Vote record for transparency
@@ -381,6 +538,39 @@ pub struct CreateUpdateTokenLimitsProposalInput {
     pub new_max_attempts: Option<u8>,
     pub new_regular_limits: Option<TokenLimits>,
     pub new_subscribed_limits: Option<TokenLimits>,
+    pub new_limit_mode: Option<LimitMode>,
+    pub external_link: Option<String>,
+}
+
+/// Input for creating an UpdateEmissionSchedule proposal
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CreateUpdateEmissionScheduleProposalInput {
+    pub title: String,
+    pub description: String,
+    /// Complete replacement schedule
+    pub schedule: EmissionSchedule,
+    pub external_link: Option<String>,
+}
+
+/// Input for creating an UpsertBadge proposal
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CreateUpsertBadgeProposalInput {
+    pub title: String,
+    pub description: String,
+    /// Badge to create, or replace if the id already exists
+    pub badge: BadgeDefinition,
+    pub external_link: Option<String>,
+}
+
+/// Input for creating a SetRewardMultiplier proposal
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CreateSetRewardMultiplierProposalInput {
+    pub title: String,
+    pub description: String,
+    /// Quiz unit whose reward is scaled
+    pub content_id: String,
+    /// 10,000 BPS = 1x
+    pub multiplier_bps: u32,
     pub external_link: Option<String>,
 }
 
@@ -396,6 +586,30 @@ pub struct CreateDeleteContentProposalInput {
     pub external_link: Option<String>,
 }
 
+/// Input for creating an AddTranslation proposal
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CreateAddTranslationProposalInput {
+    pub title: String,
+    pub description: String,
+    /// ID of the translated content node
+    pub content_id: String,
+    pub translation: LocalizedContent,
+    pub external_link: Option<String>,
+}
+
+/// Input for creating a RemoveTranslation proposal
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CreateRemoveTranslationProposalInput {
+    pub title: String,
+    pub description: String,
+    /// ID of the translated content node
+    pub content_id: String,
+    pub locale: String,
+    /// Reason for removal
+    pub reason: String,
+    pub external_link: Option<String>,
+}
+
 // ============================================================================
 // ADMIN GOVERNANCE INPUT TYPES
 // ============================================================================
@@ -466,6 +680,24 @@ pub struct InitArgs {
     pub learning_engine_id: Option<Principal>,
 }
 
+/// Inbox category (mirrors user_profile::NotificationCategory)
+#[derive(CandidType, Deserialize, Clone, Copy, Debug)]
+pub enum NotificationCategory {
+    Governance,
+    Subscription,
+    Kyc,
+    Content,
+}
+
+/// Notification routed through the staking hub (mirrors staking_hub::NotificationInput)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NotificationInput {
+    pub category: NotificationCategory,
+    pub title: String,
+    pub body: String,
+    pub link: Option<String>,
+}
+
 /// GlobalStats struct for querying staking hub
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct _GlobalStats {
@@ -59,6 +59,15 @@ async fn verify_identity(user: Principal) -> Result<VerificationTier, String> {
         .await
         .map_err(|e| format!("Shard call failed: {:?}", e))?;
 
+    // 4. Tell the user (best effort; the decision is already applied)
+    let notification = NotificationInput {
+        category: NotificationCategory::Kyc,
+        title: "Identity verification updated".to_string(),
+        body: format!("Your verification tier is now {:?}.", tier),
+        link: None,
+    };
+    let _ = ic_cdk::call::<_, (Result<u64, String>,)>(shard_id, "internal_notify", (vec![user], notification)).await;
+
     Ok(tier)
 }
 
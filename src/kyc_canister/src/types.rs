@@ -19,3 +19,21 @@ pub struct KycStatus {
 pub struct InitArgs {
     pub staking_hub_id: Principal,
 }
+
+/// Inbox category (mirrors user_profile::NotificationCategory)
+#[derive(CandidType, Deserialize, Clone, Copy, Debug)]
+pub enum NotificationCategory {
+    Governance,
+    Subscription,
+    Kyc,
+    Content,
+}
+
+/// Notification posted to a shard inbox (mirrors user_profile::NotificationInput)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NotificationInput {
+    pub category: NotificationCategory,
+    pub title: String,
+    pub body: String,
+    pub link: Option<String>,
+}
@@ -2,3 +2,63 @@
 // Constants for Learning Engine
 
 // No specific constants found in the quick scan, but good to have the file ready.
+
+/// Maximum nodes returned by get_subtree_outline (bounds query cost and reply size)
+pub const MAX_OUTLINE_NODES: usize = 5_000;
+
+/// Longest short text answer that is compared (longer answers are wrong)
+pub const MAX_TEXT_ANSWER_LEN: usize = 200;
+
+/// Prerequisites declared on one node
+pub const MAX_NODE_PREREQUISITES: usize = 20;
+
+/// Quiz units a node may require once prerequisites are resolved
+pub const MAX_PREREQUISITE_UNITS: usize = 500;
+
+/// Quiz units visited when checking the prerequisite graph for cycles
+pub const MAX_PREREQUISITE_GRAPH_UNITS: usize = 10_000;
+
+/// Units per distribute_prerequisites call when requirements change
+pub const MAX_PREREQUISITE_UPDATES_PER_PUSH: usize = 100;
+
+/// Search index: terms are lowercased alphanumeric runs of 2-32 bytes
+pub const MIN_SEARCH_TERM_LEN: usize = 2;
+pub const MAX_SEARCH_TERM_LEN: usize = 32;
+
+/// Distinct terms indexed per node (the highest scoring are kept)
+pub const MAX_SEARCH_TERMS_PER_NODE: usize = 2_000;
+
+/// Nodes with longer ids are not indexed
+pub const MAX_SEARCH_ID_LEN: usize = 200;
+
+/// Title bytes kept in the index for display
+pub const MAX_SEARCH_TITLE_LEN: usize = 200;
+
+/// Field weights of a term occurrence
+pub const SEARCH_TITLE_WEIGHT: u32 = 8;
+pub const SEARCH_DESCRIPTION_WEIGHT: u32 = 4;
+pub const SEARCH_PARAPHRASE_WEIGHT: u32 = 2;
+pub const SEARCH_CONTENT_WEIGHT: u32 = 1;
+
+/// Words per query; postings scanned per word (bounds query cost)
+pub const MAX_SEARCH_QUERY_TERMS: usize = 8;
+pub const MAX_SEARCH_POSTINGS_SCANNED: usize = 20_000;
+
+/// Hits per page
+pub const MAX_SEARCH_PAGE: u32 = 50;
+
+/// Nodes indexed per message while backfilling the search index
+pub const SEARCH_BACKFILL_BATCH: usize = 100;
+
+/// Locale of the text stored on content nodes until governance changes it
+pub const DEFAULT_FALLBACK_LOCALE: &str = "en";
+
+/// Locale tags: 2-16 bytes of letters, digits and '-'
+pub const MIN_LOCALE_LEN: usize = 2;
+pub const MAX_LOCALE_LEN: usize = 16;
+
+/// Translations per node
+pub const MAX_TRANSLATIONS_PER_NODE: usize = 30;
+
+/// Longest content id plus locale that can be translated
+pub const MAX_TRANSLATION_KEY_LEN: usize = 300;
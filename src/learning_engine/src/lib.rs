@@ -27,6 +27,14 @@ fn post_upgrade() {
     // Rebuild quiz index to ensure hashes are stable/up-to-date
     rebuild_quiz_index();
 
+    // Content loaded before full-text search existed is indexed once, in
+    // batches so a large tree does not exhaust the upgrade's instruction limit.
+    // A backfill interrupted by another upgrade resumes from its cursor.
+    let search_index_empty = SEARCH_DOCUMENTS.with(|d| d.borrow().is_empty());
+    if search_backfill_active() || (search_index_empty && start_search_backfill()) {
+        schedule_search_backfill();
+    }
+
     // Schedule job resumption after a short delay (can't spawn directly in post_upgrade)
     ic_cdk_timers::set_timer(std::time::Duration::from_secs(1), || {
         ic_cdk::spawn(async {
@@ -35,6 +43,15 @@ fn post_upgrade() {
     });
 }
 
+/// Index the next backfill batch in its own message, chaining until done
+fn schedule_search_backfill() {
+    ic_cdk_timers::set_timer(std::time::Duration::ZERO, || {
+        if backfill_search_batch() {
+            schedule_search_backfill();
+        }
+    });
+}
+
 // ============================================================================
 // CONTENT NODE MANAGEMENT
 // ============================================================================
@@ -75,16 +92,24 @@ fn add_content_nodes(nodes: Vec<ContentNode>) -> Result<u32, String> {
 }
 
 /// Get a content node by ID (public version without answers)
+/// Text and quiz are in `locale` when translated, else the fallback locale
 #[query]
-fn get_content_node(id: String) -> Option<PublicContentNode> {
+fn get_content_node(id: String, locale: Option<String>) -> Option<PublicContentNode> {
     CONTENT_NODES.with(|c| {
-        c.borrow().get(&id).map(|node| to_public_node(&node))
+        c.borrow().get(&id).map(|node| to_public_node(&node, locale.as_deref()))
     })
 }
 
-/// Get children of a content node
+/// Get the questions a learner draws from a quiz, in answer order
+/// (`user` is the learner's profile principal, which seeds the draw)
 #[query]
-fn get_children(parent_id: String) -> Vec<PublicContentNode> {
+fn get_drawn_quiz(content_id: String, user: Principal, locale: Option<String>) -> Option<DrawnQuiz> {
+    CONTENT_NODES.with(|c| c.borrow().get(&content_id)).and_then(|node| drawn_quiz(user, &node, locale.as_deref()))
+}
+
+/// Get children of a content node, localized like get_content_node
+#[query]
+fn get_children(parent_id: String, locale: Option<String>) -> Vec<PublicContentNode> {
     let child_ids = CHILDREN_INDEX.with(|idx| {
         idx.borrow().get(&parent_id).map(|c| c.0.clone()).unwrap_or_default()
     });
@@ -92,27 +117,34 @@ fn get_children(parent_id: String) -> Vec<PublicContentNode> {
     CONTENT_NODES.with(|c| {
         let nodes = c.borrow();
         let mut children: Vec<PublicContentNode> = child_ids.iter()
-            .filter_map(|id| nodes.get(id).map(|n| to_public_node(&n)))
+            .filter_map(|id| nodes.get(id).map(|n| to_public_node(&n, locale.as_deref())))
             .collect();
         children.sort_by_key(|n| n.order);
         children
     })
 }
 
-/// Get all root nodes (nodes without parents)
+/// Get all root nodes (nodes without parents), localized like get_content_node
 #[query]
-fn get_root_nodes() -> Vec<PublicContentNode> {
+fn get_root_nodes(locale: Option<String>) -> Vec<PublicContentNode> {
     CONTENT_NODES.with(|c| {
         let mut roots: Vec<PublicContentNode> = c.borrow()
             .iter()
             .filter(|(_, node)| node.parent_id.is_none())
-            .map(|(_, node)| to_public_node(&node))
+            .map(|(_, node)| to_public_node(&node, locale.as_deref()))
             .collect();
         roots.sort_by_key(|n| n.order);
         roots
     })
 }
 
+/// Search titles, descriptions, paraphrases and content, best match first
+/// Optionally limited to one display type and/or the subtree under `root_id`
+#[query]
+fn search_content(request: SearchRequest) -> SearchPage {
+    search_content_impl(request)
+}
+
 /// Delete a content node
 #[update]
 async fn delete_content_node(id: String, proposal_id: u64) -> Result<(), String> {
@@ -132,24 +164,185 @@ async fn delete_content_node(id: String, proposal_id: u64) -> Result<(), String>
 // QUIZ FUNCTIONS
 // ============================================================================
 
-/// Get quiz cache data for user profile shards
+/// Get the public view of a unit's quiz cache data (numeric ranges withheld)
 #[query]
 fn get_quiz_data(content_id: String) -> Option<QuizCacheData> {
-    QUIZ_INDEX.with(|q| q.borrow().get(&content_id))
+    QUIZ_INDEX.with(|q| q.borrow().get(&content_id)).map(with_prerequisites).map(public_quiz_view)
+}
+
+/// Get the public view of a unit's quiz cache data in a locale (the node's
+/// own quiz when that locale has no translated quiz), with the locale that
+/// served it
+#[query]
+fn get_localized_quiz_data(content_id: String, locale: String) -> Option<LocalizedQuizData> {
+    localized_quiz_data(&content_id, &locale).map(|mut data| {
+        data.cache = public_quiz_view(data.cache);
+        data
+    })
+}
+
+/// Get quiz cache data for user profile shards (hub or registered shards only)
+#[update]
+async fn get_shard_quiz_data(content_id: String) -> Option<QuizCacheData> {
+    if !is_hub_or_shard(ic_cdk::caller()).await {
+        ic_cdk::trap("Unauthorized: Caller is not a registered shard");
+    }
+    QUIZ_INDEX.with(|q| q.borrow().get(&content_id)).map(with_prerequisites)
+}
+
+/// Get a unit's quiz cache data in a locale for user profile shards (hub or
+/// registered shards only)
+#[update]
+async fn get_shard_localized_quiz_data(content_id: String, locale: String) -> Option<LocalizedQuizData> {
+    if !is_hub_or_shard(ic_cdk::caller()).await {
+        ic_cdk::trap("Unauthorized: Caller is not a registered shard");
+    }
+    localized_quiz_data(&content_id, &locale)
 }
 
 /// Get all quiz cache data (for full shard sync)
+/// Numeric ranges are only included when the staking hub asks
 #[query]
 fn get_all_quiz_cache_data() -> Vec<(String, QuizCacheData)> {
-    QUIZ_INDEX.with(|q| {
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    let for_hub = staking_hub_id != Principal::anonymous() && ic_cdk::caller() == staking_hub_id;
+    let caches: Vec<(String, QuizCacheData)> = QUIZ_INDEX.with(|q| {
         q.borrow().iter().collect()
-    })
+    });
+    caches.into_iter()
+        .map(|(id, cache)| {
+            let cache = with_prerequisites(cache);
+            (id, if for_hub { cache } else { public_quiz_view(cache) })
+        })
+        .collect()
+}
+
+/// Get the IDs of all quiz units under a content node (including the node itself)
+/// Used to resolve "complete a whole subtree" achievements
+#[query]
+fn get_subtree_quiz_ids(root_id: String) -> Vec<String> {
+    collect_subtree_quiz_ids(&root_id)
+}
+
+/// Get the tree metadata of a content subtree (including the root)
+/// Used by user_profile shards to compute course progress
+#[query]
+fn get_subtree_outline(root_id: String) -> Result<Vec<ContentOutlineNode>, String> {
+    collect_subtree_outline(&root_id)
+}
+
+/// Which nodes under `root_id` a learner can attempt, given the quiz units
+/// they have passed (user_profile shards supply these via get_unlocked_nodes)
+#[query]
+fn get_unlocked_nodes(root_id: String, completed: Vec<String>) -> Result<Vec<NodeUnlockStatus>, String> {
+    collect_unlock_status(&root_id, &completed)
 }
 
 /// Verify quiz answers (called by user_profile shards or directly)
+/// Shards pass the learner whose draw the answers follow; otherwise the caller's
+/// Answers to a translated quiz are checked against that locale's quiz
+#[update]
+fn verify_quiz(content_id: String, answers: Vec<u8>, user: Option<Principal>, locale: Option<String>) -> (bool, u64, u64) {
+    verify_quiz_impl(content_id, answers, user.unwrap_or_else(ic_cdk::caller), locale)
+}
+
+// ============================================================================
+// LOCALIZATION
+// ============================================================================
+
+/// Add or update a translation of a content node (governance or admin)
+#[update]
+fn add_translation(content_id: String, translation: LocalizedContent, proposal_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let gov_id = GOVERNANCE_CANISTER_ID.with(|id| *id.borrow().get());
+    
+    // Check if authorized (governance or controller)
+    let is_gov = gov_id != Principal::anonymous() && caller == gov_id;
+    if !is_gov && !ic_cdk::api::is_controller(&caller) {
+         return Err("Unauthorized".to_string());
+    }
+
+    let node = CONTENT_NODES.with(|c| c.borrow().get(&content_id))
+        .ok_or("Node not found")?;
+    let translation = validate_translation(&node, translation)?;
+    check_translation_capacity(&content_id, std::slice::from_ref(&translation))?;
+    add_translation_internal(&content_id, translation, Some(proposal_id))
+}
+
+/// Delete a translation of a content node (governance or admin)
 #[update]
-fn verify_quiz(content_id: String, answers: Vec<u8>) -> (bool, u64, u64) {
-    verify_quiz_impl(content_id, answers)
+fn delete_translation(content_id: String, locale: String, proposal_id: u64) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let gov_id = GOVERNANCE_CANISTER_ID.with(|id| *id.borrow().get());
+    
+    // Check if authorized (governance or controller)
+    let is_gov = gov_id != Principal::anonymous() && caller == gov_id;
+    if !is_gov && !ic_cdk::api::is_controller(&caller) {
+         return Err("Unauthorized".to_string());
+    }
+
+    delete_translation_impl(content_id, locale, proposal_id)
+}
+
+/// Get every translation of a content node (with answers; as authored)
+#[query]
+fn get_translations(content_id: String) -> Vec<Translation> {
+    node_locales(&content_id).into_iter()
+        .filter_map(|locale| TRANSLATIONS.with(|t| t.borrow().get(&TranslationKey { content_id: content_id.clone(), locale })))
+        .collect()
+}
+
+/// Get version history of one translation
+#[query]
+fn get_translation_history(content_id: String, locale: String) -> Vec<(u64, TranslationSnapshot)> {
+    let Ok(locale) = normalize_locale(&locale) else {
+        return Vec::new();
+    };
+    let start = TranslationVersionKey { content_id: content_id.clone(), locale: locale.clone(), version: 0 };
+    TRANSLATION_HISTORY.with(|h| {
+        h.borrow()
+            .range(start..)
+            .take_while(|(key, _)| key.content_id == content_id && key.locale == locale)
+            .map(|(key, snapshot)| (key.version, snapshot))
+            .collect()
+    })
+}
+
+/// Get a translation at a specific version
+#[query]
+fn get_translation_at_version(content_id: String, locale: String, version: u64) -> Option<LocalizedContent> {
+    let locale = normalize_locale(&locale).ok()?;
+    TRANSLATION_HISTORY.with(|h| {
+        h.borrow()
+            .get(&TranslationVersionKey { content_id, locale, version })
+            .map(|snapshot| snapshot.content)
+    })
+}
+
+/// Set the locale the nodes' own text is written in (governance or admin)
+#[update]
+fn set_fallback_locale(locale: String) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let gov_id = GOVERNANCE_CANISTER_ID.with(|id| *id.borrow().get());
+    
+    // Check if authorized (governance or controller)
+    let is_gov = gov_id != Principal::anonymous() && caller == gov_id;
+    if !is_gov && !ic_cdk::api::is_controller(&caller) {
+         return Err("Unauthorized".to_string());
+    }
+
+    let locale = normalize_locale(&locale)?;
+    let translated = TRANSLATIONS.with(|t| t.borrow().iter().any(|(key, _)| key.locale == locale));
+    if translated {
+        return Err(format!("Some nodes have a '{}' translation; delete those first", locale));
+    }
+    FALLBACK_LOCALE.with(|l| l.borrow_mut().set(locale).map(|_| ()).map_err(|e| format!("{:?}", e)))
+}
+
+/// Get the locale the nodes' own text is written in
+#[query]
+fn get_fallback_locale() -> String {
+    fallback_locale()
 }
 
 // ============================================================================
@@ -1,6 +1,8 @@
+use std::collections::{HashMap, HashSet};
 use candid::Principal;
 use crate::types::*;
 use crate::state::*;
+use crate::constants::*;
 
 // ============================================================================
 // HELPER FUNCTIONS
@@ -23,6 +25,1011 @@ pub fn stable_hash(data: &[u8]) -> [u8; 32] {
     res
 }
 
+// ============================================================================
+// QUESTION TYPES
+// ============================================================================
+
+/// Canonical form of a short text answer: lowercase, punctuation dropped,
+/// runs of whitespace collapsed to one space
+pub fn normalize_text_answer(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn sorted_indexes(indexes: &[u8]) -> Vec<u8> {
+    let mut sorted = indexes.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    sorted
+}
+
+/// Commitment a shard verifies answers to this question against
+pub fn answer_commitment(question: &QuizQuestion) -> AnswerCommitment {
+    match &question.answer_key {
+        None => AnswerCommitment::SingleChoice(stable_hash(&question.answer.to_le_bytes())),
+        Some(AnswerKey::MultiSelect(indexes)) => AnswerCommitment::MultiSelect(stable_hash(&sorted_indexes(indexes))),
+        Some(AnswerKey::TrueFalse(value)) => AnswerCommitment::TrueFalse(stable_hash(&[*value as u8])),
+        Some(AnswerKey::Numeric { value, tolerance, .. }) => {
+            let tolerance = (*tolerance).min(i64::MAX as u64) as i64;
+            AnswerCommitment::Numeric {
+                min: value.saturating_sub(tolerance),
+                max: value.saturating_add(tolerance),
+            }
+        }
+        Some(AnswerKey::Ordering(order)) => AnswerCommitment::Ordering(stable_hash(order)),
+        Some(AnswerKey::ShortText(accepted)) => AnswerCommitment::ShortText(
+            accepted.iter().map(|a| stable_hash(normalize_text_answer(a).as_bytes())).collect()
+        ),
+    }
+}
+
+/// Public description of how a question is answered
+pub fn question_kind(question: &QuizQuestion) -> QuestionKind {
+    match &question.answer_key {
+        None => QuestionKind::SingleChoice,
+        Some(AnswerKey::MultiSelect(_)) => QuestionKind::MultiSelect,
+        Some(AnswerKey::TrueFalse(_)) => QuestionKind::TrueFalse,
+        Some(AnswerKey::Numeric { decimals, .. }) => QuestionKind::Numeric { decimals: *decimals },
+        Some(AnswerKey::Ordering(_)) => QuestionKind::Ordering,
+        Some(AnswerKey::ShortText(_)) => QuestionKind::ShortText,
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Pool indexes of the questions a learner gets, in answer order
+///
+/// The seed is derived from the learner's profile principal and the content
+/// id only, so a learner keeps their draw across content updates. Shards
+/// derive draws the same way (user_profile::service::draw_questions).
+pub fn draw_questions(user: Principal, content_id: &str, pool_size: usize, draw_count: Option<u8>) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..pool_size).collect();
+    let Some(draw_count) = draw_count else {
+        return order;
+    };
+    let count = (draw_count as usize).min(pool_size);
+
+    let mut seed_bytes = user.as_slice().to_vec();
+    seed_bytes.push(0);
+    seed_bytes.extend_from_slice(content_id.as_bytes());
+    let hash = stable_hash(&seed_bytes);
+    let mut state = u64::from_le_bytes(hash[0..8].try_into().unwrap());
+
+    // Partial Fisher-Yates: only the drawn prefix is shuffled
+    for i in 0..count {
+        let j = i + (splitmix64(&mut state) % (pool_size - i) as u64) as usize;
+        order.swap(i, j);
+    }
+    order.truncate(count);
+    order
+}
+
+/// A learner's questions (without answers), in the order they answer them
+pub fn drawn_quiz(user: Principal, node: &ContentNode, locale: Option<&str>) -> Option<DrawnQuiz> {
+    let (node, _) = localized_node(node, locale);
+    let quiz = node.quiz.as_ref()?;
+    let draw = draw_questions(user, &node.id, quiz.questions.len(), quiz.draw_count);
+    Some(DrawnQuiz {
+        content_id: node.id.clone(),
+        questions: draw.iter().map(|i| {
+            let question = &quiz.questions[*i];
+            PublicQuizQuestion {
+                question: question.question.clone(),
+                options: question.options.clone(),
+                kind: question_kind(question),
+            }
+        }).collect(),
+        pool_indexes: draw.iter().map(|i| *i as u8).collect(),
+    })
+}
+
+/// Quiz cache entry for QUIZ_INDEX (commitments only when a question needs them)
+pub fn build_quiz_cache(content_id: &str, quiz: &QuizData, version: u64) -> QuizCacheData {
+    let all_single_choice = quiz.questions.iter().all(|q| q.answer_key.is_none());
+    QuizCacheData {
+        content_id: content_id.to_string(),
+        answer_hashes: quiz.questions.iter()
+            .map(|q| stable_hash(&q.answer.to_le_bytes()))
+            .collect(),
+        question_count: quiz.questions.len() as u8,
+        version,
+        commitments: if all_single_choice {
+            None
+        } else {
+            Some(quiz.questions.iter().map(answer_commitment).collect())
+        },
+        draw_count: quiz.draw_count,
+        prerequisites: None,
+    }
+}
+
+/// Decode a submitted answer payload: Candid-encoded vec of QuizAnswer, or
+/// the legacy encoding of one option index per byte
+pub fn decode_quiz_answers(payload: &[u8]) -> Result<Vec<QuizAnswer>, String> {
+    if payload.starts_with(b"DIDL") {
+        return candid::decode_one(payload).map_err(|e| format!("Invalid answer encoding: {}", e));
+    }
+    Ok(payload.iter().map(|b| QuizAnswer::SingleChoice(*b)).collect())
+}
+
+/// Whether an answer satisfies a commitment (a mismatched answer type is wrong)
+pub fn answer_matches(commitment: &AnswerCommitment, answer: &QuizAnswer) -> bool {
+    match (commitment, answer) {
+        (AnswerCommitment::SingleChoice(hash), QuizAnswer::SingleChoice(index)) => stable_hash(&index.to_le_bytes()) == *hash,
+        (AnswerCommitment::MultiSelect(hash), QuizAnswer::MultiSelect(indexes)) => stable_hash(&sorted_indexes(indexes)) == *hash,
+        (AnswerCommitment::TrueFalse(hash), QuizAnswer::TrueFalse(value)) => stable_hash(&[*value as u8]) == *hash,
+        // The legacy byte encoding answers true/false with 1/0
+        (AnswerCommitment::TrueFalse(hash), QuizAnswer::SingleChoice(byte)) => *byte <= 1 && stable_hash(&[*byte]) == *hash,
+        (AnswerCommitment::Numeric { min, max }, QuizAnswer::Numeric(value)) => min <= value && value <= max,
+        (AnswerCommitment::Ordering(hash), QuizAnswer::Ordering(order)) => stable_hash(order) == *hash,
+        (AnswerCommitment::ShortText(hashes), QuizAnswer::ShortText(text)) => {
+            text.len() <= MAX_TEXT_ANSWER_LEN && hashes.contains(&stable_hash(normalize_text_answer(text).as_bytes()))
+        }
+        _ => false,
+    }
+}
+
+/// Commitments of a cached quiz, one per question
+pub fn quiz_commitments(quiz: &QuizCacheData) -> Vec<AnswerCommitment> {
+    match &quiz.commitments {
+        Some(commitments) => commitments.clone(),
+        None => quiz.answer_hashes.iter().map(|h| AnswerCommitment::SingleChoice(*h)).collect(),
+    }
+}
+
+/// A quiz cache entry as served to anyone but the hub and shards: numeric
+/// ranges would give the answers away, so they are withheld
+pub fn public_quiz_view(mut cache: QuizCacheData) -> QuizCacheData {
+    if let Some(commitments) = cache.commitments.as_mut() {
+        for commitment in commitments.iter_mut() {
+            if matches!(commitment, AnswerCommitment::Numeric { .. }) {
+                *commitment = AnswerCommitment::Withheld;
+            }
+        }
+    }
+    cache
+}
+
+/// Whether `caller` is the staking hub or one of its registered shards
+///
+/// Unknown callers are checked with the hub once; confirmed shards are
+/// remembered until the next upgrade.
+pub async fn is_hub_or_shard(caller: Principal) -> bool {
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    if staking_hub_id == Principal::anonymous() {
+        return false;
+    }
+    if caller == staking_hub_id || VERIFIED_SHARDS.with(|s| s.borrow().contains(&caller)) {
+        return true;
+    }
+
+    let result: Result<(bool,), _> = ic_cdk::call(staking_hub_id, "is_registered_shard", (caller,)).await;
+    let registered = matches!(result, Ok((true,)));
+    if registered {
+        VERIFIED_SHARDS.with(|s| s.borrow_mut().insert(caller));
+    }
+    registered
+}
+
+// ============================================================================
+// PREREQUISITES
+// ============================================================================
+
+/// Read-only view of the content tree, optionally with one node replaced by
+/// the version about to be stored (so it can be validated first)
+struct ContentGraph<'a> {
+    pending: Option<&'a ContentNode>,
+}
+
+impl ContentGraph<'_> {
+    fn node(&self, id: &str) -> Option<ContentNode> {
+        match self.pending {
+            Some(node) if node.id == id => Some(node.clone()),
+            _ => CONTENT_NODES.with(|c| c.borrow().get(&id.to_string())),
+        }
+    }
+
+    fn has_quiz(&self, id: &str) -> bool {
+        match self.pending {
+            Some(node) if node.id == id => node.quiz.is_some(),
+            _ => QUIZ_INDEX.with(|q| q.borrow().contains_key(&id.to_string())),
+        }
+    }
+
+    fn children(&self, id: &str) -> Vec<String> {
+        let mut children = CHILDREN_INDEX.with(|idx| idx.borrow().get(&id.to_string()))
+            .map(|c| c.0)
+            .unwrap_or_default();
+        if let Some(node) = self.pending {
+            if node.parent_id.as_deref() == Some(id) && !children.contains(&node.id) {
+                children.push(node.id.clone());
+            }
+        }
+        children
+    }
+
+    /// Quiz units in the subtree rooted at `root_id`
+    fn subtree_quiz_ids(&self, root_id: &str, include_root: bool) -> Vec<String> {
+        let mut quiz_ids = Vec::new();
+        let mut seen = HashSet::new();
+        let mut stack = vec![root_id.to_string()];
+
+        while let Some(id) = stack.pop() {
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            if (include_root || id != root_id) && self.has_quiz(&id) {
+                quiz_ids.push(id.clone());
+            }
+            stack.extend(self.children(&id).into_iter().rev());
+        }
+        quiz_ids
+    }
+
+    /// Quiz units a prerequisite stands for (a missing node counts as a unit;
+    /// loads reject such references, so only older nodes can hold one)
+    fn resolve(&self, prerequisite: &Prerequisite) -> Vec<String> {
+        match prerequisite {
+            Prerequisite::Node(id) if self.has_quiz(id) || self.node(id).is_none() => vec![id.clone()],
+            Prerequisite::Node(id) => self.subtree_quiz_ids(id, true),
+            Prerequisite::AllChildrenOf(id) => self.subtree_quiz_ids(id, false),
+        }
+    }
+
+    /// Quiz units required before `id`: its own prerequisites and its ancestors'
+    fn effective_prerequisites(&self, id: &str) -> Vec<String> {
+        let mut units = Vec::new();
+        let mut seen = HashSet::new();
+        let mut current = Some(id.to_string());
+
+        while let Some(node_id) = current {
+            if !seen.insert(node_id.clone()) {
+                break;
+            }
+            let Some(node) = self.node(&node_id) else {
+                break;
+            };
+            for prerequisite in node.prerequisites.iter().flatten() {
+                units.extend(self.resolve(prerequisite));
+            }
+            current = node.parent_id;
+        }
+
+        units.sort();
+        units.dedup();
+        units
+    }
+}
+
+/// Quiz units required before `content_id`, own and inherited prerequisites combined
+pub fn prerequisite_units(content_id: &str) -> Vec<String> {
+    ContentGraph { pending: None }.effective_prerequisites(content_id)
+}
+
+/// Attach resolved prerequisites to a quiz cache entry before serving it
+pub fn with_prerequisites(mut cache: QuizCacheData) -> QuizCacheData {
+    let units = prerequisite_units(&cache.content_id);
+    cache.prerequisites = if units.is_empty() { None } else { Some(units) };
+    cache
+}
+
+/// Check a node's prerequisites before it is stored
+///
+/// The graph was acyclic before, so a new cycle must pass through a quiz unit
+/// whose requirements change: one in the node's subtree. Those are walked
+/// depth-first with the node in place.
+pub fn validate_prerequisites(node: &ContentNode) -> Result<(), String> {
+    let own = node.prerequisites.as_deref().unwrap_or_default();
+    if own.len() > MAX_NODE_PREREQUISITES {
+        return Err(format!("A node can declare at most {} prerequisites", MAX_NODE_PREREQUISITES));
+    }
+    for prerequisite in own {
+        match prerequisite {
+            Prerequisite::Node(id) | Prerequisite::AllChildrenOf(id) if id.is_empty() => {
+                return Err("Prerequisite node id cannot be empty".to_string());
+            }
+            Prerequisite::Node(id) if *id == node.id => {
+                return Err(format!("Node '{}' cannot require itself", node.id));
+            }
+            Prerequisite::Node(id) | Prerequisite::AllChildrenOf(id)
+                if *id != node.id && !CONTENT_NODES.with(|c| c.borrow().contains_key(id)) => {
+                return Err(format!("Prerequisite '{}' is not loaded; load it before the nodes that require it", id));
+            }
+            _ => {}
+        }
+    }
+
+    let graph = ContentGraph { pending: Some(node) };
+    let mut done: HashSet<String> = HashSet::new();
+    let mut on_path: HashSet<String> = HashSet::new();
+
+    for start in graph.subtree_quiz_ids(&node.id, true) {
+        if done.contains(&start) {
+            continue;
+        }
+        let edges = graph.effective_prerequisites(&start);
+        if edges.len() > MAX_PREREQUISITE_UNITS {
+            return Err(format!("'{}' would require {} quiz units; at most {} are supported", start, edges.len(), MAX_PREREQUISITE_UNITS));
+        }
+        on_path.insert(start.clone());
+        let mut stack = vec![(start, edges)];
+
+        while let Some((_, edges)) = stack.last_mut() {
+            match edges.pop() {
+                Some(next) => {
+                    if on_path.contains(&next) {
+                        return Err(format!("Prerequisites of '{}' would form a cycle through '{}'", node.id, next));
+                    }
+                    if done.contains(&next) {
+                        continue;
+                    }
+                    if done.len() + on_path.len() >= MAX_PREREQUISITE_GRAPH_UNITS {
+                        return Err("Prerequisite graph is too large to validate".to_string());
+                    }
+                    let next_edges = graph.effective_prerequisites(&next);
+                    on_path.insert(next.clone());
+                    stack.push((next, next_edges));
+                }
+                None => {
+                    if let Some((id, _)) = stack.pop() {
+                        on_path.remove(&id);
+                        done.insert(id);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Record which nodes `node_id` requires, replacing its previous entries
+pub fn index_prerequisites(node_id: &str, previous: &[Prerequisite], current: &[Prerequisite]) {
+    PREREQUISITE_DEPENDENTS.with(|d| {
+        let mut index = d.borrow_mut();
+        for prerequisite in previous {
+            let target = prerequisite.target().to_string();
+            if let Some(mut dependents) = index.get(&target) {
+                dependents.0.retain(|id| id != node_id);
+                if dependents.0.is_empty() {
+                    index.remove(&target);
+                } else {
+                    index.insert(target, dependents);
+                }
+            }
+        }
+        for prerequisite in current {
+            let target = prerequisite.target().to_string();
+            let mut dependents = index.get(&target).unwrap_or_default();
+            if !dependents.0.iter().any(|id| id == node_id) {
+                dependents.0.push(node_id.to_string());
+                index.insert(target, dependents);
+            }
+        }
+    });
+}
+
+/// Nodes (other than `node_id` itself) that declare a prerequisite on it
+pub fn prerequisite_dependents(node_id: &str) -> Vec<String> {
+    PREREQUISITE_DEPENDENTS.with(|d| d.borrow().get(&node_id.to_string()))
+        .map(|d| d.0)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|id| id != node_id)
+        .collect()
+}
+
+/// Quiz units whose requirements may change when `node_id` is stored or
+/// removed, with their current requirements
+///
+/// Those are the units under the node (they inherit its prerequisites) and
+/// under any node requiring it or one of its ancestors (their subtree sets
+/// change). `parent_ids` are the node's parent before and after the change.
+pub fn prerequisite_state(node_id: &str, parent_ids: &[Option<String>]) -> Vec<(String, Vec<String>)> {
+    let graph = ContentGraph { pending: None };
+    let mut targets: HashSet<String> = HashSet::new();
+    targets.insert(node_id.to_string());
+    for parent_id in parent_ids.iter().flatten() {
+        let mut current = Some(parent_id.clone());
+        while let Some(id) = current {
+            if !targets.insert(id.clone()) {
+                break;
+            }
+            current = graph.node(&id).and_then(|n| n.parent_id);
+        }
+    }
+
+    let mut roots: Vec<String> = vec![node_id.to_string()];
+    for target in &targets {
+        roots.extend(prerequisite_dependents(target));
+    }
+    let mut units: Vec<String> = roots.iter()
+        .flat_map(|root| graph.subtree_quiz_ids(root, true))
+        .collect();
+    units.sort();
+    units.dedup();
+
+    units.into_iter()
+        .map(|unit| {
+            let required = graph.effective_prerequisites(&unit);
+            (unit, required)
+        })
+        .collect()
+}
+
+/// Push the units whose requirements differ from `previous` (taken with
+/// prerequisite_state before the change) to every shard via the Hub
+pub fn push_changed_prerequisites(previous: Vec<(String, Vec<String>)>) {
+    let changed: Vec<(String, Vec<String>)> = previous.into_iter()
+        .filter(|(unit, _)| QUIZ_INDEX.with(|q| q.borrow().contains_key(unit)))
+        .filter_map(|(unit, before)| {
+            let after = prerequisite_units(&unit);
+            (after != before).then_some((unit, after))
+        })
+        .collect();
+
+    let hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    if changed.is_empty() || hub_id == Principal::anonymous() {
+        return;
+    }
+    for chunk in changed.chunks(MAX_PREREQUISITE_UPDATES_PER_PUSH) {
+        let updates = chunk.to_vec();
+        ic_cdk::spawn(async move {
+            let _ = ic_cdk::call::<_, (Result<u64, String>,)>(
+                hub_id,
+                "distribute_prerequisites",
+                (updates,)
+            ).await;
+        });
+    }
+}
+
+/// Unlock status of every node under `root_id` (parents first) for a learner
+/// who has passed `completed`
+pub fn collect_unlock_status(root_id: &str, completed: &[String]) -> Result<Vec<NodeUnlockStatus>, String> {
+    let outline = collect_subtree_outline(root_id)?;
+    let completed: HashSet<&String> = completed.iter().collect();
+
+    Ok(outline.into_iter().map(|node| {
+        let missing: Vec<String> = prerequisite_units(&node.id).into_iter()
+            .filter(|unit| !completed.contains(unit))
+            .collect();
+        NodeUnlockStatus {
+            id: node.id,
+            unlocked: missing.is_empty(),
+            missing,
+        }
+    }).collect())
+}
+
+// ============================================================================
+// SEARCH INDEX
+// ============================================================================
+
+/// Split text into lowercase alphanumeric terms, skipping runs that are too
+/// short or too long to be useful
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(|word| word.to_lowercase())
+        .filter(|word| (MIN_SEARCH_TERM_LEN..=MAX_SEARCH_TERM_LEN).contains(&word.len()))
+}
+
+/// Cut a string to at most `max_len` bytes on a character boundary
+fn truncate_to(text: &str, max_len: usize) -> String {
+    let mut end = text.len().min(max_len);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text[..end].to_string()
+}
+
+/// Weighted term frequencies of a node's searchable fields, best first
+fn node_term_scores(node: &ContentNode) -> Vec<(String, u32)> {
+    let fields = [
+        (Some(&node.title), SEARCH_TITLE_WEIGHT),
+        (node.description.as_ref(), SEARCH_DESCRIPTION_WEIGHT),
+        (node.paraphrase.as_ref(), SEARCH_PARAPHRASE_WEIGHT),
+        (node.content.as_ref(), SEARCH_CONTENT_WEIGHT),
+    ];
+    let mut scores: HashMap<String, u32> = HashMap::new();
+    for (text, weight) in fields {
+        for term in text.into_iter().flat_map(|t| tokenize(t)) {
+            let score = scores.entry(term).or_default();
+            *score = score.saturating_add(weight);
+        }
+    }
+
+    let mut scores: Vec<(String, u32)> = scores.into_iter().collect();
+    scores.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    scores.truncate(MAX_SEARCH_TERMS_PER_NODE);
+    scores
+}
+
+/// Remove a node from the search index
+pub fn unindex_content_node(id: &str) {
+    let id = id.to_string();
+    SEARCH_DOCUMENTS.with(|d| d.borrow_mut().remove(&id));
+    let Some(terms) = SEARCH_TERMS.with(|t| t.borrow_mut().remove(&id)) else {
+        return;
+    };
+    SEARCH_POSTINGS.with(|p| {
+        let mut postings = p.borrow_mut();
+        for term in terms.0 {
+            postings.remove(&SearchPostingKey { term, content_id: id.clone() });
+        }
+    });
+}
+
+/// Index a node's title, description, paraphrase and content, replacing
+/// any earlier entry (nodes with very long ids are left out)
+pub fn index_content_node(node: &ContentNode) {
+    unindex_content_node(&node.id);
+    if node.id.is_empty() || node.id.len() > MAX_SEARCH_ID_LEN {
+        return;
+    }
+
+    let scores = node_term_scores(node);
+    SEARCH_POSTINGS.with(|p| {
+        let mut postings = p.borrow_mut();
+        for (term, score) in &scores {
+            postings.insert(SearchPostingKey { term: term.clone(), content_id: node.id.clone() }, *score);
+        }
+    });
+    SEARCH_TERMS.with(|t| {
+        t.borrow_mut().insert(node.id.clone(), SearchTerms(scores.into_iter().map(|(term, _)| term).collect()));
+    });
+    SEARCH_DOCUMENTS.with(|d| d.borrow_mut().insert(node.id.clone(), SearchDocument {
+        title: truncate_to(&node.title, MAX_SEARCH_TITLE_LEN),
+        display_type: truncate_to(&node.display_type, MAX_SEARCH_TITLE_LEN),
+        parent_id: node.parent_id.clone().filter(|p| p.len() <= MAX_SEARCH_ID_LEN),
+    }));
+}
+
+/// Start indexing every node in batches (used once, after the upgrade that
+/// adds the index). Returns false when there is nothing to index.
+pub fn start_search_backfill() -> bool {
+    if CONTENT_NODES.with(|c| c.borrow().is_empty()) {
+        return false;
+    }
+    SEARCH_BACKFILL.with(|b| {
+        b.borrow_mut().set(SearchBackfill { active: true, last_indexed: None })
+            .expect("Failed to start search backfill")
+    });
+    true
+}
+
+/// Whether the search index backfill still has nodes to index
+pub fn search_backfill_active() -> bool {
+    SEARCH_BACKFILL.with(|b| b.borrow().get().active)
+}
+
+/// Index the next batch of nodes after the backfill cursor. Returns whether
+/// more nodes remain.
+pub fn backfill_search_batch() -> bool {
+    let state = SEARCH_BACKFILL.with(|b| b.borrow().get().clone());
+    if !state.active {
+        return false;
+    }
+
+    let batch: Vec<(String, ContentNode)> = CONTENT_NODES.with(|c| {
+        let nodes = c.borrow();
+        match state.last_indexed {
+            Some(last) => nodes.range((std::ops::Bound::Excluded(last), std::ops::Bound::Unbounded))
+                .take(SEARCH_BACKFILL_BATCH)
+                .collect(),
+            None => nodes.iter().take(SEARCH_BACKFILL_BATCH).collect(),
+        }
+    });
+    for (_, node) in &batch {
+        index_content_node(node);
+    }
+
+    let more = batch.len() == SEARCH_BACKFILL_BATCH;
+    let next = SearchBackfill {
+        active: more,
+        last_indexed: if more { batch.last().map(|(id, _)| id.clone()) } else { None },
+    };
+    SEARCH_BACKFILL.with(|b| b.borrow_mut().set(next).expect("Failed to update search backfill"));
+    more
+}
+
+/// Whether `id` is `root_id` or below it (memoized per search)
+fn in_subtree(id: &str, root_id: &str, memo: &mut HashMap<String, bool>) -> bool {
+    let mut path = Vec::new();
+    let mut current = Some(id.to_string());
+    let result = loop {
+        let Some(node_id) = current else {
+            break false;
+        };
+        if node_id == root_id {
+            break true;
+        }
+        if let Some(known) = memo.get(&node_id) {
+            break *known;
+        }
+        if path.contains(&node_id) {
+            break false;
+        }
+        current = SEARCH_DOCUMENTS.with(|d| d.borrow().get(&node_id)).and_then(|doc| doc.parent_id);
+        path.push(node_id);
+    };
+    for node_id in path {
+        memo.insert(node_id, result);
+    }
+    result
+}
+
+/// Ranked full-text search
+///
+/// Every query word must match a node; a word matches indexed terms it is a
+/// prefix of, exact terms counting double. A node's score is the sum over
+/// query words of its best matching term's weighted frequency. Each word
+/// scans at most MAX_SEARCH_POSTINGS_SCANNED postings; when a word has more,
+/// the page is marked truncated and no total is reported.
+pub fn search_content_impl(request: SearchRequest) -> SearchPage {
+    let mut words: Vec<String> = tokenize(&request.keywords).collect();
+    words.sort();
+    words.dedup();
+    words.truncate(MAX_SEARCH_QUERY_TERMS);
+    if words.is_empty() {
+        return SearchPage { hits: Vec::new(), total: Some(0), truncated: false };
+    }
+
+    let mut truncated = search_backfill_active();
+
+    let mut scores: Option<HashMap<String, u32>> = None;
+    for word in &words {
+        let mut matches: HashMap<String, u32> = HashMap::new();
+        SEARCH_POSTINGS.with(|p| {
+            let start = SearchPostingKey { term: word.clone(), content_id: String::new() };
+            let postings_map = p.borrow();
+            let mut postings = postings_map.range(start..)
+                .take_while(|(k, _)| k.term.starts_with(word.as_str()));
+            for (key, score) in postings.by_ref().take(MAX_SEARCH_POSTINGS_SCANNED) {
+                let score = if key.term == *word { score.saturating_mul(2) } else { score };
+                let best = matches.entry(key.content_id).or_default();
+                *best = (*best).max(score);
+            }
+            if postings.next().is_some() {
+                truncated = true;
+            }
+        });
+        scores = Some(match scores {
+            None => matches,
+            Some(previous) => previous.into_iter()
+                .filter_map(|(id, score)| matches.get(&id).map(|m| (id, score.saturating_add(*m))))
+                .collect(),
+        });
+    }
+
+    let mut memo = HashMap::new();
+    let mut hits: Vec<SearchHit> = scores.unwrap_or_default().into_iter()
+        .filter_map(|(id, score)| {
+            let doc = SEARCH_DOCUMENTS.with(|d| d.borrow().get(&id))?;
+            if request.display_type.as_ref().is_some_and(|t| !t.eq_ignore_ascii_case(&doc.display_type)) {
+                return None;
+            }
+            if request.root_id.as_ref().is_some_and(|root| !in_subtree(&id, root, &mut memo)) {
+                return None;
+            }
+            Some(SearchHit {
+                id,
+                title: doc.title,
+                display_type: doc.display_type,
+                parent_id: doc.parent_id,
+                score,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+
+    let total = (!truncated).then_some(hits.len() as u64);
+    let hits = hits.into_iter()
+        .skip(request.offset as usize)
+        .take(request.limit.min(MAX_SEARCH_PAGE) as usize)
+        .collect();
+    SearchPage { hits, total, truncated }
+}
+
+// ============================================================================
+// LOCALIZATION
+// ============================================================================
+
+/// Locale of the text stored on the nodes themselves
+pub fn fallback_locale() -> String {
+    FALLBACK_LOCALE.with(|l| l.borrow().get().clone())
+}
+
+/// Canonical form of a locale tag: lowercase, '-' separated ("pt_BR" -> "pt-br")
+pub fn normalize_locale(locale: &str) -> Result<String, String> {
+    let locale = locale.trim().to_lowercase().replace('_', "-");
+    if locale.len() < MIN_LOCALE_LEN || locale.len() > MAX_LOCALE_LEN {
+        return Err(format!("Locale must be {}-{} characters", MIN_LOCALE_LEN, MAX_LOCALE_LEN));
+    }
+    if !locale.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') || locale.starts_with('-') || locale.ends_with('-') {
+        return Err(format!("Invalid locale '{}'", locale));
+    }
+    Ok(locale)
+}
+
+/// The first of the requested locale and its language ("pt-br" -> "pt")
+/// that `available` accepts; None means the fallback
+fn resolve_in(requested: Option<&str>, available: impl Fn(&str) -> bool) -> Option<String> {
+    let requested = normalize_locale(requested?).ok()?;
+    let fallback = fallback_locale();
+    let mut chain = vec![requested.clone()];
+    if let Some((language, _)) = requested.split_once('-') {
+        chain.push(language.to_string());
+    }
+    chain.into_iter()
+        .take_while(|locale| *locale != fallback)
+        .find(|locale| available(locale))
+}
+
+/// The stored translation locale that serves a request's text
+pub fn resolve_locale(content_id: &str, requested: Option<&str>) -> Option<String> {
+    resolve_in(requested, |locale| TRANSLATIONS.with(|t| t.borrow().contains_key(&TranslationKey {
+        content_id: content_id.to_string(),
+        locale: locale.to_string(),
+    })))
+}
+
+/// The translated quiz locale that serves a request's quiz (a translation
+/// without its own quiz does not count, so "pt-br" text may pair with a "pt" quiz)
+pub fn resolve_quiz_locale(content_id: &str, requested: Option<&str>) -> Option<String> {
+    resolve_in(requested, |locale| LOCALIZED_QUIZ_INDEX.with(|q| q.borrow().contains_key(&TranslationKey {
+        content_id: content_id.to_string(),
+        locale: locale.to_string(),
+    })))
+}
+
+/// Locales with a translated quiz of a unit
+pub fn quiz_locales(content_id: &str) -> Vec<String> {
+    let start = TranslationKey { content_id: content_id.to_string(), locale: String::new() };
+    LOCALIZED_QUIZ_INDEX.with(|q| {
+        q.borrow().range(start..)
+            .take_while(|(key, _)| key.content_id == content_id)
+            .map(|(key, _)| key.locale)
+            .collect()
+    })
+}
+
+/// Locales with a translation of a node
+pub fn node_locales(content_id: &str) -> Vec<String> {
+    let start = TranslationKey { content_id: content_id.to_string(), locale: String::new() };
+    TRANSLATIONS.with(|t| {
+        t.borrow().range(start..)
+            .take_while(|(key, _)| key.content_id == content_id)
+            .map(|(key, _)| key.locale)
+            .collect()
+    })
+}
+
+/// Whether a translated quiz asks the same kinds of questions as the node's,
+/// so a learner's draw and answers mean the same thing in every locale
+pub fn quiz_shape_matches(base: &QuizData, translated: &QuizData) -> bool {
+    base.draw_count == translated.draw_count
+        && base.questions.len() == translated.questions.len()
+        && base.questions.iter().zip(translated.questions.iter())
+            .all(|(a, b)| question_kind(a) == question_kind(b))
+}
+
+/// A node with its text and quiz in the requested locale, plus the locale
+/// of the text served; the quiz is the one localized_quiz_cache verifies
+pub fn localized_node(node: &ContentNode, locale: Option<&str>) -> (ContentNode, String) {
+    let mut node = node.clone();
+    let quiz_locale = resolve_quiz_locale(&node.id, locale);
+    if let Some(quiz_locale) = quiz_locale {
+        let key = TranslationKey { content_id: node.id.clone(), locale: quiz_locale };
+        let quiz = TRANSLATIONS.with(|t| t.borrow().get(&key)).and_then(|t| t.content.quiz);
+        if let (Some(base), Some(quiz)) = (&node.quiz, quiz) {
+            if quiz_shape_matches(base, &quiz) {
+                node.quiz = Some(quiz);
+            }
+        }
+    }
+
+    let Some(locale) = resolve_locale(&node.id, locale) else {
+        return (node, fallback_locale());
+    };
+    let key = TranslationKey { content_id: node.id.clone(), locale: locale.clone() };
+    if let Some(translation) = TRANSLATIONS.with(|t| t.borrow().get(&key)) {
+        let content = translation.content;
+        node.title = content.title;
+        node.description = content.description.or(node.description);
+        node.content = content.content.or(node.content);
+        node.paraphrase = content.paraphrase.or(node.paraphrase);
+    }
+    (node, locale)
+}
+
+/// Quiz cache for a unit in a locale, falling back to the node's own quiz
+pub fn localized_quiz_cache(content_id: &str, locale: Option<&str>) -> Option<QuizCacheData> {
+    resolve_quiz_locale(content_id, locale)
+        .and_then(|locale| LOCALIZED_QUIZ_INDEX.with(|q| q.borrow().get(&TranslationKey {
+            content_id: content_id.to_string(),
+            locale,
+        })))
+        .or_else(|| QUIZ_INDEX.with(|q| q.borrow().get(&content_id.to_string())))
+}
+
+/// A unit's quiz for a requested locale, labelled with the locale that
+/// actually served it so shards never cache a fallback under the request
+pub fn localized_quiz_data(content_id: &str, locale: &str) -> Option<LocalizedQuizData> {
+    let resolved = resolve_quiz_locale(content_id, Some(locale));
+    let cache = match &resolved {
+        Some(resolved) => LOCALIZED_QUIZ_INDEX.with(|q| q.borrow().get(&TranslationKey {
+            content_id: content_id.to_string(),
+            locale: resolved.clone(),
+        })),
+        None => QUIZ_INDEX.with(|q| q.borrow().get(&content_id.to_string())),
+    }?;
+    Some(LocalizedQuizData {
+        locale: resolved,
+        locales: quiz_locales(content_id),
+        cache: with_prerequisites(cache),
+    })
+}
+
+/// Check a translation against its node; returns it with the locale normalized
+pub fn validate_translation(node: &ContentNode, mut translation: LocalizedContent) -> Result<LocalizedContent, String> {
+    translation.locale = normalize_locale(&translation.locale)?;
+    if translation.locale == fallback_locale() {
+        return Err(format!("'{}' is the fallback locale; update the node itself instead", translation.locale));
+    }
+    if node.id.contains('\0') || node.id.len() + 1 + translation.locale.len() > MAX_TRANSLATION_KEY_LEN {
+        return Err("Content id is too long to translate".to_string());
+    }
+    if translation.title.trim().is_empty() {
+        return Err(format!("Translation '{}' has an empty title", translation.locale));
+    }
+    if let Some(ref quiz) = translation.quiz {
+        let Some(ref base) = node.quiz else {
+            return Err(format!("Translation '{}' has a quiz but node '{}' does not", translation.locale, node.id));
+        };
+        if !quiz_shape_matches(base, quiz) {
+            return Err(format!(
+                "Translated quiz '{}' must have the same question kinds and draw count as the node's quiz",
+                translation.locale
+            ));
+        }
+    }
+    Ok(translation)
+}
+
+/// Enforce MAX_TRANSLATIONS_PER_NODE for a node gaining `translations`
+pub fn check_translation_capacity(content_id: &str, translations: &[LocalizedContent]) -> Result<(), String> {
+    let mut locales: HashSet<String> = node_locales(content_id).into_iter().collect();
+    locales.extend(translations.iter().map(|t| t.locale.clone()));
+    if locales.len() > MAX_TRANSLATIONS_PER_NODE {
+        return Err(format!("A node can have at most {} translations", MAX_TRANSLATIONS_PER_NODE));
+    }
+    Ok(())
+}
+
+/// Rebuild the localized quiz cache of one translation, dropping it when the
+/// translation has no quiz or no longer matches the node's quiz
+fn store_localized_quiz(content_id: &str, locale: &str) {
+    let key = TranslationKey { content_id: content_id.to_string(), locale: locale.to_string() };
+    let base = CONTENT_NODES.with(|c| c.borrow().get(&content_id.to_string())).and_then(|n| n.quiz);
+    let translated = TRANSLATIONS.with(|t| t.borrow().get(&key)).and_then(|t| t.content.quiz);
+    match (base, translated) {
+        (Some(base), Some(quiz)) if quiz_shape_matches(&base, &quiz) => {
+            let cache_data = build_quiz_cache(content_id, &quiz, increment_version());
+            LOCALIZED_QUIZ_INDEX.with(|q| q.borrow_mut().insert(key, cache_data));
+        }
+        _ => {
+            LOCALIZED_QUIZ_INDEX.with(|q| q.borrow_mut().remove(&key));
+        }
+    }
+}
+
+/// Push a unit's translated quiz locales to the Hub; shards replace their
+/// list and drop the unit's localized caches (refetched on the next attempt)
+fn push_quiz_locales(content_id: &str) {
+    let hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    if hub_id == Principal::anonymous() {
+        return;
+    }
+    let unit_id = content_id.to_string();
+    let locales = quiz_locales(content_id);
+    ic_cdk::spawn(async move {
+        let _ = ic_cdk::call::<_, ()>(
+            hub_id,
+            "distribute_quiz_locales",
+            (unit_id, locales)
+        ).await;
+    });
+}
+
+/// Re-derive every localized quiz of a node after its own quiz changed
+fn refresh_localized_quizzes(content_id: &str) {
+    let locales = node_locales(content_id);
+    for locale in &locales {
+        store_localized_quiz(content_id, locale);
+    }
+    if !locales.is_empty() {
+        push_quiz_locales(content_id);
+    }
+}
+
+/// Add or update a translation (already validated against its node)
+pub fn add_translation_internal(content_id: &str, translation: LocalizedContent, proposal_id: Option<u64>) -> Result<(), String> {
+    let now = ic_cdk::api::time();
+    let locale = translation.locale.clone();
+    let key = TranslationKey { content_id: content_id.to_string(), locale: locale.clone() };
+
+    // Snapshot the previous text, or the new one on creation
+    let previous = TRANSLATIONS.with(|t| t.borrow().get(&key));
+    let (snapshot_version, snapshot) = match previous {
+        Some(ref old) => (old.version, TranslationSnapshot {
+            content: old.content.clone(),
+            modified_at: now,
+            modified_by_proposal: proposal_id.unwrap_or(0),
+            change_type: ChangeType::Updated,
+        }),
+        None => (1, TranslationSnapshot {
+            content: translation.clone(),
+            modified_at: now,
+            modified_by_proposal: proposal_id.unwrap_or(0),
+            change_type: ChangeType::Created,
+        }),
+    };
+    TRANSLATION_HISTORY.with(|h| h.borrow_mut().insert(
+        TranslationVersionKey { content_id: content_id.to_string(), locale: locale.clone(), version: snapshot_version },
+        snapshot,
+    ));
+
+    let version = previous.map(|t| t.version + 1).unwrap_or(1);
+    TRANSLATIONS.with(|t| t.borrow_mut().insert(key, Translation {
+        content: translation,
+        version,
+        updated_at: now,
+    }));
+
+    store_localized_quiz(content_id, &locale);
+    push_quiz_locales(content_id);
+    Ok(())
+}
+
+/// Remove a translation, keeping a deletion snapshot
+fn remove_translation(content_id: &str, locale: &str, proposal_id: u64) -> Result<(), String> {
+    let key = TranslationKey { content_id: content_id.to_string(), locale: locale.to_string() };
+    let translation = TRANSLATIONS.with(|t| t.borrow().get(&key))
+        .ok_or_else(|| format!("No '{}' translation of '{}'", locale, content_id))?;
+
+    TRANSLATION_HISTORY.with(|h| h.borrow_mut().insert(
+        TranslationVersionKey { content_id: content_id.to_string(), locale: locale.to_string(), version: translation.version + 1 },
+        TranslationSnapshot {
+            content: translation.content,
+            modified_at: ic_cdk::api::time(),
+            modified_by_proposal: proposal_id,
+            change_type: ChangeType::Deleted,
+        },
+    ));
+    TRANSLATIONS.with(|t| t.borrow_mut().remove(&key));
+    LOCALIZED_QUIZ_INDEX.with(|q| q.borrow_mut().remove(&key));
+    Ok(())
+}
+
+/// Delete one translation of a node; learners in that locale fall back
+pub fn delete_translation_impl(content_id: String, locale: String, proposal_id: u64) -> Result<(), String> {
+    let locale = normalize_locale(&locale)?;
+    remove_translation(&content_id, &locale, proposal_id)?;
+    push_quiz_locales(&content_id);
+    Ok(())
+}
+
 /// Helper to rebuild quiz index (useful after upgrades or hash changes)
 pub fn rebuild_quiz_index() {
     // 1. Collect all nodes that have quizzes (in separate scope to avoid borrow hold)
@@ -38,14 +1045,7 @@ pub fn rebuild_quiz_index() {
         let mut idx = q.borrow_mut();
         for node in nodes_with_quizzes {
             if let Some(quiz) = node.quiz {
-                 let cache_data = QuizCacheData {
-                    content_id: node.id.clone(),
-                    answer_hashes: quiz.questions.iter()
-                        .map(|q| stable_hash(&q.answer.to_le_bytes()))
-                        .collect(),
-                    question_count: quiz.questions.len() as u8,
-                    version: node.version, 
-                };
+                let cache_data = build_quiz_cache(&node.id, &quiz, node.version);
                 idx.insert(node.id, cache_data);
             }
         }
@@ -75,8 +1075,11 @@ pub fn set_content_version(content_id: &str, version: u64) {
     });
 }
 
-/// Convert internal ContentNode to public version (without answers)
-pub fn to_public_node(node: &ContentNode) -> PublicContentNode {
+/// Convert internal ContentNode to public version (without answers), in the
+/// requested locale when a translation exists
+pub fn to_public_node(node: &ContentNode, locale: Option<&str>) -> PublicContentNode {
+    let (node, served_locale) = localized_node(node, locale);
+    let node = &node;
     PublicContentNode {
         id: node.id.clone(),
         parent_id: node.parent_id.clone(),
@@ -91,8 +1094,13 @@ pub fn to_public_node(node: &ContentNode) -> PublicContentNode {
             questions: q.questions.iter().map(|question| PublicQuizQuestion {
                 question: question.question.clone(),
                 options: question.options.clone(),
+                kind: question_kind(question),
             }).collect(),
+            draw_count: q.draw_count,
         }),
+        prerequisites: node.prerequisites.clone(),
+        locale: served_locale,
+        locales: node_locales(&node.id),
         created_at: node.created_at,
         updated_at: node.updated_at,
         version: node.version,
@@ -104,9 +1112,23 @@ pub fn to_public_node(node: &ContentNode) -> PublicContentNode {
 // ============================================================================
 
 /// Add or update a content node (internal function)
-pub fn add_content_node_internal(node: ContentNode, proposal_id: Option<u64>) -> Result<(), String> {
+pub fn add_content_node_internal(mut node: ContentNode, proposal_id: Option<u64>) -> Result<(), String> {
+    validate_prerequisites(&node)?;
+
+    // Translations are stored per locale, not on the node
+    let translations = node.translations.take().unwrap_or_default();
+    let translations = translations.into_iter()
+        .map(|t| validate_translation(&node, t))
+        .collect::<Result<Vec<_>, _>>()?;
+    check_translation_capacity(&node.id, &translations)?;
+
     let id = node.id.clone();
     let now = ic_cdk::api::time();
+    let previous = CONTENT_NODES.with(|c| c.borrow().get(&id));
+    let prerequisites_before = prerequisite_state(&id, &[
+        previous.as_ref().and_then(|p| p.parent_id.clone()),
+        node.parent_id.clone(),
+    ]);
     
     // Check if this is an update
     let is_update = CONTENT_NODES.with(|c| c.borrow().contains_key(&id));
@@ -146,6 +1168,7 @@ pub fn add_content_node_internal(node: ContentNode, proposal_id: Option<u64>) ->
     
     // 1. Add to main content map - O(1)
     CONTENT_NODES.with(|c| c.borrow_mut().insert(id.clone(), node.clone()));
+    index_content_node(&node);
     
     // 2. Update children index - O(1)
     if let Some(ref parent_id) = node.parent_id {
@@ -159,16 +1182,15 @@ pub fn add_content_node_internal(node: ContentNode, proposal_id: Option<u64>) ->
         });
     }
     
+    index_prerequisites(
+        &id,
+        previous.as_ref().and_then(|p| p.prerequisites.as_deref()).unwrap_or_default(),
+        node.prerequisites.as_deref().unwrap_or_default(),
+    );
+    
     // 3. If has quiz, update quiz index - O(1)
     if let Some(ref quiz) = node.quiz {
-        let cache_data = QuizCacheData {
-            content_id: id.clone(),
-            answer_hashes: quiz.questions.iter()
-                .map(|q| stable_hash(&q.answer.to_le_bytes())) // Updated to stable_hash
-                .collect(),
-            question_count: quiz.questions.len() as u8,
-            version: increment_version(),
-        };
+        let cache_data = build_quiz_cache(&id, quiz, increment_version());
         QUIZ_INDEX.with(|q| q.borrow_mut().insert(id.clone(), cache_data));
     } else {
         // Remove from quiz index if quiz was removed
@@ -183,10 +1205,18 @@ pub fn add_content_node_internal(node: ContentNode, proposal_id: Option<u64>) ->
     };
     set_content_version(&id, new_version);
 
+    // 4b. Re-check stored translations against the new quiz, then add new ones
+    if is_update {
+        refresh_localized_quizzes(&id);
+    }
+    for translation in translations {
+        add_translation_internal(&id, translation, proposal_id)?;
+    }
+
     // 5. Push cache to Hub (if not batch loading i.e. proposal_id is None)
     if proposal_id.is_none() {
         // Retrieve fresh copy to push
-        let maybe_cache = QUIZ_INDEX.with(|q| q.borrow().get(&id));
+        let maybe_cache = QUIZ_INDEX.with(|q| q.borrow().get(&id)).map(with_prerequisites);
         if let Some(cache_data) = maybe_cache {
             let hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
              if hub_id != Principal::anonymous() {
@@ -201,6 +1231,28 @@ pub fn add_content_node_internal(node: ContentNode, proposal_id: Option<u64>) ->
              }
         }
     }
+
+    // 5b. Units that require this node (or inherit from it) see the new graph
+    push_changed_prerequisites(prerequisites_before);
+
+    // 6. Tell learners who completed this node (via the Hub)
+    if is_update {
+        let hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+        if hub_id != Principal::anonymous() {
+            let notice = ContentUpdateNotice {
+                content_id: id,
+                title: node.title.clone(),
+                version: new_version,
+            };
+            ic_cdk::spawn(async move {
+                let _ = ic_cdk::call::<_, (Result<u64, String>,)>(
+                    hub_id,
+                    "route_content_update",
+                    (notice,)
+                ).await;
+            });
+        }
+    }
     
     Ok(())
 }
@@ -371,6 +1423,60 @@ pub async fn continue_loading_impl(proposal_id: u64) -> Result<(), String> {
     }
 }
 
+/// Collect the IDs of all quiz-bearing nodes in the subtree rooted at `root_id`
+/// (including the root itself), in depth-first order
+pub fn collect_subtree_quiz_ids(root_id: &str) -> Vec<String> {
+    let mut quiz_ids = Vec::new();
+    let mut stack = vec![root_id.to_string()];
+
+    while let Some(id) = stack.pop() {
+        if QUIZ_INDEX.with(|q| q.borrow().contains_key(&id)) {
+            quiz_ids.push(id.clone());
+        }
+        if let Some(children) = CHILDREN_INDEX.with(|idx| idx.borrow().get(&id)) {
+            // Push in reverse so children are visited in stored order
+            stack.extend(children.0.into_iter().rev());
+        }
+    }
+
+    quiz_ids
+}
+
+/// Collect the outline of the subtree rooted at `root_id` (including the root),
+/// parents before children, siblings in stored order
+pub fn collect_subtree_outline(root_id: &str) -> Result<Vec<ContentOutlineNode>, String> {
+    if !CONTENT_NODES.with(|c| c.borrow().contains_key(&root_id.to_string())) {
+        return Err("Node not found".to_string());
+    }
+
+    let mut outline = Vec::new();
+    let mut stack = vec![root_id.to_string()];
+
+    while let Some(id) = stack.pop() {
+        let Some(node) = CONTENT_NODES.with(|c| c.borrow().get(&id)) else {
+            continue;
+        };
+        if outline.len() >= MAX_OUTLINE_NODES {
+            return Err(format!("Subtree has more than {} nodes; request a smaller root", MAX_OUTLINE_NODES));
+        }
+        outline.push(ContentOutlineNode {
+            has_quiz: QUIZ_INDEX.with(|q| q.borrow().contains_key(&id)),
+            id: node.id,
+            parent_id: node.parent_id,
+            order: node.order,
+            display_type: node.display_type,
+            title: node.title,
+            version: node.version,
+        });
+        if let Some(children) = CHILDREN_INDEX.with(|idx| idx.borrow().get(&id)) {
+            // Push in reverse so children are visited in stored order
+            stack.extend(children.0.into_iter().rev());
+        }
+    }
+
+    Ok(outline)
+}
+
 /// Delete a content node
 pub fn delete_content_node_impl(id: String, proposal_id: u64) -> Result<(), String> {
     let now = ic_cdk::api::time();
@@ -387,6 +1493,12 @@ pub fn delete_content_node_impl(id: String, proposal_id: u64) -> Result<(), Stri
     if has_children {
         return Err("Cannot delete node with children".to_string());
     }
+
+    let dependents = prerequisite_dependents(&id);
+    if !dependents.is_empty() {
+        return Err(format!("Cannot delete node required by: {}", dependents.join(", ")));
+    }
+    let prerequisites_before = prerequisite_state(&id, std::slice::from_ref(&node.parent_id));
     
     // Save deletion snapshot
     let current_version = get_content_version(&id);
@@ -415,9 +1527,21 @@ pub fn delete_content_node_impl(id: String, proposal_id: u64) -> Result<(), Stri
     
     // Remove from quiz index
     QUIZ_INDEX.with(|q| q.borrow_mut().remove(&id));
+
+    // Remove translations (each keeps its own deletion snapshot)
+    let locales = node_locales(&id);
+    for locale in &locales {
+        remove_translation(&id, locale, proposal_id)?;
+    }
+    if !locales.is_empty() {
+        push_quiz_locales(&id);
+    }
     
     // Remove from content nodes
     CONTENT_NODES.with(|c| c.borrow_mut().remove(&id));
+    unindex_content_node(&id);
+    index_prerequisites(&id, node.prerequisites.as_deref().unwrap_or_default(), &[]);
+    push_changed_prerequisites(prerequisites_before);
     
     increment_version();
     
@@ -425,23 +1549,29 @@ pub fn delete_content_node_impl(id: String, proposal_id: u64) -> Result<(), Stri
 }
 
 /// Verify quiz answers implementation
-pub fn verify_quiz_impl(content_id: String, answers: Vec<u8>) -> (bool, u64, u64) {
-    let quiz = match QUIZ_INDEX.with(|q| q.borrow().get(&content_id)) {
+///
+/// Answers follow the user's draw from the pool (see draw_questions).
+pub fn verify_quiz_impl(content_id: String, answers: Vec<u8>, user: Principal, locale: Option<String>) -> (bool, u64, u64) {
+    let quiz = match localized_quiz_cache(&content_id, locale.as_deref()) {
         Some(q) => q,
         None => return (false, 0, 0),
     };
 
-    let total = quiz.question_count as u64;
+    let commitments = quiz_commitments(&quiz);
+    let pool_size = (quiz.question_count as usize).min(commitments.len());
+    let draw = draw_questions(user, &quiz.content_id, pool_size, quiz.draw_count);
+    let total = draw.len() as u64;
+    let Ok(answers) = decode_quiz_answers(&answers) else {
+        return (false, 0, total);
+    };
     if total == 0 || total != answers.len() as u64 {
         return (false, 0, total);
     }
 
-    let mut correct = 0;
-    for (i, ans) in answers.iter().enumerate() {
-        if stable_hash(&ans.to_le_bytes()) == quiz.answer_hashes[i] {
-            correct += 1;
-        }
-    }
+    let correct = draw.iter()
+        .zip(answers.iter())
+        .filter(|(index, answer)| answer_matches(&commitments[**index], answer))
+        .count() as u64;
 
     // Default pass threshold if not specified/synced
     // In the new architecture, Shards verify locally using their own config.
@@ -451,3 +1581,300 @@ pub fn verify_quiz_impl(content_id: String, answers: Vec<u8>) -> (bool, u64, u64
 
     (passed, correct, total)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn learner(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    #[test]
+    fn draw_questions_is_deterministic_per_learner_and_content() {
+        let first = draw_questions(learner(1), "unit_1", 20, Some(5));
+        assert_eq!(first, draw_questions(learner(1), "unit_1", 20, Some(5)));
+        assert_ne!(first, draw_questions(learner(2), "unit_1", 20, Some(5)));
+        assert_ne!(first, draw_questions(learner(1), "unit_2", 20, Some(5)));
+    }
+
+    #[test]
+    fn draw_questions_returns_a_distinct_subset_of_the_pool() {
+        let draw = draw_questions(learner(3), "unit_1", 20, Some(5));
+        assert_eq!(draw.len(), 5);
+        let distinct: HashSet<usize> = draw.iter().copied().collect();
+        assert_eq!(distinct.len(), 5);
+        assert!(draw.iter().all(|i| *i < 20));
+    }
+
+    #[test]
+    fn draw_questions_without_draw_count_keeps_authored_order() {
+        assert_eq!(draw_questions(learner(1), "unit_1", 4, None), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn draw_questions_caps_the_draw_at_the_pool_size() {
+        let mut draw = draw_questions(learner(1), "unit_1", 3, Some(10));
+        draw.sort_unstable();
+        assert_eq!(draw, vec![0, 1, 2]);
+        assert!(draw_questions(learner(1), "unit_1", 0, Some(3)).is_empty());
+    }
+
+    fn question(answer: u8, answer_key: Option<AnswerKey>) -> QuizQuestion {
+        QuizQuestion {
+            question: "?".to_string(),
+            options: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            answer,
+            answer_key,
+        }
+    }
+
+    fn commitment(answer_key: AnswerKey) -> AnswerCommitment {
+        answer_commitment(&question(0, Some(answer_key)))
+    }
+
+    #[test]
+    fn numeric_answers_match_within_the_tolerance() {
+        let range = commitment(AnswerKey::Numeric { value: 314, tolerance: 2, decimals: 2 });
+        assert!(answer_matches(&range, &QuizAnswer::Numeric(312)));
+        assert!(answer_matches(&range, &QuizAnswer::Numeric(314)));
+        assert!(answer_matches(&range, &QuizAnswer::Numeric(316)));
+        assert!(!answer_matches(&range, &QuizAnswer::Numeric(311)));
+        assert!(!answer_matches(&range, &QuizAnswer::Numeric(317)));
+        assert!(!answer_matches(&range, &QuizAnswer::SingleChoice(0)));
+    }
+
+    #[test]
+    fn numeric_tolerance_saturates_at_the_i64_bounds() {
+        let range = commitment(AnswerKey::Numeric { value: i64::MAX - 1, tolerance: 10, decimals: 0 });
+        assert!(answer_matches(&range, &QuizAnswer::Numeric(i64::MAX)));
+        assert!(!answer_matches(&range, &QuizAnswer::Numeric(i64::MAX - 12)));
+
+        let range = commitment(AnswerKey::Numeric { value: 0, tolerance: u64::MAX, decimals: 0 });
+        assert!(answer_matches(&range, &QuizAnswer::Numeric(i64::MIN + 1)));
+        assert!(answer_matches(&range, &QuizAnswer::Numeric(i64::MAX)));
+    }
+
+    #[test]
+    fn withheld_numeric_ranges_match_nothing() {
+        assert!(!answer_matches(&AnswerCommitment::Withheld, &QuizAnswer::Numeric(0)));
+    }
+
+    #[test]
+    fn hashed_commitments_match_their_answers() {
+        let single = answer_commitment(&question(2, None));
+        assert!(answer_matches(&single, &QuizAnswer::SingleChoice(2)));
+        assert!(!answer_matches(&single, &QuizAnswer::SingleChoice(1)));
+
+        let multi = commitment(AnswerKey::MultiSelect(vec![2, 0]));
+        assert!(answer_matches(&multi, &QuizAnswer::MultiSelect(vec![0, 2, 2])));
+        assert!(!answer_matches(&multi, &QuizAnswer::MultiSelect(vec![0])));
+
+        let truth = commitment(AnswerKey::TrueFalse(true));
+        assert!(answer_matches(&truth, &QuizAnswer::TrueFalse(true)));
+        assert!(answer_matches(&truth, &QuizAnswer::SingleChoice(1)));
+        assert!(!answer_matches(&truth, &QuizAnswer::SingleChoice(0)));
+        assert!(!answer_matches(&truth, &QuizAnswer::SingleChoice(2)));
+
+        let order = commitment(AnswerKey::Ordering(vec![1, 0, 2]));
+        assert!(answer_matches(&order, &QuizAnswer::Ordering(vec![1, 0, 2])));
+        assert!(!answer_matches(&order, &QuizAnswer::Ordering(vec![0, 1, 2])));
+
+        let text = commitment(AnswerKey::ShortText(vec!["New York".to_string()]));
+        assert!(answer_matches(&text, &QuizAnswer::ShortText("  new-york! ".to_string())));
+        assert!(!answer_matches(&text, &QuizAnswer::ShortText("York".to_string())));
+    }
+
+    #[test]
+    fn public_quiz_view_withholds_only_numeric_ranges() {
+        let quiz = QuizData {
+            questions: vec![
+                question(1, None),
+                question(0, Some(AnswerKey::Numeric { value: 10, tolerance: 0, decimals: 0 })),
+            ],
+            draw_count: None,
+        };
+        let view = public_quiz_view(build_quiz_cache("unit_1", &quiz, 1));
+        let commitments = view.commitments.expect("mixed quiz has commitments");
+        assert!(matches!(commitments[0], AnswerCommitment::SingleChoice(_)));
+        assert!(matches!(commitments[1], AnswerCommitment::Withheld));
+    }
+
+    fn node(id: &str, parent_id: Option<&str>, with_quiz: bool, prerequisites: Vec<Prerequisite>) -> ContentNode {
+        ContentNode {
+            id: id.to_string(),
+            parent_id: parent_id.map(str::to_string),
+            order: 0,
+            display_type: "Unit".to_string(),
+            title: id.to_string(),
+            description: None,
+            content: None,
+            paraphrase: None,
+            media: None,
+            quiz: with_quiz.then(|| QuizData { questions: vec![question(0, None)], draw_count: None }),
+            prerequisites: (!prerequisites.is_empty()).then_some(prerequisites),
+            translations: None,
+            created_at: 0,
+            updated_at: 0,
+            version: 1,
+        }
+    }
+
+    /// Store a node with its child and quiz index entries, bypassing validation
+    fn store(node: ContentNode) {
+        if let Some(parent_id) = &node.parent_id {
+            CHILDREN_INDEX.with(|idx| {
+                let mut idx = idx.borrow_mut();
+                let mut children = idx.get(parent_id).unwrap_or_default();
+                children.0.push(node.id.clone());
+                idx.insert(parent_id.clone(), children);
+            });
+        }
+        if let Some(quiz) = &node.quiz {
+            QUIZ_INDEX.with(|q| q.borrow_mut().insert(node.id.clone(), build_quiz_cache(&node.id, quiz, 1)));
+        }
+        CONTENT_NODES.with(|c| c.borrow_mut().insert(node.id.clone(), node));
+    }
+
+    fn requires(id: &str) -> Prerequisite {
+        Prerequisite::Node(id.to_string())
+    }
+
+    #[test]
+    fn validate_prerequisites_accepts_a_chain() {
+        store(node("u1", None, true, vec![]));
+        store(node("u2", None, true, vec![requires("u1")]));
+        assert!(validate_prerequisites(&node("u3", None, true, vec![requires("u2")])).is_ok());
+    }
+
+    #[test]
+    fn validate_prerequisites_rejects_a_direct_cycle() {
+        store(node("u1", None, true, vec![]));
+        store(node("u2", None, true, vec![requires("u1")]));
+        let err = validate_prerequisites(&node("u1", None, true, vec![requires("u2")])).unwrap_err();
+        assert!(err.contains("cycle"), "{}", err);
+    }
+
+    #[test]
+    fn validate_prerequisites_rejects_a_longer_cycle() {
+        store(node("u1", None, true, vec![]));
+        store(node("u2", None, true, vec![requires("u1")]));
+        store(node("u3", None, true, vec![requires("u2")]));
+        let err = validate_prerequisites(&node("u1", None, true, vec![requires("u3")])).unwrap_err();
+        assert!(err.contains("cycle"), "{}", err);
+    }
+
+    #[test]
+    fn validate_prerequisites_rejects_a_cycle_through_an_inherited_requirement() {
+        // u1 inherits chapter's requirement on u2, and u2 requires every unit in chapter
+        store(node("chapter", None, false, vec![]));
+        store(node("u1", Some("chapter"), true, vec![]));
+        store(node("u2", None, true, vec![Prerequisite::AllChildrenOf("chapter".to_string())]));
+        let err = validate_prerequisites(&node("chapter", None, false, vec![requires("u2")])).unwrap_err();
+        assert!(err.contains("cycle"), "{}", err);
+    }
+
+    #[test]
+    fn validate_prerequisites_rejects_self_and_unknown_targets() {
+        store(node("u1", None, true, vec![]));
+        assert!(validate_prerequisites(&node("u1", None, true, vec![requires("u1")])).is_err());
+        assert!(validate_prerequisites(&node("u1", None, true, vec![requires("missing")])).is_err());
+        assert!(validate_prerequisites(&node("u1", None, true, vec![requires("")])).is_err());
+    }
+
+    #[test]
+    fn tokenize_lowercases_and_drops_short_and_long_runs() {
+        let long = "x".repeat(MAX_SEARCH_TERM_LEN + 1);
+        let text = format!("The CELL-cycle: a {} Über2 step", long);
+        let terms: Vec<String> = tokenize(&text).collect();
+        assert_eq!(terms, vec!["the", "cell", "cycle", "über2", "step"]);
+    }
+
+    fn search(keywords: &str) -> SearchPage {
+        search_content_impl(SearchRequest {
+            keywords: keywords.to_string(),
+            display_type: None,
+            root_id: None,
+            offset: 0,
+            limit: MAX_SEARCH_PAGE,
+        })
+    }
+
+    fn hit_ids(page: &SearchPage) -> Vec<&str> {
+        page.hits.iter().map(|h| h.id.as_str()).collect()
+    }
+
+    #[test]
+    fn search_matches_prefixes_and_ranks_exact_terms_first() {
+        let mut photon = node("photon", None, false, vec![]);
+        photon.title = "Photon energy".to_string();
+        let mut photosynthesis = node("photosynthesis", None, false, vec![]);
+        photosynthesis.title = "Photosynthesis".to_string();
+        index_content_node(&photon);
+        index_content_node(&photosynthesis);
+
+        let page = search("photo");
+        assert_eq!(page.total, Some(2));
+        assert!(!page.truncated);
+
+        let page = search("photon");
+        assert_eq!(hit_ids(&page), vec!["photon"]);
+
+        let page = search("PHOTO energy");
+        assert_eq!(hit_ids(&page), vec!["photon"]);
+
+        assert_eq!(search("chlorophyll").total, Some(0));
+    }
+
+    #[test]
+    fn search_filters_by_subtree() {
+        let mut chapter = node("chapter", None, false, vec![]);
+        chapter.title = "Cells".to_string();
+        let mut inside = node("inside", Some("chapter"), false, vec![]);
+        inside.title = "Cell membrane".to_string();
+        let mut outside = node("outside", None, false, vec![]);
+        outside.title = "Cell phones".to_string();
+        for n in [&chapter, &inside, &outside] {
+            index_content_node(n);
+        }
+
+        let page = search_content_impl(SearchRequest {
+            keywords: "cell".to_string(),
+            display_type: None,
+            root_id: Some("chapter".to_string()),
+            offset: 0,
+            limit: 10,
+        });
+        let mut ids = hit_ids(&page);
+        ids.sort();
+        assert_eq!(ids, vec!["chapter", "inside"]);
+    }
+
+    #[test]
+    fn search_flags_truncated_prefix_scans_without_a_total() {
+        SEARCH_POSTINGS.with(|p| {
+            let mut postings = p.borrow_mut();
+            for i in 0..=MAX_SEARCH_POSTINGS_SCANNED {
+                postings.insert(SearchPostingKey { term: format!("zz{}", i), content_id: "n".to_string() }, 1);
+            }
+        });
+        let page = search("zz");
+        assert!(page.truncated);
+        assert_eq!(page.total, None);
+
+        // An exact word with few postings is not affected by the scan limit
+        let page = search("zz7");
+        assert!(!page.truncated);
+    }
+
+    #[test]
+    fn search_is_truncated_while_the_index_is_backfilled() {
+        store(node("u1", None, false, vec![]));
+        assert!(start_search_backfill());
+        assert!(search("u1").truncated);
+        assert!(!backfill_search_batch());
+        let page = search("u1");
+        assert!(!page.truncated);
+        assert_eq!(hit_ids(&page), vec!["u1"]);
+    }
+}
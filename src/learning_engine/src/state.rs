@@ -1,8 +1,10 @@
 use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
 use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, StableCell};
 use std::cell::RefCell;
+use std::collections::BTreeSet;
 use candid::Principal;
 use crate::types::*;
+use crate::constants::DEFAULT_FALLBACK_LOCALE;
 
 pub type Memory = VirtualMemory<DefaultMemoryImpl>;
 
@@ -77,4 +79,74 @@ thread_local! {
             MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(9)))
         )
     );
+
+    /// Search index postings: (term, content_id) -> weighted term frequency
+    pub static SEARCH_POSTINGS: RefCell<StableBTreeMap<SearchPostingKey, u32, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(10)))
+        )
+    );
+
+    /// Search index documents: content_id -> title, display type and parent
+    pub static SEARCH_DOCUMENTS: RefCell<StableBTreeMap<String, SearchDocument, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(11)))
+        )
+    );
+
+    /// Search index terms: content_id -> terms it is posted under
+    pub static SEARCH_TERMS: RefCell<StableBTreeMap<String, SearchTerms, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(12)))
+        )
+    );
+
+    /// Translations: (content_id, locale) -> current translation
+    pub static TRANSLATIONS: RefCell<StableBTreeMap<TranslationKey, Translation, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(13)))
+        )
+    );
+
+    /// Per-locale version history of translations
+    pub static TRANSLATION_HISTORY: RefCell<StableBTreeMap<TranslationVersionKey, TranslationSnapshot, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(14)))
+        )
+    );
+
+    /// Quiz index of translated quizzes: (content_id, locale) -> quiz cache data
+    pub static LOCALIZED_QUIZ_INDEX: RefCell<StableBTreeMap<TranslationKey, QuizCacheData, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(15)))
+        )
+    );
+
+    /// Locale of the text stored on the nodes themselves; served when a
+    /// requested locale has no translation
+    pub static FALLBACK_LOCALE: RefCell<StableCell<String, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(16))),
+            DEFAULT_FALLBACK_LOCALE.to_string()
+        ).unwrap()
+    );
+
+    /// Index: prerequisite target id -> nodes declaring a prerequisite on it
+    pub static PREREQUISITE_DEPENDENTS: RefCell<StableBTreeMap<String, ChildrenList, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(17)))
+        )
+    );
+
+    /// Cursor of the search index backfill started after an upgrade
+    pub static SEARCH_BACKFILL: RefCell<StableCell<SearchBackfill, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(18))),
+            SearchBackfill::default()
+        ).unwrap()
+    );
+
+    /// Shards the staking hub has confirmed as registered (heap only;
+    /// re-confirmed after an upgrade)
+    pub static VERIFIED_SHARDS: RefCell<BTreeSet<Principal>> = const { RefCell::new(BTreeSet::new()) };
 }
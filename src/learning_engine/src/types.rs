@@ -1,4 +1,5 @@
 use candid::{CandidType, Deserialize, Principal, Encode, Decode};
+use crate::constants::{MAX_TRANSLATION_KEY_LEN, MAX_SEARCH_ID_LEN, MAX_SEARCH_TERMS_PER_NODE, MAX_SEARCH_TERM_LEN, MAX_SEARCH_TITLE_LEN};
 use ic_stable_structures::{storable::Bound, Storable};
 use std::borrow::Cow;
 
@@ -54,16 +55,73 @@ pub struct QuizQuestion {
     pub question: String,
     /// List of answer options
     pub options: Vec<String>,
-    /// Index of the correct answer (0-based)
+    /// Index of the correct answer (0-based), for single-choice questions
     pub answer: u8,
+    /// Answer key of any other question type (None = single choice)
+    pub answer_key: Option<AnswerKey>,
+}
+
+/// Correct answer of a question that is not single-choice
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum AnswerKey {
+    /// Every correct option index (order does not matter)
+    MultiSelect(Vec<u8>),
+    TrueFalse(bool),
+    /// Correct value and accepted deviation, both scaled by 10^decimals
+    Numeric { value: i64, tolerance: u64, decimals: u8 },
+    /// Option indexes in the correct order
+    Ordering(Vec<u8>),
+    /// Accepted answers, compared after normalization (case, punctuation, spacing)
+    ShortText(Vec<String>),
+}
+
+/// Per-question commitment shards verify answers against
+///
+/// Hashes are stable_hash of the canonical answer bytes. A numeric range
+/// cannot be committed by hash, so its bounds are stored as they are and
+/// only served to the hub and shards; public views show it as Withheld.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum AnswerCommitment {
+    /// Hash of the option index
+    SingleChoice([u8; 32]),
+    /// Hash of the sorted, deduplicated option indexes
+    MultiSelect([u8; 32]),
+    /// Hash of 1 (true) or 0 (false)
+    TrueFalse([u8; 32]),
+    /// Inclusive range, scaled by 10^decimals
+    Numeric { min: i64, max: i64 },
+    /// Hash of the option indexes in order
+    Ordering([u8; 32]),
+    /// Hashes of the normalized accepted answers
+    ShortText(Vec<[u8; 32]>),
+    /// A numeric range left out of a public view
+    Withheld,
+}
+
+/// A submitted answer to one question
+///
+/// submit_quiz takes either one byte per question (single choice; 0/1 for
+/// true/false) or a Candid-encoded vec of QuizAnswer.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum QuizAnswer {
+    SingleChoice(u8),
+    MultiSelect(Vec<u8>),
+    TrueFalse(bool),
+    /// Scaled by 10^decimals of the question
+    Numeric(i64),
+    Ordering(Vec<u8>),
+    ShortText(String),
 }
 
 /// Quiz data attached to a content node
 /// NOTE: Configuration is now managed by the Staking Hub
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct QuizData {
-    /// Quiz questions
+    /// Quiz questions (the pool when draw_count is set)
     pub questions: Vec<QuizQuestion>,
+    /// Questions each learner draws from the pool, in a per-learner order
+    /// (None = every question, in authored order)
+    pub draw_count: Option<u8>,
 }
 
 /// Quiz cache data stored in QUIZ_INDEX for O(1) lookup by user profile shards
@@ -77,6 +135,14 @@ pub struct QuizCacheData {
     pub question_count: u8,
     /// Version of this quiz data
     pub version: u64,
+    /// One commitment per question; None when every question is single-choice
+    /// (answer_hashes then suffice)
+    pub commitments: Option<Vec<AnswerCommitment>>,
+    /// Questions drawn per learner (see draw_questions); None = all, in order
+    pub draw_count: Option<u8>,
+    /// Quiz units to pass first, own and inherited prerequisites combined
+    /// Resolved when served, as other nodes may load later; None = ungated
+    pub prerequisites: Option<Vec<String>>,
 }
 
 impl Storable for QuizCacheData {
@@ -89,7 +155,7 @@ impl Storable for QuizCacheData {
     }
 
     const BOUND: Bound = Bound::Bounded {
-        max_size: 1000,
+        max_size: 100_000, // 255 questions with up to MAX_ACCEPTED_TEXT_ANSWERS hashes each
         is_fixed_size: false,
     };
 }
@@ -132,12 +198,46 @@ pub struct ContentNode {
     // Quiz (optional - ANY node at ANY level can have a quiz)
     pub quiz: Option<QuizData>,
     
+    // Gating (optional - also applies to every node below this one)
+    pub prerequisites: Option<Vec<Prerequisite>>,
+    
+    // Localization (optional - carried in from staging, then stored per
+    // locale by add_content_node_internal; always None once stored)
+    pub translations: Option<Vec<LocalizedContent>>,
+    
     // Metadata
     pub created_at: u64,
     pub updated_at: u64,
     pub version: u64,
 }
 
+/// Requirement a learner must meet before taking a node's quiz
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum Prerequisite {
+    /// The node is passed; a node without a quiz needs every quiz under it
+    Node(String),
+    /// Every quiz below the node (its own quiz excluded)
+    AllChildrenOf(String),
+}
+
+impl Prerequisite {
+    /// Node the requirement refers to
+    pub fn target(&self) -> &str {
+        match self {
+            Prerequisite::Node(id) | Prerequisite::AllChildrenOf(id) => id,
+        }
+    }
+}
+
+/// Whether a node is open to a learner (see get_unlocked_nodes)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NodeUnlockStatus {
+    pub id: String,
+    pub unlocked: bool,
+    /// Quiz units still to pass, own and inherited prerequisites combined
+    pub missing: Vec<String>,
+}
+
 impl Default for ContentNode {
     fn default() -> Self {
         let now = ic_cdk::api::time();
@@ -152,6 +252,8 @@ impl Default for ContentNode {
             paraphrase: None,
             media: None,
             quiz: None,
+            prerequisites: None,
+            translations: None,
             created_at: now,
             updated_at: now,
             version: 1,
@@ -174,6 +276,29 @@ impl Storable for ContentNode {
     };
 }
 
+/// Sent to the staking hub when an existing node changes, so shards can
+/// notify the learners who completed it (mirrors staking_hub::ContentUpdateNotice)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ContentUpdateNotice {
+    pub content_id: String,
+    pub title: String,
+    pub version: u64,
+}
+
+/// Tree metadata of a node, used by shards to compute course progress
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ContentOutlineNode {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub order: u32,
+    pub display_type: String,
+    pub title: String,
+    /// True if the node carries a quiz
+    pub has_quiz: bool,
+    /// Content version of the node
+    pub version: u64,
+}
+
 /// Public version of ContentNode - EXCLUDES QUIZ ANSWERS
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct PublicContentNode {
@@ -188,6 +313,12 @@ pub struct PublicContentNode {
     pub media: Option<MediaContent>,
     /// Quiz questions WITHOUT answers
     pub quiz: Option<PublicQuizData>,
+    pub prerequisites: Option<Vec<Prerequisite>>,
+    /// Locale of the text and quiz served (the fallback locale if no
+    /// translation matched the request)
+    pub locale: String,
+    /// Locales with a translation of this node
+    pub locales: Vec<String>,
     pub created_at: u64,
     pub updated_at: u64,
     pub version: u64,
@@ -196,7 +327,9 @@ pub struct PublicContentNode {
 /// Public quiz data - EXCLUDES ANSWERS
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct PublicQuizData {
+    /// The whole pool; a learner's draw comes from get_drawn_quiz
     pub questions: Vec<PublicQuizQuestion>,
+    pub draw_count: Option<u8>,
 }
 
 /// Public quiz question - EXCLUDES ANSWER
@@ -204,9 +337,31 @@ pub struct PublicQuizData {
 pub struct PublicQuizQuestion {
     pub question: String,
     pub options: Vec<String>,
+    pub kind: QuestionKind,
     // Note: answer field is intentionally omitted
 }
 
+/// A learner's draw from a quiz pool, in the order answers are submitted
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct DrawnQuiz {
+    pub content_id: String,
+    pub questions: Vec<PublicQuizQuestion>,
+    /// Pool index of each drawn question
+    pub pool_indexes: Vec<u8>,
+}
+
+/// How a question is answered (the public side of AnswerKey)
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum QuestionKind {
+    SingleChoice,
+    MultiSelect,
+    TrueFalse,
+    /// Answers are scaled by 10^decimals
+    Numeric { decimals: u8 },
+    Ordering,
+    ShortText,
+}
+
 // ============================================================================
 // CONTENT LOADING STRUCTURES
 // ============================================================================
@@ -326,3 +481,282 @@ impl Storable for ChildrenList {
         is_fixed_size: false,
     };
 }
+
+// ============================================================================
+// SEARCH INDEX
+// ============================================================================
+
+/// Posting key: (term, content_id), encoded as `term 0x00 content_id` so the
+/// postings of a term, and of every term sharing a prefix, are contiguous
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SearchPostingKey {
+    pub term: String,
+    pub content_id: String,
+}
+
+impl Storable for SearchPostingKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(self.term.len() + 1 + self.content_id.len());
+        bytes.extend_from_slice(self.term.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(self.content_id.as_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let split = bytes.iter().position(|b| *b == 0).expect("Failed to decode SearchPostingKey");
+        Self {
+            term: String::from_utf8(bytes[..split].to_vec()).expect("Failed to decode SearchPostingKey"),
+            content_id: String::from_utf8(bytes[split + 1..].to_vec()).expect("Failed to decode SearchPostingKey"),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: (MAX_SEARCH_TERM_LEN + 1 + MAX_SEARCH_ID_LEN) as u32,
+        is_fixed_size: false,
+    };
+}
+
+/// What the search index keeps per node to filter and display a hit
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SearchDocument {
+    pub title: String,
+    pub display_type: String,
+    /// None for roots (and parents whose id is too long to index)
+    pub parent_id: Option<String>,
+}
+
+impl Storable for SearchDocument {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode SearchDocument")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: (2 * MAX_SEARCH_TITLE_LEN + MAX_SEARCH_ID_LEN + 100) as u32,
+        is_fixed_size: false,
+    };
+}
+
+/// Terms a node is indexed under, so its postings can be removed
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct SearchTerms(pub Vec<String>);
+
+impl Storable for SearchTerms {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode SearchTerms")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: (MAX_SEARCH_TERMS_PER_NODE * (MAX_SEARCH_TERM_LEN + 4) + 100) as u32,
+        is_fixed_size: false,
+    };
+}
+
+/// Progress of the batched search index backfill
+///
+/// Kept in stable memory so an upgrade in the middle of the backfill resumes
+/// after the last indexed node instead of starting over or stopping.
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct SearchBackfill {
+    pub active: bool,
+    /// Last content id indexed; None before the first batch
+    pub last_indexed: Option<String>,
+}
+
+impl Storable for SearchBackfill {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode SearchBackfill")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// Full-text search request (see search_content)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SearchRequest {
+    /// Words to find; every word must match, and each also matches as a prefix
+    pub keywords: String,
+    /// Only nodes of this display type, e.g. "Chapter"
+    pub display_type: Option<String>,
+    /// Only nodes in this subtree (the root included)
+    pub root_id: Option<String>,
+    pub offset: u32,
+    pub limit: u32,
+}
+
+/// A matching node, best first
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SearchHit {
+    pub id: String,
+    pub title: String,
+    pub display_type: String,
+    pub parent_id: Option<String>,
+    pub score: u32,
+}
+
+/// A page of search results
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SearchPage {
+    pub hits: Vec<SearchHit>,
+    /// Matches across all pages; None when the results are truncated
+    pub total: Option<u64>,
+    /// Some matches may be missing: a query word matched more postings than
+    /// are scanned per query, or the index is still being backfilled
+    pub truncated: bool,
+}
+
+// ============================================================================
+// LOCALIZATION
+// ============================================================================
+
+/// A node's text and quiz in one locale
+///
+/// Optional fields left empty fall back to the node's own values, which are
+/// in the fallback locale.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LocalizedContent {
+    /// Language tag such as "es" or "pt-BR" (stored lowercase)
+    pub locale: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub content: Option<String>,
+    pub paraphrase: Option<String>,
+    /// Same number and kinds of questions as the node's quiz; accepted
+    /// answers may differ (e.g. short text in the target language)
+    pub quiz: Option<QuizData>,
+}
+
+/// A unit's quiz for a requested locale (shards cache it under `locale`)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LocalizedQuizData {
+    /// Locale of the translated quiz served; None = the node's own quiz
+    pub locale: Option<String>,
+    /// Every locale with a translated quiz of this unit
+    pub locales: Vec<String>,
+    pub cache: QuizCacheData,
+}
+
+/// A stored translation with its own version counter
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Translation {
+    pub content: LocalizedContent,
+    pub version: u64,
+    pub updated_at: u64,
+}
+
+impl Storable for Translation {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode Translation")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 100000,
+        is_fixed_size: false,
+    };
+}
+
+/// Key of a translation: (content_id, locale), encoded as `content_id 0x00
+/// locale` so a node's translations are contiguous
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TranslationKey {
+    pub content_id: String,
+    pub locale: String,
+}
+
+impl Storable for TranslationKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(self.content_id.len() + 1 + self.locale.len());
+        bytes.extend_from_slice(self.content_id.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(self.locale.as_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let split = bytes.iter().position(|b| *b == 0).expect("Failed to decode TranslationKey");
+        Self {
+            content_id: String::from_utf8(bytes[..split].to_vec()).expect("Failed to decode TranslationKey"),
+            locale: String::from_utf8(bytes[split + 1..].to_vec()).expect("Failed to decode TranslationKey"),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: MAX_TRANSLATION_KEY_LEN as u32,
+        is_fixed_size: false,
+    };
+}
+
+/// Key of a translation snapshot: `content_id 0x00 locale 0x00 version (BE)`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TranslationVersionKey {
+    pub content_id: String,
+    pub locale: String,
+    pub version: u64,
+}
+
+impl Storable for TranslationVersionKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(self.content_id.len() + self.locale.len() + 10);
+        bytes.extend_from_slice(self.content_id.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(self.locale.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&self.version.to_be_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let version_start = bytes.len() - 8;
+        let split = bytes.iter().position(|b| *b == 0).expect("Failed to decode TranslationVersionKey");
+        Self {
+            content_id: String::from_utf8(bytes[..split].to_vec()).expect("Failed to decode TranslationVersionKey"),
+            locale: String::from_utf8(bytes[split + 1..version_start - 1].to_vec()).expect("Failed to decode TranslationVersionKey"),
+            version: u64::from_be_bytes(bytes[version_start..].try_into().unwrap()),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: MAX_TRANSLATION_KEY_LEN as u32 + 9,
+        is_fixed_size: false,
+    };
+}
+
+/// A translation as it was before a change (per-locale audit trail)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TranslationSnapshot {
+    pub content: LocalizedContent,
+    pub modified_at: u64,
+    pub modified_by_proposal: u64,
+    pub change_type: ChangeType,
+}
+
+impl Storable for TranslationSnapshot {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode TranslationSnapshot")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 100000,
+        is_fixed_size: false,
+    };
+}
@@ -1,5 +1,35 @@
+use rate_limit::RateLimit;
+
 /// Maximum chunk size for uploads (2MB)
 pub const MAX_CHUNK_SIZE: usize = 2 * 1024 * 1024;
 
 /// Maximum file size (100MB)
 pub const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
+
+/// Default argument size limit for ingress messages
+pub const MAX_INGRESS_ARG_BYTES: usize = 4 * 1024;
+
+/// Per-method argument size limits overriding MAX_INGRESS_ARG_BYTES
+/// (one chunk of data plus room for the other arguments)
+pub const INGRESS_ARG_LIMITS: &[(&str, usize)] = &[
+    ("upload_chunk", MAX_CHUNK_SIZE + 4 * 1024),
+    ("upload_file", MAX_CHUNK_SIZE + 4 * 1024),
+];
+
+/// Methods that reject the anonymous principal anyway (controller-only)
+pub const ANONYMOUS_REJECTED_METHODS: &[&str] = &["add_allowed_uploader", "remove_allowed_uploader"];
+
+/// Opening, finishing and cancelling uploads
+pub const UPLOAD_SESSION_RATE_LIMIT: RateLimit = RateLimit { bucket: "upload_session", capacity: 10, refill_interval_ns: 60 * 1_000_000_000 };
+
+/// Chunk uploads (a 100MB file is 50 chunks)
+pub const UPLOAD_CHUNK_RATE_LIMIT: RateLimit = RateLimit { bucket: "upload_chunk", capacity: 60, refill_interval_ns: 1_000_000_000 };
+
+/// Rate-limited methods and their buckets (must agree with the guards in inspect.rs)
+pub const RATE_LIMITED_METHODS: &[(&str, RateLimit)] = &[
+    ("start_upload", UPLOAD_SESSION_RATE_LIMIT),
+    ("upload_file", UPLOAD_SESSION_RATE_LIMIT),
+    ("finalize_upload", UPLOAD_SESSION_RATE_LIMIT),
+    ("cancel_upload", UPLOAD_SESSION_RATE_LIMIT),
+    ("upload_chunk", UPLOAD_CHUNK_RATE_LIMIT),
+];
@@ -7,11 +7,13 @@ mod constants;
 mod types;
 mod state;
 mod service;
+mod inspect;
 
-use ic_cdk::{init, query, update};
+use ic_cdk::{init, query, update, inspect_message};
 use candid::Principal;
 use crate::types::*;
 use crate::state::*;
+use crate::inspect::*;
 
 // ============================================================================
 // INITIALIZATION
@@ -25,12 +27,26 @@ fn init(args: InitArgs) {
     });
 }
 
+// ============================================================================
+// INGRESS FILTERING
+// ============================================================================
+
+/// Drop ingress that would be rejected anyway before it costs consensus cycles
+#[inspect_message]
+fn inspect_message() {
+    let method = ic_cdk::api::call::method_name();
+    match check_ingress(&method, ic_cdk::caller(), ic_cdk::api::call::arg_data_raw_size()) {
+        Ok(()) => ic_cdk::api::call::accept_message(),
+        Err(e) => ic_cdk::trap(&e),
+    }
+}
+
 // ============================================================================
 // UPLOAD FUNCTIONS
 // ============================================================================
 
 /// Start a new upload session
-#[update]
+#[update(guard = "limit_upload_session")]
 fn start_upload(
     filename: String,
     content_type: String,
@@ -41,19 +57,19 @@ fn start_upload(
 }
 
 /// Upload a chunk of data
-#[update]
+#[update(guard = "limit_upload_chunk")]
 fn upload_chunk(session_id: String, chunk_index: u32, data: Vec<u8>) -> Result<(), String> {
     service::upload_chunk_impl(ic_cdk::caller(), session_id, chunk_index, data)
 }
 
 /// Finalize upload and get the file hash
-#[update]
+#[update(guard = "limit_upload_session")]
 fn finalize_upload(session_id: String) -> Result<String, String> {
     service::finalize_upload_impl(ic_cdk::caller(), ic_cdk::api::time(), session_id)
 }
 
 /// Simple single-chunk upload for small files
-#[update]
+#[update(guard = "limit_upload_session")]
 fn upload_file(
     filename: String,
     content_type: String,
@@ -64,7 +80,7 @@ fn upload_file(
 }
 
 /// Cancel an upload session
-#[update]
+#[update(guard = "limit_upload_session")]
 fn cancel_upload(session_id: String) -> Result<(), String> {
     service::cancel_upload_impl(ic_cdk::caller(), session_id)
 }
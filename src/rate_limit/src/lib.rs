@@ -0,0 +1,125 @@
+//! Per-caller token-bucket rate limiting shared by the canisters' ingress
+//! filters and update guards
+//!
+//! inspect_message runs on a single replica before consensus and its state
+//! changes are discarded, so it only peeks at the caller's bucket with
+//! `check` to drop spam early. Tokens are spent by `consume` from the guard
+//! of each rate-limited method, which also holds for messages that skipped
+//! inspection.
+//!
+//! Each canister keeps its own method table and guards; callers pass in the
+//! caller and the current time so this crate does not depend on ic-cdk.
+
+use candid::Principal;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Rate-limit buckets tracked on the heap before stale ones are evicted
+pub const MAX_RATE_LIMIT_BUCKETS: usize = 10_000;
+
+thread_local! {
+    /// Rate-limit buckets: (caller, bucket) -> time the bucket is full again
+    /// Heap only (reset on upgrade), bounded by MAX_RATE_LIMIT_BUCKETS
+    static RATE_LIMIT_BUCKETS: RefCell<HashMap<(Principal, &'static str), u64>> = RefCell::new(HashMap::new());
+}
+
+/// Token-bucket limit applied per caller to ingress updates
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimit {
+    /// Methods naming the same bucket draw from the same tokens
+    pub bucket: &'static str,
+    /// Burst size (tokens in a full bucket)
+    pub capacity: u64,
+    /// Time to regain one token
+    pub refill_interval_ns: u64,
+}
+
+/// Limit configured for `method` in a canister's method table
+pub fn rate_limit_for(methods: &[(&str, RateLimit)], method: &str) -> Option<RateLimit> {
+    methods.iter()
+        .find(|(name, _)| *name == method)
+        .map(|(_, limit)| *limit)
+}
+
+/// Time at which the caller's bucket is full again after spending one more
+/// token, or an error if the bucket is empty
+///
+/// A bucket is stored as the time it will be full; absent means full.
+fn next_full_at(caller: Principal, limit: &RateLimit, now: u64) -> Result<u64, String> {
+    let full_at = RATE_LIMIT_BUCKETS.with(|b| b.borrow().get(&(caller, limit.bucket)).copied())
+        .unwrap_or(now)
+        .max(now);
+    let next = full_at + limit.refill_interval_ns;
+    let window = limit.capacity * limit.refill_interval_ns;
+    if next - now > window {
+        let retry_secs = (next - now - window).div_ceil(1_000_000_000);
+        return Err(format!("Rate limit exceeded for {}: retry in {}s", limit.bucket, retry_secs));
+    }
+    Ok(next)
+}
+
+/// Whether the caller has a token left, without spending it
+pub fn check(caller: Principal, limit: &RateLimit, now: u64) -> Result<(), String> {
+    next_full_at(caller, limit, now).map(|_| ())
+}
+
+/// Spend one token from the caller's bucket
+///
+/// The map is bounded by MAX_RATE_LIMIT_BUCKETS: buckets that are full again
+/// carry no state and are dropped first, then the one closest to full.
+pub fn consume(caller: Principal, limit: RateLimit, now: u64) -> Result<(), String> {
+    let next = next_full_at(caller, &limit, now)?;
+
+    RATE_LIMIT_BUCKETS.with(|b| {
+        let mut buckets = b.borrow_mut();
+        let key = (caller, limit.bucket);
+        if !buckets.contains_key(&key) && buckets.len() >= MAX_RATE_LIMIT_BUCKETS {
+            buckets.retain(|_, full_at| *full_at > now);
+            if buckets.len() >= MAX_RATE_LIMIT_BUCKETS {
+                let closest = buckets.iter()
+                    .min_by_key(|(_, full_at)| **full_at)
+                    .map(|(key, _)| *key);
+                if let Some(closest) = closest {
+                    buckets.remove(&closest);
+                }
+            }
+        }
+        buckets.insert(key, next);
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+    const LIMIT: RateLimit = RateLimit { bucket: "test", capacity: 2, refill_interval_ns: 10 * SECOND };
+
+    #[test]
+    fn consume_allows_a_burst_then_refills_one_token_per_interval() {
+        let caller = Principal::from_slice(&[1; 29]);
+        let now = 1_000 * SECOND;
+        consume(caller, LIMIT, now).unwrap();
+        consume(caller, LIMIT, now).unwrap();
+        assert_eq!(check(caller, &LIMIT, now), Err("Rate limit exceeded for test: retry in 10s".to_string()));
+
+        // Checking does not spend a token
+        assert!(check(caller, &LIMIT, now + 10 * SECOND).is_ok());
+        consume(caller, LIMIT, now + 10 * SECOND).unwrap();
+        assert!(consume(caller, LIMIT, now + 10 * SECOND).is_err());
+    }
+
+    #[test]
+    fn buckets_are_separate_per_caller_and_bucket() {
+        let now = 1_000 * SECOND;
+        let other = RateLimit { bucket: "other", ..LIMIT };
+        for caller in [Principal::from_slice(&[2; 29]), Principal::from_slice(&[3; 29])] {
+            consume(caller, LIMIT, now).unwrap();
+            consume(caller, LIMIT, now).unwrap();
+            consume(caller, other, now).unwrap();
+        }
+        assert_eq!(rate_limit_for(&[("a", LIMIT), ("b", other)], "b").map(|l| l.bucket), Some("other"));
+        assert!(rate_limit_for(&[("a", LIMIT)], "c").is_none());
+    }
+}
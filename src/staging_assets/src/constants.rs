@@ -0,0 +1,39 @@
+use rate_limit::RateLimit;
+
+/// Default argument size limit for ingress messages
+pub const MAX_INGRESS_ARG_BYTES: usize = 4 * 1024;
+
+/// Per-method argument size limits overriding MAX_INGRESS_ARG_BYTES
+/// (staged content is sent in one message, up to the 2MB ingress limit)
+pub const INGRESS_ARG_LIMITS: &[(&str, usize)] = &[("stage_content", 2 * 1024 * 1024)];
+
+/// Methods that reject the anonymous principal anyway (governance, learning engine or controller only)
+pub const ANONYMOUS_REJECTED_METHODS: &[&str] = &[
+    "add_allowed_stager", "mark_loaded", "mark_loading", "mark_rejected",
+    "remove_allowed_stager", "set_governance_canister_id", "set_learning_engine_id",
+];
+
+/// Quiz validation at staging time (shards count questions in a u8)
+pub const MAX_QUIZ_QUESTIONS: usize = 255;
+pub const MAX_QUIZ_OPTIONS: usize = 20;
+pub const MAX_ACCEPTED_TEXT_ANSWERS: usize = 5;
+/// Must not exceed the learning engine's MAX_TEXT_ANSWER_LEN
+pub const MAX_TEXT_ANSWER_LEN: usize = 200;
+pub const MAX_NUMERIC_DECIMALS: u8 = 9;
+/// Must not exceed the learning engine's MAX_NODE_PREREQUISITES
+pub const MAX_NODE_PREREQUISITES: usize = 20;
+/// Must not exceed the learning engine's MAX_TRANSLATIONS_PER_NODE
+pub const MAX_TRANSLATIONS_PER_NODE: usize = 30;
+
+/// Staging new content
+pub const STAGING_RATE_LIMIT: RateLimit = RateLimit { bucket: "staging", capacity: 5, refill_interval_ns: 10 * 60 * 1_000_000_000 };
+
+/// Managing already staged content
+pub const STAGED_CONTENT_RATE_LIMIT: RateLimit = RateLimit { bucket: "staged_content", capacity: 20, refill_interval_ns: 30 * 1_000_000_000 };
+
+/// Rate-limited methods and their buckets (must agree with the guards in inspect.rs)
+pub const RATE_LIMITED_METHODS: &[(&str, RateLimit)] = &[
+    ("stage_content", STAGING_RATE_LIMIT),
+    ("set_proposal_id", STAGED_CONTENT_RATE_LIMIT),
+    ("delete_staged_content", STAGED_CONTENT_RATE_LIMIT),
+];
@@ -0,0 +1,45 @@
+//! Ingress filtering (canister_inspect_message) and per-caller rate limiting
+//!
+//! The token buckets live in the shared rate_limit crate; this module holds
+//! the ingress checks for this canister and one guard per bucket.
+
+use candid::Principal;
+use rate_limit::RateLimit;
+use crate::constants::*;
+
+/// Decide whether an ingress message is accepted
+pub fn check_ingress(method: &str, caller: Principal, arg_size: usize) -> Result<(), String> {
+    if caller == Principal::anonymous() && ANONYMOUS_REJECTED_METHODS.contains(&method) {
+        return Err("Anonymous actions are not allowed.".to_string());
+    }
+
+    let max_size = INGRESS_ARG_LIMITS.iter()
+        .find(|(name, _)| *name == method)
+        .map(|(_, size)| *size)
+        .unwrap_or(MAX_INGRESS_ARG_BYTES);
+    if arg_size > max_size {
+        return Err(format!("Arguments too large: {} bytes (max {})", arg_size, max_size));
+    }
+
+    if let Some(limit) = rate_limit::rate_limit_for(RATE_LIMITED_METHODS, method) {
+        rate_limit::check(caller, &limit, ic_cdk::api::time())?;
+    }
+    Ok(())
+}
+
+/// Spend one token from the caller's bucket
+fn consume_rate_limit(limit: RateLimit) -> Result<(), String> {
+    rate_limit::consume(ic_cdk::caller(), limit, ic_cdk::api::time())
+}
+
+// ============================================================================
+// GUARDS (one per bucket, referenced from #[update(guard = ...)])
+// ============================================================================
+
+pub fn limit_staging() -> Result<(), String> {
+    consume_rate_limit(STAGING_RATE_LIMIT)
+}
+
+pub fn limit_staged_content() -> Result<(), String> {
+    consume_rate_limit(STAGED_CONTENT_RATE_LIMIT)
+}
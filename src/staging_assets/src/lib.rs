@@ -4,14 +4,17 @@
 //! governance approval. Content is staged here, a proposal is created,
 //! and if approved, the learning_engine fetches content from here.
 
+mod constants;
 mod types;
 mod state;
 mod service;
+mod inspect;
 
-use ic_cdk::{init, query, update};
+use ic_cdk::{init, query, update, inspect_message};
 use candid::Principal;
 use crate::types::*;
 use crate::state::*;
+use crate::inspect::*;
 
 // ============================================================================
 // INITIALIZATION
@@ -29,13 +32,27 @@ fn init(args: InitArgs) {
     });
 }
 
+// ============================================================================
+// INGRESS FILTERING
+// ============================================================================
+
+/// Drop ingress that would be rejected anyway before it costs consensus cycles
+#[inspect_message]
+fn inspect_message() {
+    let method = ic_cdk::api::call::method_name();
+    match check_ingress(&method, ic_cdk::caller(), ic_cdk::api::call::arg_data_raw_size()) {
+        Ok(()) => ic_cdk::api::call::accept_message(),
+        Err(e) => ic_cdk::trap(&e),
+    }
+}
+
 // ============================================================================
 // STAGING FUNCTIONS
 // ============================================================================
 
 /// Stage content for governance approval
 /// Returns the content_hash which is used to reference this staged content
-#[update]
+#[update(guard = "limit_staging")]
 fn stage_content(
     title: String,
     description: String,
@@ -45,7 +62,7 @@ fn stage_content(
 }
 
 /// Associate a proposal with staged content
-#[update]
+#[update(guard = "limit_staged_content")]
 fn set_proposal_id(content_hash: String, proposal_id: u64) -> Result<(), String> {
     service::set_proposal_id_impl(ic_cdk::caller(), content_hash, proposal_id)
 }
@@ -69,7 +86,7 @@ fn mark_rejected(content_hash: String) -> Result<(), String> {
 }
 
 /// Delete staged content (cleanup after loading or rejection)
-#[update]
+#[update(guard = "limit_staged_content")]
 fn delete_staged_content(content_hash: String) -> Result<(), String> {
     service::delete_staged_content_impl(ic_cdk::caller(), content_hash)
 }
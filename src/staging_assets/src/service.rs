@@ -1,5 +1,6 @@
 use candid::{Principal, Encode};
 use sha2::{Sha256, Digest};
+use crate::constants::*;
 use crate::state::*;
 use crate::types::*;
 
@@ -54,6 +55,14 @@ pub fn stage_content_impl(
     if title.is_empty() || title.len() > 200 {
         return Err("Title must be 1-200 characters".to_string());
     }
+
+    for node in &nodes {
+        if let Some(ref quiz) = node.quiz {
+            validate_quiz(quiz).map_err(|e| format!("Invalid quiz in {}: {}", node.id, e))?;
+        }
+        validate_prerequisites(node).map_err(|e| format!("Invalid prerequisites in {}: {}", node.id, e))?;
+        validate_translations(node).map_err(|e| format!("Invalid translations in {}: {}", node.id, e))?;
+    }
     
     // Compute hash of the content
     let content_bytes = Encode!(&nodes).map_err(|e| format!("Failed to encode: {}", e))?;
@@ -84,6 +93,145 @@ pub fn stage_content_impl(
     Ok(content_hash)
 }
 
+/// Check a node's own prerequisite list (the graph is checked on load)
+pub fn validate_prerequisites(node: &ContentNode) -> Result<(), String> {
+    let prerequisites = node.prerequisites.as_deref().unwrap_or_default();
+    if prerequisites.len() > MAX_NODE_PREREQUISITES {
+        return Err(format!("at most {} prerequisites per node", MAX_NODE_PREREQUISITES));
+    }
+    for prerequisite in prerequisites {
+        match prerequisite {
+            Prerequisite::Node(id) | Prerequisite::AllChildrenOf(id) if id.is_empty() => {
+                return Err("prerequisite node id cannot be empty".to_string());
+            }
+            Prerequisite::Node(id) if *id == node.id => {
+                return Err("a node cannot require itself".to_string());
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Check a node's translations (locale tags and quiz shape are checked again on load)
+pub fn validate_translations(node: &ContentNode) -> Result<(), String> {
+    let translations = node.translations.as_deref().unwrap_or_default();
+    if translations.len() > MAX_TRANSLATIONS_PER_NODE {
+        return Err(format!("at most {} translations per node", MAX_TRANSLATIONS_PER_NODE));
+    }
+    let mut locales = Vec::with_capacity(translations.len());
+    for translation in translations {
+        let locale = translation.locale.to_lowercase().replace('_', "-");
+        if locale.is_empty() {
+            return Err("translation locale cannot be empty".to_string());
+        }
+        if locales.contains(&locale) {
+            return Err(format!("duplicate translation '{}'", translation.locale));
+        }
+        if translation.title.trim().is_empty() {
+            return Err(format!("translation '{}' has an empty title", translation.locale));
+        }
+        if let Some(ref quiz) = translation.quiz {
+            let Some(ref base) = node.quiz else {
+                return Err(format!("translation '{}' has a quiz but the node does not", translation.locale));
+            };
+            if quiz.questions.len() != base.questions.len() || quiz.draw_count != base.draw_count {
+                return Err(format!("translated quiz '{}' must match the node's question count and draw count", translation.locale));
+            }
+            validate_quiz(quiz).map_err(|e| format!("translated quiz '{}': {}", translation.locale, e))?;
+        }
+        locales.push(locale);
+    }
+    Ok(())
+}
+
+/// Check that every question can be answered and verified
+pub fn validate_quiz(quiz: &QuizData) -> Result<(), String> {
+    if quiz.questions.is_empty() || quiz.questions.len() > MAX_QUIZ_QUESTIONS {
+        return Err(format!("a quiz must have 1-{} questions", MAX_QUIZ_QUESTIONS));
+    }
+    if let Some(draw_count) = quiz.draw_count {
+        if draw_count == 0 || draw_count as usize > quiz.questions.len() {
+            return Err(format!("draw count must be 1-{} (the pool size)", quiz.questions.len()));
+        }
+    }
+    for (i, question) in quiz.questions.iter().enumerate() {
+        validate_question(question).map_err(|e| format!("question {}: {}", i + 1, e))?;
+    }
+    Ok(())
+}
+
+fn validate_question(question: &QuizQuestion) -> Result<(), String> {
+    if question.question.trim().is_empty() {
+        return Err("question text is empty".to_string());
+    }
+    if question.options.len() > MAX_QUIZ_OPTIONS {
+        return Err(format!("at most {} options", MAX_QUIZ_OPTIONS));
+    }
+    let option_count = question.options.len();
+    let in_range = |indexes: &[u8]| indexes.iter().all(|i| (*i as usize) < option_count);
+
+    match &question.answer_key {
+        None => {
+            if option_count < 2 {
+                return Err("single choice needs at least 2 options".to_string());
+            }
+            if question.answer as usize >= option_count {
+                return Err("answer is not an option index".to_string());
+            }
+        }
+        Some(AnswerKey::MultiSelect(indexes)) => {
+            if option_count < 2 || indexes.is_empty() {
+                return Err("multi-select needs at least 2 options and 1 correct option".to_string());
+            }
+            if !in_range(indexes) {
+                return Err("correct options must be option indexes".to_string());
+            }
+        }
+        Some(AnswerKey::TrueFalse(_)) => {
+            if !question.options.is_empty() {
+                return Err("true/false questions take no options".to_string());
+            }
+        }
+        Some(AnswerKey::Numeric { tolerance, decimals, .. }) => {
+            if !question.options.is_empty() {
+                return Err("numeric questions take no options".to_string());
+            }
+            if *decimals > MAX_NUMERIC_DECIMALS {
+                return Err(format!("at most {} decimals", MAX_NUMERIC_DECIMALS));
+            }
+            if *tolerance > i64::MAX as u64 {
+                return Err("tolerance is too large".to_string());
+            }
+        }
+        Some(AnswerKey::Ordering(order)) => {
+            if option_count < 2 || order.len() != option_count {
+                return Err("ordering must list every option (at least 2)".to_string());
+            }
+            let mut seen = vec![false; option_count];
+            for index in order {
+                match seen.get_mut(*index as usize) {
+                    Some(slot) if !*slot => *slot = true,
+                    _ => return Err("ordering must use each option index once".to_string()),
+                }
+            }
+        }
+        Some(AnswerKey::ShortText(accepted)) => {
+            if !question.options.is_empty() {
+                return Err("short text questions take no options".to_string());
+            }
+            if accepted.is_empty() || accepted.len() > MAX_ACCEPTED_TEXT_ANSWERS {
+                return Err(format!("1-{} accepted answers", MAX_ACCEPTED_TEXT_ANSWERS));
+            }
+            // Matching ignores everything but letters and digits
+            if accepted.iter().any(|a| a.len() > MAX_TEXT_ANSWER_LEN || !a.chars().any(char::is_alphanumeric)) {
+                return Err(format!("accepted answers need a letter or digit and at most {} bytes", MAX_TEXT_ANSWER_LEN));
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn set_proposal_id_impl(caller: Principal, content_hash: String, proposal_id: u64) -> Result<(), String> {
     let mut content = STAGED_CONTENT.with(|s| s.borrow().get(&content_hash))
         .ok_or("Staged content not found")?;
@@ -30,13 +30,51 @@ pub struct MediaContent {
 pub struct QuizQuestion {
     pub question: String,
     pub options: Vec<String>,
+    /// Index of the correct answer, for single-choice questions
     pub answer: u8,
+    /// Answer key of any other question type (None = single choice)
+    pub answer_key: Option<AnswerKey>,
+}
+
+/// Correct answer of a question that is not single-choice
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum AnswerKey {
+    MultiSelect(Vec<u8>),
+    TrueFalse(bool),
+    Numeric { value: i64, tolerance: u64, decimals: u8 },
+    Ordering(Vec<u8>),
+    ShortText(Vec<String>),
 }
 
 /// Quiz data attached to a content node
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct QuizData {
     pub questions: Vec<QuizQuestion>,
+    /// Questions each learner draws from the pool (None = all, in order)
+    pub draw_count: Option<u8>,
+}
+
+/// Requirement before a node's quiz can be taken (mirrors learning_engine::Prerequisite)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum Prerequisite {
+    /// The node is passed; a node without a quiz needs every quiz under it
+    Node(String),
+    /// Every quiz below the node (its own quiz excluded)
+    AllChildrenOf(String),
+}
+
+/// A node's text and quiz in one locale (mirrors learning_engine::LocalizedContent)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LocalizedContent {
+    /// Language tag such as "es" or "pt-BR"
+    pub locale: String,
+    pub title: String,
+    /// Fields left empty fall back to the node's own
+    pub description: Option<String>,
+    pub content: Option<String>,
+    pub paraphrase: Option<String>,
+    /// Must ask the same kinds of questions as the node's quiz
+    pub quiz: Option<QuizData>,
 }
 
 /// The universal content node
@@ -52,6 +90,10 @@ pub struct ContentNode {
     pub paraphrase: Option<String>,
     pub media: Option<MediaContent>,
     pub quiz: Option<QuizData>,
+    /// Checked for cycles by the learning engine when loaded
+    pub prerequisites: Option<Vec<Prerequisite>>,
+    /// Per-locale variants, stored separately by the learning engine
+    pub translations: Option<Vec<LocalizedContent>>,
     pub created_at: u64,
     pub updated_at: u64,
     pub version: u64,
@@ -9,6 +9,51 @@ pub const SHARD_SOFT_LIMIT: u64 = 90_000;  // Start creating new shard at 90K us
 pub const SHARD_HARD_LIMIT: u64 = 100_000; // Max users per shard
 pub const AUTO_SCALE_INTERVAL_SECS: u64 = 60; // Check every minute
 
+// Emission Schedule
+pub const BPS_TOTAL: u64 = 10_000; // 10,000 BPS = 100.00% of MAX_SUPPLY
+pub const EMISSION_PROJECTION_STEP_BPS: u64 = 1_000; // Project rewards every 10% for smooth curves
+pub const MAX_DECAY_EXPONENT: u8 = 8;
+pub const MAX_EMISSION_MILESTONES: u64 = 64; // Cap projection size (a halving reward is 0 after 64 epochs anyway)
+
+// Global Emission Budget (rolling windows, 8 decimals)
+pub const DEFAULT_DAILY_EMISSION_BUDGET: u64 = 500_000 * 100_000_000;    // 500K GHC per rolling 24h
+pub const DEFAULT_WEEKLY_EMISSION_BUDGET: u64 = 2_500_000 * 100_000_000; // 2.5M GHC per rolling 7d
+pub const NANOS_PER_HOUR: u64 = 60 * 60 * 1_000_000_000;
+pub const EMISSION_DAY_HOURS: u64 = 24;
+pub const EMISSION_WEEK_HOURS: u64 = 7 * 24;
+
+// Achievement Badges
+pub const MAX_BADGE_DEFINITIONS: u64 = 100;
+pub const MAX_BADGE_ID_LEN: usize = 64;
+pub const MAX_BADGE_NAME_LEN: usize = 100;
+pub const MAX_BADGE_DESCRIPTION_LEN: usize = 500;
+pub const MAX_BADGE_SUBTREE_UNITS: usize = 500;
+pub const MAX_BADGE_BONUS_REWARD: u64 = 100 * 100_000_000; // 100 GHC
+pub const BADGE_DEFINITION_MAX_SIZE: u32 = 48_000;
+
+// Reward Multipliers & Sponsored Bounties
+pub const DEFAULT_REWARD_MULTIPLIER_BPS: u32 = 10_000; // 1x (no override)
+pub const MIN_REWARD_MULTIPLIER_BPS: u32 = 5_000;      // 0.5x
+pub const MAX_REWARD_MULTIPLIER_BPS: u32 = 50_000;     // 5x
+pub const MAX_OPEN_BOUNTIES: usize = 100;
+pub const MAX_BOUNTY_SUBTREE_UNITS: usize = 500;
+pub const MAX_BOUNTY_UNIT_ID_LEN: usize = 200;
+pub const MIN_BOUNTY_REWARD_PER_PASS: u64 = 100_000;             // 0.001 GHC
+pub const MAX_BOUNTY_REWARD_PER_PASS: u64 = 1_000 * 100_000_000; // 1,000 GHC
+pub const BOUNTY_MAX_SIZE: u32 = 120_000;
+pub const MAX_BOUNTY_PAYOUT_PAGE: u32 = 100;
+pub const UNIT_RULES_PER_CALL: usize = 500; // Rules pushed to a shard per call
+
+// Referral Codes
+pub const REFERRAL_CODE_LENGTH: usize = 8;
+pub const REFERRAL_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+// Peer-to-peer Transfers
+pub const MAX_TRANSFER_FAILURE_REASON_LEN: usize = 256; // Keeps StakedTransferRecord within its bound
+
+// Notifications
+pub const MAX_NOTIFICATION_RECIPIENTS: usize = 500; // Per route_notification call (and per shard call)
+
 // Token Limit Ranges (8 decimals)
 pub const MIN_REGULAR_DAILY: u64 = 2 * 100_000_000;
 pub const MAX_REGULAR_DAILY: u64 = 10_000 * 100_000_000;   // Up to 10,000 GHC
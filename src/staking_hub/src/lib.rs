@@ -7,6 +7,7 @@ use ic_cdk::{init, query, update};
 use ic_cdk_timers::set_timer_interval;
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
 use candid::{Principal, Nat};
 
 use types::*;
@@ -58,6 +59,19 @@ fn init(args: InitArgs) {
 
 #[ic_cdk::post_upgrade]
 fn post_upgrade() {
+    // Hubs upgraded from before the emission schedule existed keep their
+    // governance-set flat reward as the schedule's base reward
+    EMISSION_SCHEDULE.with(|s| {
+        let mut cell = s.borrow_mut();
+        let mut schedule = cell.get().clone();
+        let current_reward = TOKEN_LIMITS_CONFIG.with(|c| c.borrow().get().reward_amount);
+        if schedule.curve == EmissionCurve::Flat && schedule.base_reward_amount != current_reward {
+            schedule.base_reward_amount = current_reward;
+            schedule.min_reward_amount = schedule.min_reward_amount.min(current_reward);
+            cell.set(schedule).expect("Failed to migrate emission schedule");
+        }
+    });
+
     // Restart auto-scaling timer after upgrade
     start_auto_scale_timer();
 }
@@ -100,6 +114,59 @@ async fn distribute_quiz_cache(unit_id: String, cache_data: QuizCacheData) -> Re
     Ok(success_count)
 }
 
+/// Distribute a unit's translated quiz locales to all shards
+/// Called by the learning engine when a translation is added, changed or removed
+#[update]
+async fn distribute_quiz_locales(unit_id: String, locales: Vec<String>) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    let learning_id = LEARNING_CONTENT_ID.with(|id| *id.borrow().get());
+    if caller != learning_id {
+        return Err("Unauthorized".to_string());
+    }
+
+    let shards: Vec<Principal> = SHARD_REGISTRY.with(|r| {
+        r.borrow().iter().map(|(_, s)| s.canister_id).collect()
+    });
+
+    let mut success_count = 0;
+    for shard in shards {
+        let u = unit_id.clone();
+        let l = locales.clone();
+        // Fire and forget to avoid blocking hub
+        ic_cdk::spawn(async move {
+            let _ = ic_cdk::call::<_, ()>(shard, "receive_quiz_locales", (u, l)).await;
+        });
+        success_count += 1;
+    }
+    Ok(success_count)
+}
+
+/// Distribute the resolved prerequisites of units to all shards
+/// Called by the learning engine when the content graph changes what a unit requires
+#[update]
+async fn distribute_prerequisites(updates: Vec<(String, Vec<String>)>) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    let learning_id = LEARNING_CONTENT_ID.with(|id| *id.borrow().get());
+    if caller != learning_id {
+        return Err("Unauthorized".to_string());
+    }
+
+    let shards: Vec<Principal> = SHARD_REGISTRY.with(|r| {
+        r.borrow().iter().map(|(_, s)| s.canister_id).collect()
+    });
+
+    let mut success_count = 0;
+    for shard in shards {
+        let u = updates.clone();
+        // Fire and forget to avoid blocking hub
+        ic_cdk::spawn(async move {
+            let _ = ic_cdk::call::<_, ()>(shard, "receive_prerequisites", (u,)).await;
+        });
+        success_count += 1;
+    }
+    Ok(success_count)
+}
+
 /// Distribute token limits update to all shards
 /// Called by itself (after governance update) or manually by controllers
 #[update]
@@ -137,6 +204,7 @@ async fn update_token_limits(
     new_max_attempts: Option<u8>,
     regular_limits: Option<TokenLimits>,
     subscribed_limits: Option<TokenLimits>,
+    limit_mode: Option<LimitMode>,
 ) -> Result<(), String> {
     // Auth check: in production, verify caller is governance canister
     // For now, allow controllers
@@ -177,15 +245,29 @@ async fn update_token_limits(
         }
     }
 
+    // The reward amount is the base of the emission schedule; the effective
+    // reward is derived from it below
+    if let Some(val) = new_reward_amount {
+        let mut schedule = EMISSION_SCHEDULE.with(|s| s.borrow().get().clone());
+        schedule.base_reward_amount = val;
+        schedule.min_reward_amount = schedule.min_reward_amount.min(val);
+        validate_emission_schedule(&schedule)?;
+        EMISSION_SCHEDULE.with(|s| s.borrow_mut().set(schedule).expect("Failed to update emission schedule"));
+    }
+
+    let total_allocated = GLOBAL_STATS.with(|s| s.borrow().get().total_allocated);
+    let effective_reward = EMISSION_SCHEDULE.with(|s| compute_effective_reward(s.borrow().get(), total_allocated));
+
     let new_config = TOKEN_LIMITS_CONFIG.with(|c| {
         let mut cell = c.borrow_mut();
         let mut config = cell.get().clone();
         
-        if let Some(val) = new_reward_amount { config.reward_amount = val; }
+        config.reward_amount = effective_reward;
         if let Some(val) = new_pass_threshold { config.pass_threshold_percent = val; }
         if let Some(val) = new_max_attempts { config.max_daily_attempts = val; }
         if let Some(limits) = regular_limits { config.regular_limits = limits; }
         if let Some(limits) = subscribed_limits { config.subscribed_limits = limits; }
+        if let Some(mode) = limit_mode { config.limit_mode = Some(mode); }
         
         config.version += 1;
         cell.set(config.clone()).expect("Failed to update token limits config");
@@ -203,6 +285,342 @@ fn get_token_limits() -> TokenLimitsConfig {
     TOKEN_LIMITS_CONFIG.with(|c| c.borrow().get().clone())
 }
 
+// ===============================
+// Emission Schedule
+// ===============================
+
+/// Replace the emission schedule and push the resulting reward to all shards
+/// Called by governance after an UpdateEmissionSchedule proposal passes, or by controllers
+#[update]
+async fn update_emission_schedule(schedule: EmissionSchedule) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    let governance_id = GOVERNANCE_CANISTER_ID.with(|id| *id.borrow().get());
+    let is_gov = governance_id != Principal::anonymous() && caller == governance_id;
+    if !is_gov && !ic_cdk::api::is_controller(&caller) {
+        return Err("Unauthorized: Only governance or controllers can update the emission schedule".to_string());
+    }
+
+    validate_emission_schedule(&schedule)?;
+
+    EMISSION_SCHEDULE.with(|s| {
+        s.borrow_mut().set(schedule).expect("Failed to update emission schedule");
+    });
+
+    let config = match refresh_effective_reward() {
+        Some(config) => config,
+        None => TOKEN_LIMITS_CONFIG.with(|c| c.borrow().get().clone()),
+    };
+    let reward_amount = config.reward_amount;
+    distribute_token_limits_internal(config).await;
+
+    Ok(reward_amount)
+}
+
+#[query]
+fn get_emission_schedule() -> EmissionSchedule {
+    EMISSION_SCHEDULE.with(|s| s.borrow().get().clone())
+}
+
+/// Project the per-quiz reward at each milestone of the emission curve
+#[query]
+fn get_emission_projection() -> Vec<EmissionMilestone> {
+    let schedule = EMISSION_SCHEDULE.with(|s| s.borrow().get().clone());
+    let total_allocated = GLOBAL_STATS.with(|s| s.borrow().get().total_allocated);
+    project_emission_milestones(&schedule, total_allocated)
+}
+
+/// Update the global rolling emission budget (governance or controllers)
+#[update]
+fn update_emission_budget(config: EmissionBudgetConfig) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let governance_id = GOVERNANCE_CANISTER_ID.with(|id| *id.borrow().get());
+    let is_gov = governance_id != Principal::anonymous() && caller == governance_id;
+    if !is_gov && !ic_cdk::api::is_controller(&caller) {
+        return Err("Unauthorized: Only governance or controllers can update the emission budget".to_string());
+    }
+
+    validate_emission_budget(&config)?;
+
+    EMISSION_BUDGET_CONFIG.with(|c| {
+        c.borrow_mut().set(config).expect("Failed to update emission budget");
+    });
+
+    Ok(())
+}
+
+/// Current usage and remaining allowance of the global emission budget
+#[query]
+fn get_emission_budget_status() -> EmissionBudgetStatus {
+    get_emission_budget_status_internal()
+}
+
+/// Allowance a shard can currently draw and the bound limiting it
+#[query]
+fn get_shard_emission_status(shard: Principal) -> ShardEmissionStatus {
+    shard_emission_status(shard)
+}
+
+/// Create or replace an achievement badge (governance or controllers)
+///
+/// SubtreeCompleted rules are resolved to their quiz units here, then the
+/// definition is pushed to all shards. Returns the number of shards updated.
+#[update]
+async fn upsert_badge_definition(badge: BadgeDefinition) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    let governance_id = GOVERNANCE_CANISTER_ID.with(|id| *id.borrow().get());
+    let is_gov = governance_id != Principal::anonymous() && caller == governance_id;
+    if !is_gov && !ic_cdk::api::is_controller(&caller) {
+        return Err("Unauthorized: Only governance or controllers can manage badges".to_string());
+    }
+
+    validate_badge_definition(&badge)?;
+    let mut badge = badge;
+    resolve_badge_rule(&mut badge).await?;
+
+    BADGE_DEFINITIONS.with(|b| b.borrow_mut().insert(badge.id.clone(), badge.clone()));
+
+    Ok(distribute_badge_definition_internal(badge).await)
+}
+
+#[query]
+fn get_badge_definitions() -> Vec<BadgeDefinition> {
+    BADGE_DEFINITIONS.with(|b| b.borrow().iter().map(|(_, badge)| badge).collect())
+}
+
+// ===============================
+// Reward Multipliers & Sponsored Bounties
+// ===============================
+
+/// Set a unit's reward multiplier and push it to all shards
+/// Called by governance after a SetRewardMultiplier proposal passes, or by controllers.
+/// 10,000 BPS (1x) removes the override. Returns the number of shards updated.
+#[update]
+async fn set_reward_multiplier(content_id: String, multiplier_bps: u32) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    let governance_id = GOVERNANCE_CANISTER_ID.with(|id| *id.borrow().get());
+    let is_gov = governance_id != Principal::anonymous() && caller == governance_id;
+    if !is_gov && !ic_cdk::api::is_controller(&caller) {
+        return Err("Unauthorized: Only governance or controllers can set reward multipliers".to_string());
+    }
+
+    validate_reward_multiplier(&content_id, multiplier_bps)?;
+
+    REWARD_MULTIPLIERS.with(|m| {
+        let mut map = m.borrow_mut();
+        if multiplier_bps == DEFAULT_REWARD_MULTIPLIER_BPS {
+            map.remove(&content_id);
+        } else {
+            map.insert(content_id.clone(), multiplier_bps);
+        }
+    });
+
+    Ok(distribute_unit_reward_rules(vec![content_id]).await)
+}
+
+#[query]
+fn get_reward_multipliers() -> Vec<(String, u32)> {
+    REWARD_MULTIPLIERS.with(|m| m.borrow().iter().collect())
+}
+
+/// Fund a bounty for every quiz unit under `root_id`
+///
+/// Pulls `amount` from the caller via ICRC-2 (approve the hub first). Each
+/// learner who passes a covered unit earns `reward_per_pass` from the escrow,
+/// once per unit, until it runs dry. Returns the bounty id.
+#[update]
+async fn fund_bounty(root_id: String, amount: u64, reward_per_pass: u64) -> Result<u64, String> {
+    let sponsor = ic_cdk::caller();
+    if sponsor == Principal::anonymous() {
+        return Err("Anonymous principals cannot fund bounties".to_string());
+    }
+
+    validate_bounty(&root_id, amount, reward_per_pass)?;
+    let unit_ids = resolve_bounty_units(&root_id).await?;
+
+    let ledger_id = LEDGER_ID.with(|id| *id.borrow().get());
+    let args = TransferFromArgs {
+        spender_subaccount: None,
+        from: Account { owner: sponsor, subaccount: None },
+        to: Account { owner: ic_cdk::api::id(), subaccount: None },
+        amount: Nat::from(amount),
+        fee: None,
+        memo: None,
+        created_at_time: None,
+    };
+
+    let (result,): (Result<Nat, TransferFromError>,) = ic_cdk::call(
+        ledger_id,
+        "icrc2_transfer_from",
+        (args,)
+    ).await.map_err(|(code, msg)| format!("Transfer call failed: {:?} {}", code, msg))?;
+    result.map_err(|e| format!("Deposit failed: {:?}", e))?;
+
+    let id = BOUNTY_COUNT.with(|c| {
+        let mut cell = c.borrow_mut();
+        let id = *cell.get();
+        cell.set(id + 1).expect("Failed to update bounty count");
+        id
+    });
+    BOUNTIES.with(|b| b.borrow_mut().insert(id, Bounty {
+        id,
+        sponsor,
+        root_id,
+        unit_ids: unit_ids.clone(),
+        reward_per_pass,
+        deposited: amount,
+        remaining: amount,
+        paid_out: 0,
+        payout_count: 0,
+        created_at: ic_cdk::api::time(),
+        status: BountyStatus::Open,
+        closed_at: None,
+    }));
+
+    distribute_unit_reward_rules(unit_ids).await;
+    Ok(id)
+}
+
+/// Close a bounty and refund the remaining escrow to its sponsor
+/// Callable by the sponsor, governance or controllers. Returns the amount
+/// refunded, net of the ledger fee (escrow not above the fee stays put).
+#[update]
+async fn close_bounty(bounty_id: u64) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    let mut bounty = BOUNTIES.with(|b| b.borrow().get(&bounty_id)).ok_or("Bounty not found")?;
+
+    let governance_id = GOVERNANCE_CANISTER_ID.with(|id| *id.borrow().get());
+    let is_gov = governance_id != Principal::anonymous() && caller == governance_id;
+    if caller != bounty.sponsor && !is_gov && !ic_cdk::api::is_controller(&caller) {
+        return Err("Unauthorized: Only the sponsor, governance or controllers can close a bounty".to_string());
+    }
+    match bounty.status {
+        BountyStatus::Open => {}
+        BountyStatus::Closing => return Err("Bounty is already closing".to_string()),
+        BountyStatus::Closed => return Err("Bounty is already closed".to_string()),
+    }
+
+    // Mark Closing before the ledger calls so no payouts are reserved or
+    // released meanwhile
+    let escrow = bounty.remaining;
+    bounty.remaining = 0;
+    bounty.status = BountyStatus::Closing;
+    BOUNTIES.with(|b| b.borrow_mut().insert(bounty_id, bounty.clone()));
+
+    let refund = match refund_bounty_escrow(bounty.sponsor, escrow).await {
+        Ok(refund) => refund,
+        Err(error) => {
+            // Rollback: re-read so only the escrow taken above is restored
+            BOUNTIES.with(|b| {
+                let mut map = b.borrow_mut();
+                if let Some(mut current) = map.get(&bounty_id) {
+                    current.remaining += escrow;
+                    current.status = BountyStatus::Open;
+                    map.insert(bounty_id, current);
+                }
+            });
+            return Err(error);
+        }
+    };
+
+    BOUNTIES.with(|b| {
+        let mut map = b.borrow_mut();
+        if let Some(mut current) = map.get(&bounty_id) {
+            current.status = BountyStatus::Closed;
+            current.closed_at = Some(ic_cdk::api::time());
+            map.insert(bounty_id, current);
+        }
+    });
+
+    distribute_unit_reward_rules(bounty.unit_ids).await;
+    Ok(refund)
+}
+
+/// Send a closed bounty's escrow back to its sponsor, paying the ledger fee
+/// out of it. Returns the amount received by the sponsor.
+async fn refund_bounty_escrow(sponsor: Principal, escrow: u64) -> Result<u64, String> {
+    if escrow == 0 {
+        return Ok(0);
+    }
+    let ledger_id = LEDGER_ID.with(|id| *id.borrow().get());
+
+    let (fee,): (Nat,) = ic_cdk::call(ledger_id, "icrc1_fee", ())
+        .await
+        .map_err(|(code, msg)| format!("Fee query failed: {:?} {}", code, msg))?;
+    let fee = u64::try_from(&fee.0).map_err(|_| "Ledger fee out of range".to_string())?;
+    if escrow <= fee {
+        return Ok(0);
+    }
+
+    let args = TransferArg {
+        from_subaccount: None,
+        to: Account { owner: sponsor, subaccount: None },
+        amount: Nat::from(escrow - fee),
+        fee: Some(Nat::from(fee)),
+        memo: None,
+        created_at_time: None,
+    };
+
+    let call_result: Result<(Result<Nat, TransferError>,), _> = ic_cdk::call(
+        ledger_id,
+        "icrc1_transfer",
+        (args,)
+    ).await;
+    match call_result {
+        Ok((Ok(_),)) => Ok(escrow - fee),
+        Ok((Err(e),)) => Err(format!("Ledger transfer failed: {:?}", e)),
+        Err((code, msg)) => Err(format!("Transfer call failed: {:?} {}", code, msg)),
+    }
+}
+
+#[query]
+fn get_bounty(bounty_id: u64) -> Option<Bounty> {
+    BOUNTIES.with(|b| b.borrow().get(&bounty_id))
+}
+
+/// List bounties, newest first
+#[query]
+fn list_bounties(include_closed: bool) -> Vec<Bounty> {
+    BOUNTIES.with(|b| {
+        b.borrow().iter().rev()
+            .map(|(_, bounty)| bounty)
+            .filter(|bounty| include_closed || bounty.status != BountyStatus::Closed)
+            .collect()
+    })
+}
+
+/// Page through a bounty's payouts
+#[query]
+fn get_bounty_payouts(bounty_id: u64, offset: u64, limit: u32) -> Vec<BountyPayout> {
+    let start = BountyPayoutKey { bounty_id, user: Principal::from_slice(&[]), unit_id: String::new() };
+    BOUNTY_PAYOUTS.with(|p| {
+        p.borrow().range(start..)
+            .take_while(|(k, _)| k.bounty_id == bounty_id)
+            .skip(offset as usize)
+            .take(limit.min(MAX_BOUNTY_PAYOUT_PAGE) as usize)
+            .map(|(_, payout)| payout)
+            .collect()
+    })
+}
+
+/// Set the governance canister allowed to apply economy proposals (controller only)
+#[update]
+fn admin_set_governance_canister_id(new_id: Principal) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Unauthorized: Only controllers can set the governance canister".to_string());
+    }
+
+    GOVERNANCE_CANISTER_ID.with(|id| {
+        id.borrow_mut().set(new_id).expect("Failed to set governance canister ID");
+    });
+
+    Ok(())
+}
+
+#[query]
+fn get_governance_canister_id() -> Principal {
+    GOVERNANCE_CANISTER_ID.with(|id| *id.borrow().get())
+}
+
 #[query]
 fn get_subscription_manager_id() -> Principal {
     SUBSCRIPTION_MANAGER_ID.with(|id| *id.borrow().get())
@@ -213,6 +631,11 @@ fn get_kyc_manager_id() -> Principal {
     KYC_MANAGER_ID.with(|id| *id.borrow().get())
 }
 
+#[query]
+fn get_credentials_collection_id() -> Principal {
+    CREDENTIALS_COLLECTION_ID.with(|id| *id.borrow().get())
+}
+
 
 // ===============================
 // Shard Discovery Queries (for Frontend)
@@ -358,6 +781,37 @@ async fn admin_broadcast_kyc_manager(new_id: Principal) -> Result<u64, String> {
     Ok(success_count)
 }
 
+/// Broadcast the credentials NFT collection ID to all registered shards
+/// 
+/// SECURITY: Only callable by controllers
+#[update]
+async fn admin_broadcast_credentials_collection(new_id: Principal) -> Result<u64, String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Unauthorized: Only controllers can broadcast".to_string());
+    }
+
+    // Update local state
+    CREDENTIALS_COLLECTION_ID.with(|id| {
+        id.borrow_mut().set(new_id).expect("Failed to set credentials collection ID");
+    });
+
+    // Get list of shards
+    let shards: Vec<Principal> = SHARD_REGISTRY.with(|r| {
+        r.borrow().iter().map(|(_, s)| s.canister_id).collect()
+    });
+
+    let mut success_count = 0;
+    for shard in shards {
+        // Call each shard to update its credentials collection
+        let result = ic_cdk::call::<_, ()>(shard, "internal_sync_credentials_collection", (new_id,)).await;
+        if result.is_ok() {
+            success_count += 1;
+        }
+    }
+
+    Ok(success_count)
+}
+
 /// Get the archive canister ID for a specific shard
 #[query]
 fn get_archive_for_shard(shard_id: Principal) -> Option<Principal> {
@@ -444,7 +898,7 @@ fn sync_shard(
         return Err("Unauthorized: Caller is not a registered shard".to_string());
     }
     
-    GLOBAL_STATS.with(|s| {
+    let granted_allowance = GLOBAL_STATS.with(|s| {
         let mut cell = s.borrow_mut();
         let mut stats = cell.get().clone();
         
@@ -478,8 +932,29 @@ fn sync_shard(
         };
         
         cell.set(stats).expect("Failed to update global stats");
-        Ok(granted_allowance)
-    })
+        granted_allowance
+    });
+
+    // ─────────────────────────────────────────────────────────────────
+    // Step 3: Enforce the global emission budget
+    // ─────────────────────────────────────────────────────────────────
+    // Grants are clamped to the rolling daily/weekly budget and to this
+    // shard's share of it, plus shares idle shards have released today.
+    // A zero grant tells the shard the budget is spent.
+    let granted_allowance = ration_allowance_grant(caller, granted_allowance);
+
+    // ─────────────────────────────────────────────────────────────────
+    // Step 4: Follow the emission curve
+    // ─────────────────────────────────────────────────────────────────
+    // New minting moves total_allocated along the curve; if that crosses into
+    // a lower reward, push the new config to all shards in the background.
+    if let Some(config) = refresh_effective_reward() {
+        ic_cdk::spawn(async move {
+            distribute_token_limits_internal(config).await;
+        });
+    }
+
+    Ok(granted_allowance)
 }
 
 /// Process unstake request from a shard - returns 100% (no penalty)
@@ -585,6 +1060,9 @@ fn register_user_location(user: Principal) -> Result<(), String> {
     if !is_registered {
         return Err("Unauthorized: Caller is not a registered shard".to_string());
     }
+    if CANONICAL_OF_LINKED.with(|l| l.borrow().contains_key(&user)) {
+        return Err("Principal is linked to an existing account".to_string());
+    }
     
     // Store user -> shard mapping
     USER_SHARD_MAP.with(|m| {
@@ -594,6 +1072,217 @@ fn register_user_location(user: Principal) -> Result<(), String> {
     Ok(())
 }
 
+/// Get (or issue) the referral code of a user (called by the user's shard)
+#[update]
+fn register_referral_code(user: Principal) -> Result<String, String> {
+    let caller = ic_cdk::caller();
+
+    // Only the shard holding the user can request their code
+    if USER_SHARD_MAP.with(|m| m.borrow().get(&user)) != Some(caller) {
+        return Err("Unauthorized: User is not registered in the calling shard".to_string());
+    }
+
+    Ok(get_or_create_referral_code(user))
+}
+
+/// Resolve a referral code to the referrer's principal
+#[query]
+fn resolve_referral_code(code: String) -> Option<Principal> {
+    REFERRAL_CODES.with(|c| c.borrow().get(&code.trim().to_uppercase()))
+}
+
+/// Route a qualified referral to the referrer's shard so it can pay the
+/// referrer bonus (called by the referee's shard)
+///
+/// Returns the bonus credited to the referrer, or the referrer shard's
+/// rejection (e.g. referral cap reached).
+#[update]
+async fn route_referral_reward(referrer: Principal, referee: Principal) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+
+    if USER_SHARD_MAP.with(|m| m.borrow().get(&referee)) != Some(caller) {
+        return Err("Unauthorized: Referee is not registered in the calling shard".to_string());
+    }
+
+    // A shard that missed receive_referrer_migration may still name a
+    // recovered referrer by its old principal
+    let referrer = current_principal(referrer);
+    let referrer_shard = USER_SHARD_MAP.with(|m| m.borrow().get(&referrer))
+        .ok_or("Referrer shard not found")?;
+
+    let result: Result<(Result<u64, String>,), _> = ic_cdk::call(
+        referrer_shard,
+        "internal_credit_referral",
+        (referrer, referee)
+    ).await;
+
+    match result {
+        Ok((inner,)) => inner,
+        Err((code, msg)) => Err(format!("Referrer shard call failed: {:?} {}", code, msg)),
+    }
+}
+
+/// Move a user's registry entries to a new principal after account recovery
+/// (called by the shard holding the user)
+#[update]
+fn migrate_user_location(old_user: Principal, new_user: Principal) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    if USER_SHARD_MAP.with(|m| m.borrow().get(&old_user)) != Some(caller) {
+        return Err("Unauthorized: User is not registered in the calling shard".to_string());
+    }
+    if let Some(existing) = USER_SHARD_MAP.with(|m| m.borrow().get(&new_user)) {
+        if existing != caller {
+            return Err("New principal is already registered in another shard".to_string());
+        }
+    }
+    if CANONICAL_OF_LINKED.with(|l| l.borrow().contains_key(&new_user)) {
+        return Err("New principal is linked to an existing account".to_string());
+    }
+
+    USER_SHARD_MAP.with(|m| {
+        let mut map = m.borrow_mut();
+        map.remove(&old_user);
+        map.insert(new_user, caller);
+    });
+
+    // Referral codes follow the account
+    if let Some(code) = USER_REFERRAL_CODES.with(|c| c.borrow_mut().remove(&old_user)) {
+        REFERRAL_CODES.with(|c| c.borrow_mut().insert(code.clone(), new_user));
+        USER_REFERRAL_CODES.with(|c| c.borrow_mut().insert(new_user, code));
+    }
+
+    // So do referrals made by the user, which are kept on the referees' shards
+    RECOVERED_PRINCIPALS.with(|r| r.borrow_mut().insert(old_user, new_user));
+    let shards: Vec<Principal> = SHARD_REGISTRY.with(|r| {
+        r.borrow().iter().map(|(_, s)| s.canister_id).collect()
+    });
+    for shard in shards {
+        // Fire and forget; route_referral_reward also resolves the old principal
+        ic_cdk::spawn(async move {
+            let _ = ic_cdk::call::<_, ()>(shard, "receive_referrer_migration", (old_user, new_user)).await;
+        });
+    }
+
+    Ok(())
+}
+
+/// Attach a secondary principal to a canonical account (called by the
+/// canonical principal's shard once both principals completed the handshake)
+#[update]
+fn register_linked_principal(canonical: Principal, secondary: Principal) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    if USER_SHARD_MAP.with(|m| m.borrow().get(&canonical)) != Some(caller) {
+        return Err("Unauthorized: User is not registered in the calling shard".to_string());
+    }
+    if CANONICAL_OF_LINKED.with(|l| l.borrow().contains_key(&canonical)) {
+        return Err("Only a canonical principal can link other principals".to_string());
+    }
+    if USER_SHARD_MAP.with(|m| m.borrow().contains_key(&secondary)) {
+        return Err("Principal is already registered or linked".to_string());
+    }
+
+    USER_SHARD_MAP.with(|m| m.borrow_mut().insert(secondary, caller));
+    CANONICAL_OF_LINKED.with(|l| l.borrow_mut().insert(secondary, canonical));
+
+    Ok(())
+}
+
+/// Detach a secondary principal (called by the shard holding the account)
+/// Idempotent so shards can safely retry.
+#[update]
+fn unregister_linked_principal(secondary: Principal) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    if !CANONICAL_OF_LINKED.with(|l| l.borrow().contains_key(&secondary)) {
+        return Ok(());
+    }
+    if USER_SHARD_MAP.with(|m| m.borrow().get(&secondary)) != Some(caller) {
+        return Err("Unauthorized: Principal is not linked in the calling shard".to_string());
+    }
+
+    CANONICAL_OF_LINKED.with(|l| l.borrow_mut().remove(&secondary));
+    USER_SHARD_MAP.with(|m| m.borrow_mut().remove(&secondary));
+
+    Ok(())
+}
+
+/// Resolve a principal to the canonical principal of its account
+/// (unlinked principals resolve to themselves)
+#[query]
+fn get_canonical_principal(user: Principal) -> Principal {
+    CANONICAL_OF_LINKED.with(|l| l.borrow().get(&user)).unwrap_or(user)
+}
+
+/// Route a staked-balance transfer to the recipient's shard
+/// Called by the sender's shard after debiting; idempotent per transfer_id.
+/// Ok means credited, Err means the sender should be refunded.
+#[update]
+async fn route_staked_transfer(request: StakedTransferRequest) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+
+    if USER_SHARD_MAP.with(|m| m.borrow().get(&request.from)) != Some(caller) {
+        return Err("Unauthorized: Sender is not registered in the calling shard".to_string());
+    }
+
+    if let Some(record) = STAKED_TRANSFERS.with(|t| t.borrow().get(&request.transfer_id)) {
+        return match record.outcome {
+            StakedTransferOutcome::Completed => Ok(()),
+            StakedTransferOutcome::Failed { reason } => Err(reason),
+        };
+    }
+
+    route_staked_transfer_internal(caller, request).await
+}
+
+#[query]
+fn get_staked_transfer(transfer_id: String) -> Option<StakedTransferRecord> {
+    STAKED_TRANSFERS.with(|t| t.borrow().get(&transfer_id))
+}
+
+/// Pay a learner from the bounties covering a unit they just passed
+/// Called by the learner's shard; each bounty pays a user once per unit.
+/// Returns the amount credited (0 if no open bounty applies).
+#[update]
+async fn route_bounty_claim(user: Principal, unit_id: String) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+
+    if USER_SHARD_MAP.with(|m| m.borrow().get(&user)) != Some(caller) {
+        return Err("Unauthorized: User is not registered in the calling shard".to_string());
+    }
+
+    route_bounty_claim_internal(caller, user, unit_id).await
+}
+
+/// Post a notification to users on any shard
+/// Callable by governance, the KYC and subscription managers and the learning
+/// engine, each for its own category. Returns the number of inboxes reached.
+#[update]
+async fn route_notification(users: Vec<Principal>, input: NotificationInput) -> Result<u64, String> {
+    let allowed = notification_category_for(ic_cdk::caller())
+        .ok_or("Unauthorized: Caller cannot post notifications")?;
+    if input.category != allowed {
+        return Err(format!("Unauthorized: Caller can only post {:?} notifications", allowed));
+    }
+    if users.len() > MAX_NOTIFICATION_RECIPIENTS {
+        return Err(format!("At most {} recipients per call", MAX_NOTIFICATION_RECIPIENTS));
+    }
+
+    Ok(route_notification_internal(users, input).await)
+}
+
+/// Tell every shard that a content node changed (learning engine only)
+/// Shards notify the users who completed it; returns the number of shards reached
+#[update]
+async fn route_content_update(notice: ContentUpdateNotice) -> Result<u64, String> {
+    if ic_cdk::caller() != LEARNING_CONTENT_ID.with(|id| *id.borrow().get()) {
+        return Err("Unauthorized: Only the learning engine can post content updates".to_string());
+    }
+
+    Ok(broadcast_content_update(notice).await)
+}
+
 /// Get which shard a user is registered in
 #[query]
 fn get_user_shard(user: Principal) -> Option<Principal> {
@@ -654,6 +1343,9 @@ fn get_total_voting_power() -> u64 {
 /// For board members, operational_governance calculates their weighted VUC locally.
 #[update]
 async fn fetch_user_voting_power(user: Principal) -> u64 {
+    // Linked wallets share the canonical account's balance
+    let user = CANONICAL_OF_LINKED.with(|l| l.borrow().get(&user)).unwrap_or(user);
+
     // Look up user's shard
     let shard_id = USER_SHARD_MAP.with(|m| m.borrow().get(&user));
     
@@ -239,6 +239,800 @@ pub async fn sync_new_shard(shard_id: Principal) -> Result<(), String> {
         "receive_token_limits",
         (config,)
     ).await;
+
+    // 4. Push badge definitions (one per call to stay under the message limit)
+    let badges: Vec<BadgeDefinition> = BADGE_DEFINITIONS.with(|b| {
+        b.borrow().iter().map(|(_, badge)| badge).collect()
+    });
+    for badge in badges {
+        let _ = ic_cdk::call::<_, ()>(
+            shard_id,
+            "receive_badge_definition",
+            (badge,)
+        ).await;
+    }
+
+    // 5. Push per-unit reward rules (multipliers and bounty coverage)
+    let rules = all_unit_reward_rules();
+    if !rules.is_empty() {
+        push_unit_reward_rules(shard_id, &rules).await;
+    }
+
+    // 6. Push the credentials collection (if one has been configured)
+    let credentials_id = CREDENTIALS_COLLECTION_ID.with(|id| *id.borrow().get());
+    if credentials_id != Principal::anonymous() {
+        let _ = ic_cdk::call::<_, ()>(
+            shard_id,
+            "internal_sync_credentials_collection",
+            (credentials_id,)
+        ).await;
+    }
     
     Ok(())
 }
+
+// ===============================
+// Emission Schedule
+// ===============================
+
+/// Validate an emission schedule before it is stored
+pub fn validate_emission_schedule(schedule: &EmissionSchedule) -> Result<(), String> {
+    if schedule.base_reward_amount == 0 {
+        return Err("Base reward amount must be greater than 0".to_string());
+    }
+    if schedule.min_reward_amount > schedule.base_reward_amount {
+        return Err("Minimum reward cannot exceed the base reward".to_string());
+    }
+    match schedule.curve {
+        EmissionCurve::Flat => {}
+        EmissionCurve::Halving { epoch_bps } => {
+            if epoch_bps == 0 || epoch_bps as u64 > BPS_TOTAL {
+                return Err(format!("Halving epoch must be between 1 and {} BPS", BPS_TOTAL));
+            }
+        }
+        EmissionCurve::SmoothDecay { exponent } => {
+            if exponent == 0 || exponent > MAX_DECAY_EXPONENT {
+                return Err(format!("Decay exponent must be between 1 and {}", MAX_DECAY_EXPONENT));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compute the effective per-quiz reward for a given total_allocated
+///
+/// Uses u128 intermediates so the curve stays exact across the full MAX_SUPPLY range.
+pub fn compute_effective_reward(schedule: &EmissionSchedule, total_allocated: u64) -> u64 {
+    let allocated = total_allocated.min(MAX_SUPPLY);
+    let base = schedule.base_reward_amount;
+
+    let decayed = match schedule.curve {
+        EmissionCurve::Flat => base,
+        EmissionCurve::Halving { epoch_bps } => {
+            let epoch_size = (MAX_SUPPLY as u128 * epoch_bps as u128 / BPS_TOTAL as u128).max(1);
+            let halvings = allocated as u128 / epoch_size;
+            if halvings >= 64 { 0 } else { base >> halvings }
+        }
+        EmissionCurve::SmoothDecay { exponent } => {
+            let remaining = (MAX_SUPPLY - allocated) as u128;
+            let mut reward = base as u128;
+            for _ in 0..exponent {
+                reward = reward * remaining / MAX_SUPPLY as u128;
+            }
+            reward as u64
+        }
+    };
+
+    decayed.max(schedule.min_reward_amount)
+}
+
+/// Project the effective reward at each milestone of the emission curve
+///
+/// Halving curves report every epoch boundary, smooth curves every
+/// EMISSION_PROJECTION_STEP_BPS. The current position is always included first.
+pub fn project_emission_milestones(schedule: &EmissionSchedule, total_allocated: u64) -> Vec<EmissionMilestone> {
+    let step_bps = match schedule.curve {
+        EmissionCurve::Halving { epoch_bps } => epoch_bps as u64,
+        _ => EMISSION_PROJECTION_STEP_BPS,
+    };
+
+    let current_bps = (total_allocated.min(MAX_SUPPLY) as u128 * BPS_TOTAL as u128 / MAX_SUPPLY as u128) as u64;
+    let mut milestones = vec![EmissionMilestone {
+        allocated_bps: current_bps,
+        total_allocated,
+        reward_amount: compute_effective_reward(schedule, total_allocated),
+        reached: true,
+    }];
+
+    let mut bps = step_bps;
+    let mut count = 0;
+    while bps <= BPS_TOTAL && count < MAX_EMISSION_MILESTONES {
+        let allocated = (MAX_SUPPLY as u128 * bps as u128 / BPS_TOTAL as u128) as u64;
+        milestones.push(EmissionMilestone {
+            allocated_bps: bps,
+            total_allocated: allocated,
+            reward_amount: compute_effective_reward(schedule, allocated),
+            reached: total_allocated >= allocated,
+        });
+        bps += step_bps;
+        count += 1;
+    }
+
+    milestones
+}
+
+/// Recompute the effective reward from the emission schedule and store it in
+/// TOKEN_LIMITS_CONFIG if it changed
+///
+/// Returns the new config when an update was made so the caller can distribute it.
+pub fn refresh_effective_reward() -> Option<TokenLimitsConfig> {
+    let schedule = EMISSION_SCHEDULE.with(|s| s.borrow().get().clone());
+    let total_allocated = GLOBAL_STATS.with(|s| s.borrow().get().total_allocated);
+    let effective = compute_effective_reward(&schedule, total_allocated);
+
+    TOKEN_LIMITS_CONFIG.with(|c| {
+        let mut cell = c.borrow_mut();
+        let mut config = cell.get().clone();
+        if config.reward_amount == effective {
+            return None;
+        }
+        config.reward_amount = effective;
+        config.version += 1;
+        cell.set(config.clone()).expect("Failed to update token limits config");
+        Some(config)
+    })
+}
+
+// ===============================
+// Global Emission Budget
+// ===============================
+
+/// Validate an emission budget before it is stored
+pub fn validate_emission_budget(config: &EmissionBudgetConfig) -> Result<(), String> {
+    if config.daily_budget == 0 {
+        return Err("Daily budget must be greater than 0".to_string());
+    }
+    if config.weekly_budget < config.daily_budget {
+        return Err("Weekly budget cannot be smaller than the daily budget".to_string());
+    }
+    Ok(())
+}
+
+fn current_hour() -> u64 {
+    ic_cdk::api::time() / NANOS_PER_HOUR
+}
+
+/// Compute the current budget usage across all shards
+fn emission_budget_status() -> EmissionBudgetStatus {
+    let config = EMISSION_BUDGET_CONFIG.with(|c| c.borrow().get().clone());
+    let hour = current_hour();
+
+    let mut granted_last_24h: u64 = 0;
+    let mut granted_last_7d: u64 = 0;
+
+    SHARD_EMISSION_WINDOWS.with(|w| {
+        for (_, window) in w.borrow().iter() {
+            granted_last_24h = granted_last_24h.saturating_add(window.granted_since(hour, EMISSION_DAY_HOURS));
+            granted_last_7d = granted_last_7d.saturating_add(window.granted_since(hour, EMISSION_WEEK_HOURS));
+        }
+    });
+
+    let registered_shards = REGISTERED_SHARDS.with(|m| m.borrow().len());
+
+    EmissionBudgetStatus {
+        daily_budget: config.daily_budget,
+        weekly_budget: config.weekly_budget,
+        granted_last_24h,
+        granted_last_7d,
+        remaining_daily: config.daily_budget.saturating_sub(granted_last_24h),
+        remaining_weekly: config.weekly_budget.saturating_sub(granted_last_7d),
+        registered_shards,
+        per_shard_daily_cap: config.daily_budget / registered_shards.max(1),
+    }
+}
+
+/// Public snapshot of the global emission budget
+pub fn get_emission_budget_status_internal() -> EmissionBudgetStatus {
+    emission_budget_status()
+}
+
+/// Compute how much allowance `shard` can draw right now and which bound limits it
+///
+/// Every registered shard is guaranteed `daily_budget / registered_shards`.
+/// Shares other shards have not drawn are released to everyone progressively
+/// over the UTC day (none at 00:00, nearly all by 23:00), so a busy shard can
+/// use idle capacity late in the day without starving shards that have not
+/// synced yet.
+pub fn shard_emission_status(shard: Principal) -> ShardEmissionStatus {
+    let budget = emission_budget_status();
+    let hour = current_hour();
+    let share = budget.per_shard_daily_cap;
+
+    let mut shard_granted_last_24h: u64 = 0;
+    let mut unclaimed_by_others: u64 = 0;
+    REGISTERED_SHARDS.with(|m| {
+        SHARD_EMISSION_WINDOWS.with(|w| {
+            let windows = w.borrow();
+            for (id, _) in m.borrow().iter() {
+                let used = windows.get(&id)
+                    .map(|window| window.granted_since(hour, EMISSION_DAY_HOURS))
+                    .unwrap_or(0);
+                if id == shard {
+                    shard_granted_last_24h = used;
+                } else {
+                    unclaimed_by_others = unclaimed_by_others.saturating_add(share.saturating_sub(used));
+                }
+            }
+        });
+    });
+
+    let hour_of_day = hour % EMISSION_DAY_HOURS;
+    let released = (unclaimed_by_others as u128 * hour_of_day as u128 / EMISSION_DAY_HOURS as u128) as u64;
+    let shard_daily_limit = share.saturating_add(released);
+
+    let remaining_supply = GLOBAL_STATS.with(|s| MAX_SUPPLY.saturating_sub(s.borrow().get().total_allocated));
+
+    // Ties go to the broadest bound, so users are told about a global limit
+    // before their shard's share.
+    let (available, limited_by) = [
+        (remaining_supply, AllowanceLimit::MaxSupply),
+        (budget.remaining_weekly, AllowanceLimit::WeeklyBudget),
+        (budget.remaining_daily, AllowanceLimit::DailyBudget),
+        (shard_daily_limit.saturating_sub(shard_granted_last_24h), AllowanceLimit::ShardShare),
+    ]
+    .into_iter()
+    .fold((u64::MAX, AllowanceLimit::MaxSupply), |best, candidate| {
+        if candidate.0 < best.0 { candidate } else { best }
+    });
+
+    ShardEmissionStatus {
+        budget,
+        shard_granted_last_24h,
+        shard_daily_limit,
+        available,
+        limited_by,
+    }
+}
+
+/// Clamp an allowance grant to the global emission budget and record it
+///
+/// See `shard_emission_status` for how the shard's share is computed.
+/// Returns the amount actually granted (0 when the budget or the shard's
+/// share is spent).
+pub fn ration_allowance_grant(shard: Principal, requested: u64) -> u64 {
+    if requested == 0 {
+        return 0;
+    }
+
+    let granted = requested.min(shard_emission_status(shard).available);
+
+    if granted > 0 {
+        let hour = current_hour();
+        let mut window = SHARD_EMISSION_WINDOWS.with(|w| w.borrow().get(&shard)).unwrap_or_default();
+        window.record(hour, granted);
+        SHARD_EMISSION_WINDOWS.with(|w| w.borrow_mut().insert(shard, window));
+    }
+
+    granted
+}
+
+// ===============================
+// Achievement Badges
+// ===============================
+
+/// Validate a badge definition before subtree resolution
+pub fn validate_badge_definition(badge: &BadgeDefinition) -> Result<(), String> {
+    if badge.id.is_empty() || badge.id.len() > MAX_BADGE_ID_LEN {
+        return Err(format!("Badge id must be 1-{} characters", MAX_BADGE_ID_LEN));
+    }
+    if badge.name.is_empty() || badge.name.len() > MAX_BADGE_NAME_LEN {
+        return Err(format!("Badge name must be 1-{} characters", MAX_BADGE_NAME_LEN));
+    }
+    if badge.description.len() > MAX_BADGE_DESCRIPTION_LEN {
+        return Err(format!("Badge description must be at most {} characters", MAX_BADGE_DESCRIPTION_LEN));
+    }
+    if badge.bonus_reward > MAX_BADGE_BONUS_REWARD {
+        return Err(format!("Badge bonus cannot exceed {} e8s", MAX_BADGE_BONUS_REWARD));
+    }
+    match &badge.rule {
+        BadgeRule::QuizzesCompleted { count } if *count == 0 => {
+            return Err("Quiz count must be greater than 0".to_string());
+        }
+        BadgeRule::StreakDays { days } if *days == 0 => {
+            return Err("Streak length must be greater than 0".to_string());
+        }
+        BadgeRule::SubtreeCompleted { root_id, .. } if root_id.is_empty() => {
+            return Err("Subtree root id cannot be empty".to_string());
+        }
+        _ => {}
+    }
+
+    let is_new = !BADGE_DEFINITIONS.with(|b| b.borrow().contains_key(&badge.id));
+    let count = BADGE_DEFINITIONS.with(|b| b.borrow().len());
+    if is_new && count >= MAX_BADGE_DEFINITIONS {
+        return Err(format!("Maximum of {} badge definitions reached", MAX_BADGE_DEFINITIONS));
+    }
+    Ok(())
+}
+
+/// Fill in the unit list of a SubtreeCompleted rule from the learning engine
+pub async fn resolve_badge_rule(badge: &mut BadgeDefinition) -> Result<(), String> {
+    let root_id = match &badge.rule {
+        BadgeRule::SubtreeCompleted { root_id, .. } => root_id.clone(),
+        _ => return Ok(()),
+    };
+
+    let learning_id = LEARNING_CONTENT_ID.with(|id| *id.borrow().get());
+    let (unit_ids,): (Vec<String>,) = ic_cdk::call(
+        learning_id,
+        "get_subtree_quiz_ids",
+        (root_id.clone(),)
+    ).await.map_err(|(c, m)| format!("Learning engine call failed: {:?} {}", c, m))?;
+
+    if unit_ids.is_empty() {
+        return Err(format!("Content node '{}' has no quizzes in its subtree", root_id));
+    }
+    if unit_ids.len() > MAX_BADGE_SUBTREE_UNITS {
+        return Err(format!("Subtree has {} quizzes; at most {} are supported", unit_ids.len(), MAX_BADGE_SUBTREE_UNITS));
+    }
+
+    badge.rule = BadgeRule::SubtreeCompleted { root_id, unit_ids };
+
+    if Encode!(badge).map(|b| b.len()).unwrap_or(usize::MAX) > BADGE_DEFINITION_MAX_SIZE as usize {
+        return Err("Badge definition is too large".to_string());
+    }
+    Ok(())
+}
+
+/// Push a badge definition to every registered shard
+pub async fn distribute_badge_definition_internal(badge: BadgeDefinition) -> u64 {
+    let shards: Vec<Principal> = SHARD_REGISTRY.with(|r| {
+        r.borrow().iter().map(|(_, s)| s.canister_id).collect()
+    });
+
+    let mut success_count = 0;
+    for shard in shards {
+        let result = ic_cdk::call::<_, ()>(shard, "receive_badge_definition", (badge.clone(),)).await;
+        if result.is_ok() {
+            success_count += 1;
+        }
+    }
+    success_count
+}
+
+// ===============================
+// Reward Multipliers & Sponsored Bounties
+// ===============================
+
+/// Current reward rule of a unit (multiplier plus open bounty coverage)
+pub fn unit_reward_rule(unit_id: &str) -> UnitRewardRule {
+    let multiplier_bps = REWARD_MULTIPLIERS.with(|m| m.borrow().get(&unit_id.to_string()))
+        .unwrap_or(DEFAULT_REWARD_MULTIPLIER_BPS);
+    let has_bounty = BOUNTIES.with(|b| {
+        b.borrow().iter().any(|(_, bounty)| bounty.is_open() && bounty.unit_ids.iter().any(|u| u == unit_id))
+    });
+    UnitRewardRule { multiplier_bps, has_bounty }
+}
+
+/// Reward rules of every unit with a multiplier or an open bounty
+pub fn all_unit_reward_rules() -> Vec<(String, UnitRewardRule)> {
+    let mut unit_ids: Vec<String> = REWARD_MULTIPLIERS.with(|m| m.borrow().iter().map(|(id, _)| id).collect());
+    BOUNTIES.with(|b| {
+        for (_, bounty) in b.borrow().iter().filter(|(_, bounty)| bounty.is_open()) {
+            unit_ids.extend(bounty.unit_ids);
+        }
+    });
+    unit_ids.sort();
+    unit_ids.dedup();
+    unit_ids.into_iter().map(|id| {
+        let rule = unit_reward_rule(&id);
+        (id, rule)
+    }).collect()
+}
+
+/// Push the current rules of `unit_ids` to every registered shard
+///
+/// Units that went back to the default are sent too, so shards drop them.
+pub async fn distribute_unit_reward_rules(unit_ids: Vec<String>) -> u64 {
+    let rules: Vec<(String, UnitRewardRule)> = unit_ids.into_iter().map(|id| {
+        let rule = unit_reward_rule(&id);
+        (id, rule)
+    }).collect();
+    let shards: Vec<Principal> = SHARD_REGISTRY.with(|r| {
+        r.borrow().iter().map(|(_, s)| s.canister_id).collect()
+    });
+
+    let mut success_count = 0;
+    for shard in shards {
+        if push_unit_reward_rules(shard, &rules).await {
+            success_count += 1;
+        }
+    }
+    success_count
+}
+
+/// Send rules to one shard in chunks; true if every chunk was accepted
+pub async fn push_unit_reward_rules(shard: Principal, rules: &[(String, UnitRewardRule)]) -> bool {
+    let mut ok = true;
+    for chunk in rules.chunks(UNIT_RULES_PER_CALL) {
+        let result = ic_cdk::call::<_, ()>(shard, "receive_unit_reward_rules", (chunk.to_vec(),)).await;
+        ok &= result.is_ok();
+    }
+    ok
+}
+
+pub fn validate_reward_multiplier(content_id: &str, multiplier_bps: u32) -> Result<(), String> {
+    if content_id.is_empty() || content_id.len() > MAX_BOUNTY_UNIT_ID_LEN {
+        return Err(format!("Content id must be 1-{} characters", MAX_BOUNTY_UNIT_ID_LEN));
+    }
+    if !(MIN_REWARD_MULTIPLIER_BPS..=MAX_REWARD_MULTIPLIER_BPS).contains(&multiplier_bps) {
+        return Err(format!(
+            "Multiplier must be between {} and {} BPS",
+            MIN_REWARD_MULTIPLIER_BPS, MAX_REWARD_MULTIPLIER_BPS
+        ));
+    }
+    Ok(())
+}
+
+/// Validate a bounty before pulling the deposit
+pub fn validate_bounty(root_id: &str, amount: u64, reward_per_pass: u64) -> Result<(), String> {
+    if root_id.is_empty() || root_id.len() > MAX_BOUNTY_UNIT_ID_LEN {
+        return Err(format!("Root id must be 1-{} characters", MAX_BOUNTY_UNIT_ID_LEN));
+    }
+    if !(MIN_BOUNTY_REWARD_PER_PASS..=MAX_BOUNTY_REWARD_PER_PASS).contains(&reward_per_pass) {
+        return Err(format!(
+            "Reward per pass must be between {} and {} e8s",
+            MIN_BOUNTY_REWARD_PER_PASS, MAX_BOUNTY_REWARD_PER_PASS
+        ));
+    }
+    if amount < reward_per_pass {
+        return Err("Deposit must cover at least one reward".to_string());
+    }
+    let open_count = BOUNTIES.with(|b| b.borrow().iter().filter(|(_, bounty)| bounty.status != BountyStatus::Closed).count());
+    if open_count >= MAX_OPEN_BOUNTIES {
+        return Err(format!("Maximum of {} open bounties reached", MAX_OPEN_BOUNTIES));
+    }
+    Ok(())
+}
+
+/// Resolve the quiz units covered by a bounty from the learning engine
+pub async fn resolve_bounty_units(root_id: &str) -> Result<Vec<String>, String> {
+    let learning_id = LEARNING_CONTENT_ID.with(|id| *id.borrow().get());
+    let (unit_ids,): (Vec<String>,) = ic_cdk::call(
+        learning_id,
+        "get_subtree_quiz_ids",
+        (root_id.to_string(),)
+    ).await.map_err(|(c, m)| format!("Learning engine call failed: {:?} {}", c, m))?;
+
+    if unit_ids.is_empty() {
+        return Err(format!("Content node '{}' has no quizzes in its subtree", root_id));
+    }
+    if unit_ids.len() > MAX_BOUNTY_SUBTREE_UNITS {
+        return Err(format!("Subtree has {} quizzes; at most {} are supported", unit_ids.len(), MAX_BOUNTY_SUBTREE_UNITS));
+    }
+    if unit_ids.iter().any(|id| id.len() > MAX_BOUNTY_UNIT_ID_LEN) {
+        return Err(format!("Unit ids must be at most {} characters", MAX_BOUNTY_UNIT_ID_LEN));
+    }
+    Ok(unit_ids)
+}
+
+/// Reserve payouts for a user who passed `unit_id`
+///
+/// Takes `reward_per_pass` from every open bounty covering the unit that has
+/// not paid this user for it yet, and picks up any Unpaid payouts left by an
+/// earlier failed credit. Returns the total and the reserved keys so the
+/// caller can release them if crediting fails.
+pub fn reserve_bounty_payouts(user: Principal, unit_id: &str) -> (u64, Vec<BountyPayoutKey>) {
+    let now = ic_cdk::api::time();
+    let candidates: Vec<Bounty> = BOUNTIES.with(|b| {
+        b.borrow().iter()
+            .map(|(_, bounty)| bounty)
+            .filter(|bounty| bounty.unit_ids.iter().any(|u| u == unit_id))
+            .collect()
+    });
+
+    let mut total = 0u64;
+    let mut keys = Vec::new();
+    for mut bounty in candidates {
+        let key = BountyPayoutKey { bounty_id: bounty.id, user, unit_id: unit_id.to_string() };
+        if let Some(mut payout) = BOUNTY_PAYOUTS.with(|p| p.borrow().get(&key)) {
+            if payout.status == BountyPayoutStatus::Unpaid {
+                payout.status = BountyPayoutStatus::InFlight;
+                total += payout.amount;
+                BOUNTY_PAYOUTS.with(|p| p.borrow_mut().insert(key.clone(), payout));
+                keys.push(key);
+            }
+            continue;
+        }
+        if !bounty.is_open() {
+            continue;
+        }
+        let amount = bounty.reward_per_pass;
+        bounty.remaining -= amount;
+        bounty.paid_out += amount;
+        bounty.payout_count += 1;
+        BOUNTIES.with(|b| b.borrow_mut().insert(bounty.id, bounty.clone()));
+        BOUNTY_PAYOUTS.with(|p| p.borrow_mut().insert(key.clone(), BountyPayout {
+            bounty_id: bounty.id,
+            user,
+            unit_id: unit_id.to_string(),
+            amount,
+            paid_at: now,
+            status: BountyPayoutStatus::InFlight,
+        }));
+        total += amount;
+        keys.push(key);
+    }
+    (total, keys)
+}
+
+/// Mark reserved payouts as delivered
+pub fn confirm_bounty_payouts(keys: &[BountyPayoutKey]) {
+    BOUNTY_PAYOUTS.with(|p| {
+        let mut map = p.borrow_mut();
+        for key in keys {
+            if let Some(mut payout) = map.get(key) {
+                payout.status = BountyPayoutStatus::Credited;
+                map.insert(key.clone(), payout);
+            }
+        }
+    });
+}
+
+/// Undo reservations whose credit was rejected by the shard
+///
+/// Only an open bounty takes its escrow back. A Closing or Closed bounty has
+/// already settled (or is settling) its refund, so the payout stays reserved
+/// as Unpaid and is credited on the shard's next claim.
+pub fn release_bounty_payouts(keys: Vec<BountyPayoutKey>) {
+    for key in keys {
+        let Some(mut payout) = BOUNTY_PAYOUTS.with(|p| p.borrow().get(&key)) else {
+            continue;
+        };
+        let bounty = BOUNTIES.with(|b| b.borrow().get(&key.bounty_id));
+        match bounty {
+            Some(mut bounty) if bounty.status == BountyStatus::Open => {
+                bounty.remaining += payout.amount;
+                bounty.paid_out -= payout.amount;
+                bounty.payout_count -= 1;
+                BOUNTIES.with(|b| b.borrow_mut().insert(bounty.id, bounty));
+                BOUNTY_PAYOUTS.with(|p| p.borrow_mut().remove(&key));
+            }
+            _ => {
+                payout.status = BountyPayoutStatus::Unpaid;
+                BOUNTY_PAYOUTS.with(|p| p.borrow_mut().insert(key, payout));
+            }
+        }
+    }
+}
+
+/// Pay a learner from the bounties covering a unit they passed
+///
+/// The escrowed tokens become staked balance on the learner's shard; nothing
+/// is minted, so only `total_staked` moves. internal_credit_bounty has no
+/// awaits, so a rejected call means nothing was credited and the
+/// reservation is released. Returns the amount credited.
+pub async fn route_bounty_claim_internal(shard: Principal, user: Principal, unit_id: String) -> Result<u64, String> {
+    let (amount, keys) = reserve_bounty_payouts(user, &unit_id);
+    if amount == 0 {
+        return Ok(0);
+    }
+    let exhausted: Vec<String> = BOUNTIES.with(|b| {
+        let map = b.borrow();
+        keys.iter()
+            .filter_map(|k| map.get(&k.bounty_id))
+            .filter(|bounty| !bounty.is_open())
+            .flat_map(|bounty| bounty.unit_ids)
+            .collect()
+    });
+
+    let call_result: Result<(Result<(), String>,), _> = ic_cdk::call(
+        shard,
+        "internal_credit_bounty",
+        (user, unit_id, amount)
+    ).await;
+    let result = match call_result {
+        Ok((inner,)) => inner,
+        Err((code, msg)) => Err(format!("Learner shard call failed: {:?} {}", code, msg)),
+    };
+
+    if let Err(e) = result {
+        release_bounty_payouts(keys);
+        return Err(e);
+    }
+    confirm_bounty_payouts(&keys);
+
+    GLOBAL_STATS.with(|s| {
+        let mut cell = s.borrow_mut();
+        let mut stats = cell.get().clone();
+        stats.total_staked = stats.total_staked.saturating_add(amount);
+        cell.set(stats).expect("Failed to update global stats");
+    });
+
+    // A bounty that can no longer pay stops being advertised to shards
+    if !exhausted.is_empty() {
+        distribute_unit_reward_rules(exhausted).await;
+    }
+    Ok(amount)
+}
+
+// ===============================
+// Referrals
+// ===============================
+
+/// Derive a human-friendly referral code from a principal
+///
+/// `salt` is bumped by the caller on the (unlikely) event of a collision.
+pub fn derive_referral_code(user: &Principal, salt: u32) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(user.as_slice());
+    hasher.update(salt.to_le_bytes());
+    let digest = hasher.finalize();
+
+    digest.iter()
+        .take(REFERRAL_CODE_LENGTH)
+        .map(|b| REFERRAL_CODE_ALPHABET[*b as usize % REFERRAL_CODE_ALPHABET.len()] as char)
+        .collect()
+}
+
+/// Return the user's referral code, issuing a new unique one if needed
+pub fn get_or_create_referral_code(user: Principal) -> String {
+    if let Some(code) = USER_REFERRAL_CODES.with(|c| c.borrow().get(&user)) {
+        return code;
+    }
+
+    let mut salt = 0u32;
+    let code = loop {
+        let candidate = derive_referral_code(&user, salt);
+        if !REFERRAL_CODES.with(|c| c.borrow().contains_key(&candidate)) {
+            break candidate;
+        }
+        salt += 1;
+    };
+
+    REFERRAL_CODES.with(|c| c.borrow_mut().insert(code.clone(), user));
+    USER_REFERRAL_CODES.with(|c| c.borrow_mut().insert(user, code.clone()));
+    code
+}
+
+/// Principal an account is registered under now, following recoveries
+///
+/// An account can be recovered more than once (even back to an earlier
+/// principal), so the walk stops at the first registered principal and
+/// takes at most one step per recorded recovery.
+pub fn current_principal(user: Principal) -> Principal {
+    let mut current = user;
+    let hops = RECOVERED_PRINCIPALS.with(|r| r.borrow().len());
+    for _ in 0..hops {
+        if USER_SHARD_MAP.with(|m| m.borrow().contains_key(&current)) {
+            break;
+        }
+        match RECOVERED_PRINCIPALS.with(|r| r.borrow().get(&current)) {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    current
+}
+
+// ===============================
+// Peer-to-peer Transfers
+// ===============================
+
+/// Cut a string to at most `max_len` bytes on a character boundary
+fn truncate_to(text: &str, max_len: usize) -> String {
+    let mut end = text.len().min(max_len);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text[..end].to_string()
+}
+
+/// Credit the recipient's shard and record the outcome
+///
+/// internal_credit_transfer has no awaits, so a rejected call means nothing
+/// was credited and the failure is final.
+pub async fn route_staked_transfer_internal(from_shard: Principal, request: StakedTransferRequest) -> Result<(), String> {
+    let recipient = CANONICAL_OF_LINKED.with(|l| l.borrow().get(&request.to)).unwrap_or(request.to);
+    let to_shard = USER_SHARD_MAP.with(|m| m.borrow().get(&recipient));
+
+    let result = match to_shard {
+        _ if request.amount == 0 => Err("Amount must be greater than 0".to_string()),
+        _ if recipient == request.from => Err("Cannot transfer to yourself".to_string()),
+        None => Err("Recipient not registered".to_string()),
+        Some(shard) => {
+            let credit = StakedTransferRequest { to: recipient, ..request.clone() };
+            let call_result: Result<(Result<(), String>,), _> = ic_cdk::call(
+                shard,
+                "internal_credit_transfer",
+                (credit,)
+            ).await;
+            match call_result {
+                Ok((inner,)) => inner,
+                Err((code, msg)) => Err(format!("Recipient shard call failed: {:?} {}", code, msg)),
+            }
+        }
+    };
+
+    let outcome = match &result {
+        Ok(()) => StakedTransferOutcome::Completed,
+        Err(reason) => StakedTransferOutcome::Failed { reason: truncate_to(reason, MAX_TRANSFER_FAILURE_REASON_LEN) },
+    };
+    STAKED_TRANSFERS.with(|t| t.borrow_mut().insert(request.transfer_id, StakedTransferRecord {
+        from_shard,
+        to_shard,
+        recipient,
+        amount: request.amount,
+        outcome,
+        settled_at: ic_cdk::api::time(),
+    }));
+
+    result
+}
+
+// ===============================
+// Notifications
+// ===============================
+
+/// Category a canister may post through the hub, if any
+pub fn notification_category_for(caller: Principal) -> Option<NotificationCategory> {
+    if caller == Principal::anonymous() {
+        None
+    } else if caller == GOVERNANCE_CANISTER_ID.with(|id| *id.borrow().get()) {
+        Some(NotificationCategory::Governance)
+    } else if caller == KYC_MANAGER_ID.with(|id| *id.borrow().get()) {
+        Some(NotificationCategory::Kyc)
+    } else if caller == SUBSCRIPTION_MANAGER_ID.with(|id| *id.borrow().get()) {
+        Some(NotificationCategory::Subscription)
+    } else if caller == LEARNING_CONTENT_ID.with(|id| *id.borrow().get()) {
+        Some(NotificationCategory::Content)
+    } else {
+        None
+    }
+}
+
+/// Group recipients by shard and post to each shard's inbox
+/// Unregistered users are skipped; returns the number of inboxes reached.
+pub async fn route_notification_internal(users: Vec<Principal>, input: NotificationInput) -> u64 {
+    let mut by_shard: Vec<(Principal, Vec<Principal>)> = Vec::new();
+    for user in users {
+        let Some(shard) = USER_SHARD_MAP.with(|m| m.borrow().get(&user)) else {
+            continue;
+        };
+        match by_shard.iter_mut().find(|(s, _)| *s == shard) {
+            Some((_, list)) => list.push(user),
+            None => by_shard.push((shard, vec![user])),
+        }
+    }
+
+    let mut delivered = 0;
+    for (shard, recipients) in by_shard {
+        let result: Result<(Result<u64, String>,), _> = ic_cdk::call(
+            shard,
+            "internal_notify",
+            (recipients, input.clone())
+        ).await;
+        if let Ok((Ok(count),)) = result {
+            delivered += count;
+        }
+    }
+    delivered
+}
+
+/// Forward a content update to every shard; returns the number reached
+pub async fn broadcast_content_update(notice: ContentUpdateNotice) -> u64 {
+    let shards: Vec<Principal> = SHARD_REGISTRY.with(|r| {
+        r.borrow().iter().map(|(_, s)| s.canister_id).collect()
+    });
+
+    let mut success_count = 0;
+    for shard in shards {
+        let result = ic_cdk::call::<_, ()>(shard, "internal_notify_content_update", (notice.clone(),)).await;
+        if result.is_ok() {
+            success_count += 1;
+        }
+    }
+    success_count
+}
@@ -25,6 +25,23 @@ pub type Memory = VirtualMemory<DefaultMemoryImpl>;
 //   9 - EMBEDDED_ARCHIVE_WASM: Archive canister WASM for auto-deployment
 //   10 - TOKEN_LIMITS_CONFIG: Global token limits configuration
 //   11 - USER_SHARD_MAP: User to shard mapping
+//   12 - SUBSCRIPTION_MANAGER_ID: Subscription manager canister principal
+//   13 - KYC_MANAGER_ID: KYC manager canister principal
+//   14 - GOVERNANCE_CANISTER_ID: Governance canister principal
+//   15 - EMISSION_SCHEDULE: Governable reward decay schedule
+//   16 - EMISSION_BUDGET_CONFIG: Global rolling emission budget
+//   17 - SHARD_EMISSION_WINDOWS: Per-shard hourly allowance grants
+//   18 - BADGE_DEFINITIONS: Governance-managed achievement badges
+//   19 - REFERRAL_CODES: Referral code to referrer mapping
+//   20 - USER_REFERRAL_CODES: Referrer to referral code mapping
+//   21 - CANONICAL_OF_LINKED: Secondary principal to canonical principal mapping
+//   22 - CREDENTIALS_COLLECTION_ID: Soulbound credentials NFT collection principal
+//   23 - STAKED_TRANSFERS: Settled cross-shard transfers by transfer id
+//   24 - REWARD_MULTIPLIERS: Governance-set reward multipliers by unit
+//   25 - BOUNTIES: Sponsored bounty escrows by id
+//   26 - BOUNTY_PAYOUTS: Bounty payouts by (bounty, user, unit)
+//   27 - BOUNTY_COUNT: Next bounty id
+//   28 - RECOVERED_PRINCIPALS: Recovered principal to its replacement mapping
 
 thread_local! {
     // ─────────────────────────────────────────────────────────────────────
@@ -152,6 +169,55 @@ thread_local! {
         )
     );
 
+    /// Map of linked (secondary) principal -> canonical principal
+    /// Linked principals are also present in USER_SHARD_MAP (pointing at the
+    /// canonical principal's shard) so lookups route to the right shard
+    pub static CANONICAL_OF_LINKED: RefCell<StableBTreeMap<Principal, Principal, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(21)))
+        )
+    );
+
+    /// Settled cross-shard transfers: transfer id -> outcome
+    /// Lets the sender's shard retry without the recipient being credited twice
+    pub static STAKED_TRANSFERS: RefCell<StableBTreeMap<String, StakedTransferRecord, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(23)))
+        )
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Reward Multipliers & Sponsored Bounties
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Map of unit id -> reward multiplier in BPS (absent = 1x)
+    pub static REWARD_MULTIPLIERS: RefCell<StableBTreeMap<String, u32, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(24)))
+        )
+    );
+
+    /// Map of bounty id -> Bounty (kept after closing for the record)
+    pub static BOUNTIES: RefCell<StableBTreeMap<u64, Bounty, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(25)))
+        )
+    );
+
+    /// Payouts by (bounty, user, unit); also guards against paying twice
+    pub static BOUNTY_PAYOUTS: RefCell<StableBTreeMap<BountyPayoutKey, BountyPayout, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(26)))
+        )
+    );
+
+    pub static BOUNTY_COUNT: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(27))),
+            0
+        ).unwrap()
+    );
+
     /// Global token limits and reward configuration 
     /// Source of truth for all shards
     pub static TOKEN_LIMITS_CONFIG: RefCell<StableCell<TokenLimitsConfig, Memory>> = RefCell::new(
@@ -178,4 +244,94 @@ thread_local! {
             Principal::anonymous()
         ).unwrap()
     );
+
+    /// Principal ID of the soulbound credentials NFT collection
+    /// Broadcast to all shards so they can mint badges and certificates
+    pub static CREDENTIALS_COLLECTION_ID: RefCell<StableCell<Principal, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(22))),
+            Principal::anonymous()
+        ).unwrap()
+    );
+
+    /// Principal ID of the governance canister
+    /// Authorized to apply approved economy proposals (emission schedule, etc.)
+    pub static GOVERNANCE_CANISTER_ID: RefCell<StableCell<Principal, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(14))),
+            Principal::anonymous()
+        ).unwrap()
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Emission Schedule
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Reward decay schedule keyed off total_allocated / MAX_SUPPLY
+    /// The effective reward derived from it is pushed to shards via TOKEN_LIMITS_CONFIG
+    pub static EMISSION_SCHEDULE: RefCell<StableCell<EmissionSchedule, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(15))),
+            EmissionSchedule::default()
+        ).unwrap()
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Global Emission Budget
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Rolling daily / weekly cap on allowance granted across all shards
+    pub static EMISSION_BUDGET_CONFIG: RefCell<StableCell<EmissionBudgetConfig, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(16))),
+            EmissionBudgetConfig::default()
+        ).unwrap()
+    );
+
+    /// Hourly allowance grants per shard: shard principal -> window
+    /// Summed across shards to enforce the global budget and rationed per shard
+    pub static SHARD_EMISSION_WINDOWS: RefCell<StableBTreeMap<Principal, ShardEmissionWindow, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(17)))
+        )
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Achievements
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Badge definitions: badge id -> definition
+    /// Source of truth for all shards (pushed via receive_badge_definition)
+    pub static BADGE_DEFINITIONS: RefCell<StableBTreeMap<String, BadgeDefinition, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(18)))
+        )
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Referrals
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Map of referral code -> referrer principal
+    /// Lets a shard resolve codes issued to users on any other shard
+    pub static REFERRAL_CODES: RefCell<StableBTreeMap<String, Principal, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(19)))
+        )
+    );
+
+    /// Map of referrer principal -> referral code (one code per user)
+    pub static USER_REFERRAL_CODES: RefCell<StableBTreeMap<Principal, String, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(20)))
+        )
+    );
+
+    /// Map of recovered principal -> principal the account moved to
+    /// Lets referral records that still name the old principal be routed
+    pub static RECOVERED_PRINCIPALS: RefCell<StableBTreeMap<Principal, Principal, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(28)))
+        )
+    );
 }
@@ -139,6 +139,15 @@ impl Default for TokenLimits {
     }
 }
 
+/// How the period token limits are measured
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LimitMode {
+    /// Counters reset on UTC day, week (Sunday), month and year boundaries
+    Calendar,
+    /// Usage over the last 24h, 7d, 30d and 365d
+    Rolling,
+}
+
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct TokenLimitsConfig {
     pub reward_amount: u64,
@@ -147,6 +156,8 @@ pub struct TokenLimitsConfig {
     pub regular_limits: TokenLimits,
     pub subscribed_limits: TokenLimits,
     pub version: u64,
+    /// None means Calendar (configs stored before limit modes existed)
+    pub limit_mode: Option<LimitMode>,
 }
 
 impl Default for TokenLimitsConfig {
@@ -163,6 +174,7 @@ impl Default for TokenLimitsConfig {
                 max_yearly_tokens: MIN_SUBSCRIBED_YEARLY, // 400 GHC
             },
             version: 1, // Start with version 1 to indicate it's valid
+            limit_mode: None,
         }
     }
 }
@@ -177,6 +189,418 @@ impl Storable for TokenLimitsConfig {
     const BOUND: Bound = Bound::Bounded { max_size: 300, is_fixed_size: false };
 }
 
+// ─────────────────────────────────────────────────────────────────
+// Emission Schedule
+// ─────────────────────────────────────────────────────────────────
+
+/// Shape of the reward decay curve, keyed off total_allocated / MAX_SUPPLY
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum EmissionCurve {
+    /// No decay: the base reward is paid until MAX_SUPPLY is reached
+    Flat,
+    /// Reward halves every time another `epoch_bps` of MAX_SUPPLY is allocated
+    Halving { epoch_bps: u16 },
+    /// Smooth decay: reward = base * (1 - allocated / MAX_SUPPLY) ^ exponent
+    /// An exponent of 1 is a straight linear decay
+    SmoothDecay { exponent: u8 },
+}
+
+/// Governable emission schedule
+///
+/// The hub derives the effective `reward_amount` in TokenLimitsConfig from this
+/// schedule and pushes it to shards whenever it changes.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EmissionSchedule {
+    /// Reward paid per quiz before any decay is applied (in e8s)
+    pub base_reward_amount: u64,
+    /// Decay curve applied to the base reward
+    pub curve: EmissionCurve,
+    /// Floor below which the effective reward never decays (in e8s)
+    pub min_reward_amount: u64,
+}
+
+impl Default for EmissionSchedule {
+    fn default() -> Self {
+        Self {
+            base_reward_amount: TokenLimitsConfig::default().reward_amount,
+            curve: EmissionCurve::Flat,
+            min_reward_amount: 0,
+        }
+    }
+}
+
+impl Storable for EmissionSchedule {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode EmissionSchedule")
+    }
+    const BOUND: Bound = Bound::Bounded { max_size: 200, is_fixed_size: false };
+}
+
+/// Projected reward at a point along the emission curve
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EmissionMilestone {
+    /// Share of MAX_SUPPLY allocated at this milestone (in basis points)
+    pub allocated_bps: u64,
+    /// Absolute total_allocated at this milestone (in e8s)
+    pub total_allocated: u64,
+    /// Effective reward per quiz once this milestone is reached (in e8s)
+    pub reward_amount: u64,
+    /// Whether total_allocated has already passed this milestone
+    pub reached: bool,
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Global Emission Budget
+// ─────────────────────────────────────────────────────────────────
+
+/// System-wide cap on minting allowance granted per rolling window
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EmissionBudgetConfig {
+    /// Max allowance granted across all shards in any rolling 24h (in e8s)
+    pub daily_budget: u64,
+    /// Max allowance granted across all shards in any rolling 7d (in e8s)
+    pub weekly_budget: u64,
+}
+
+impl Default for EmissionBudgetConfig {
+    fn default() -> Self {
+        Self {
+            daily_budget: DEFAULT_DAILY_EMISSION_BUDGET,
+            weekly_budget: DEFAULT_WEEKLY_EMISSION_BUDGET,
+        }
+    }
+}
+
+impl Storable for EmissionBudgetConfig {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode EmissionBudgetConfig")
+    }
+    const BOUND: Bound = Bound::Bounded { max_size: 100, is_fixed_size: false };
+}
+
+/// Allowance granted to a shard within one hour
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EmissionBucket {
+    /// Hours since epoch
+    pub hour: u64,
+    /// Allowance granted during this hour (in e8s)
+    pub granted: u64,
+}
+
+/// Hourly grant history of one shard, pruned to the weekly window
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct ShardEmissionWindow {
+    pub buckets: Vec<EmissionBucket>,
+}
+
+impl ShardEmissionWindow {
+    /// Sum of grants in the `hours` most recent hours (including the current one)
+    pub fn granted_since(&self, current_hour: u64, hours: u64) -> u64 {
+        let start = current_hour.saturating_sub(hours - 1);
+        self.buckets.iter()
+            .filter(|b| b.hour >= start)
+            .map(|b| b.granted)
+            .sum()
+    }
+
+    /// Record a grant in the current hour and drop buckets outside the weekly window
+    pub fn record(&mut self, current_hour: u64, amount: u64) {
+        let start = current_hour.saturating_sub(EMISSION_WEEK_HOURS - 1);
+        self.buckets.retain(|b| b.hour >= start);
+        match self.buckets.last_mut() {
+            Some(bucket) if bucket.hour == current_hour => bucket.granted += amount,
+            _ => self.buckets.push(EmissionBucket { hour: current_hour, granted: amount }),
+        }
+    }
+}
+
+impl Storable for ShardEmissionWindow {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode ShardEmissionWindow")
+    }
+    const BOUND: Bound = Bound::Bounded { max_size: 8192, is_fixed_size: false };
+}
+
+/// Snapshot of the global emission budget for frontends and shards
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EmissionBudgetStatus {
+    pub daily_budget: u64,
+    pub weekly_budget: u64,
+    /// Allowance granted across all shards in the rolling 24h
+    pub granted_last_24h: u64,
+    /// Allowance granted across all shards in the rolling 7d
+    pub granted_last_7d: u64,
+    pub remaining_daily: u64,
+    pub remaining_weekly: u64,
+    /// Registered shards, including full ones (they stop taking users but keep minting)
+    pub registered_shards: u64,
+    /// Share of the daily budget reserved for each registered shard
+    pub per_shard_daily_cap: u64,
+}
+
+/// Which bound currently limits the allowance a shard can draw
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AllowanceLimit {
+    /// Total allocation is close to MAX_SUPPLY
+    MaxSupply,
+    /// Rolling 7d budget across all shards
+    WeeklyBudget,
+    /// Rolling 24h budget across all shards
+    DailyBudget,
+    /// This shard's share of the daily budget (plus any share released by idle shards)
+    ShardShare,
+}
+
+/// Emission budget as seen by one shard
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ShardEmissionStatus {
+    pub budget: EmissionBudgetStatus,
+    /// Allowance granted to this shard in the rolling 24h
+    pub shard_granted_last_24h: u64,
+    /// Daily share plus the unused shares of other shards released so far today
+    pub shard_daily_limit: u64,
+    /// Largest grant the shard could receive right now
+    pub available: u64,
+    /// The bound that `available` is held to
+    pub limited_by: AllowanceLimit,
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Achievement Badges
+// ─────────────────────────────────────────────────────────────────
+
+/// Condition a user must meet to earn a badge
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum BadgeRule {
+    /// Lifetime number of passed quizzes (1 = "first quiz")
+    QuizzesCompleted { count: u64 },
+    /// Consecutive days with at least one passed quiz
+    StreakDays { days: u64 },
+    /// Every quiz under a content node has been passed
+    /// `unit_ids` is resolved by the hub from the learning engine; callers leave it empty
+    SubtreeCompleted { root_id: String, unit_ids: Vec<String> },
+}
+
+/// Governance-managed achievement definition, distributed to all shards
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct BadgeDefinition {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub rule: BadgeRule,
+    /// One-off bonus paid when earned, clamped by the user's token limits (in e8s)
+    pub bonus_reward: u64,
+    /// Inactive badges are no longer awarded; already earned ones are kept
+    pub active: bool,
+}
+
+impl Storable for BadgeDefinition {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode BadgeDefinition")
+    }
+    const BOUND: Bound = Bound::Bounded { max_size: BADGE_DEFINITION_MAX_SIZE, is_fixed_size: false };
+}
+
+/// Cross-shard transfer of staked balance (sent by the sender's shard)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct StakedTransferRequest {
+    /// Unique per sender shard; used to deduplicate retries end to end
+    pub transfer_id: String,
+    pub from: Principal,
+    pub to: Principal,
+    pub amount: u64,
+    pub memo: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum StakedTransferOutcome {
+    Completed,
+    Failed { reason: String },
+}
+
+/// Settled outcome of a routed transfer, returned again on retries
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct StakedTransferRecord {
+    pub from_shard: Principal,
+    pub to_shard: Option<Principal>,
+    /// Canonical recipient
+    pub recipient: Principal,
+    pub amount: u64,
+    pub outcome: StakedTransferOutcome,
+    pub settled_at: u64,
+}
+
+impl Storable for StakedTransferRecord {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode StakedTransferRecord")
+    }
+    const BOUND: Bound = Bound::Bounded { max_size: 1000, is_fixed_size: false };
+}
+
+/// Lifecycle of a bounty
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BountyStatus {
+    Open,
+    /// Refund in flight; no new payouts are reserved and none are released
+    Closing,
+    Closed,
+}
+
+/// Sponsor-funded reward escrow for the quiz units of a content subtree
+///
+/// Deposits are pulled into the hub's account via ICRC-2; payouts move
+/// escrowed tokens into learners' staked balances instead of minting.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Bounty {
+    pub id: u64,
+    pub sponsor: Principal,
+    pub root_id: String,
+    /// Quiz units in the subtree, resolved when the bounty is funded
+    pub unit_ids: Vec<String>,
+    /// Paid to a learner for each unit they pass (once per unit)
+    pub reward_per_pass: u64,
+    pub deposited: u64,
+    pub remaining: u64,
+    pub paid_out: u64,
+    pub payout_count: u64,
+    pub created_at: u64,
+    pub status: BountyStatus,
+    /// Set when closed; the remaining escrow was refunded to the sponsor
+    pub closed_at: Option<u64>,
+}
+
+impl Bounty {
+    pub fn is_open(&self) -> bool {
+        self.status == BountyStatus::Open && self.remaining >= self.reward_per_pass
+    }
+}
+
+impl Storable for Bounty {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode Bounty")
+    }
+    const BOUND: Bound = Bound::Bounded { max_size: BOUNTY_MAX_SIZE, is_fixed_size: false };
+}
+
+/// Key of a bounty payout: one per (bounty, learner, unit)
+///
+/// Encoded by hand (big-endian bounty id first) so a bounty's payouts are
+/// contiguous and ordered.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BountyPayoutKey {
+    pub bounty_id: u64,
+    pub user: Principal,
+    pub unit_id: String,
+}
+
+impl Storable for BountyPayoutKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let user = self.user.as_slice();
+        let mut bytes = Vec::with_capacity(9 + user.len() + self.unit_id.len());
+        bytes.extend_from_slice(&self.bounty_id.to_be_bytes());
+        bytes.push(user.len() as u8);
+        bytes.extend_from_slice(user);
+        bytes.extend_from_slice(self.unit_id.as_bytes());
+        Cow::Owned(bytes)
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let bounty_id = u64::from_be_bytes(bytes[0..8].try_into().unwrap());
+        let user_len = bytes[8] as usize;
+        Self {
+            bounty_id,
+            user: Principal::from_slice(&bytes[9..9 + user_len]),
+            unit_id: String::from_utf8(bytes[9 + user_len..].to_vec()).expect("Failed to decode BountyPayoutKey"),
+        }
+    }
+    const BOUND: Bound = Bound::Bounded { max_size: 9 + 29 + MAX_BOUNTY_UNIT_ID_LEN as u32, is_fixed_size: false };
+}
+
+/// Delivery state of a bounty payout
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BountyPayoutStatus {
+    /// Reserved; the credit call to the learner's shard is in flight
+    InFlight,
+    /// The credit failed after the bounty stopped being open; the escrow stays
+    /// reserved and the shard's next claim retries it
+    Unpaid,
+    Credited,
+}
+
+/// A payout from a bounty's escrow
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct BountyPayout {
+    pub bounty_id: u64,
+    pub user: Principal,
+    pub unit_id: String,
+    pub amount: u64,
+    pub paid_at: u64,
+    pub status: BountyPayoutStatus,
+}
+
+impl Storable for BountyPayout {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode BountyPayout")
+    }
+    const BOUND: Bound = Bound::Bounded { max_size: 500, is_fixed_size: false };
+}
+
+/// Reward rule of a quiz unit, pushed to every shard
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct UnitRewardRule {
+    /// Multiplier applied to the quiz reward (10,000 BPS = 1x)
+    pub multiplier_bps: u32,
+    /// Whether an open bounty covers the unit (shown to learners; shards claim
+    /// on every pass and the hub decides)
+    pub has_bounty: bool,
+}
+
+/// Inbox category (mirrors user_profile::NotificationCategory)
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum NotificationCategory {
+    Governance,
+    Subscription,
+    Kyc,
+    Content,
+}
+
+/// Notification routed to user inboxes (mirrors user_profile::NotificationInput)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NotificationInput {
+    pub category: NotificationCategory,
+    pub title: String,
+    pub body: String,
+    pub link: Option<String>,
+}
+
+/// Content node update fanned out to every shard
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ContentUpdateNotice {
+    pub content_id: String,
+    pub title: String,
+    pub version: u64,
+}
+
 /// Quiz cache data structure for distribution
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct QuizCacheData {
@@ -184,6 +608,23 @@ pub struct QuizCacheData {
     pub answer_hashes: Vec<[u8; 32]>,
     pub question_count: u8,
     pub version: u64,
+    /// Per-question commitments for non-single-choice quizzes (forwarded as is)
+    pub commitments: Option<Vec<AnswerCommitment>>,
+    /// Questions drawn per learner from the pool (None = all)
+    pub draw_count: Option<u8>,
+    /// Quiz units to pass first (None = ungated)
+    pub prerequisites: Option<Vec<String>>,
+}
+
+/// Per-question answer commitment (see the learning engine)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum AnswerCommitment {
+    SingleChoice([u8; 32]),
+    MultiSelect([u8; 32]),
+    TrueFalse([u8; 32]),
+    Numeric { min: i64, max: i64 },
+    Ordering([u8; 32]),
+    ShortText(Vec<[u8; 32]>),
 }
 
 // ============================================================================
@@ -0,0 +1,8 @@
+/// Plan sold by request_checkout
+pub const DEFAULT_PLAN_ID: &str = "monthly";
+
+/// Length of one paid period
+pub const SUBSCRIPTION_PERIOD_NS: u64 = 30 * 24 * 60 * 60 * 1_000_000_000; // 30 days
+
+/// Longest accepted invoice id (keeps InvoiceKey within its bound)
+pub const MAX_INVOICE_ID_LEN: usize = 100;
@@ -6,9 +6,11 @@ use ic_cdk::{init, query, update};
 
 mod types;
 mod state;
+mod constants;
 
 use types::*;
 use state::*;
+use constants::*;
 
 #[init]
 fn init(args: InitArgs) {
@@ -42,6 +44,9 @@ async fn request_checkout(shard_id: Principal) -> Result<String, String> {
             amount: 1000, // $10.00
             timestamp: ic_cdk::api::time(),
             status: "pending".to_string(),
+            plan_id: Some(DEFAULT_PLAN_ID.to_string()),
+            expires_at: None,
+            auto_renew: Some(true),
         };
         s.borrow_mut().insert(user, record);
     });
@@ -61,37 +66,126 @@ async fn confirm_payment(session_id: String) -> Result<(), String> {
     // this prevents spoofing from the Bridge.
     verify_with_stripe(&session_id).await?;
 
-    // 3. Update local state
-    let shard_id = SUBSCRIPTIONS.with(|s| {
-        let mut map = s.borrow_mut();
-        if let Some(mut record) = map.get(&user) {
-            record.status = "paid".to_string();
-            let shard = record.shard_id;
-            map.insert(user, record);
-            Ok(shard)
-        } else {
-            Err("Subscription record missing".to_string())
-        }
-    })?;
+    // 3. Push the paid period to the user's shard (the session id makes retries no-ops)
+    let record = SUBSCRIPTIONS.with(|s| s.borrow().get(&user))
+        .ok_or("Subscription record missing".to_string())?;
+    push_extension(user, record, session_id.clone()).await?;
+
+    // Success! Clean up pending session
+    PENDING_SESSIONS.with(|s| s.borrow_mut().remove(&session_id));
+    Ok(())
+}
+
+/// Confirm a recurring charge for an auto-renewing subscription
+/// 
+/// Called by the billing bridge for each paid invoice; the invoice is
+/// verified with Stripe and applied to the shard as a period extension.
+#[update]
+async fn confirm_renewal(user: Principal, invoice_id: String) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    let bridge_id = BILLING_BRIDGE_ID.with(|id| *id.borrow().get());
+    let is_bridge = bridge_id != Principal::anonymous() && caller == bridge_id;
+    if !is_bridge && !ic_cdk::api::is_controller(&caller) {
+        return Err("Unauthorized: only the billing bridge can confirm renewals".to_string());
+    }
+    if invoice_id.is_empty() || invoice_id.len() > MAX_INVOICE_ID_LEN {
+        return Err(format!("Invoice id must be 1-{} characters", MAX_INVOICE_ID_LEN));
+    }
+
+    let record = SUBSCRIPTIONS.with(|s| s.borrow().get(&user))
+        .ok_or("Subscription record missing".to_string())?;
+    if record.status != "paid" {
+        return Err("Subscription was never activated".to_string());
+    }
+    if !record.auto_renew.unwrap_or(false) {
+        return Err("Auto-renew is disabled for this subscription".to_string());
+    }
+
+    // Claim the invoice before awaiting so a concurrent replay is rejected too
+    let key = InvoiceKey { user, invoice_id: invoice_id.clone() };
+    if PROCESSED_INVOICES.with(|p| p.borrow().contains_key(&key)) {
+        return Err("Invoice already applied".to_string());
+    }
+    PROCESSED_INVOICES.with(|p| p.borrow_mut().insert(key.clone(), ic_cdk::api::time()));
+
+    let result = match verify_with_stripe(&invoice_id).await {
+        Ok(()) => push_extension(user, record, invoice_id).await,
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        // Nothing was applied; the bridge may retry this invoice
+        PROCESSED_INVOICES.with(|p| p.borrow_mut().remove(&key));
+    }
+    result
+}
+
+/// Turn auto-renew on or off for the caller's subscription
+#[update]
+async fn set_auto_renew(enabled: bool) -> Result<(), String> {
+    let user = ic_cdk::caller();
+    let mut record = SUBSCRIPTIONS.with(|s| s.borrow().get(&user))
+        .ok_or("Subscription record missing".to_string())?;
+    record.auto_renew = Some(enabled);
+    let shard_id = record.shard_id;
+    SUBSCRIPTIONS.with(|s| s.borrow_mut().insert(user, record));
 
-    // 4. Activate the subscription on the user's specific shard
     let shard_call: Result<(Result<(), String>,), _> = ic_cdk::call(
         shard_id,
-        "internal_set_subscription",
-        (user, true)
+        "internal_set_subscription_auto_renew",
+        (user, enabled)
     ).await;
 
     match shard_call {
-        Ok((Ok(()),)) => {
-            // Success! Clean up pending session
-            PENDING_SESSIONS.with(|s| s.borrow_mut().remove(&session_id));
-            Ok(())
-        },
-        Ok((Err(e),)) => Err(format!("Shard activation failed: {}", e)),
+        Ok((Ok(()),)) => Ok(()),
+        Ok((Err(e),)) => Err(format!("Shard update failed: {}", e)),
         Err((code, msg)) => Err(format!("Shard call failed: {:?} {}", code, msg)),
     }
 }
 
+/// Extend the subscription on the user's shard by one period and record the
+/// resulting expiry; returns the new expiry
+async fn push_extension(user: Principal, record: SubscriptionRecord, extension_id: String) -> Result<u64, String> {
+    let extension = SubscriptionExtension {
+        extension_id,
+        plan_id: record.plan_id.clone().unwrap_or_else(|| DEFAULT_PLAN_ID.to_string()),
+        duration_ns: SUBSCRIPTION_PERIOD_NS,
+        auto_renew: record.auto_renew.unwrap_or(false),
+    };
+
+    let shard_call: Result<(Result<SubscriptionPeriod, String>,), _> = ic_cdk::call(
+        record.shard_id,
+        "internal_extend_subscription",
+        (user, extension)
+    ).await;
+
+    let period = match shard_call {
+        Ok((Ok(period),)) => period,
+        Ok((Err(e),)) => return Err(format!("Shard activation failed: {}", e)),
+        Err((code, msg)) => return Err(format!("Shard call failed: {:?} {}", code, msg)),
+    };
+
+    SUBSCRIPTIONS.with(|s| {
+        let mut map = s.borrow_mut();
+        if let Some(mut record) = map.get(&user) {
+            record.status = "paid".to_string();
+            record.expires_at = Some(period.expires_at);
+            map.insert(user, record);
+        }
+    });
+
+    // Tell the user (best effort; the extension is already applied)
+    let days_left = period.expires_at.saturating_sub(ic_cdk::api::time()) / (24 * 60 * 60 * 1_000_000_000);
+    let notification = NotificationInput {
+        category: NotificationCategory::Subscription,
+        title: "Subscription active".to_string(),
+        body: format!("Your {} subscription is active for another {} days.", period.plan_id, days_left),
+        link: None,
+    };
+    let _ = ic_cdk::call::<_, (Result<u64, String>,)>(record.shard_id, "internal_notify", (vec![user], notification)).await;
+
+    Ok(period.expires_at)
+}
+
 async fn verify_with_stripe(session_id: &str) -> Result<(), String> {
     // This is where you would call the actual Stripe API
     // GET https://api.stripe.com/v1/checkout/sessions/{session_id}
@@ -132,8 +226,11 @@ async fn verify_with_stripe(session_id: &str) -> Result<(), String> {
 
 #[query]
 fn get_subscription_status(user: Principal) -> bool {
+    let now = ic_cdk::api::time();
     SUBSCRIPTIONS.with(|s| {
-        s.borrow().get(&user).map(|r| r.status == "paid").unwrap_or(false)
+        s.borrow().get(&user)
+            .map(|r| r.status == "paid" && r.expires_at.is_none_or(|expires_at| expires_at > now))
+            .unwrap_or(false)
     })
 }
 
@@ -165,6 +262,20 @@ fn admin_update_staking_hub(new_id: Principal) -> Result<(), String> {
     Ok(())
 }
 
+#[update]
+fn admin_set_billing_bridge(bridge_id: Principal) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Only controllers can set the billing bridge".to_string());
+    }
+    BILLING_BRIDGE_ID.with(|id| id.borrow_mut().set(bridge_id).expect("Failed to set billing bridge ID"));
+    Ok(())
+}
+
+#[query]
+fn get_billing_bridge() -> Principal {
+    BILLING_BRIDGE_ID.with(|id| *id.borrow().get())
+}
+
 #[query]
 fn get_staking_hub() -> Principal {
     STAKING_HUB_ID.with(|id| *id.borrow().get())
@@ -29,4 +29,19 @@ thread_local! {
             MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(2)))
         )
     );
+
+    /// Billing bridge allowed to confirm renewals (anonymous = controllers only)
+    pub static BILLING_BRIDGE_ID: RefCell<StableCell<Principal, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(3))),
+            Principal::anonymous()
+        ).unwrap()
+    );
+
+    /// Renewal invoices already applied: (user, invoice_id) -> time applied
+    pub static PROCESSED_INVOICES: RefCell<StableBTreeMap<InvoiceKey, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(4)))
+        )
+    );
 }
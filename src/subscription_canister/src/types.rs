@@ -15,6 +15,12 @@ pub struct SubscriptionRecord {
     pub amount: u64,
     pub timestamp: u64,
     pub status: String, // "pending", "paid", "expired"
+    /// Plan being paid for (None on records from before plans existed)
+    pub plan_id: Option<String>,
+    /// End of the paid period as confirmed by the shard
+    pub expires_at: Option<u64>,
+    /// Whether billing renews the plan each period
+    pub auto_renew: Option<bool>,
 }
 
 impl Storable for SubscriptionRecord {
@@ -31,3 +37,65 @@ impl Storable for SubscriptionRecord {
         is_fixed_size: false,
     };
 }
+
+/// A paid period pushed to the user's shard (mirrors user_profile::SubscriptionExtension)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SubscriptionExtension {
+    /// Payment / invoice id, unique per paid period
+    pub extension_id: String,
+    pub plan_id: String,
+    pub duration_ns: u64,
+    pub auto_renew: bool,
+}
+
+/// Subscription period held by the shard (mirrors user_profile::SubscriptionPeriod)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SubscriptionPeriod {
+    pub plan_id: String,
+    pub started_at: u64,
+    pub expires_at: u64,
+    pub auto_renew: bool,
+    pub last_extension_id: String,
+    pub updated_at: u64,
+    pub expiry_reminded_at: Option<u64>,
+}
+
+/// Inbox category (mirrors user_profile::NotificationCategory)
+#[derive(CandidType, Deserialize, Clone, Copy, Debug)]
+pub enum NotificationCategory {
+    Governance,
+    Subscription,
+    Kyc,
+    Content,
+}
+
+/// Notification posted to a shard inbox (mirrors user_profile::NotificationInput)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NotificationInput {
+    pub category: NotificationCategory,
+    pub title: String,
+    pub body: String,
+    pub link: Option<String>,
+}
+
+/// A renewal invoice applied for a user (replays are rejected)
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InvoiceKey {
+    pub user: Principal,
+    pub invoice_id: String,
+}
+
+impl Storable for InvoiceKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode InvoiceKey")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 200,
+        is_fixed_size: false,
+    };
+}
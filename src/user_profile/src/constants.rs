@@ -1,3 +1,5 @@
+use rate_limit::RateLimit;
+
 // Archive retention: keep last 100 transactions per user locally
 pub const TRANSACTION_RETENTION_LIMIT: u64 = 100;
 
@@ -5,6 +7,16 @@ pub const TRANSACTION_RETENTION_LIMIT: u64 = 100;
 // Set higher than RETENTION_LIMIT to avoid archiving on every transaction
 pub const ARCHIVE_TRIGGER_THRESHOLD: u64 = 150;
 
+// Transaction history paging
+pub const DEFAULT_HISTORY_PAGE_SIZE: u32 = 20;
+pub const MAX_HISTORY_PAGE_SIZE: u32 = 100;
+// Max transactions examined per history call (bounds cost when filters match little)
+pub const MAX_HISTORY_SCAN: u64 = 500;
+// Sequences fetched from the archive per call (archive caps ranges at 100)
+pub const ARCHIVE_FETCH_CHUNK: u64 = 100;
+// Max archives followed through next_archive links
+pub const MAX_ARCHIVE_CHAIN_DEPTH: u32 = 8;
+
 // Periodic archive check interval (6 hours in seconds)
 pub const ARCHIVE_CHECK_INTERVAL_SECS: u64 = 6 * 60 * 60;
 
@@ -15,6 +27,145 @@ pub const _SYNC_INTERVAL_SECS: u64 = 5;
 pub const ALLOWANCE_LOW_THRESHOLD: u64 = 500 * 100_000_000; // 500 Tokens
 pub const ALLOWANCE_REFILL_AMOUNT: u64 = 1000 * 100_000_000; // 1000 Tokens
 
+// Referral Program
+pub const REFERRAL_QUALIFYING_QUIZZES: u32 = 3;            // Referee must pass this many quizzes
+pub const REFERRAL_BONUS_AMOUNT: u64 = 2 * 100_000_000;    // 2 Tokens to each party
+pub const MAX_REFERRAL_REWARDS_PER_REFERRER: u64 = 50;     // Lifetime cap per referrer
+pub const MAX_REFERRAL_REWARDS_PER_DAY: u64 = 5;           // Daily cap per referrer
+pub const REFERRAL_SETTLEMENT_RETRY_NS: u64 = 60 * 60 * 1_000_000_000; // Pending settlements are retried hourly
+pub const REFERRAL_SETTLEMENT_EXPIRY_NS: u64 = 30 * 24 * 60 * 60 * 1_000_000_000; // then rejected after 30 days
+pub const REFERRAL_SETTLEMENTS_PER_SWEEP: usize = 20;
+
+// Account Recovery
+pub const MAX_RECOVERY_GUARDIANS: usize = 5;
+pub const RECOVERY_DELAY_NS: u64 = 7 * 24 * 60 * 60 * 1_000_000_000; // 7 days for the old principal to cancel
+pub const MAX_ARCHIVE_REKEY_ROUNDS: u32 = 100;
+
+// Linked Principals (multi-wallet identity)
+pub const MAX_LINKED_PRINCIPALS: usize = 5;
+pub const LINK_CHALLENGE_TTL_NS: u64 = 10 * 60 * 1_000_000_000; // 10 minutes to complete a link
+
+// Subscriptions
+pub const SUBSCRIPTION_EXPIRY_CHECK_INTERVAL_SECS: u64 = 60 * 60; // Downgrade sweep every hour
+pub const SUBSCRIPTION_RENEWAL_GRACE_NS: u64 = 3 * 24 * 60 * 60 * 1_000_000_000; // Auto-renewing plans stay active 3 days past expiry
+pub const MANUAL_SUBSCRIPTION_PERIOD_NS: u64 = 30 * 24 * 60 * 60 * 1_000_000_000; // Admin activations and pre-expiry legacy flags
+pub const MAX_SUBSCRIPTIONS_SCANNED_PER_SWEEP: usize = 2_000; // Periods checked per sweep; a cursor walks the rest on later sweeps
+pub const MAX_EXTENSION_ID_LEN: usize = 100; // Keeps UserExtensionKey within its bound
+
+// Completion Certificates
+pub const DEFAULT_CERTIFICATE_KEY_NAME: &str = "key_1"; // Mainnet threshold ECDSA key ("dfx_test_key" locally)
+pub const CERTIFICATE_DERIVATION_PATH: &[u8] = b"completion-certificates";
+
+// Data Export & Erasure
+pub const MAX_EXPORT_ARCHIVED_TRANSACTIONS: u64 = 2_000; // Per export; the rest via get_transaction_history
+
+// Peer-to-peer Transfers
+pub const MAX_TRANSFER_IDEMPOTENCY_KEY_LEN: usize = 64;
+pub const MAX_TRANSFER_MEMO_LEN: usize = 100;
+pub const STAKED_TRANSFER_RETRY_AFTER_NS: u64 = 10 * 60 * 1_000_000_000; // A Pending transfer this old is treated as interrupted
+
+// Notifications
+pub const MAX_NOTIFICATIONS_PER_USER: u64 = 200; // Oldest are dropped beyond this
+pub const MAX_NOTIFICATION_TITLE_LEN: usize = 120;
+pub const MAX_NOTIFICATION_BODY_LEN: usize = 1_000;
+pub const MAX_NOTIFICATION_LINK_LEN: usize = 200;
+pub const MAX_NOTIFY_BATCH: usize = 500; // Recipients per internal_notify call
+pub const DEFAULT_NOTIFICATION_PAGE_SIZE: u32 = 20;
+pub const MAX_NOTIFICATION_PAGE_SIZE: u32 = 100;
+pub const SUBSCRIPTION_EXPIRY_REMINDER_NS: u64 = 3 * 24 * 60 * 60 * 1_000_000_000; // Remind non-renewing users 3 days ahead
+pub const CONTENT_NOTICE_SWEEP_INTERVAL_SECS: u64 = 60;
+pub const CONTENT_NOTICE_USERS_PER_SWEEP: usize = 2_000;
+
+// Bounty claims (retried by the content notice sweep until the Hub answers)
+pub const BOUNTY_CLAIM_RETRY_AFTER_NS: u64 = 2 * 60 * 1_000_000_000; // Leave the first attempt time to finish
+pub const BOUNTY_CLAIM_EXPIRY_NS: u64 = 7 * 24 * 60 * 60 * 1_000_000_000; // Unanswered claims older than this are dropped
+pub const BOUNTY_CLAIMS_RETRIED_PER_SWEEP: usize = 20;
+
+// Rolling-window limits: buckets kept per tier (enough to cover 24h, 30d and 365d)
+pub const HOUR_NS: u64 = 60 * 60 * 1_000_000_000;
+pub const DAY_NS: u64 = 24 * HOUR_NS;
+pub const WEEK_NS: u64 = 7 * DAY_NS;
+pub const ROLLING_HOURLY_BUCKETS: u64 = 25;
+pub const ROLLING_DAILY_BUCKETS: u64 = 31;
+pub const ROLLING_WEEKLY_BUCKETS: u64 = 54;
+
+// Quiz Answers
+pub const MAX_TEXT_ANSWER_LEN: usize = 200; // Longer short text answers are wrong
+
+// Spaced Repetition (SM-2)
+pub const DEFAULT_EASE_MILLI: u16 = 2_500; // SM-2 ease factor x1000
+pub const MIN_EASE_MILLI: u16 = 1_300;
+pub const REVIEW_PASS_QUALITY: u8 = 3; // Quality 0-5; below this the unit lapses
+pub const MAX_REVIEW_INTERVAL_DAYS: u16 = 365;
+pub const REVIEW_REWARD_PERCENT: u64 = 10; // Of the quiz reward, for a due review; 0 disables
+pub const MAX_DUE_REVIEWS: usize = 100; // Per get_due_reviews call
+
+// Reward Multipliers & Sponsored Bounties (rules are pushed by the staking hub)
+pub const DEFAULT_REWARD_MULTIPLIER_BPS: u32 = 10_000; // 1x
+
+// Prerequisites
+pub const MAX_LISTED_PREREQUISITES: usize = 5; // Named in a refusal; the rest are counted
+
+// Localized Quizzes (must agree with the learning engine's locale rules)
+pub const MIN_LOCALE_LEN: usize = 2;
+pub const MAX_LOCALE_LEN: usize = 16;
+
+// Audit Log
+pub const MAX_AUDIT_VALUE_LEN: usize = 600; // before/after renderings are cut to this many bytes
+pub const AUDIT_RETENTION_LIMIT: u64 = 1_000; // Entries kept locally; older ones are archived
+pub const AUDIT_ARCHIVE_BATCH: u64 = 100; // Entries sent per archive call
+pub const DEFAULT_AUDIT_PAGE_SIZE: u32 = 20;
+pub const MAX_AUDIT_PAGE_SIZE: u32 = 100;
+
+// Ingress Filtering (canister_inspect_message)
+pub const MAX_INGRESS_ARG_BYTES: usize = 16 * 1024; // Default for methods not in INGRESS_ARG_LIMITS
+pub const INGRESS_ARG_LIMITS: &[(&str, usize)] = &[];
+pub const ANONYMOUS_REJECTED_METHODS: &[&str] = &[
+    "admin_set_certificate_key_name", "admin_set_kyc_status", "admin_set_kyc_tier",
+    "admin_set_subscription", "admin_set_user_stats", "approve_recovery", "cancel_recovery",
+    "claim_certificate", "complete_link", "debug_trigger_archive", "erase_my_data",
+    "finalize_recovery", "initiate_recovery", "mark_all_notifications_read",
+    "mark_notifications_read", "practice_quiz", "register_user", "request_link_challenge",
+    "retry_staked_transfer", "set_archive_canister", "set_notification_preferences", "set_recovery_guardians",
+    "submit_quiz", "sync_my_credentials", "transfer_staked", "unlink_principal", "unstake",
+    "update_profile", "verify_humanity",
+];
+pub const REGISTRATION_RATE_LIMIT: RateLimit = RateLimit { bucket: "registration", capacity: 3, refill_interval_ns: 10 * 60 * 1_000_000_000 };
+pub const PROFILE_RATE_LIMIT: RateLimit = RateLimit { bucket: "profile", capacity: 10, refill_interval_ns: 60 * 1_000_000_000 };
+pub const QUIZ_RATE_LIMIT: RateLimit = RateLimit { bucket: "quiz", capacity: 10, refill_interval_ns: 30 * 1_000_000_000 };
+pub const PRACTICE_RATE_LIMIT: RateLimit = RateLimit { bucket: "practice", capacity: 20, refill_interval_ns: 15 * 1_000_000_000 };
+pub const STAKING_RATE_LIMIT: RateLimit = RateLimit { bucket: "staking", capacity: 5, refill_interval_ns: 60 * 1_000_000_000 };
+pub const ACCOUNT_RATE_LIMIT: RateLimit = RateLimit { bucket: "account", capacity: 5, refill_interval_ns: 5 * 60 * 1_000_000_000 };
+pub const INBOX_RATE_LIMIT: RateLimit = RateLimit { bucket: "inbox", capacity: 30, refill_interval_ns: 2 * 1_000_000_000 };
+pub const MAINTENANCE_RATE_LIMIT: RateLimit = RateLimit { bucket: "maintenance", capacity: 2, refill_interval_ns: 60 * 1_000_000_000 };
+// Must agree with the guards in inspect.rs
+pub const RATE_LIMITED_METHODS: &[(&str, RateLimit)] = &[
+    ("register_user", REGISTRATION_RATE_LIMIT),
+    ("update_profile", PROFILE_RATE_LIMIT),
+    ("set_notification_preferences", PROFILE_RATE_LIMIT),
+    ("set_recovery_guardians", PROFILE_RATE_LIMIT),
+    ("submit_quiz", QUIZ_RATE_LIMIT),
+    ("practice_quiz", PRACTICE_RATE_LIMIT),
+    ("unstake", STAKING_RATE_LIMIT),
+    ("transfer_staked", STAKING_RATE_LIMIT),
+    ("retry_staked_transfer", STAKING_RATE_LIMIT),
+    ("claim_certificate", ACCOUNT_RATE_LIMIT),
+    ("sync_my_credentials", ACCOUNT_RATE_LIMIT),
+    ("get_certificate_signing_key", ACCOUNT_RATE_LIMIT),
+    ("request_link_challenge", ACCOUNT_RATE_LIMIT),
+    ("complete_link", ACCOUNT_RATE_LIMIT),
+    ("unlink_principal", ACCOUNT_RATE_LIMIT),
+    ("initiate_recovery", ACCOUNT_RATE_LIMIT),
+    ("approve_recovery", ACCOUNT_RATE_LIMIT),
+    ("cancel_recovery", ACCOUNT_RATE_LIMIT),
+    ("finalize_recovery", ACCOUNT_RATE_LIMIT),
+    ("erase_my_data", ACCOUNT_RATE_LIMIT),
+    ("verify_humanity", ACCOUNT_RATE_LIMIT),
+    ("mark_notifications_read", INBOX_RATE_LIMIT),
+    ("mark_all_notifications_read", INBOX_RATE_LIMIT),
+    ("debug_force_sync", MAINTENANCE_RATE_LIMIT),
+];
+
 // Days in months (standard, non-leap)
 pub const DAYS_IN_MONTHS: [u64; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
 // Days in months (leap year)
@@ -1,15 +1,19 @@
-use ic_cdk::{init, query, update, post_upgrade};
+use ic_cdk::{init, query, update, inspect_message, post_upgrade};
+use ic_cdk_timers::set_timer_interval;
 use candid::Principal;
+use std::time::Duration;
 
 mod types;
 mod state;
 mod constants;
 mod service;
+mod inspect;
 
 use types::*;
 use state::*;
 use constants::*;
 use service::*;
+use inspect::*;
 
 
 // ============================================================================
@@ -20,19 +24,56 @@ use service::*;
 fn init(args: InitArgs) {
     STAKING_HUB_ID.with(|id| id.borrow_mut().set(args.staking_hub_id).expect("Failed to set Staking Hub ID"));
     LEARNING_CONTENT_ID.with(|id| id.borrow_mut().set(args.learning_content_id).expect("Failed to set Learning Content ID"));
+
+    start_subscription_expiry_timer();
+    start_content_notice_timer();
 }
 
 #[post_upgrade]
 fn post_upgrade() {
-    // Migration logic if needed
+    // Shards upgraded from before subscription periods existed
+    migrate_legacy_subscriptions();
+
+    start_subscription_expiry_timer();
+    start_content_notice_timer();
+}
+
+fn start_subscription_expiry_timer() {
+    set_timer_interval(Duration::from_secs(SUBSCRIPTION_EXPIRY_CHECK_INTERVAL_SECS), || {
+        let batch = next_subscription_batch();
+        downgrade_expired_subscriptions(&batch);
+        send_subscription_expiry_reminders(&batch);
+    });
+}
+
+fn start_content_notice_timer() {
+    set_timer_interval(Duration::from_secs(CONTENT_NOTICE_SWEEP_INTERVAL_SECS), || {
+        process_content_notices();
+        retry_bounty_claims();
+        retry_referral_settlements();
+    });
+}
+
+// ============================================================================
+// INGRESS FILTERING
+// ============================================================================
+
+/// Drop ingress that would be rejected anyway before it costs consensus cycles
+#[inspect_message]
+fn inspect_message() {
+    let method = ic_cdk::api::call::method_name();
+    match check_ingress(&method, ic_cdk::caller(), ic_cdk::api::call::arg_data_raw_size()) {
+        Ok(()) => ic_cdk::api::call::accept_message(),
+        Err(e) => ic_cdk::trap(&e),
+    }
 }
 
 // ============================================================================
 // UPDATE OPERATIONS
 // ============================================================================
 
-#[update]
-async fn register_user(args: UserProfileUpdate) -> Result<(), String> {
+#[update(guard = "limit_registration")]
+async fn register_user(args: UserProfileUpdate, referral_code: Option<String>) -> Result<(), String> {
     let user = ic_cdk::caller();
     
     if user == Principal::anonymous() {
@@ -42,6 +83,36 @@ async fn register_user(args: UserProfileUpdate) -> Result<(), String> {
     if USER_PROFILES.with(|p| p.borrow().contains_key(&user)) {
         return Err("User already registered".to_string());
     }
+    if is_linked_principal(user) {
+        return Err("This principal is linked to an existing account".to_string());
+    }
+
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+
+    // Resolve the referral code through the hub (the referrer may live on any shard)
+    let referrer = match referral_code.filter(|c| !c.trim().is_empty()) {
+        Some(code) => {
+            let (referrer,): (Option<Principal>,) = ic_cdk::call(
+                staking_hub_id,
+                "resolve_referral_code",
+                (code,)
+            ).await.map_err(|(c, m)| format!("Referral lookup failed: {:?} {}", c, m))?;
+            let referrer = referrer.ok_or("Invalid referral code")?;
+            if referrer == user {
+                return Err("You cannot refer yourself".to_string());
+            }
+            Some(referrer)
+        }
+        None => None,
+    };
+
+    // Re-check after the await in case of a concurrent registration
+    if USER_PROFILES.with(|p| p.borrow().contains_key(&user)) {
+        return Err("User already registered".to_string());
+    }
+    if is_linked_principal(user) {
+        return Err("This principal is linked to an existing account".to_string());
+    }
 
     let new_profile = UserProfile {
         email: args.email,
@@ -56,9 +127,19 @@ async fn register_user(args: UserProfileUpdate) -> Result<(), String> {
     };
 
     USER_PROFILES.with(|p| p.borrow_mut().insert(user, new_profile));
+
+    if let Some(referrer) = referrer {
+        REFERRALS.with(|r| r.borrow_mut().insert(user, ReferralRecord {
+            referrer,
+            registered_at: ic_cdk::api::time(),
+            quizzes_passed: 0,
+            status: ReferralStatus::Qualifying,
+            referee_bonus: 0,
+            referrer_bonus: 0,
+        }));
+    }
     
     // Register user's shard location with staking_hub (for governance voting power lookup)
-    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
     let _: Result<(Result<(), String>,), _> = ic_cdk::call(
         staking_hub_id,
         "register_user_location",
@@ -70,12 +151,15 @@ async fn register_user(args: UserProfileUpdate) -> Result<(), String> {
     Ok(())
 }
 
-#[update]
+#[update(guard = "limit_profile")]
 fn update_profile(args: UserProfileUpdate) -> Result<(), String> {
     let user = ic_cdk::caller();
     if user == Principal::anonymous() {
         return Err("Anonymous actions are not allowed.".to_string());
     }
+    if is_erased(user) {
+        return Err("Account data has been erased".to_string());
+    }
     
     USER_PROFILES.with(|p| {
         let mut map = p.borrow_mut();
@@ -94,6 +178,7 @@ fn update_profile(args: UserProfileUpdate) -> Result<(), String> {
 
 #[query]
 fn get_profile(user: Principal) -> Option<UserProfile> {
+    let user = resolve_account(user);
     USER_PROFILES.with(|p| p.borrow().get(&user))
 }
 
@@ -106,11 +191,37 @@ fn receive_quiz_cache(unit_id: String, cache: QuizCacheData) {
          ic_cdk::trap("Unauthorized cache update");
     }
     
+    // Translated quizzes are re-derived from the unit's quiz; refetch them
+    clear_localized_quiz_caches(&unit_id);
     QUIZ_CACHE.with(|q| {
         q.borrow_mut().insert(unit_id, cache);
     });
 }
 
+/// Receive the translated quiz locales of a unit from the Hub (sent when a
+/// translation is added, changed or removed)
+#[update]
+fn receive_quiz_locales(unit_id: String, locales: Vec<String>) {
+    let hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    if ic_cdk::caller() != hub_id {
+         ic_cdk::trap("Unauthorized cache update");
+    }
+
+    set_quiz_locales(&unit_id, locales);
+}
+
+/// Receive the resolved prerequisites of units from the Hub (sent when the
+/// content graph changes what they require)
+#[update]
+fn receive_prerequisites(updates: Vec<(String, Vec<String>)>) {
+    let hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    if ic_cdk::caller() != hub_id {
+         ic_cdk::trap("Unauthorized cache update");
+    }
+
+    apply_prerequisite_updates(updates);
+}
+
 /// Receive full cache sync from Hub (for new shards)
 #[update]
 fn receive_full_quiz_cache(caches: Vec<(String, QuizCacheData)>) {
@@ -140,6 +251,505 @@ fn receive_token_limits(config: TokenLimitsConfig) {
     });
 }
 
+/// Receive a badge definition from the staking hub
+#[update]
+fn receive_badge_definition(badge: BadgeDefinition) {
+    let hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    if ic_cdk::caller() != hub_id {
+         ic_cdk::trap("Unauthorized badge update");
+    }
+
+    BADGE_DEFINITIONS.with(|b| {
+        b.borrow_mut().insert(badge.id.clone(), badge);
+    });
+}
+
+#[query]
+fn get_badge_definitions() -> Vec<BadgeDefinition> {
+    BADGE_DEFINITIONS.with(|b| b.borrow().iter().map(|(_, badge)| badge).collect())
+}
+
+/// Receive per-unit reward rules from the staking hub
+#[update]
+fn receive_unit_reward_rules(rules: Vec<(String, UnitRewardRule)>) {
+    let hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    if ic_cdk::caller() != hub_id {
+         ic_cdk::trap("Unauthorized reward rule update");
+    }
+
+    apply_unit_reward_rules(rules);
+}
+
+/// Get a unit's reward multiplier and whether a sponsor bounty covers it
+#[query]
+fn get_unit_reward_rule(unit_id: String) -> UnitRewardRule {
+    unit_reward_rule(&unit_id)
+}
+
+/// Get a user's streak and earned badges
+#[query]
+fn get_user_achievements(user: Principal) -> UserAchievements {
+    let streak = USER_STREAKS.with(|s| s.borrow().get(&user)).unwrap_or_default();
+    let badges = USER_BADGES.with(|b| b.borrow().get(&user)).unwrap_or_default();
+
+    UserAchievements {
+        current_streak: effective_current_streak(&streak, get_current_day()),
+        longest_streak: streak.longest_streak,
+        total_quizzes_passed: streak.total_quizzes_passed,
+        badges: badges.0,
+    }
+}
+
+/// Get the caller's referral code (issued by the staking hub on first request)
+#[update]
+async fn get_my_referral_code() -> Result<String, String> {
+    let user = ic_cdk::caller();
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&user)) {
+        return Err("User not registered".to_string());
+    }
+    if is_erased(user) {
+        return Err("Account data has been erased".to_string());
+    }
+
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    let result: Result<(Result<String, String>,), _> = ic_cdk::call(
+        staking_hub_id,
+        "register_referral_code",
+        (user,)
+    ).await;
+
+    match result {
+        Ok((inner,)) => inner,
+        Err((code, msg)) => Err(format!("Hub call failed: {:?} {}", code, msg)),
+    }
+}
+
+/// Get who referred a user and the rewards they earned as a referrer
+#[query]
+fn get_referral_info(user: Principal) -> ReferralInfo {
+    ReferralInfo {
+        referred_by: REFERRALS.with(|r| r.borrow().get(&user)),
+        referrer_stats: REFERRER_STATS.with(|s| s.borrow().get(&user)).unwrap_or_default(),
+    }
+}
+
+/// Pay the referrer side of a qualified referral (routed by the staking hub)
+#[update]
+fn internal_credit_referral(referrer: Principal, referee: Principal) -> Result<u64, String> {
+    let hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    if ic_cdk::caller() != hub_id {
+        return Err("Unauthorized: Only the staking hub can credit referrals".to_string());
+    }
+    if referrer == referee {
+        return Err("Self-referral is not allowed".to_string());
+    }
+
+    let previous = staked_balance_audit_state(referrer);
+    let bonus = credit_referrer(referrer)?;
+    record_audit("internal_credit_referral", referrer, &previous, &staked_balance_audit_state(referrer));
+    Ok(bonus)
+}
+
+/// Receive a referrer's recovered principal from the staking hub
+#[update]
+fn receive_referrer_migration(old_referrer: Principal, new_referrer: Principal) {
+    let hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    if ic_cdk::caller() != hub_id {
+         ic_cdk::trap("Unauthorized referrer migration");
+    }
+
+    rekey_referrer(old_referrer, new_referrer);
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Account Recovery
+// ─────────────────────────────────────────────────────────────────
+
+/// Register recovery guardians for the caller's account (empty list removes them)
+#[update(guard = "limit_profile")]
+fn set_recovery_guardians(guardians: Vec<Principal>, threshold: u8) -> Result<(), String> {
+    let user = ic_cdk::caller();
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&user)) {
+        return Err("User not registered".to_string());
+    }
+    if is_erased(user) {
+        return Err("Account data has been erased".to_string());
+    }
+    if is_recovery_executing(user) {
+        return Err("A recovery is being executed for this account".to_string());
+    }
+
+    let mut unique: Vec<Principal> = Vec::new();
+    for guardian in guardians {
+        if guardian == Principal::anonymous() || guardian == user {
+            return Err("Guardians must be distinct, non-anonymous principals other than yourself".to_string());
+        }
+        if !unique.contains(&guardian) {
+            unique.push(guardian);
+        }
+    }
+
+    if unique.is_empty() {
+        RECOVERY_CONFIGS.with(|c| c.borrow_mut().remove(&user));
+        return Ok(());
+    }
+    if unique.len() > MAX_RECOVERY_GUARDIANS {
+        return Err(format!("At most {} guardians are allowed", MAX_RECOVERY_GUARDIANS));
+    }
+    if threshold == 0 || threshold as usize > unique.len() {
+        return Err(format!("Threshold must be between 1 and {}", unique.len()));
+    }
+
+    RECOVERY_CONFIGS.with(|c| c.borrow_mut().insert(user, RecoveryConfig {
+        guardians: unique,
+        threshold,
+        updated_at: ic_cdk::api::time(),
+    }));
+    Ok(())
+}
+
+/// Start moving `account` to `new_principal` (guardians only)
+/// Returns the time after which the recovery can be finalized
+#[update(guard = "limit_account")]
+fn initiate_recovery(account: Principal, new_principal: Principal) -> Result<u64, String> {
+    let guardian = ic_cdk::caller();
+    let config = RECOVERY_CONFIGS.with(|c| c.borrow().get(&account))
+        .ok_or("Account has no recovery guardians")?;
+    if !config.guardians.contains(&guardian) {
+        return Err("Unauthorized: Caller is not a guardian of this account".to_string());
+    }
+    if new_principal == Principal::anonymous() || new_principal == account {
+        return Err("Invalid new principal".to_string());
+    }
+    if USER_PROFILES.with(|p| p.borrow().contains_key(&new_principal)) || is_linked_principal(new_principal) {
+        return Err("New principal is already registered".to_string());
+    }
+    if PENDING_RECOVERIES.with(|r| r.borrow().contains_key(&account)) {
+        return Err("A recovery is already pending for this account".to_string());
+    }
+
+    let now = ic_cdk::api::time();
+    let executable_at = now + RECOVERY_DELAY_NS;
+    PENDING_RECOVERIES.with(|r| r.borrow_mut().insert(account, RecoveryRequest {
+        new_principal,
+        initiated_by: guardian,
+        approvals: vec![guardian],
+        created_at: now,
+        executable_at,
+        status: RecoveryStatus::Pending,
+    }));
+
+    Ok(executable_at)
+}
+
+/// Approve a pending recovery (guardians only); returns the approval count
+#[update(guard = "limit_account")]
+fn approve_recovery(account: Principal) -> Result<u64, String> {
+    let guardian = ic_cdk::caller();
+    let config = RECOVERY_CONFIGS.with(|c| c.borrow().get(&account))
+        .ok_or("Account has no recovery guardians")?;
+    if !config.guardians.contains(&guardian) {
+        return Err("Unauthorized: Caller is not a guardian of this account".to_string());
+    }
+
+    PENDING_RECOVERIES.with(|r| {
+        let mut map = r.borrow_mut();
+        let mut request = map.get(&account).ok_or("No pending recovery for this account")?;
+        if request.status != RecoveryStatus::Pending {
+            return Err("Recovery is already being executed".to_string());
+        }
+        if request.approvals.contains(&guardian) {
+            return Err("Already approved".to_string());
+        }
+        request.approvals.push(guardian);
+        let count = request.approvals.len() as u64;
+        map.insert(account, request);
+        Ok(count)
+    })
+}
+
+/// Cancel a pending recovery of the caller's own account
+#[update(guard = "limit_account")]
+fn cancel_recovery() -> Result<(), String> {
+    let user = ic_cdk::caller();
+    PENDING_RECOVERIES.with(|r| {
+        let mut map = r.borrow_mut();
+        let request = map.get(&user).ok_or("No pending recovery for this account")?;
+        if request.status != RecoveryStatus::Pending {
+            return Err("Recovery is already being executed".to_string());
+        }
+        map.remove(&user);
+        Ok(())
+    })
+}
+
+/// Finalize an approved recovery once its time lock has passed
+/// Callable by a guardian or the new principal; returns the new principal
+#[update(guard = "limit_account")]
+async fn finalize_recovery(account: Principal) -> Result<Principal, String> {
+    let caller = ic_cdk::caller();
+    let request = PENDING_RECOVERIES.with(|r| r.borrow().get(&account))
+        .ok_or("No pending recovery for this account")?;
+    let is_guardian = RECOVERY_CONFIGS.with(|c| {
+        c.borrow().get(&account).is_some_and(|config| config.guardians.contains(&caller))
+    });
+    if caller != request.new_principal && !is_guardian {
+        return Err("Unauthorized: Only a guardian or the new principal can finalize".to_string());
+    }
+
+    finalize_recovery_internal(account).await
+}
+
+#[query]
+fn get_recovery_info(account: Principal) -> RecoveryInfo {
+    RecoveryInfo {
+        config: RECOVERY_CONFIGS.with(|c| c.borrow().get(&account)),
+        pending: PENDING_RECOVERIES.with(|r| r.borrow().get(&account)),
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Linked Principals
+// ─────────────────────────────────────────────────────────────────
+
+/// Start linking `secondary` to the caller's profile (canonical principal only)
+/// Returns a one-time token that `secondary` must pass to complete_link
+#[update(guard = "limit_account")]
+async fn request_link_challenge(secondary: Principal) -> Result<String, String> {
+    let canonical = ic_cdk::caller();
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&canonical)) {
+        return Err("Only the canonical principal of a registered profile can link wallets".to_string());
+    }
+    if is_erased(canonical) {
+        return Err("Account data has been erased".to_string());
+    }
+    if is_recovery_executing(canonical) {
+        return Err("Account is being migrated to a new principal".to_string());
+    }
+    if secondary == Principal::anonymous() || secondary == canonical {
+        return Err("Invalid principal to link".to_string());
+    }
+    if USER_PROFILES.with(|p| p.borrow().contains_key(&secondary)) || is_linked_principal(secondary) {
+        return Err("Principal already belongs to an account".to_string());
+    }
+    let linked_count = LINKED_OF_CANONICAL.with(|l| l.borrow().get(&canonical)).map_or(0, |l| l.0.len());
+    if linked_count >= MAX_LINKED_PRINCIPALS {
+        return Err(format!("At most {} principals can be linked", MAX_LINKED_PRINCIPALS));
+    }
+
+    let token = generate_link_token().await?;
+    // Replaces any earlier outstanding challenge
+    LINK_CHALLENGES.with(|c| c.borrow_mut().insert(canonical, LinkChallenge {
+        token: token.clone(),
+        secondary,
+        expires_at: ic_cdk::api::time() + LINK_CHALLENGE_TTL_NS,
+    }));
+
+    Ok(token)
+}
+
+/// Complete a link from the secondary principal's own session
+#[update(guard = "limit_account")]
+async fn complete_link(canonical: Principal, token: String) -> Result<(), String> {
+    let secondary = ic_cdk::caller();
+    let challenge = LINK_CHALLENGES.with(|c| c.borrow().get(&canonical))
+        .ok_or("No pending link challenge for this account")?;
+    if challenge.secondary != secondary || challenge.token != token {
+        return Err("Invalid link challenge".to_string());
+    }
+    // One-time use, whatever the outcome
+    LINK_CHALLENGES.with(|c| c.borrow_mut().remove(&canonical));
+    if ic_cdk::api::time() > challenge.expires_at {
+        return Err("Link challenge has expired".to_string());
+    }
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&canonical)) || is_recovery_executing(canonical) {
+        return Err("Account is not available for linking".to_string());
+    }
+    if USER_PROFILES.with(|p| p.borrow().contains_key(&secondary)) || is_linked_principal(secondary) {
+        return Err("Principal already belongs to an account".to_string());
+    }
+
+    // The hub rejects principals registered on any shard
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    let result: Result<(Result<(), String>,), _> = ic_cdk::call(
+        staking_hub_id,
+        "register_linked_principal",
+        (canonical, secondary)
+    ).await;
+    match result {
+        Ok((Ok(()),)) => {}
+        Ok((Err(msg),)) => return Err(format!("Hub rejected link: {}", msg)),
+        Err((code, msg)) => return Err(format!("Hub call failed: {:?} {}", code, msg)),
+    }
+
+    // Re-check after the await (registration, another link or a recovery may have raced us)
+    let linked_count = LINKED_OF_CANONICAL.with(|l| l.borrow().get(&canonical)).map_or(0, |l| l.0.len());
+    if USER_PROFILES.with(|p| p.borrow().contains_key(&secondary))
+        || is_linked_principal(secondary)
+        || !USER_PROFILES.with(|p| p.borrow().contains_key(&canonical))
+        || is_recovery_executing(canonical)
+        || linked_count >= MAX_LINKED_PRINCIPALS
+    {
+        let _ = unregister_linked_principal_at_hub(secondary).await;
+        return Err("Account changed while linking; please retry".to_string());
+    }
+
+    PRINCIPAL_LINKS.with(|l| l.borrow_mut().insert(secondary, canonical));
+    LINKED_OF_CANONICAL.with(|l| {
+        let mut map = l.borrow_mut();
+        let mut list = map.get(&canonical).unwrap_or_default();
+        list.0.push(LinkedPrincipal { principal: secondary, linked_at: ic_cdk::api::time() });
+        map.insert(canonical, list);
+    });
+
+    Ok(())
+}
+
+/// Detach a secondary principal (callable by its canonical principal or by the secondary itself)
+/// The profile and balance always stay with the canonical principal.
+#[update(guard = "limit_account")]
+async fn unlink_principal(secondary: Principal) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let canonical = PRINCIPAL_LINKS.with(|l| l.borrow().get(&secondary))
+        .ok_or("Principal is not linked to an account")?;
+    if caller != canonical && caller != secondary {
+        return Err("Unauthorized: Only the account owner or the linked principal can unlink".to_string());
+    }
+    if is_recovery_executing(canonical) {
+        return Err("Account is being migrated to a new principal".to_string());
+    }
+
+    // Hub first: if it fails, the link stays fully intact on both sides
+    unregister_linked_principal_at_hub(secondary).await?;
+    remove_local_link(canonical, secondary);
+
+    Ok(())
+}
+
+/// Get the canonical principal and linked principals of the account `user` belongs to
+#[query]
+fn get_linked_principals(user: Principal) -> AccountLinks {
+    let canonical = resolve_account(user);
+    AccountLinks {
+        canonical,
+        linked: LINKED_OF_CANONICAL.with(|l| l.borrow().get(&canonical)).unwrap_or_default().0,
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Completion Certificates
+// ─────────────────────────────────────────────────────────────────
+
+/// Claim the certificate for a completed course (every quiz under `course_id` passed)
+/// Returns the existing certificate if one was already issued
+#[update(guard = "limit_account")]
+async fn claim_certificate(course_id: String) -> Result<CompletionCertificate, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("Anonymous actions are not allowed.".to_string());
+    }
+    let user = resolve_account(caller);
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&user)) {
+        return Err("User not registered".to_string());
+    }
+    if is_erased(user) {
+        return Err("Account data has been erased".to_string());
+    }
+    if is_recovery_executing(user) {
+        return Err("Account is being migrated to a new principal".to_string());
+    }
+
+    let certificate = issue_certificate_internal(user, course_id).await?;
+    spawn_credential_mints(vec![certificate_credential_args(&certificate)]);
+    Ok(certificate)
+}
+
+/// Mint any badges and certificates missing from the credentials collection
+/// (e.g. earned before the collection existed, or a background mint failed)
+#[update(guard = "limit_account")]
+async fn sync_my_credentials() -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("Anonymous actions are not allowed.".to_string());
+    }
+    let user = resolve_account(caller);
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&user)) {
+        return Err("User not registered".to_string());
+    }
+    if is_erased(user) {
+        return Err("Account data has been erased".to_string());
+    }
+    if is_recovery_executing(user) {
+        return Err("Account is being migrated to a new principal".to_string());
+    }
+
+    sync_user_credentials(user).await
+}
+
+/// Public verification: look up a certificate by id
+/// Check `signature` against get_certificate_signing_key rather than trusting this reply
+#[query]
+fn verify_certificate(certificate_id: String) -> Option<CompletionCertificate> {
+    CERTIFICATES.with(|c| c.borrow().get(&certificate_id))
+}
+
+#[query]
+fn get_user_certificates(user: Principal) -> Vec<CompletionCertificate> {
+    user_certificates(resolve_account(user))
+}
+
+/// Public key that verifies this shard's certificate signatures
+#[update(guard = "limit_account")]
+async fn get_certificate_signing_key() -> Result<CertificateSigningKey, String> {
+    certificate_signing_key().await
+}
+
+/// Admin: Set the threshold ECDSA key used for certificates (e.g. "dfx_test_key" locally)
+#[update]
+fn admin_set_certificate_key_name(key_name: String) -> Result<(), String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Unauthorized: Only controllers can set the certificate key".to_string());
+    }
+    let previous = CERTIFICATE_KEY_NAME.with(|k| k.borrow().get().clone());
+    CERTIFICATE_KEY_NAME.with(|k| k.borrow_mut().set(key_name.clone()).expect("Failed to set certificate key name"));
+    record_audit("admin_set_certificate_key_name", ic_cdk::id(), &previous, &key_name);
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Data Export & Erasure
+// ─────────────────────────────────────────────────────────────────
+
+/// Export everything held about the caller's account (linked wallets export the canonical account)
+#[query(composite = true)]
+async fn export_my_data() -> Result<UserDataExport, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("Anonymous actions are not allowed.".to_string());
+    }
+    export_user_data_internal(resolve_account(caller)).await
+}
+
+/// Erase the caller's personal data (canonical principal only)
+/// Balances and transaction amounts are kept; the profile is tombstoned.
+/// Safe to call again if a previous attempt did not complete.
+#[update(guard = "limit_account")]
+async fn erase_my_data() -> Result<ErasureRecord, String> {
+    let user = ic_cdk::caller();
+    if user == Principal::anonymous() {
+        return Err("Anonymous actions are not allowed.".to_string());
+    }
+    if is_linked_principal(user) {
+        return Err("Only the canonical principal can erase the account".to_string());
+    }
+    erase_user_data_internal(user).await
+}
+
+#[query]
+fn get_erasure_status(user: Principal) -> Option<ErasureRecord> {
+    ERASURE_RECORDS.with(|e| e.borrow().get(&resolve_account(user)))
+}
+
 /// Get the locally cached token limits
 #[query]
 fn get_token_limits() -> TokenLimitsConfig {
@@ -156,17 +766,32 @@ fn get_kyc_manager_id() -> Principal {
     KYC_MANAGER_ID.with(|id| *id.borrow().get())
 }
 
-#[update]
-async fn submit_quiz(unit_id: String, answers: Vec<u8>) -> Result<u64, String> {
-    let user = ic_cdk::caller();
-    if user == Principal::anonymous() {
+#[query]
+fn get_credentials_collection_id() -> Principal {
+    CREDENTIALS_COLLECTION_ID.with(|id| *id.borrow().get())
+}
+
+/// `locale` selects a translated quiz (the unit's own quiz if it has none);
+/// completion and rewards are per unit whichever locale is answered
+#[update(guard = "limit_quiz")]
+async fn submit_quiz(unit_id: String, answers: Vec<u8>, locale: Option<String>) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
         return Err("Anonymous actions are not allowed.".to_string());
     }
+    // Linked wallets act on the canonical profile
+    let user = resolve_account(caller);
     
     // 0. Check Registration
     if !USER_PROFILES.with(|p| p.borrow().contains_key(&user)) {
         return Err("User not registered".to_string());
     }
+    if is_erased(user) {
+        return Err("Account data has been erased".to_string());
+    }
+    if is_recovery_executing(user) {
+        return Err("Account is being migrated to a new principal".to_string());
+    }
 
     let key = UserQuizKey { user, unit_id: unit_id.clone() };
     
@@ -178,58 +803,36 @@ async fn submit_quiz(unit_id: String, answers: Vec<u8>) -> Result<u64, String> {
     if config.version == 0 {
         return Err("Configuration not yet initialized from Staking Hub".to_string());
     }
+    let decoded_answers = decode_quiz_answers(&answers)?;
+    let locale = locale.map(|l| normalize_locale(&l)).transpose()?;
 
     // 2. Check Time Limits
+    let now = ic_cdk::api::time();
     let current_day = get_current_day();
-    let current_week = get_week_index(current_day);
-    let current_date = day_to_date(current_day);
-    
-    let mut stats = USER_TIME_STATS.with(|s| {
-        s.borrow().get(&user).unwrap_or_default()
-    });
-
-    // Check for Resets
-    let last_date = day_to_date(stats.last_active_day);
-    let last_week = get_week_index(stats.last_active_day);
     
-    // If we've moved to a new day (or user was never active)
-    // Note: If last_active_day is 0 (default), this logic handles it correctly
-    if current_day > stats.last_active_day {
-        stats.daily_quizzes = 0;
-        stats.daily_earnings = 0;
-        
-        // Check Weekly Reset
-        if current_week > last_week {
-            stats.weekly_quizzes = 0;
-            stats.weekly_earnings = 0;
-        }
-        
-        // Check Monthly Reset (Month or Year changed)
-        if current_date.month != last_date.month || current_date.year != last_date.year {
-            stats.monthly_quizzes = 0;
-            stats.monthly_earnings = 0;
-        }
-        
-        // Check Yearly Reset
-        if current_date.year != last_date.year {
-            stats.yearly_quizzes = 0;
-            stats.yearly_earnings = 0;
-        }
-        
-        // Update last active day
-        stats.last_active_day = current_day;
-    }
+    // Calendar counters are kept in every mode (resets applied here)
+    let mut stats = project_calendar_stats(
+        USER_TIME_STATS.with(|s| s.borrow().get(&user).unwrap_or_default()),
+        current_day,
+    );
+    stats.last_active_day = stats.last_active_day.max(current_day);
+
+    let limit_mode = config.limit_mode.unwrap_or(LimitMode::Calendar);
+    let usage = match limit_mode {
+        LimitMode::Calendar => stats.clone(),
+        LimitMode::Rolling => rolling_usage_stats(user, now),
+    };
+    let [day_name, week_name, month_name, year_name] = limit_period_names(limit_mode);
 
-    // Enforce Limits
-    let reward_amount = config.reward_amount;
+    // Enforce Limits (governance may scale the reward per unit)
+    let reward_amount = unit_reward_amount(config.reward_amount, &unit_id);
     
-    if stats.daily_quizzes >= config.max_daily_attempts {
-        return Err("Daily quiz limit reached".to_string());
+    if usage.daily_quizzes >= config.max_daily_attempts {
+        return Err(format!("{} quiz limit reached", day_name));
     }
 
-    let is_subscribed = USER_PROFILES.with(|p| {
-        p.borrow().get(&user).map(|profile| profile.is_subscribed).unwrap_or(false)
-    });
+    // Evaluated against the period, not the cached flag (the downgrade timer may lag)
+    let is_subscribed = is_subscription_active(user, now);
 
     let limits = if is_subscribed {
         &config.subscribed_limits
@@ -237,21 +840,20 @@ async fn submit_quiz(unit_id: String, answers: Vec<u8>) -> Result<u64, String> {
         &config.regular_limits
     };
     
-    if stats.daily_earnings + reward_amount > limits.max_daily_tokens {
-        return Err(format!("Daily token limit reached ({}/{})", stats.daily_earnings, limits.max_daily_tokens));
+    if usage.daily_earnings + reward_amount > limits.max_daily_tokens {
+        return Err(format!("{} token limit reached ({}/{})", day_name, usage.daily_earnings, limits.max_daily_tokens));
     }
-    if stats.weekly_earnings + reward_amount > limits.max_weekly_tokens {
-        return Err(format!("Weekly token limit reached ({}/{})", stats.weekly_earnings, limits.max_weekly_tokens));
+    if usage.weekly_earnings + reward_amount > limits.max_weekly_tokens {
+        return Err(format!("{} token limit reached ({}/{})", week_name, usage.weekly_earnings, limits.max_weekly_tokens));
     }
-    if stats.monthly_earnings + reward_amount > limits.max_monthly_tokens {
-         return Err(format!("Monthly token limit reached ({}/{})", stats.monthly_earnings, limits.max_monthly_tokens));
+    if usage.monthly_earnings + reward_amount > limits.max_monthly_tokens {
+         return Err(format!("{} token limit reached ({}/{})", month_name, usage.monthly_earnings, limits.max_monthly_tokens));
     }
-    if stats.yearly_earnings + reward_amount > limits.max_yearly_tokens {
-         return Err(format!("Yearly token limit reached ({}/{})", stats.yearly_earnings, limits.max_yearly_tokens));
+    if usage.yearly_earnings + reward_amount > limits.max_yearly_tokens {
+         return Err(format!("{} token limit reached ({}/{})", year_name, usage.yearly_earnings, limits.max_yearly_tokens));
     }
 
     // 3. Check Minting Allowance (Hard Cap Enforcement)
-    let reward_amount = config.reward_amount;
     let current_allowance = MINTING_ALLOWANCE.with(|a| *a.borrow().get());
     
     if current_allowance < reward_amount {
@@ -262,7 +864,7 @@ async fn submit_quiz(unit_id: String, answers: Vec<u8>) -> Result<u64, String> {
         // Re-check
         let new_allowance = MINTING_ALLOWANCE.with(|a| *a.borrow().get());
         if new_allowance < reward_amount {
-            return Err("Global minting limit reached or Hub unavailable.".to_string());
+            return Err(explain_allowance_shortfall(reward_amount - new_allowance).await);
         }
     }
     
@@ -275,66 +877,51 @@ async fn submit_quiz(unit_id: String, answers: Vec<u8>) -> Result<u64, String> {
     // Try Local Cache First
     let learning_content_id = LEARNING_CONTENT_ID.with(|id| *id.borrow().get());
     
-    let (passed, correct_count, total_questions) = if let Some(cache) = QUIZ_CACHE.with(|q| q.borrow().get(&unit_id)) {
+    let (passed, correct_count, total_questions) = if let Some(cache) = cached_quiz(&unit_id, locale.as_deref()) {
         // Local Verification - no inter-canister call!
-        let total = cache.question_count as u64;
+        check_prerequisites(user, &cache)?;
+        let total = drawn_question_count(&cache);
         
-        if total != answers.len() as u64 {
-             (false, 0, total)
-        } else {
-             let mut correct = 0;
-             for (i, ans) in answers.iter().enumerate() {
-                 if stable_hash(&ans.to_le_bytes()) == cache.answer_hashes[i] {
-                     correct += 1;
-                 }
-             }
-             let threshold = config.pass_threshold_percent as u64;
-             let passed = if total > 0 { (correct * 100 / total) >= threshold } else { false };
-             (passed, correct, total)
+        match score_answers(&cache, user, &decoded_answers) {
+            Some(results) => {
+                let correct = results.iter().filter(|r| **r).count() as u64;
+                let threshold = config.pass_threshold_percent as u64;
+                let passed = if total > 0 { (correct * 100 / total) >= threshold } else { false };
+                (passed, correct, total)
+            }
+            None => (false, 0, total),
         }
     } else {
         // Cache miss - fetch from learning engine, store locally, then verify
-        // 1. Fetch quiz cache data from learning engine
-        let fetch_result: Result<(Option<QuizCacheData>,), _> = ic_cdk::call(
-            learning_content_id,
-            "get_quiz_data",
-            (unit_id.clone(),)
-        ).await;
+        // 1. Fetch quiz cache data from learning engine (stored locally for future use)
+        let fetch_result = fetch_quiz_cache(&unit_id, locale.as_deref()).await;
         
         match fetch_result {
-            Ok((Some(cache_data),)) => {
-                // 2. Store in local cache for future use
-                QUIZ_CACHE.with(|q| {
-                    q.borrow_mut().insert(unit_id.clone(), cache_data.clone());
-                });
-                
-                // 3. Verify locally with the fetched data
-                let total = cache_data.question_count as u64;
+            Ok(Some(cache_data)) => {
+                // 2. Verify locally with the fetched data
+                check_prerequisites(user, &cache_data)?;
+                let total = drawn_question_count(&cache_data);
                 
-                if total != answers.len() as u64 {
-                    (false, 0, total)
-                } else {
-                    let mut correct = 0;
-                    for (i, ans) in answers.iter().enumerate() {
-                        if stable_hash(&ans.to_le_bytes()) == cache_data.answer_hashes[i] {
-                            correct += 1;
-                        }
+                match score_answers(&cache_data, user, &decoded_answers) {
+                    Some(results) => {
+                        let correct = results.iter().filter(|r| **r).count() as u64;
+                        let threshold = config.pass_threshold_percent as u64;
+                        let passed = if total > 0 { (correct * 100 / total) >= threshold } else { false };
+                        (passed, correct, total)
                     }
-                    let threshold = config.pass_threshold_percent as u64;
-                    let passed = if total > 0 { (correct * 100 / total) >= threshold } else { false };
-                    (passed, correct, total)
+                    None => (false, 0, total),
                 }
             }
-            Ok((None,)) => {
+            Ok(None) => {
                 // Quiz not found
                 return Err("Quiz not found in learning engine".to_string());
             }
-            Err((_code, _msg)) => {
+            Err(_) => {
                 // Fallback to remote verification if cache fetch fails
-                ic_cdk::call::<(String, Vec<u8>), (bool, u64, u64)>(
+                ic_cdk::call::<(String, Vec<u8>, Option<Principal>, Option<String>), (bool, u64, u64)>(
                     learning_content_id,
                     "verify_quiz",
-                    (unit_id.clone(), answers)
+                    (unit_id.clone(), answers, Some(user), locale.clone())
                 ).await.map_err(|(code, msg)| format!("Failed to verify quiz: {:?} {}", code, msg))?
             }
         }
@@ -345,6 +932,7 @@ async fn submit_quiz(unit_id: String, answers: Vec<u8>) -> Result<u64, String> {
     }
     
     // 6. Update Stats
+    record_rolling_usage(user, ic_cdk::api::time(), 1, if passed { reward_amount } else { 0 });
     stats.daily_quizzes += 1;
     stats.weekly_quizzes += 1;
     stats.monthly_quizzes += 1;
@@ -415,8 +1003,25 @@ async fn submit_quiz(unit_id: String, answers: Vec<u8>) -> Result<u64, String> {
         cell.set(stats).expect("Failed to update pending stats");
     });
 
-    // 8. Mark Completed
+    // 8. Mark Completed (the score feeds certificate summaries; reviews start tomorrow)
+    QUIZ_SCORES.with(|q| q.borrow_mut().insert(key.clone(), QuizScore {
+        correct: correct_count,
+        total: total_questions,
+        passed_at: ic_cdk::api::time(),
+    }));
     COMPLETED_QUIZZES.with(|q| q.borrow_mut().insert(key, true));
+    schedule_first_review(user, &unit_id);
+
+    // 9. Streaks & Achievements (bonuses respect the same token limits)
+    let streak = record_streak_activity(user);
+    let new_badges = evaluate_badges(user, &streak, &unit_id);
+    spawn_credential_mints(new_badges.iter().filter_map(|b| badge_credential_args(user, b)).collect());
+
+    // 10. Referral progress (settles both bonuses once the referee qualifies)
+    progress_referral(user);
+
+    // 11. Sponsor bounties (paid from escrow by the hub, on top of the reward)
+    spawn_bounty_claim(user, &unit_id);
 
     Ok(reward_amount)
 }
@@ -446,44 +1051,11 @@ async fn maybe_archive_user(user: Principal) -> Result<u64, String> {
     }
 }
 
+/// Get a user's quiz usage and remaining quota under the configured limit mode
+/// (calendar counters are projected past resets; this is just a view)
 #[query]
-fn get_user_stats(user: Principal) -> UserTimeStats {
-    let current_day = get_current_day();
-    let current_week = get_week_index(current_day);
-    let current_date = day_to_date(current_day);
-    
-    USER_TIME_STATS.with(|s| {
-        let s = s.borrow();
-        if let Some(mut stats) = s.get(&user) {
-            // Return 'projected' stats (reset if needed) so UI sees correct available quota
-            // This is just a view, doesn't modify state
-             let last_date = day_to_date(stats.last_active_day);
-             let last_week = get_week_index(stats.last_active_day);
-             
-             if current_day > stats.last_active_day {
-                stats.daily_quizzes = 0;
-                stats.daily_earnings = 0;
-                
-                if current_week > last_week {
-                    stats.weekly_quizzes = 0;
-                    stats.weekly_earnings = 0;
-                }
-                
-                if current_date.month != last_date.month || current_date.year != last_date.year {
-                    stats.monthly_quizzes = 0;
-                    stats.monthly_earnings = 0;
-                }
-                
-                if current_date.year != last_date.year {
-                    stats.yearly_quizzes = 0;
-                    stats.yearly_earnings = 0;
-                }
-             }
-             stats
-        } else {
-            UserTimeStats::default()
-        }
-    })
+fn get_user_stats(user: Principal) -> UserQuotaStats {
+    user_quota_stats(user)
 }
 
 #[query]
@@ -492,52 +1064,96 @@ fn get_current_day() -> u64 {
 }
 
 
+/// Get a user's progress through the content subtree rooted at `root_id`
+/// (per node: passed quiz-bearing nodes over the total in its subtree)
+#[query(composite = true)]
+async fn get_course_progress(user: Principal, root_id: String) -> Result<CourseProgress, String> {
+    let user = resolve_account(user);
+    let learning_content_id = LEARNING_CONTENT_ID.with(|id| *id.borrow().get());
+    let (outline,): (Result<Vec<ContentOutlineNode>, String>,) = ic_cdk::call(
+        learning_content_id,
+        "get_subtree_outline",
+        (root_id.clone(),)
+    ).await.map_err(|(code, msg)| format!("Learning engine call failed: {:?} {}", code, msg))?;
+
+    Ok(compute_course_progress(user, root_id, outline?))
+}
+
+/// Which nodes under `root_id` the user can attempt, per their prerequisites
+#[query(composite = true)]
+async fn get_unlocked_nodes(user: Principal, root_id: String) -> Result<Vec<NodeUnlockStatus>, String> {
+    let user = resolve_account(user);
+    let learning_content_id = LEARNING_CONTENT_ID.with(|id| *id.borrow().get());
+    let (unlocked,): (Result<Vec<NodeUnlockStatus>, String>,) = ic_cdk::call(
+        learning_content_id,
+        "get_unlocked_nodes",
+        (root_id, completed_units(user))
+    ).await.map_err(|(code, msg)| format!("Learning engine call failed: {:?} {}", code, msg))?;
+
+    unlocked
+}
+
 #[query]
 fn is_quiz_completed(user: Principal, unit_id: String) -> bool {
     let key = UserQuizKey { user, unit_id };
     COMPLETED_QUIZZES.with(|q| q.borrow().contains_key(&key))
 }
 
-#[update]
-async fn unstake(amount: u64) -> Result<u64, String> {
-    let user = ic_cdk::caller();
-    if user == Principal::anonymous() {
+/// Score answers without minting, counting toward limits or completing the unit
+#[update(guard = "limit_practice")]
+async fn practice_quiz(unit_id: String, answers: Vec<u8>, locale: Option<String>) -> Result<PracticeResult, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
         return Err("Anonymous actions are not allowed.".to_string());
     }
-    
-    // 1. Check Balance and Registration
-    let mut profile = USER_PROFILES.with(|p| p.borrow().get(&user).ok_or("User not registered".to_string()))?;
+    let user = resolve_account(caller);
 
-    if profile.staked_balance < amount {
-        return Err(format!("Insufficient balance. Available: {}", profile.staked_balance));
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&user)) {
+        return Err("User not registered".to_string());
+    }
+    if is_erased(user) {
+        return Err("Account data has been erased".to_string());
+    }
+    if is_recovery_executing(user) {
+        return Err("Account is being migrated to a new principal".to_string());
     }
 
-    // 2. Update Local State (Optimistic Update)
-    profile.staked_balance -= amount;
-    
-    // Log Transaction (Optimistic)
-    let tx_index = profile.transaction_count;
-    profile.transaction_count += 1;
-    
-    let tx_record = TransactionRecord {
-        timestamp: ic_cdk::api::time(),
-        tx_type: TransactionType::Unstake,
-        amount,
-    };
-    
-    USER_TRANSACTIONS.with(|t| t.borrow_mut().insert(TransactionKey { user, index: tx_index }, tx_record));
+    practice_quiz_internal(user, unit_id, answers, locale).await
+}
+
+#[query]
+fn get_practice_record(user: Principal, unit_id: String) -> Option<PracticeRecord> {
+    PRACTICE_RECORDS.with(|p| p.borrow().get(&UserQuizKey { user, unit_id }))
+}
+
+#[query]
+fn get_practice_records(user: Principal) -> Vec<(String, PracticeRecord)> {
+    user_practice_records(user)
+}
+
+/// Passed units due for spaced-repetition review today (review them via practice_quiz)
+#[query]
+fn get_due_reviews(user: Principal) -> Vec<DueReview> {
+    due_reviews(user)
+}
+
+#[update(guard = "limit_staking")]
+async fn unstake(amount: u64) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("Anonymous actions are not allowed.".to_string());
+    }
+    // Linked wallets act on the canonical profile
+    let user = resolve_account(caller);
     
-    USER_PROFILES.with(|p| p.borrow_mut().insert(user, profile.clone()));
+    if is_recovery_executing(user) {
+        return Err("Account is being migrated to a new principal".to_string());
+    }
 
-    // 3. Update Pending Stats (Reduce Total Staked)
-    PENDING_STATS.with(|s| {
-        let mut cell = s.borrow_mut();
-        let mut stats = cell.get().clone();
-        stats.staked_delta -= amount as i64;
-        cell.set(stats).expect("Failed to update pending stats");
-    });
+    // 1. Debit locally first (Optimistic Update)
+    debit_unstake(user, amount)?;
 
-    // 4. Call Hub to Process Unstake (Transfer Tokens) - No penalty!
+    // 2. Call Hub to Process Unstake (Transfer Tokens) - No penalty!
     let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
     let result: Result<(Result<u64, String>,), _> = ic_cdk::call(
         staking_hub_id,
@@ -545,53 +1161,165 @@ async fn unstake(amount: u64) -> Result<u64, String> {
         (user, amount)
     ).await;
 
+    // 3. On failure, put the amount back on top of whatever was credited
+    // during the await
     match result {
         Ok((Ok(return_amount),)) => Ok(return_amount),
         Ok((Err(msg),)) => {
-            // Rollback Local State
-            profile.staked_balance += amount;
-            profile.transaction_count -= 1; // Revert count
-            USER_TRANSACTIONS.with(|t| t.borrow_mut().remove(&TransactionKey { user, index: tx_index }));
-            
-            USER_PROFILES.with(|p| p.borrow_mut().insert(user, profile));
-            
-            PENDING_STATS.with(|s| {
-                let mut cell = s.borrow_mut();
-                let mut stats = cell.get().clone();
-                stats.staked_delta += amount as i64;
-                cell.set(stats).expect("Failed to rollback pending stats");
-            });
+            refund_unstake(user, amount);
             Err(format!("Hub Rejected Unstake: {}", msg))
-        },
+        }
         Err((code, msg)) => {
-            // Rollback Local State
-            profile.staked_balance += amount;
-            profile.transaction_count -= 1; // Revert count
-            USER_TRANSACTIONS.with(|t| t.borrow_mut().remove(&TransactionKey { user, index: tx_index }));
-            
-            USER_PROFILES.with(|p| p.borrow_mut().insert(user, profile));
-            
-            PENDING_STATS.with(|s| {
-                let mut cell = s.borrow_mut();
-                let mut stats = cell.get().clone();
-                stats.staked_delta += amount as i64;
-                cell.set(stats).expect("Failed to rollback pending stats");
-            });
+            refund_unstake(user, amount);
             Err(format!("Hub Call Failed: {:?} {}", code, msg))
         }
     }
 }
 
+// ─────────────────────────────────────────────────────────────────
+// Notifications
+// ─────────────────────────────────────────────────────────────────
+
+/// Page through the caller's inbox, newest first
+/// `before` is the next_cursor of the previous page
+#[query]
+fn get_my_notifications(before: Option<u64>, limit: Option<u32>, unread_only: bool) -> NotificationPage {
+    notification_page(resolve_account(ic_cdk::caller()), before, limit, unread_only)
+}
+
+/// Mark notifications as read; returns the number that changed
+#[update(guard = "limit_inbox")]
+fn mark_notifications_read(ids: Vec<u64>) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("Anonymous actions are not allowed.".to_string());
+    }
+
+    Ok(mark_notifications_read_internal(resolve_account(caller), Some(ids)))
+}
+
+#[update(guard = "limit_inbox")]
+fn mark_all_notifications_read() -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("Anonymous actions are not allowed.".to_string());
+    }
+
+    Ok(mark_notifications_read_internal(resolve_account(caller), None))
+}
+
+#[query]
+fn get_notification_preferences() -> NotificationPreferences {
+    let user = resolve_account(ic_cdk::caller());
+    NOTIFICATION_PREFERENCES.with(|p| p.borrow().get(&user)).unwrap_or_default()
+}
+
+/// Choose which categories are delivered (muted ones are dropped, not stored)
+#[update(guard = "limit_profile")]
+fn set_notification_preferences(preferences: NotificationPreferences) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("Anonymous actions are not allowed.".to_string());
+    }
+    let user = resolve_account(caller);
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&user)) {
+        return Err("User not registered".to_string());
+    }
+    if is_erased(user) {
+        return Err("Account data has been erased".to_string());
+    }
+
+    let mut muted = Vec::new();
+    for category in preferences.muted {
+        if !muted.contains(&category) {
+            muted.push(category);
+        }
+    }
+    NOTIFICATION_PREFERENCES.with(|p| p.borrow_mut().insert(user, NotificationPreferences { muted }));
+    Ok(())
+}
+
+// ─────────────────────────────────────────────────────────────────
+// Peer-to-peer Transfers
+// ─────────────────────────────────────────────────────────────────
+
+/// Send staked balance to another learner (on any shard) without unstaking
+/// Retrying with the same idempotency key returns the original transfer
+#[update(guard = "limit_staking")]
+async fn transfer_staked(args: StakedTransferArgs) -> Result<StakedTransfer, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("Anonymous actions are not allowed.".to_string());
+    }
+    // Linked wallets act on the canonical profile
+    let user = resolve_account(caller);
+
+    if is_recovery_executing(user) {
+        return Err("Account is being migrated to a new principal".to_string());
+    }
+
+    transfer_staked_internal(user, args).await
+}
+
+/// Settle a transfer left Unresolved because the hub could not be reached
+#[update(guard = "limit_staking")]
+async fn retry_staked_transfer(idempotency_key: String) -> Result<StakedTransfer, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("Anonymous actions are not allowed.".to_string());
+    }
+
+    retry_staked_transfer_internal(resolve_account(caller), idempotency_key).await
+}
+
+#[query]
+fn get_outgoing_transfers(user: Principal) -> Vec<StakedTransfer> {
+    user_outgoing_transfers(resolve_account(user))
+}
+
+/// Internal: Credit a transfer routed by the Staking Hub
+#[update]
+fn internal_credit_transfer(request: StakedTransferRequest) -> Result<(), String> {
+    if ic_cdk::caller() != STAKING_HUB_ID.with(|id| *id.borrow().get()) {
+        return Err("Unauthorized: Only Staking Hub can credit transfers".to_string());
+    }
+
+    let recipient = resolve_account(request.to);
+    let previous = staked_balance_audit_state(recipient);
+    credit_incoming_transfer(request)?;
+    record_audit("internal_credit_transfer", recipient, &previous, &staked_balance_audit_state(recipient));
+    Ok(())
+}
+
+/// Internal: Credit a bounty payout routed by the Staking Hub
+#[update]
+fn internal_credit_bounty(user: Principal, unit_id: String, amount: u64) -> Result<(), String> {
+    if ic_cdk::caller() != STAKING_HUB_ID.with(|id| *id.borrow().get()) {
+        return Err("Unauthorized: Only Staking Hub can credit bounties".to_string());
+    }
+    if !COMPLETED_QUIZZES.with(|q| q.borrow().contains_key(&UserQuizKey { user, unit_id })) {
+        return Err("Unit not passed".to_string());
+    }
+
+    let previous = staked_balance_audit_state(user);
+    credit_bounty_reward(user, amount)?;
+    record_audit("internal_credit_bounty", user, &previous, &staked_balance_audit_state(user));
+    Ok(())
+}
+
 #[query]
 fn get_user_transactions(user: Principal) -> Vec<TransactionRecord> {
-    let count = USER_PROFILES.with(|p| {
-        p.borrow().get(&user).map(|profile| profile.transaction_count).unwrap_or(0)
+    let (count, archived) = USER_PROFILES.with(|p| {
+        p.borrow().get(&user)
+            .map(|profile| (profile.transaction_count, profile.archived_transaction_count))
+            .unwrap_or((0, 0))
     });
     
+    // Only locally held transactions; use get_transaction_history for the full history
     let mut transactions = Vec::new();
     USER_TRANSACTIONS.with(|t| {
         let t = t.borrow();
-        for i in 0..count {
+        for i in archived..count {
             if let Some(record) = t.get(&TransactionKey { user, index: i }) {
                 transactions.push(record);
             }
@@ -601,7 +1329,16 @@ fn get_user_transactions(user: Principal) -> Vec<TransactionRecord> {
     transactions
 }
 
-#[update]
+/// Get a page of a user's transaction history, newest first
+///
+/// Transparently spans local storage and the archive chain. Pass the returned
+/// `next_cursor` back to continue; filters apply across both sources.
+#[query(composite = true)]
+async fn get_transaction_history(user: Principal, request: TransactionHistoryRequest) -> Result<TransactionHistoryPage, String> {
+    get_transaction_history_internal(user, request).await
+}
+
+#[update(guard = "limit_maintenance")]
 async fn debug_force_sync() -> Result<(), String> {
     sync_with_hub_internal().await
 }
@@ -616,7 +1353,7 @@ fn get_user_count() -> u64 {
 // Verification Logic
 // ─────────────────────────────────────────────────────────────────
 
-#[update]
+#[update(guard = "limit_account")]
 async fn verify_humanity() -> Result<bool, String> {
     let user = ic_cdk::caller();
     if user == Principal::anonymous() {
@@ -649,23 +1386,27 @@ async fn verify_humanity() -> Result<bool, String> {
     })
 }
 
-/// Admin function to manually set KYC tier (or via Trusted Oracle)
+/// Admin function to manually set KYC tier (ADMIN ONLY - Controllers)
 #[update]
 fn admin_set_kyc_tier(target_user: Principal, tier: VerificationTier) -> Result<(), String> {
-    // Access Control: In a real app, strict checks here!
-    // For now, we allow the Governance Canister or a specific admin implementation to call this.
-    // let caller = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Unauthorized: Only controllers can set KYC tiers".to_string());
+    }
     
+    let target_user = resolve_account(target_user);
+    let previous = kyc_audit_state(target_user);
     USER_PROFILES.with(|p| {
         let mut map = p.borrow_mut();
         if let Some(mut profile) = map.get(&target_user) {
-            profile.verification_tier = tier;
+            profile.verification_tier = tier.clone();
             map.insert(target_user, profile);
             Ok(())
         } else {
             Err("User not found".to_string())
         }
-    })
+    })?;
+    record_audit("admin_set_kyc_tier", target_user, &previous, &Some(tier));
+    Ok(())
 }
 
 /// Synchronize the subscription manager ID from the Staking Hub
@@ -680,9 +1421,11 @@ fn internal_sync_subscription_manager(new_id: Principal) {
         ic_cdk::trap("Unauthorized: Only Staking Hub can sync subscription manager");
     }
     
+    let previous = SUBSCRIPTION_MANAGER_ID.with(|id| *id.borrow().get());
     SUBSCRIPTION_MANAGER_ID.with(|id| {
         id.borrow_mut().set(new_id).expect("Failed to set subscription manager ID");
     });
+    record_audit("internal_sync_subscription_manager", ic_cdk::id(), &previous, &new_id);
 }
 
 /// Set subscription status for a user
@@ -697,16 +1440,62 @@ fn internal_set_subscription(user: Principal, active: bool) -> Result<(), String
         return Err("Unauthorized: Caller is not the authorized Subscription Manager".to_string());
     }
     
-    USER_PROFILES.with(|p| {
-        let mut map = p.borrow_mut();
-        if let Some(mut profile) = map.get(&user) {
-            profile.is_subscribed = active;
-            map.insert(user, profile);
-            Ok(())
-        } else {
-            Err("User not found in this shard".to_string())
-        }
-    })
+    let user = resolve_account(user);
+    let previous = subscription_audit_state(user);
+    set_subscription_manual(user, active)?;
+    record_audit("internal_set_subscription", user, &previous, &subscription_audit_state(user));
+    Ok(())
+}
+
+/// Apply a paid subscription period (renewals stack onto the current period)
+/// 
+/// SECURITY: Strictly only callable by the authorized Subscription Manager canister
+#[update]
+fn internal_extend_subscription(user: Principal, extension: SubscriptionExtension) -> Result<SubscriptionPeriod, String> {
+    let caller = ic_cdk::caller();
+    let manager_id = SUBSCRIPTION_MANAGER_ID.with(|id| *id.borrow().get());
+    
+    if caller != manager_id {
+        return Err("Unauthorized: Caller is not the authorized Subscription Manager".to_string());
+    }
+    
+    let user = resolve_account(user);
+    let previous = subscription_audit_state(user);
+    let period = extend_subscription_internal(user, extension)?;
+    record_audit("internal_extend_subscription", user, &previous, &subscription_audit_state(user));
+    Ok(period)
+}
+
+/// Update the auto-renew flag of a user's current period
+/// 
+/// SECURITY: Strictly only callable by the authorized Subscription Manager canister
+#[update]
+fn internal_set_subscription_auto_renew(user: Principal, auto_renew: bool) -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    let manager_id = SUBSCRIPTION_MANAGER_ID.with(|id| *id.borrow().get());
+    
+    if caller != manager_id {
+        return Err("Unauthorized: Caller is not the authorized Subscription Manager".to_string());
+    }
+    
+    let user = resolve_account(user);
+    let previous = USER_SUBSCRIPTIONS.with(|s| {
+        let mut map = s.borrow_mut();
+        let mut period = map.get(&user).ok_or("User has no subscription period")?;
+        let previous = period.auto_renew;
+        period.auto_renew = auto_renew;
+        period.updated_at = ic_cdk::api::time();
+        map.insert(user, period);
+        Ok::<bool, String>(previous)
+    })?;
+    record_audit("internal_set_subscription_auto_renew", user, &previous, &auto_renew);
+    Ok(())
+}
+
+/// Get a user's subscription period
+#[query]
+fn get_subscription(user: Principal) -> Option<SubscriptionPeriod> {
+    USER_SUBSCRIPTIONS.with(|s| s.borrow().get(&resolve_account(user)))
 }
 
 /// Internal: Update the trusted KYC manager ID
@@ -717,9 +1506,71 @@ fn internal_sync_kyc_manager(new_id: Principal) {
         ic_cdk::trap("Unauthorized: Only Staking Hub can sync KYC manager");
     }
     
+    let previous = KYC_MANAGER_ID.with(|id| *id.borrow().get());
     KYC_MANAGER_ID.with(|id| {
         id.borrow_mut().set(new_id).expect("Failed to sync KYC manager ID");
     });
+    record_audit("internal_sync_kyc_manager", ic_cdk::id(), &previous, &new_id);
+}
+
+/// Internal: Update the soulbound credentials collection ID
+/// Called by the Staking Hub when the collection is deployed or replaced
+#[update]
+fn internal_sync_credentials_collection(new_id: Principal) {
+    if ic_cdk::caller() != STAKING_HUB_ID.with(|id| *id.borrow().get()) {
+        ic_cdk::trap("Unauthorized: Only Staking Hub can sync credentials collection");
+    }
+
+    let previous = CREDENTIALS_COLLECTION_ID.with(|id| *id.borrow().get());
+    CREDENTIALS_COLLECTION_ID.with(|id| {
+        id.borrow_mut().set(new_id).expect("Failed to sync credentials collection ID");
+    });
+    record_audit("internal_sync_credentials_collection", ic_cdk::id(), &previous, &new_id);
+}
+
+/// Internal: Post a notification to users' inboxes
+/// The hub may post any category on behalf of governance and the learning
+/// engine; the KYC and subscription managers and the learning engine may post
+/// their own category directly. Returns the number of inboxes reached.
+#[update]
+fn internal_notify(users: Vec<Principal>, input: NotificationInput) -> Result<u64, String> {
+    let caller = ic_cdk::caller();
+    if caller == Principal::anonymous() {
+        return Err("Unauthorized".to_string());
+    }
+
+    if caller != STAKING_HUB_ID.with(|id| *id.borrow().get()) {
+        let allowed = if caller == KYC_MANAGER_ID.with(|id| *id.borrow().get()) {
+            NotificationCategory::Kyc
+        } else if caller == SUBSCRIPTION_MANAGER_ID.with(|id| *id.borrow().get()) {
+            NotificationCategory::Subscription
+        } else if caller == LEARNING_CONTENT_ID.with(|id| *id.borrow().get()) {
+            NotificationCategory::Content
+        } else {
+            return Err("Unauthorized: Caller cannot post notifications".to_string());
+        };
+        if input.category != allowed {
+            return Err(format!("Unauthorized: Caller can only post {:?} notifications", allowed));
+        }
+    }
+
+    if users.len() > MAX_NOTIFY_BATCH {
+        return Err(format!("At most {} recipients per call", MAX_NOTIFY_BATCH));
+    }
+    validate_notification(&input)?;
+
+    Ok(notify_users(caller, users, &input))
+}
+
+/// Internal: Queue a content update for the users who completed that content
+/// Called by the Staking Hub when the learning engine updates a node
+#[update]
+fn internal_notify_content_update(notice: ContentUpdateNotice) {
+    if ic_cdk::caller() != STAKING_HUB_ID.with(|id| *id.borrow().get()) {
+        ic_cdk::trap("Unauthorized: Only Staking Hub can post content updates");
+    }
+
+    enqueue_content_notice(notice);
 }
 
 /// Internal: Set a user's KYC status
@@ -733,13 +1584,17 @@ fn internal_set_kyc_status(user: Principal, tier: VerificationTier) -> Result<()
         return Err(format!("Unauthorized: Caller {} is not the authorized KYC manager {}", caller, manager_id));
     }
     
+    let user = resolve_account(user);
+    let previous = kyc_audit_state(user);
     USER_PROFILES.with(|p| {
         let mut profiles = p.borrow_mut();
         let mut profile = profiles.get(&user).ok_or("User not found")?;
-        profile.verification_tier = tier;
+        profile.verification_tier = tier.clone();
         profiles.insert(user, profile);
-        Ok(())
-    })
+        Ok::<(), String>(())
+    })?;
+    record_audit("internal_set_kyc_status", user, &previous, &Some(tier));
+    Ok(())
 }
 
 
@@ -758,9 +1613,11 @@ fn set_archive_canister(archive_id: Principal) -> Result<(), String> {
         return Err("Unauthorized: Only staking_hub or a controller can set archive canister".to_string());
     }
     
+    let previous = ARCHIVE_CANISTER_ID.with(|id| *id.borrow().get());
     ARCHIVE_CANISTER_ID.with(|id| {
         id.borrow_mut().set(archive_id).expect("Failed to set archive canister ID");
     });
+    record_audit("set_archive_canister", ic_cdk::id(), &previous, &archive_id);
     
     Ok(())
 }
@@ -788,31 +1645,34 @@ fn get_archive_config() -> ArchiveConfig {
 
 /// Get transactions page with pagination info
 /// Returns local transactions for recent pages, indicates archive for older pages
+/// Superseded by get_transaction_history, which also serves archived pages
 #[query]
 fn get_transactions_page(user: Principal, page: u32) -> TransactionPage {
     let page_size: u64 = 20;
     let offset = (page as u64) * page_size;
     
-    let (local_count, archived_count) = USER_PROFILES.with(|p| {
+    // transaction_count is the total ever recorded; the oldest
+    // archived_transaction_count of them live in the archive
+    let (total_count, archived_count) = USER_PROFILES.with(|p| {
         p.borrow().get(&user).map(|profile| {
             (profile.transaction_count, profile.archived_transaction_count)
         }).unwrap_or((0, 0))
     });
     
-    let total_count = local_count + archived_count;
+    let local_count = total_count.saturating_sub(archived_count);
+    let total_pages = total_count.div_ceil(page_size).max(1) as u32;
     let archive_id = ARCHIVE_CANISTER_ID.with(|id| *id.borrow().get());
     
-    // Determine source and fetch data
+    // Determine source and fetch data (pages are numbered over local transactions first)
     if offset < local_count {
         // This page is in local storage
         let transactions = USER_TRANSACTIONS.with(|t| {
             let map = t.borrow();
-            let start_key = TransactionKey { user, index: offset };
+            let start = archived_count + offset;
+            let end = (start + page_size).min(total_count);
             
-            map.range(start_key..)
-                .take_while(|(k, _)| k.user == user)
-                .take(page_size as usize)
-                .map(|(_, v)| v)
+            (start..end)
+                .filter_map(|index| map.get(&TransactionKey { user, index }))
                 .collect()
         });
         
@@ -824,8 +1684,8 @@ fn get_transactions_page(user: Principal, page: u32) -> TransactionPage {
             archive_canister_id: Some(archive_id),
             source: "local".to_string(),
             current_page: page,
-            total_pages: 1, 
-            has_archive_data: false,
+            total_pages,
+            has_archive_data: archived_count > 0,
         }
     } else {
         // This page is in archive - return empty with archive info
@@ -837,7 +1697,7 @@ fn get_transactions_page(user: Principal, page: u32) -> TransactionPage {
             archive_canister_id: Some(archive_id),
             source: "archive".to_string(),
             current_page: page,
-            total_pages: 1,
+            total_pages,
             has_archive_data: true,
         }
     }
@@ -933,9 +1793,9 @@ fn admin_set_user_stats(user: Principal, stats: UserTimeStats) -> Result<(), Str
         return Err("Unauthorized".to_string());
     }
     
-    USER_TIME_STATS.with(|s| {
-        s.borrow_mut().insert(user, stats);
-    });
+    let user = resolve_account(user);
+    let previous = USER_TIME_STATS.with(|s| s.borrow_mut().insert(user, stats.clone()));
+    record_audit("admin_set_user_stats", user, &previous, &Some(stats));
     Ok(())
 }
 
@@ -948,16 +1808,11 @@ fn admin_set_subscription(user: Principal, active: bool) -> Result<(), String> {
         return Err("Unauthorized: Only controllers can manually set subscriptions".to_string());
     }
     
-    USER_PROFILES.with(|p| {
-        let mut map = p.borrow_mut();
-        if let Some(mut profile) = map.get(&user) {
-            profile.is_subscribed = active;
-            map.insert(user, profile);
-            Ok(())
-        } else {
-            Err("User not found in this shard".to_string())
-        }
-    })
+    let user = resolve_account(user);
+    let previous = subscription_audit_state(user);
+    set_subscription_manual(user, active)?;
+    record_audit("admin_set_subscription", user, &previous, &subscription_audit_state(user));
+    Ok(())
 }
 
 /// Admin: Set a user's KYC status (manual override)
@@ -967,13 +1822,29 @@ fn admin_set_kyc_status(user: Principal, tier: VerificationTier) -> Result<(), S
         return Err("Unauthorized".to_string());
     }
     
+    let user = resolve_account(user);
+    let previous = kyc_audit_state(user);
     USER_PROFILES.with(|p| {
         let mut profiles = p.borrow_mut();
         let mut profile = profiles.get(&user).ok_or("User not found")?;
-        profile.verification_tier = tier;
+        profile.verification_tier = tier.clone();
         profiles.insert(user, profile);
-        Ok(())
-    })
+        Ok::<(), String>(())
+    })?;
+    record_audit("admin_set_kyc_status", user, &previous, &Some(tier));
+    Ok(())
+}
+
+/// Page through the audit log of privileged mutations, newest first (ADMIN ONLY)
+/// 
+/// Older entries are read from the archive chain transparently.
+#[query(composite = true)]
+async fn admin_get_audit_log(before: Option<u64>, limit: u32) -> Result<AuditLogPage, String> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err("Unauthorized: Only controllers can read the audit log".to_string());
+    }
+    
+    get_audit_log_internal(before, limit).await
 }
 
 /// Debug info showing caller's principal (useful for frontend debugging)
@@ -2,14 +2,33 @@ use candid::Principal;
 use crate::types::*;
 use crate::state::*;
 use crate::constants::*;
+use candid::Encode;
+use ic_stable_structures::{StableBTreeMap, Storable};
+use ic_cdk::api::management_canister::ecdsa::{
+    ecdsa_public_key, sign_with_ecdsa, EcdsaCurve, EcdsaKeyId, EcdsaPublicKeyArgument, SignWithEcdsaArgument,
+};
+use sha2::{Digest, Sha256};
 
 // ============================================================================
 // DATE HELPERS
 // ============================================================================
 
+/// Current time in nanoseconds (a fixed clock in unit tests, which run
+/// outside a canister)
+pub fn now() -> u64 {
+    #[cfg(not(test))]
+    {
+        ic_cdk::api::time()
+    }
+    #[cfg(test)]
+    {
+        tests::TEST_NOW
+    }
+}
+
 /// Get current day index (days since epoch)
 pub fn get_current_day() -> u64 {
-    ic_cdk::api::time() / 86_400_000_000_000
+    now() / 86_400_000_000_000
 }
 
 /// Get week index (weeks since epoch, aligned to Sunday)
@@ -59,6 +78,148 @@ pub fn day_to_date(day: u64) -> SimpleDate {
     }
 }
 
+// ============================================================================
+// QUIZ LIMITS
+// ============================================================================
+
+/// Calendar counters as they stand on `current_day` (periods that have
+/// ended since the user was last active are zeroed)
+pub fn project_calendar_stats(mut stats: UserTimeStats, current_day: u64) -> UserTimeStats {
+    // Note: If last_active_day is 0 (default), this logic handles it correctly
+    if current_day <= stats.last_active_day {
+        return stats;
+    }
+    let current_date = day_to_date(current_day);
+    let last_date = day_to_date(stats.last_active_day);
+
+    stats.daily_quizzes = 0;
+    stats.daily_earnings = 0;
+
+    if get_week_index(current_day) > get_week_index(stats.last_active_day) {
+        stats.weekly_quizzes = 0;
+        stats.weekly_earnings = 0;
+    }
+
+    // Month or Year changed
+    if current_date.month != last_date.month || current_date.year != last_date.year {
+        stats.monthly_quizzes = 0;
+        stats.monthly_earnings = 0;
+    }
+
+    if current_date.year != last_date.year {
+        stats.yearly_quizzes = 0;
+        stats.yearly_earnings = 0;
+    }
+    stats
+}
+
+/// Add usage to the bucket `index` of one tier, dropping buckets that fell
+/// out of the `kept` most recent
+fn add_to_tier(buckets: &mut Vec<UsageBucket>, index: u64, kept: u64, quizzes: u16, earnings: u64) {
+    buckets.retain(|b| b.index + kept > index);
+    match buckets.last_mut() {
+        Some(bucket) if bucket.index == index => {
+            bucket.quizzes = bucket.quizzes.saturating_add(quizzes);
+            bucket.earnings += earnings;
+        }
+        _ => buckets.push(UsageBucket { index, quizzes, earnings }),
+    }
+}
+
+/// Sum of the buckets from `first_index` on
+fn tier_total(buckets: &[UsageBucket], first_index: u64) -> (u64, u64) {
+    buckets.iter()
+        .filter(|b| b.index >= first_index)
+        .fold((0, 0), |(quizzes, earnings), b| (quizzes + b.quizzes as u64, earnings + b.earnings))
+}
+
+/// Record a quiz attempt (or a bonus, with `quizzes` = 0) in the rolling history
+///
+/// Kept in every mode so switching to Rolling starts from real history.
+pub fn record_rolling_usage(user: Principal, now: u64, quizzes: u16, earnings: u64) {
+    ROLLING_USAGE.with(|r| {
+        let mut map = r.borrow_mut();
+        let mut usage = map.get(&user).unwrap_or_default();
+        add_to_tier(&mut usage.hourly, now / HOUR_NS, ROLLING_HOURLY_BUCKETS, quizzes, earnings);
+        add_to_tier(&mut usage.daily, now / DAY_NS, ROLLING_DAILY_BUCKETS, quizzes, earnings);
+        add_to_tier(&mut usage.weekly, now / WEEK_NS, ROLLING_WEEKLY_BUCKETS, quizzes, earnings);
+        map.insert(user, usage);
+    });
+}
+
+/// Usage over the last 24h/7d/30d/365d in the shape of UserTimeStats
+///
+/// A bucket counts if it overlaps the window, i.e. its index is at least
+/// (now - window) / bucket length.
+pub fn rolling_usage_stats(user: Principal, now: u64) -> UserTimeStats {
+    let usage = ROLLING_USAGE.with(|r| r.borrow().get(&user)).unwrap_or_default();
+    let (daily_quizzes, daily_earnings) = tier_total(&usage.hourly, now.saturating_sub(DAY_NS) / HOUR_NS);
+    let (weekly_quizzes, weekly_earnings) = tier_total(&usage.daily, now.saturating_sub(7 * DAY_NS) / DAY_NS);
+    let (monthly_quizzes, monthly_earnings) = tier_total(&usage.daily, now.saturating_sub(30 * DAY_NS) / DAY_NS);
+    let (yearly_quizzes, yearly_earnings) = tier_total(&usage.weekly, now.saturating_sub(365 * DAY_NS) / WEEK_NS);
+
+    UserTimeStats {
+        last_active_day: USER_TIME_STATS.with(|s| s.borrow().get(&user)).map_or(0, |stats| stats.last_active_day),
+        daily_quizzes: daily_quizzes.min(u8::MAX as u64) as u8,
+        daily_earnings,
+        weekly_quizzes: weekly_quizzes.min(u8::MAX as u64) as u8,
+        weekly_earnings,
+        monthly_quizzes: monthly_quizzes.min(u8::MAX as u64) as u8,
+        monthly_earnings,
+        yearly_quizzes: yearly_quizzes.min(u16::MAX as u64) as u16,
+        yearly_earnings,
+    }
+}
+
+/// A user's usage counted the way `config` measures limits
+pub fn quiz_usage(user: Principal, config: &TokenLimitsConfig, now: u64) -> UserTimeStats {
+    match config.limit_mode.unwrap_or(LimitMode::Calendar) {
+        LimitMode::Calendar => {
+            let stats = USER_TIME_STATS.with(|s| s.borrow().get(&user)).unwrap_or_default();
+            project_calendar_stats(stats, now / DAY_NS)
+        }
+        LimitMode::Rolling => rolling_usage_stats(user, now),
+    }
+}
+
+/// Names of the four limit periods in error messages
+pub fn limit_period_names(mode: LimitMode) -> [&'static str; 4] {
+    match mode {
+        LimitMode::Calendar => ["Daily", "Weekly", "Monthly", "Yearly"],
+        LimitMode::Rolling => ["24-hour", "7-day", "30-day", "365-day"],
+    }
+}
+
+/// Usage and remaining quota for `user` under the current config
+pub fn user_quota_stats(user: Principal) -> UserQuotaStats {
+    let config = TOKEN_LIMITS_CONFIG.with(|c| c.borrow().get().clone());
+    let now = ic_cdk::api::time();
+    let usage = quiz_usage(user, &config, now);
+    let limits = if is_subscription_active(user, now) {
+        &config.subscribed_limits
+    } else {
+        &config.regular_limits
+    };
+
+    UserQuotaStats {
+        last_active_day: usage.last_active_day,
+        daily_quizzes: usage.daily_quizzes,
+        daily_earnings: usage.daily_earnings,
+        weekly_quizzes: usage.weekly_quizzes,
+        weekly_earnings: usage.weekly_earnings,
+        monthly_quizzes: usage.monthly_quizzes,
+        monthly_earnings: usage.monthly_earnings,
+        yearly_quizzes: usage.yearly_quizzes,
+        yearly_earnings: usage.yearly_earnings,
+        limit_mode: config.limit_mode.unwrap_or(LimitMode::Calendar),
+        remaining_daily_attempts: config.max_daily_attempts.saturating_sub(usage.daily_quizzes),
+        remaining_daily_tokens: limits.max_daily_tokens.saturating_sub(usage.daily_earnings),
+        remaining_weekly_tokens: limits.max_weekly_tokens.saturating_sub(usage.weekly_earnings),
+        remaining_monthly_tokens: limits.max_monthly_tokens.saturating_sub(usage.monthly_earnings),
+        remaining_yearly_tokens: limits.max_yearly_tokens.saturating_sub(usage.yearly_earnings),
+    }
+}
+
 // ============================================================================
 // HUB SYNCHRONIZATION
 // ============================================================================
@@ -139,6 +300,47 @@ pub async fn sync_with_hub_internal() -> Result<(), String> {
     }
 }
 
+/// Build a user-facing error for when the hub could not refill the allowance
+///
+/// Asks the hub which bound holds this shard's allowance back (total supply,
+/// the global daily/weekly budget or this shard's share of it) so users see
+/// the actual reason rather than a generic failure. `needed` is how much more
+/// allowance the shard would have to hold to pay the pending reward.
+pub async fn explain_allowance_shortfall(needed: u64) -> String {
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    let result: Result<(ShardEmissionStatus,), _> = ic_cdk::call(
+        staking_hub_id,
+        "get_shard_emission_status",
+        (ic_cdk::id(),)
+    ).await;
+
+    let status = match result {
+        Ok((status,)) => status,
+        Err(_) => return "Global minting limit reached or Hub unavailable.".to_string(),
+    };
+
+    if status.available >= needed {
+        return "Minting allowance is being refilled. Please try again shortly.".to_string();
+    }
+
+    let budget = &status.budget;
+    match status.limited_by {
+        AllowanceLimit::MaxSupply => "Maximum token supply reached. No further rewards can be minted.".to_string(),
+        AllowanceLimit::WeeklyBudget => format!(
+            "Global weekly emission budget exhausted ({}/{} granted in the last 7 days). Please try again later.",
+            budget.granted_last_7d, budget.weekly_budget
+        ),
+        AllowanceLimit::DailyBudget => format!(
+            "Global daily emission budget exhausted ({}/{} granted in the last 24h). Please try again later.",
+            budget.granted_last_24h, budget.daily_budget
+        ),
+        AllowanceLimit::ShardShare => format!(
+            "This shard's share of the global daily emission budget is exhausted ({}/{} granted in the last 24h). Please try again later.",
+            status.shard_granted_last_24h, status.shard_daily_limit
+        ),
+    }
+}
+
 /// Rollback pending stats after a failed sync attempt
 pub fn rollback_pending_stats(staked_delta: i64, unstaked_delta: u64) {
     PENDING_STATS.with(|s| {
@@ -186,11 +388,22 @@ pub async fn run_periodic_archive() -> Result<u64, String> {
         ic_cdk::print(format!("Periodic archive completed: {} transactions archived", total_archived));
     }
     
+    match archive_audit_log(archive_id).await {
+        Ok(count) if count > 0 => ic_cdk::print(format!("Periodic archive: {} audit entries archived", count)),
+        Ok(_) => {}
+        Err(e) => ic_cdk::print(format!("Periodic audit archive failed: {}", e)),
+    }
+    
     Ok(total_archived)
 }
 
 /// Archive transactions for a specific user
 pub async fn archive_user_transactions(user: Principal, count_to_archive: u64, archive_id: Principal) -> Result<u64, String> {
+    // Don't archive under a principal that is being migrated away
+    if is_recovery_executing(user) {
+        return Ok(0);
+    }
+
     // 1. Fetch transactions to archive
     let mut txs_to_archive = Vec::new();
     let mut keys_to_remove = Vec::new();
@@ -292,20 +505,3213 @@ pub async fn archive_user_transactions(user: Principal, count_to_archive: u64, a
     Ok(archived_count)
 }
 
-/// Stable deterministic hash for answer verification (must match Learning Engine)
-pub fn stable_hash(data: &[u8]) -> [u8; 32] {
-    let mut hash: u64 = 5381;
-    for b in data {
-        hash = ((hash << 5).wrapping_add(hash)).wrapping_add(*b as u64);
+// ============================================================================
+// AUDIT LOG
+// ============================================================================
+
+/// Debug rendering of `value`, cut to MAX_AUDIT_VALUE_LEN bytes
+fn audit_value<T: std::fmt::Debug>(value: &T) -> String {
+    let mut rendered = format!("{:?}", value);
+    let mut cut = MAX_AUDIT_VALUE_LEN.min(rendered.len());
+    while !rendered.is_char_boundary(cut) {
+        cut -= 1;
     }
-    let b = hash.to_le_bytes();
-    let mut res = [0u8; 32];
-    for i in 0..8 {
-        res[i] = b[i];
+    rendered.truncate(cut);
+    rendered
+}
+
+/// Append an entry to the audit log on behalf of the current caller
+///
+/// Entries are never modified afterwards; the oldest are moved to the
+/// archive by the periodic archive task.
+pub fn record_audit<B: std::fmt::Debug, A: std::fmt::Debug>(method: &str, target: Principal, before: &B, after: &A) -> u64 {
+    let id = AUDIT_COUNT.with(|c| {
+        let mut cell = c.borrow_mut();
+        let id = *cell.get();
+        cell.set(id + 1).expect("Failed to update audit count");
+        id
+    });
+
+    AUDIT_LOG.with(|l| {
+        l.borrow_mut().insert(id, AuditEntry {
+            id,
+            timestamp: ic_cdk::api::time(),
+            caller: ic_cdk::caller(),
+            method: method.to_string(),
+            target,
+            before: audit_value(before),
+            after: audit_value(after),
+        });
+    });
+    id
+}
+
+/// A user's staked balance as recorded in the audit log
+pub fn staked_balance_audit_state(user: Principal) -> Option<u64> {
+    USER_PROFILES.with(|p| p.borrow().get(&user).map(|profile| profile.staked_balance))
+}
+
+/// A user's KYC tier as recorded in the audit log
+pub fn kyc_audit_state(user: Principal) -> Option<VerificationTier> {
+    USER_PROFILES.with(|p| p.borrow().get(&user).map(|profile| profile.verification_tier))
+}
+
+/// A user's subscription flag and period as recorded in the audit log
+pub fn subscription_audit_state(user: Principal) -> (Option<bool>, Option<SubscriptionPeriod>) {
+    (
+        USER_PROFILES.with(|p| p.borrow().get(&user).map(|profile| profile.is_subscribed)),
+        USER_SUBSCRIPTIONS.with(|s| s.borrow().get(&user)),
+    )
+}
+
+/// Move audit entries beyond AUDIT_RETENTION_LIMIT to the archive, oldest first
+pub async fn archive_audit_log(archive_id: Principal) -> Result<u64, String> {
+    let mut total_archived = 0u64;
+
+    loop {
+        let archived = AUDIT_ARCHIVED_COUNT.with(|c| *c.borrow().get());
+        let count = AUDIT_COUNT.with(|c| *c.borrow().get());
+        let excess = count.saturating_sub(archived).saturating_sub(AUDIT_RETENTION_LIMIT);
+        if excess == 0 {
+            break;
+        }
+        let end = archived + excess.min(AUDIT_ARCHIVE_BATCH);
+
+        let batch: Vec<AuditEntryToArchive> = AUDIT_LOG.with(|l| {
+            l.borrow().range(archived..end).map(|(_, entry)| AuditEntryToArchive {
+                id: entry.id,
+                timestamp: entry.timestamp,
+                caller: entry.caller,
+                method: entry.method,
+                target: entry.target,
+                before: entry.before,
+                after: entry.after,
+            }).collect()
+        });
+
+        let result: Result<(Result<u64, String>,), _> = ic_cdk::call(
+            archive_id,
+            "receive_audit_batch",
+            (batch,)
+        ).await;
+        match result {
+            Ok((Ok(_),)) => {}
+            Ok((Err(msg),)) => return Err(format!("Archive canister rejected: {}", msg)),
+            Err((code, msg)) => return Err(format!("Archive call failed: {:?} {}", code, msg)),
+        }
+
+        // The archive skips ids it already holds, so the whole batch is stored
+        AUDIT_LOG.with(|l| {
+            let mut map = l.borrow_mut();
+            for id in archived..end {
+                map.remove(&id);
+            }
+        });
+        AUDIT_ARCHIVED_COUNT.with(|c| {
+            let mut cell = c.borrow_mut();
+            let new_count = (*cell.get()).max(end);
+            cell.set(new_count).expect("Failed to update archived audit count");
+        });
+        total_archived += end - archived;
     }
-    // Fill the rest with a pattern to make it 32 bytes
-    for i in 8..32 {
-        res[i] = res[i % 8];
+
+    Ok(total_archived)
+}
+
+/// Fetch archived audit entries with id in [start, end), following the
+/// archive chain for ids the first archive does not hold
+async fn fetch_archived_audit_range(archive_id: Principal, start: u64, end: u64) -> Result<Vec<ArchivedAuditEntry>, String> {
+    let mut found: Vec<ArchivedAuditEntry> = Vec::new();
+    let mut current = Some(archive_id);
+    let mut depth = 0;
+
+    while let Some(archive) = current {
+        if depth >= MAX_ARCHIVE_CHAIN_DEPTH {
+            break;
+        }
+        let (range,): (ArchivedAuditRange,) = ic_cdk::call(
+            archive,
+            "get_audit_range",
+            (start, end)
+        ).await.map_err(|(code, msg)| format!("Archive call failed: {:?} {}", code, msg))?;
+
+        found.extend(range.entries);
+        if found.len() as u64 >= end - start {
+            break;
+        }
+        current = range.next_archive;
+        depth += 1;
+    }
+
+    Ok(found)
+}
+
+/// Build one page of the audit log, newest first, across local storage and
+/// the archive chain (the cursor is the id below which the page starts)
+pub async fn get_audit_log_internal(before: Option<u64>, limit: u32) -> Result<AuditLogPage, String> {
+    let total_count = AUDIT_COUNT.with(|c| *c.borrow().get());
+    let archived_count = AUDIT_ARCHIVED_COUNT.with(|c| *c.borrow().get());
+
+    let limit = match limit {
+        0 => DEFAULT_AUDIT_PAGE_SIZE,
+        n => n.min(MAX_AUDIT_PAGE_SIZE),
+    } as u64;
+
+    let mut cursor = before.unwrap_or(total_count).min(total_count);
+    let mut entries: Vec<AuditEntry> = Vec::new();
+
+    // Step 1: Local entries (id >= archived_count)
+    AUDIT_LOG.with(|l| {
+        let map = l.borrow();
+        while cursor > archived_count && (entries.len() as u64) < limit {
+            cursor -= 1;
+            if let Some(entry) = map.get(&cursor) {
+                entries.push(entry);
+            }
+        }
+    });
+
+    // Step 2: Archived entries (id < archived_count)
+    let archive_id = ARCHIVE_CANISTER_ID.with(|id| *id.borrow().get());
+    let needed = limit - entries.len() as u64;
+    if needed > 0 && cursor > 0 {
+        if archive_id == Principal::anonymous() {
+            // Archived entries are unreachable without an archive canister
+            cursor = 0;
+        } else {
+            let chunk_start = cursor.saturating_sub(needed);
+            match fetch_archived_audit_range(archive_id, chunk_start, cursor).await {
+                Ok(mut fetched) => {
+                    fetched.sort_by_key(|entry| std::cmp::Reverse(entry.id));
+                    entries.extend(fetched.into_iter().map(|entry| AuditEntry {
+                        id: entry.id,
+                        timestamp: entry.timestamp,
+                        caller: entry.caller,
+                        method: entry.method,
+                        target: entry.target,
+                        before: entry.before,
+                        after: entry.after,
+                    }));
+                    cursor = chunk_start;
+                }
+                // Return what we have; the cursor lets the client retry from here
+                Err(_) if !entries.is_empty() => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    Ok(AuditLogPage {
+        entries,
+        next_cursor: if cursor == 0 { None } else { Some(cursor) },
+        total_count,
+        archived_count,
+    })
+}
+
+// ============================================================================
+// TRANSACTION HISTORY
+// ============================================================================
+
+fn matches_history_filter(request: &TransactionHistoryRequest, tx_type: &TransactionType, timestamp: u64) -> bool {
+    if let Some(types) = &request.tx_types {
+        if !types.contains(tx_type) {
+            return false;
+        }
+    }
+    if let Some(to) = request.to_timestamp {
+        if timestamp > to {
+            return false;
+        }
+    }
+    true
+}
+
+/// Fetch archived transactions with sequence in [start, end), following the
+/// archive chain for sequences the first archive does not hold
+pub async fn fetch_archived_range(user: Principal, archive_id: Principal, start: u64, end: u64) -> Result<Vec<ArchivedTransaction>, String> {
+    let mut found: Vec<ArchivedTransaction> = Vec::new();
+    let mut current = Some(archive_id);
+    let mut depth = 0;
+
+    while let Some(archive) = current {
+        if depth >= MAX_ARCHIVE_CHAIN_DEPTH {
+            break;
+        }
+        let (range,): (ArchivedRange,) = ic_cdk::call(
+            archive,
+            "get_archived_range",
+            (user, start, end)
+        ).await.map_err(|(code, msg)| format!("Archive call failed: {:?} {}", code, msg))?;
+
+        found.extend(range.transactions);
+        if found.len() as u64 >= end - start {
+            break;
+        }
+        current = range.next_archive;
+        depth += 1;
+    }
+
+    Ok(found)
+}
+
+/// Build one page of a user's history, newest first, across local storage and
+/// the archive chain
+///
+/// Sequences are chronological, so the cursor is simply the sequence below
+/// which the next page starts. At most MAX_HISTORY_SCAN transactions are
+/// examined per call; a page may therefore be short but still carry a cursor.
+pub async fn get_transaction_history_internal(user: Principal, request: TransactionHistoryRequest) -> Result<TransactionHistoryPage, String> {
+    let (total_count, archived_count) = USER_PROFILES.with(|p| {
+        p.borrow().get(&user).map(|profile| (profile.transaction_count, profile.archived_transaction_count))
+    }).ok_or("User not registered")?;
+
+    let limit = match request.limit {
+        0 => DEFAULT_HISTORY_PAGE_SIZE,
+        n => n.min(MAX_HISTORY_PAGE_SIZE),
+    } as usize;
+
+    let mut cursor = request.cursor.unwrap_or(total_count).min(total_count);
+    let mut entries: Vec<TransactionHistoryEntry> = Vec::new();
+    let mut scanned: u64 = 0;
+    // Set once we pass from_timestamp: everything older is out of range too
+    let mut reached_start = false;
+
+    // ─────────────────────────────────────────────────────────────────
+    // Step 1: Local transactions (sequence >= archived_count)
+    // ─────────────────────────────────────────────────────────────────
+    USER_TRANSACTIONS.with(|t| {
+        let map = t.borrow();
+        while cursor > archived_count && entries.len() < limit && scanned < MAX_HISTORY_SCAN {
+            let sequence = cursor - 1;
+            if let Some(tx) = map.get(&TransactionKey { user, index: sequence }) {
+                if request.from_timestamp.is_some_and(|from| tx.timestamp < from) {
+                    reached_start = true;
+                    break;
+                }
+                if matches_history_filter(&request, &tx.tx_type, tx.timestamp) {
+                    entries.push(TransactionHistoryEntry {
+                        sequence,
+                        timestamp: tx.timestamp,
+                        tx_type: tx.tx_type,
+                        amount: tx.amount,
+                        archived: false,
+                    });
+                }
+            }
+            cursor = sequence;
+            scanned += 1;
+        }
+    });
+
+    // ─────────────────────────────────────────────────────────────────
+    // Step 2: Archived transactions (sequence < archived_count)
+    // ─────────────────────────────────────────────────────────────────
+    let archive_id = ARCHIVE_CANISTER_ID.with(|id| *id.borrow().get());
+    if archive_id == Principal::anonymous() && cursor <= archived_count {
+        // Archived history is unreachable without an archive canister
+        reached_start = true;
+    }
+
+    while !reached_start && cursor > 0 && entries.len() < limit && scanned < MAX_HISTORY_SCAN {
+        let chunk_start = cursor.saturating_sub(ARCHIVE_FETCH_CHUNK);
+        let fetched = match fetch_archived_range(user, archive_id, chunk_start, cursor).await {
+            Ok(fetched) => fetched,
+            // Return what we have; the cursor lets the client retry from here
+            Err(_) if !entries.is_empty() => break,
+            Err(e) => return Err(e),
+        };
+        let by_sequence: std::collections::BTreeMap<u64, ArchivedTransaction> = fetched.into_iter()
+            .map(|tx| (tx.sequence, tx))
+            .collect();
+
+        for sequence in (chunk_start..cursor).rev() {
+            if entries.len() >= limit || scanned >= MAX_HISTORY_SCAN {
+                break;
+            }
+            if let Some(tx) = by_sequence.get(&sequence) {
+                if request.from_timestamp.is_some_and(|from| tx.timestamp < from) {
+                    reached_start = true;
+                    break;
+                }
+                if let Some(tx_type) = TransactionType::from_archive_label(&tx.transaction_type) {
+                    if matches_history_filter(&request, &tx_type, tx.timestamp) {
+                        entries.push(TransactionHistoryEntry {
+                            sequence,
+                            timestamp: tx.timestamp,
+                            tx_type,
+                            amount: tx.amount,
+                            archived: true,
+                        });
+                    }
+                }
+            }
+            cursor = sequence;
+            scanned += 1;
+        }
+    }
+
+    Ok(TransactionHistoryPage {
+        entries,
+        next_cursor: if reached_start || cursor == 0 { None } else { Some(cursor) },
+        unfiltered_count: total_count,
+        local_count: total_count.saturating_sub(archived_count),
+        archived_count,
+    })
+}
+
+// ============================================================================
+// PER-USER QUIZ MAPS
+// ============================================================================
+
+/// A user's entries in a map keyed by UserQuizKey
+///
+/// UserQuizKey encodes the principal first, so a user's keys are contiguous
+/// and the empty unit_id is the smallest key for that user.
+pub fn user_quiz_entries<V: Storable>(
+    map: &StableBTreeMap<UserQuizKey, V, Memory>,
+    user: Principal,
+) -> impl Iterator<Item = (UserQuizKey, V)> + '_ {
+    map.range(UserQuizKey { user, unit_id: String::new() }..)
+        .take_while(move |(k, _)| k.user == user)
+}
+
+/// A user's keys in a map keyed by UserQuizKey
+pub fn user_quiz_keys<V: Storable>(map: &StableBTreeMap<UserQuizKey, V, Memory>, user: Principal) -> Vec<UserQuizKey> {
+    user_quiz_entries(map, user).map(|(k, _)| k).collect()
+}
+
+/// Remove every entry of `user` from a map keyed by UserQuizKey
+pub fn remove_user_quiz_entries<V: Storable>(map: &mut StableBTreeMap<UserQuizKey, V, Memory>, user: Principal) {
+    for key in user_quiz_keys(map, user) {
+        map.remove(&key);
+    }
+}
+
+/// Move every entry of `old_user` to `new_user` in a map keyed by UserQuizKey
+pub fn rekey_user_quiz_entries<V: Storable>(
+    map: &mut StableBTreeMap<UserQuizKey, V, Memory>,
+    old_user: Principal,
+    new_user: Principal,
+) {
+    let entries: Vec<(UserQuizKey, V)> = user_quiz_entries(map, old_user).collect();
+    for (key, value) in entries {
+        map.remove(&key);
+        map.insert(UserQuizKey { user: new_user, unit_id: key.unit_id }, value);
+    }
+}
+
+// ============================================================================
+// STREAKS & ACHIEVEMENTS
+// ============================================================================
+
+/// Count the quizzes a user has completed on this shard
+pub fn count_completed_quizzes(user: Principal) -> u64 {
+    COMPLETED_QUIZZES.with(|q| user_quiz_entries(&q.borrow(), user).count() as u64)
+}
+
+/// Record a passed quiz in the user's streak
+///
+/// Must be called after the quiz is marked completed. The lifetime quiz count
+/// is backfilled from COMPLETED_QUIZZES the first time a user is seen.
+pub fn record_streak_activity(user: Principal) -> LearningStreak {
+    let today = get_current_day();
+
+    let mut streak = match USER_STREAKS.with(|s| s.borrow().get(&user)) {
+        Some(mut streak) => {
+            streak.total_quizzes_passed += 1;
+            streak
+        }
+        None => LearningStreak {
+            total_quizzes_passed: count_completed_quizzes(user),
+            ..Default::default()
+        },
+    };
+
+    if streak.current_streak == 0 || streak.last_streak_day + 1 < today {
+        streak.current_streak = 1;
+    } else if streak.last_streak_day + 1 == today {
+        streak.current_streak += 1;
+    }
+    streak.last_streak_day = today;
+    streak.longest_streak = streak.longest_streak.max(streak.current_streak);
+
+    USER_STREAKS.with(|s| s.borrow_mut().insert(user, streak.clone()));
+    streak
+}
+
+/// Current streak as seen today (a streak survives until the end of the next day)
+pub fn effective_current_streak(streak: &LearningStreak, today: u64) -> u64 {
+    if streak.last_streak_day + 1 >= today {
+        streak.current_streak
+    } else {
+        0
+    }
+}
+
+/// Award every active badge the user now qualifies for and pay its bonus
+///
+/// Subtree badges are only checked when `unit_id` belongs to the subtree,
+/// which keeps the per-quiz cost proportional to the relevant badges.
+pub fn evaluate_badges(user: Principal, streak: &LearningStreak, unit_id: &str) -> Vec<EarnedBadge> {
+    let mut earned = USER_BADGES.with(|b| b.borrow().get(&user)).unwrap_or_default();
+    let definitions: Vec<BadgeDefinition> = BADGE_DEFINITIONS.with(|d| {
+        d.borrow().iter().map(|(_, def)| def).filter(|def| def.active).collect()
+    });
+
+    let mut newly_earned = Vec::new();
+    for def in definitions {
+        if earned.0.iter().any(|e| e.badge_id == def.id) {
+            continue;
+        }
+
+        let satisfied = match &def.rule {
+            BadgeRule::QuizzesCompleted { count } => streak.total_quizzes_passed >= *count,
+            BadgeRule::StreakDays { days } => streak.current_streak >= *days,
+            BadgeRule::SubtreeCompleted { unit_ids, .. } => {
+                unit_ids.iter().any(|u| u == unit_id)
+                    && COMPLETED_QUIZZES.with(|q| {
+                        let q = q.borrow();
+                        unit_ids.iter().all(|u| q.contains_key(&UserQuizKey { user, unit_id: u.clone() }))
+                    })
+            }
+        };
+        if !satisfied {
+            continue;
+        }
+
+        let bonus_awarded = credit_badge_bonus(user, def.bonus_reward);
+        newly_earned.push(EarnedBadge {
+            badge_id: def.id,
+            earned_at: ic_cdk::api::time(),
+            bonus_awarded,
+        });
+    }
+
+    if !newly_earned.is_empty() {
+        earned.0.extend(newly_earned.iter().cloned());
+        USER_BADGES.with(|b| b.borrow_mut().insert(user, earned));
+    }
+
+    newly_earned
+}
+
+/// Pay a badge bonus, clamped to the user's remaining token limits and the
+/// shard's minting allowance. Returns the amount actually credited.
+pub fn credit_badge_bonus(user: Principal, amount: u64) -> u64 {
+    credit_limited_bonus(user, amount, TransactionType::BadgeBonus)
+}
+
+/// Pay a bonus that counts toward the user's token limits (see credit_badge_bonus)
+pub fn credit_limited_bonus(user: Principal, amount: u64, tx_type: TransactionType) -> u64 {
+    if amount == 0 {
+        return 0;
+    }
+
+    let config = TOKEN_LIMITS_CONFIG.with(|c| c.borrow().get().clone());
+    let Some(mut profile) = USER_PROFILES.with(|p| p.borrow().get(&user)) else {
+        return 0;
+    };
+    let limits = if is_subscription_active(user, ic_cdk::api::time()) {
+        &config.subscribed_limits
+    } else {
+        &config.regular_limits
+    };
+    let now = ic_cdk::api::time();
+    let current_day = get_current_day();
+
+    // Calendar counters are projected before the bonus is added, as in
+    // submit_quiz, so a stale day's totals are not carried into today
+    let mut stats = project_calendar_stats(
+        USER_TIME_STATS.with(|s| s.borrow().get(&user)).unwrap_or_default(),
+        current_day,
+    );
+    stats.last_active_day = stats.last_active_day.max(current_day);
+    let usage = match config.limit_mode.unwrap_or(LimitMode::Calendar) {
+        LimitMode::Calendar => stats.clone(),
+        LimitMode::Rolling => rolling_usage_stats(user, now),
+    };
+    let allowance = MINTING_ALLOWANCE.with(|a| *a.borrow().get());
+
+    let bonus = amount
+        .min(limits.max_daily_tokens.saturating_sub(usage.daily_earnings))
+        .min(limits.max_weekly_tokens.saturating_sub(usage.weekly_earnings))
+        .min(limits.max_monthly_tokens.saturating_sub(usage.monthly_earnings))
+        .min(limits.max_yearly_tokens.saturating_sub(usage.yearly_earnings))
+        .min(allowance);
+    if bonus == 0 {
+        return 0;
+    }
+
+    stats.daily_earnings += bonus;
+    stats.weekly_earnings += bonus;
+    stats.monthly_earnings += bonus;
+    stats.yearly_earnings += bonus;
+    USER_TIME_STATS.with(|s| s.borrow_mut().insert(user, stats));
+    record_rolling_usage(user, now, 0, bonus);
+
+    apply_bonus_credit(user, &mut profile, bonus, tx_type);
+    USER_PROFILES.with(|p| p.borrow_mut().insert(user, profile));
+
+    bonus
+}
+
+/// Credit an already-clamped bonus: balance, transaction log, allowance and
+/// pending stats. The caller persists `profile`.
+pub fn apply_bonus_credit(user: Principal, profile: &mut UserProfile, bonus: u64, tx_type: TransactionType) {
+    let tx_index = profile.transaction_count;
+    profile.transaction_count += 1;
+    profile.staked_balance += bonus;
+    USER_TRANSACTIONS.with(|t| t.borrow_mut().insert(
+        TransactionKey { user, index: tx_index },
+        TransactionRecord {
+            timestamp: now(),
+            tx_type,
+            amount: bonus,
+        }
+    ));
+
+    MINTING_ALLOWANCE.with(|a| {
+        let current = *a.borrow().get();
+        a.borrow_mut().set(current - bonus).expect("Failed to update allowance");
+    });
+    PENDING_STATS.with(|s| {
+        let mut cell = s.borrow_mut();
+        let mut stats = cell.get().clone();
+        stats.staked_delta += bonus as i64;
+        cell.set(stats).expect("Failed to update pending stats");
+    });
+}
+
+// ============================================================================
+// PRACTICE MODE
+// ============================================================================
+
+/// Canonical form of a short text answer (must match the learning engine)
+pub fn normalize_text_answer(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn sorted_indexes(indexes: &[u8]) -> Vec<u8> {
+    let mut sorted = indexes.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    sorted
+}
+
+/// Decode a quiz payload: Candid-encoded vec of QuizAnswer, or the legacy
+/// encoding of one option index per byte
+pub fn decode_quiz_answers(payload: &[u8]) -> Result<Vec<QuizAnswer>, String> {
+    if payload.starts_with(b"DIDL") {
+        return candid::decode_one(payload).map_err(|e| format!("Invalid answer encoding: {}", e));
+    }
+    Ok(payload.iter().map(|b| QuizAnswer::SingleChoice(*b)).collect())
+}
+
+/// Whether an answer satisfies a commitment (a mismatched answer type is wrong)
+fn answer_matches(commitment: &AnswerCommitment, answer: &QuizAnswer) -> bool {
+    match (commitment, answer) {
+        (AnswerCommitment::SingleChoice(hash), QuizAnswer::SingleChoice(index)) => stable_hash(&index.to_le_bytes()) == *hash,
+        (AnswerCommitment::MultiSelect(hash), QuizAnswer::MultiSelect(indexes)) => stable_hash(&sorted_indexes(indexes)) == *hash,
+        (AnswerCommitment::TrueFalse(hash), QuizAnswer::TrueFalse(value)) => stable_hash(&[*value as u8]) == *hash,
+        // The byte encoding answers true/false with 1/0
+        (AnswerCommitment::TrueFalse(hash), QuizAnswer::SingleChoice(byte)) => *byte <= 1 && stable_hash(&[*byte]) == *hash,
+        (AnswerCommitment::Numeric { min, max }, QuizAnswer::Numeric(value)) => min <= value && value <= max,
+        (AnswerCommitment::Ordering(hash), QuizAnswer::Ordering(order)) => stable_hash(order) == *hash,
+        (AnswerCommitment::ShortText(hashes), QuizAnswer::ShortText(text)) => {
+            text.len() <= MAX_TEXT_ANSWER_LEN && hashes.contains(&stable_hash(normalize_text_answer(text).as_bytes()))
+        }
+        _ => false,
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Pool indexes of the questions a learner gets, in answer order
+///
+/// Must match the learning engine's draw_questions, which serves the drawn
+/// questions to the learner (get_drawn_quiz) and verifies remotely.
+pub fn draw_questions(user: Principal, content_id: &str, pool_size: usize, draw_count: Option<u8>) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..pool_size).collect();
+    let Some(draw_count) = draw_count else {
+        return order;
+    };
+    let count = (draw_count as usize).min(pool_size);
+
+    let mut seed_bytes = user.as_slice().to_vec();
+    seed_bytes.push(0);
+    seed_bytes.extend_from_slice(content_id.as_bytes());
+    let hash = stable_hash(&seed_bytes);
+    let mut state = u64::from_le_bytes(hash[0..8].try_into().unwrap());
+
+    // Partial Fisher-Yates: only the drawn prefix is shuffled
+    for i in 0..count {
+        let j = i + (splitmix64(&mut state) % (pool_size - i) as u64) as usize;
+        order.swap(i, j);
+    }
+    order.truncate(count);
+    order
+}
+
+/// Number of questions each learner answers
+pub fn drawn_question_count(cache: &QuizCacheData) -> u64 {
+    let pool_size = cache.question_count as u64;
+    cache.draw_count.map(|d| (d as u64).min(pool_size)).unwrap_or(pool_size)
+}
+
+/// Per-question correctness of the user's draw against the cached
+/// commitments, or None if the number of answers does not match the draw
+pub fn score_answers(cache: &QuizCacheData, user: Principal, answers: &[QuizAnswer]) -> Option<Vec<bool>> {
+    let commitments = match &cache.commitments {
+        Some(commitments) => commitments.clone(),
+        None => cache.answer_hashes.iter().map(|h| AnswerCommitment::SingleChoice(*h)).collect(),
+    };
+    let pool_size = (cache.question_count as usize).min(commitments.len());
+    let draw = draw_questions(user, &cache.content_id, pool_size, cache.draw_count);
+    if answers.len() != draw.len() {
+        return None;
+    }
+    Some(draw.iter()
+        .zip(answers.iter())
+        .map(|(index, answer)| answer_matches(&commitments[*index], answer))
+        .collect())
+}
+
+/// Canonical form of a locale tag, as the learning engine stores it ("pt_BR" -> "pt-br")
+pub fn normalize_locale(locale: &str) -> Result<String, String> {
+    let locale = locale.trim().to_lowercase().replace('_', "-");
+    if locale.len() < MIN_LOCALE_LEN || locale.len() > MAX_LOCALE_LEN
+        || !locale.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(format!("Invalid locale '{}'", locale));
+    }
+    Ok(locale)
+}
+
+/// Which cached quiz serves a locale: Some(None) = the unit's own quiz,
+/// Some(Some(l)) = the translated quiz of `l`, None = locales not fetched yet
+/// (same resolution as the learning engine: exact locale, then its language)
+pub fn resolve_cached_locale(unit_id: &str, locale: &str) -> Option<Option<String>> {
+    let locales = QUIZ_LOCALES.with(|l| l.borrow().get(&unit_id.to_string()))?.0;
+    let mut chain = vec![locale.to_string()];
+    if let Some((language, _)) = locale.split_once('-') {
+        chain.push(language.to_string());
+    }
+    Some(chain.into_iter().find(|candidate| locales.contains(candidate)))
+}
+
+/// Locally cached quiz of a unit, in a locale when one is given
+pub fn cached_quiz(unit_id: &str, locale: Option<&str>) -> Option<QuizCacheData> {
+    let resolved = match locale {
+        Some(locale) => resolve_cached_locale(unit_id, locale)?,
+        None => None,
+    };
+    match resolved {
+        Some(locale) => LOCALIZED_QUIZ_CACHE.with(|q| q.borrow().get(&LocalizedQuizKey {
+            unit_id: unit_id.to_string(),
+            locale,
+        })),
+        None => QUIZ_CACHE.with(|q| q.borrow().get(&unit_id.to_string())),
+    }
+}
+
+/// Drop every locale's cached quiz of a unit (refetched on the next attempt)
+pub fn clear_localized_quiz_caches(unit_id: &str) {
+    let start = LocalizedQuizKey { unit_id: unit_id.to_string(), locale: String::new() };
+    let keys: Vec<LocalizedQuizKey> = LOCALIZED_QUIZ_CACHE.with(|q| {
+        q.borrow().range(start..)
+            .take_while(|(key, _)| key.unit_id == unit_id)
+            .map(|(key, _)| key)
+            .collect()
+    });
+    LOCALIZED_QUIZ_CACHE.with(|q| {
+        let mut map = q.borrow_mut();
+        for key in keys {
+            map.remove(&key);
+        }
+    });
+}
+
+/// Replace the prerequisites of cached quizzes; localized copies are dropped
+/// and refetched with the new requirements
+pub fn apply_prerequisite_updates(updates: Vec<(String, Vec<String>)>) {
+    for (unit_id, prerequisites) in updates {
+        clear_localized_quiz_caches(&unit_id);
+        QUIZ_CACHE.with(|q| {
+            let mut map = q.borrow_mut();
+            if let Some(mut cache) = map.get(&unit_id) {
+                cache.prerequisites = if prerequisites.is_empty() { None } else { Some(prerequisites) };
+                map.insert(unit_id, cache);
+            }
+        });
+    }
+}
+
+/// Replace a unit's translated quiz locales, dropping its localized caches
+pub fn set_quiz_locales(unit_id: &str, locales: Vec<String>) {
+    clear_localized_quiz_caches(unit_id);
+    QUIZ_LOCALES.with(|l| l.borrow_mut().insert(unit_id.to_string(), QuizLocales(locales)));
+}
+
+/// Fetch a unit's quiz from the learning engine (in a locale when one is
+/// given) and cache it under the locale that actually served it
+pub async fn fetch_quiz_cache(unit_id: &str, locale: Option<&str>) -> Result<Option<QuizCacheData>, String> {
+    let learning_content_id = LEARNING_CONTENT_ID.with(|id| *id.borrow().get());
+    let Some(locale) = locale else {
+        let (data,): (Option<QuizCacheData>,) = ic_cdk::call(
+            learning_content_id,
+            "get_shard_quiz_data",
+            (unit_id.to_string(),)
+        ).await.map_err(|(code, msg)| format!("Failed to fetch quiz: {:?} {}", code, msg))?;
+        if let Some(ref cache) = data {
+            QUIZ_CACHE.with(|q| q.borrow_mut().insert(unit_id.to_string(), cache.clone()));
+        }
+        return Ok(data);
+    };
+
+    let (data,): (Option<LocalizedQuizData>,) = ic_cdk::call(
+        learning_content_id,
+        "get_shard_localized_quiz_data",
+        (unit_id.to_string(), locale.to_string())
+    ).await.map_err(|(code, msg)| format!("Failed to fetch quiz: {:?} {}", code, msg))?;
+    let Some(data) = data else {
+        return Ok(None);
+    };
+
+    set_quiz_locales(unit_id, data.locales);
+    match data.locale {
+        Some(served) => LOCALIZED_QUIZ_CACHE.with(|q| q.borrow_mut().insert(
+            LocalizedQuizKey { unit_id: unit_id.to_string(), locale: served },
+            data.cache.clone(),
+        )),
+        None => QUIZ_CACHE.with(|q| q.borrow_mut().insert(unit_id.to_string(), data.cache.clone())),
+    };
+    Ok(Some(data.cache))
+}
+
+/// Quiz data from the local cache, fetched from the learning engine on a miss
+pub async fn load_quiz_cache(unit_id: &str, locale: Option<&str>) -> Result<QuizCacheData, String> {
+    if let Some(cache) = cached_quiz(unit_id, locale) {
+        return Ok(cache);
+    }
+
+    fetch_quiz_cache(unit_id, locale).await?
+        .ok_or_else(|| "Quiz not found in learning engine".to_string())
+}
+
+/// Score a practice attempt and record it in PRACTICE_RECORDS
+///
+/// Practice never touches balances, minting allowance, quiz limits or
+/// completion, so it also works for units that are already passed.
+pub async fn practice_quiz_internal(user: Principal, unit_id: String, answers: Vec<u8>, locale: Option<String>) -> Result<PracticeResult, String> {
+    let answers = decode_quiz_answers(&answers)?;
+    let locale = locale.map(|l| normalize_locale(&l)).transpose()?;
+    let cache = load_quiz_cache(&unit_id, locale.as_deref()).await?;
+    if cache.question_count == 0 {
+        return Err("Unit not found or empty quiz".to_string());
+    }
+    let results = score_answers(&cache, user, &answers)
+        .ok_or_else(|| format!("Expected {} answers, got {}", drawn_question_count(&cache), answers.len()))?;
+
+    let correct = results.iter().filter(|r| **r).count() as u64;
+    let total = results.len() as u64;
+    let threshold = TOKEN_LIMITS_CONFIG.with(|c| c.borrow().get().pass_threshold_percent) as u64;
+
+    let key = UserQuizKey { user, unit_id: unit_id.clone() };
+    let record = PRACTICE_RECORDS.with(|p| {
+        let mut map = p.borrow_mut();
+        let previous = map.get(&key);
+        let record = PracticeRecord {
+            attempts: previous.as_ref().map(|r| r.attempts).unwrap_or(0).saturating_add(1),
+            last_correct: correct,
+            // A changed quiz (different question count) restarts the best score
+            best_correct: match previous {
+                Some(r) if r.total == total => r.best_correct.max(correct),
+                _ => correct,
+            },
+            total,
+            last_results: results.clone(),
+            last_practiced_at: ic_cdk::api::time(),
+        };
+        map.insert(key, record.clone());
+        record
+    });
+
+    let review = review_from_practice(user, &unit_id, correct, total);
+
+    Ok(PracticeResult {
+        unit_id,
+        results,
+        correct,
+        total,
+        would_pass: (correct * 100 / total) >= threshold,
+        history: record,
+        review,
+    })
+}
+
+/// All practice records of a user, by unit
+pub fn user_practice_records(user: Principal) -> Vec<(String, PracticeRecord)> {
+    PRACTICE_RECORDS.with(|p| {
+        user_quiz_entries(&p.borrow(), user)
+            .map(|(k, record)| (k.unit_id, record))
+            .collect()
+    })
+}
+
+// ============================================================================
+// SPACED REPETITION (SM-2)
+// ============================================================================
+
+fn new_review_card(today: u32, due_day: u32) -> ReviewCard {
+    ReviewCard {
+        ease_milli: DEFAULT_EASE_MILLI,
+        interval_days: 1,
+        repetitions: 1,
+        lapses: 0,
+        due_day,
+        last_review_day: today,
+    }
+}
+
+/// Put a newly passed unit on the review schedule (the pass is the first
+/// repetition, so the first review is due tomorrow)
+pub fn schedule_first_review(user: Principal, unit_id: &str) {
+    let today = get_current_day() as u32;
+    let key = UserQuizKey { user, unit_id: unit_id.to_string() };
+    REVIEW_CARDS.with(|r| r.borrow_mut().insert(key, new_review_card(today, today + 1)));
+}
+
+/// SM-2 quality (0-5) of a score, rounded to the nearest grade
+pub fn review_quality(correct: u64, total: u64) -> u8 {
+    if total == 0 {
+        return 0;
+    }
+    ((correct.min(total) * 5 + total / 2) / total) as u8
+}
+
+/// One SM-2 step: successful reviews grow the interval (1, 6, then x ease),
+/// a lapse starts over at one day; the ease moves with the quality either way
+pub fn sm2_step(card: ReviewCard, quality: u8, today: u32) -> ReviewCard {
+    let mut next = card;
+    if quality >= REVIEW_PASS_QUALITY {
+        next.repetitions = card.repetitions.saturating_add(1);
+        next.interval_days = match next.repetitions {
+            1 => 1,
+            2 => 6,
+            _ => (card.interval_days as u64 * card.ease_milli as u64)
+                .div_ceil(1_000)
+                .min(MAX_REVIEW_INTERVAL_DAYS as u64) as u16,
+        };
+    } else {
+        next.repetitions = 0;
+        next.interval_days = 1;
+        next.lapses = card.lapses.saturating_add(1);
+    }
+
+    let miss = 5 - quality.min(5) as i32;
+    let ease = card.ease_milli as i32 + 100 - miss * (80 + miss * 20);
+    next.ease_milli = ease.clamp(MIN_EASE_MILLI as i32, u16::MAX as i32) as u16;
+    next.due_day = today + next.interval_days as u32;
+    next.last_review_day = today;
+    next
+}
+
+/// Count a practice attempt as a review of a passed unit, if one is due
+///
+/// Early practice leaves the schedule alone. Units passed before scheduling
+/// existed have no card yet and are treated as due on their first practice.
+/// A successful due review pays REVIEW_REWARD_PERCENT of the quiz reward,
+/// clamped to the same token limits as quiz rewards.
+pub fn review_from_practice(user: Principal, unit_id: &str, correct: u64, total: u64) -> Option<ReviewOutcome> {
+    let today = get_current_day() as u32;
+    let key = UserQuizKey { user, unit_id: unit_id.to_string() };
+    let card = match REVIEW_CARDS.with(|r| r.borrow().get(&key)) {
+        Some(card) => card,
+        None if COMPLETED_QUIZZES.with(|q| q.borrow().contains_key(&key)) => new_review_card(today, today),
+        None => return None,
+    };
+    if card.due_day > today {
+        return None;
+    }
+
+    let quality = review_quality(correct, total);
+    let next = sm2_step(card, quality, today);
+    REVIEW_CARDS.with(|r| r.borrow_mut().insert(key, next));
+
+    let config = TOKEN_LIMITS_CONFIG.with(|c| c.borrow().get().clone());
+    let reward = if quality >= REVIEW_PASS_QUALITY && config.version > 0 {
+        credit_limited_bonus(user, unit_reward_amount(config.reward_amount, unit_id) * REVIEW_REWARD_PERCENT / 100, TransactionType::ReviewReward)
+    } else {
+        0
+    };
+
+    Some(ReviewOutcome { quality, card: next, reward })
+}
+
+/// Passed units due for review today (most overdue first)
+pub fn due_reviews(user: Principal) -> Vec<DueReview> {
+    let today = get_current_day() as u32;
+    let mut due: Vec<DueReview> = REVIEW_CARDS.with(|r| {
+        user_quiz_entries(&r.borrow(), user)
+            .filter(|(_, card)| card.due_day <= today)
+            .map(|(k, card)| DueReview { unit_id: k.unit_id, card, overdue_days: today - card.due_day })
+            .collect()
+    });
+    due.sort_by_key(|d| std::cmp::Reverse(d.overdue_days));
+    due.truncate(MAX_DUE_REVIEWS);
+    due
+}
+
+/// All review cards of a user, by unit
+pub fn user_review_cards(user: Principal) -> Vec<(String, ReviewCard)> {
+    REVIEW_CARDS.with(|r| {
+        user_quiz_entries(&r.borrow(), user)
+            .map(|(k, card)| (k.unit_id, card))
+            .collect()
+    })
+}
+
+// ============================================================================
+// UNIT REWARDS & BOUNTIES
+// ============================================================================
+
+/// Reward rule of a unit as last pushed by the hub
+pub fn unit_reward_rule(unit_id: &str) -> UnitRewardRule {
+    UNIT_REWARD_RULES.with(|r| r.borrow().get(&unit_id.to_string())).unwrap_or_default()
+}
+
+/// Scale a base reward by the unit's governance-set multiplier
+pub fn unit_reward_amount(base: u64, unit_id: &str) -> u64 {
+    let multiplier_bps = unit_reward_rule(unit_id).multiplier_bps;
+    (base as u128 * multiplier_bps as u128 / DEFAULT_REWARD_MULTIPLIER_BPS as u128) as u64
+}
+
+/// Store rules pushed by the hub; default rules are dropped
+pub fn apply_unit_reward_rules(rules: Vec<(String, UnitRewardRule)>) {
+    UNIT_REWARD_RULES.with(|r| {
+        let mut map = r.borrow_mut();
+        for (unit_id, rule) in rules {
+            if rule == UnitRewardRule::default() {
+                map.remove(&unit_id);
+            } else {
+                map.insert(unit_id, rule);
+            }
+        }
+    });
+}
+
+/// Ask the hub to pay any bounties covering a unit the user just passed
+///
+/// The claim is recorded first and only cleared once the hub answers, so a
+/// failed call is retried by retry_bounty_claims. The hub is asked whatever
+/// the cached rule says (a new bounty may not have been pushed here yet); it
+/// pays each (bounty, user, unit) once and credits this shard through
+/// internal_credit_bounty.
+pub fn spawn_bounty_claim(user: Principal, unit_id: &str) {
+    let key = UserQuizKey { user, unit_id: unit_id.to_string() };
+    PENDING_BOUNTY_CLAIMS.with(|c| c.borrow_mut().insert(key.clone(), ic_cdk::api::time()));
+    ic_cdk::spawn(send_bounty_claim(key));
+}
+
+/// Send one pending bounty claim, clearing it when the hub answers
+pub async fn send_bounty_claim(key: UserQuizKey) {
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    let result: Result<(Result<u64, String>,), _> = ic_cdk::call(
+        staking_hub_id,
+        "route_bounty_claim",
+        (key.user, key.unit_id.clone())
+    ).await;
+    if let Ok((Ok(_),)) = result {
+        PENDING_BOUNTY_CLAIMS.with(|c| c.borrow_mut().remove(&key));
+    }
+}
+
+/// Resend a batch of pending bounty claims (timer callback)
+///
+/// Walks the queue from a cursor so claims the hub keeps rejecting do not
+/// starve the rest; claims older than BOUNTY_CLAIM_EXPIRY_NS are dropped.
+/// Returns the number of claims resent.
+pub fn retry_bounty_claims() -> usize {
+    let now = ic_cdk::api::time();
+    let cursor = BOUNTY_CLAIM_CURSOR.with(|c| c.borrow().clone());
+    let batch: Vec<(UserQuizKey, u64)> = PENDING_BOUNTY_CLAIMS.with(|c| {
+        let map = c.borrow();
+        match cursor {
+            Some(cursor) => map.range((std::ops::Bound::Excluded(cursor), std::ops::Bound::Unbounded))
+                .take(BOUNTY_CLAIMS_RETRIED_PER_SWEEP)
+                .collect(),
+            None => map.iter().take(BOUNTY_CLAIMS_RETRIED_PER_SWEEP).collect(),
+        }
+    });
+
+    let next = if batch.len() < BOUNTY_CLAIMS_RETRIED_PER_SWEEP {
+        None
+    } else {
+        batch.last().map(|(key, _)| key.clone())
+    };
+    BOUNTY_CLAIM_CURSOR.with(|c| *c.borrow_mut() = next);
+
+    let mut resent = 0;
+    for (key, queued_at) in batch {
+        let age = now.saturating_sub(queued_at);
+        if age >= BOUNTY_CLAIM_EXPIRY_NS {
+            PENDING_BOUNTY_CLAIMS.with(|c| c.borrow_mut().remove(&key));
+        } else if age >= BOUNTY_CLAIM_RETRY_AFTER_NS {
+            ic_cdk::spawn(send_bounty_claim(key));
+            resent += 1;
+        }
+    }
+    resent
+}
+
+/// Credit a bounty payout routed by the hub
+///
+/// Escrowed tokens already exist, so like a transfer this leaves the minting
+/// allowance, pending stats and token limits untouched.
+pub fn credit_bounty_reward(user: Principal, amount: u64) -> Result<(), String> {
+    if amount == 0 {
+        return Err("Amount must be greater than 0".to_string());
+    }
+    ensure_can_receive(user)?;
+
+    let mut profile = USER_PROFILES.with(|p| p.borrow().get(&user)).ok_or("Recipient not registered")?;
+    apply_transfer_entry(user, &mut profile, amount, TransactionType::BountyReward);
+    USER_PROFILES.with(|p| p.borrow_mut().insert(user, profile));
+    Ok(())
+}
+
+// ============================================================================
+// REFERRALS
+// ============================================================================
+
+/// Pay a referral bonus, clamped to the shard's minting allowance
+///
+/// Referral bonuses are one-off and not counted toward the periodic token
+/// limits, so a referee hitting their daily cap does not forfeit it.
+pub fn credit_referral_bonus(user: Principal, amount: u64) -> u64 {
+    let Some(mut profile) = USER_PROFILES.with(|p| p.borrow().get(&user)) else {
+        return 0;
+    };
+    let bonus = amount.min(MINTING_ALLOWANCE.with(|a| *a.borrow().get()));
+    if bonus == 0 {
+        return 0;
+    }
+
+    apply_bonus_credit(user, &mut profile, bonus, TransactionType::ReferralBonus);
+    USER_PROFILES.with(|p| p.borrow_mut().insert(user, profile));
+    bonus
+}
+
+/// Count a passed quiz toward the user's referral and start settlement once
+/// the referee qualifies
+pub fn progress_referral(referee: Principal) {
+    let qualified = REFERRALS.with(|r| {
+        let mut map = r.borrow_mut();
+        let Some(mut record) = map.get(&referee) else {
+            return false;
+        };
+        if record.status != ReferralStatus::Qualifying {
+            return false;
+        }
+
+        record.quizzes_passed += 1;
+        let qualified = record.quizzes_passed >= REFERRAL_QUALIFYING_QUIZZES;
+        if qualified {
+            // Guard against concurrent settlements from back-to-back quizzes
+            record.status = ReferralStatus::Processing;
+        }
+        map.insert(referee, record);
+        qualified
+    });
+
+    if qualified {
+        ic_cdk::spawn(async move {
+            settle_referral(referee).await;
+        });
+    }
+}
+
+/// Ask the referrer's shard (via the hub) to pay the referrer, then pay the referee
+///
+/// Any failure, including a cap refusal by the referrer's shard, leaves the
+/// referral Pending; retry_referral_settlements tries again later and
+/// rejects it once REFERRAL_SETTLEMENT_EXPIRY_NS has passed.
+pub async fn settle_referral(referee: Principal) {
+    let Some(record) = REFERRALS.with(|r| r.borrow().get(&referee)) else {
+        return;
+    };
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+
+    let result: Result<(Result<u64, String>,), _> = ic_cdk::call(
+        staking_hub_id,
+        "route_referral_reward",
+        (record.referrer, referee)
+    ).await;
+
+    // Re-read: the referee may have been erased or moved meanwhile
+    let Some(mut record) = REFERRALS.with(|r| r.borrow().get(&referee)) else {
+        PENDING_REFERRALS.with(|p| p.borrow_mut().remove(&referee));
+        return;
+    };
+    let now = ic_cdk::api::time();
+    match result {
+        Ok((Ok(referrer_bonus),)) => {
+            record.referrer_bonus = referrer_bonus;
+            record.referee_bonus = credit_referral_bonus(referee, REFERRAL_BONUS_AMOUNT);
+            record.status = ReferralStatus::Rewarded;
+            PENDING_REFERRALS.with(|p| p.borrow_mut().remove(&referee));
+        }
+        Ok((Err(_),)) | Err(_) => {
+            record.status = ReferralStatus::Pending;
+            PENDING_REFERRALS.with(|p| {
+                let mut map = p.borrow_mut();
+                let queued_at = map.get(&referee).map(|(queued_at, _)| queued_at).unwrap_or(now);
+                map.insert(referee, (queued_at, now + REFERRAL_SETTLEMENT_RETRY_NS));
+            });
+        }
+    }
+    REFERRALS.with(|r| r.borrow_mut().insert(referee, record));
+}
+
+/// Retry due Pending referrals and reject expired ones (timer callback)
+///
+/// Walks the queue from a cursor, REFERRAL_SETTLEMENTS_PER_SWEEP entries at a
+/// time. Returns the number of settlements started.
+pub fn retry_referral_settlements() -> usize {
+    let now = ic_cdk::api::time();
+    let cursor = REFERRAL_SWEEP_CURSOR.with(|c| *c.borrow());
+    let batch: Vec<(Principal, (u64, u64))> = PENDING_REFERRALS.with(|p| {
+        let map = p.borrow();
+        match cursor {
+            Some(cursor) => map.range((std::ops::Bound::Excluded(cursor), std::ops::Bound::Unbounded))
+                .take(REFERRAL_SETTLEMENTS_PER_SWEEP)
+                .collect(),
+            None => map.iter().take(REFERRAL_SETTLEMENTS_PER_SWEEP).collect(),
+        }
+    });
+
+    let next = if batch.len() < REFERRAL_SETTLEMENTS_PER_SWEEP {
+        None
+    } else {
+        batch.last().map(|(referee, _)| *referee)
+    };
+    REFERRAL_SWEEP_CURSOR.with(|c| *c.borrow_mut() = next);
+
+    let mut started = 0;
+    for (referee, (queued_at, next_attempt_at)) in batch {
+        if now < next_attempt_at {
+            continue;
+        }
+        let expired = now.saturating_sub(queued_at) >= REFERRAL_SETTLEMENT_EXPIRY_NS;
+        let due = REFERRALS.with(|r| {
+            let mut map = r.borrow_mut();
+            let Some(mut record) = map.get(&referee) else {
+                return false;
+            };
+            if record.status != ReferralStatus::Pending {
+                return false;
+            }
+            record.status = if expired { ReferralStatus::Rejected } else { ReferralStatus::Processing };
+            map.insert(referee, record);
+            !expired
+        });
+        if due {
+            // Push the next attempt out so a slow call is not started twice
+            PENDING_REFERRALS.with(|p| p.borrow_mut().insert(referee, (queued_at, now + REFERRAL_SETTLEMENT_RETRY_NS)));
+            ic_cdk::spawn(settle_referral(referee));
+            started += 1;
+        } else {
+            PENDING_REFERRALS.with(|p| p.borrow_mut().remove(&referee));
+        }
+    }
+    started
+}
+
+/// Pay the referrer side of a qualified referral, enforcing per-referrer caps
+pub fn credit_referrer(referrer: Principal) -> Result<u64, String> {
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&referrer)) {
+        return Err("Referrer not registered".to_string());
+    }
+    if is_erased(referrer) {
+        return Err("Referrer account has been erased".to_string());
+    }
+
+    let today = get_current_day();
+    let mut stats = REFERRER_STATS.with(|s| s.borrow().get(&referrer)).unwrap_or_default();
+    if stats.last_reward_day != today {
+        stats.daily_rewarded = 0;
+        stats.last_reward_day = today;
+    }
+
+    if stats.rewarded_count >= MAX_REFERRAL_REWARDS_PER_REFERRER {
+        return Err("Referrer has reached the lifetime referral reward cap".to_string());
+    }
+    if stats.daily_rewarded >= MAX_REFERRAL_REWARDS_PER_DAY {
+        return Err("Referrer has reached the daily referral reward cap".to_string());
+    }
+
+    let bonus = credit_referral_bonus(referrer, REFERRAL_BONUS_AMOUNT);
+    if bonus == 0 {
+        // Nothing was paid (allowance exhausted), so no cap is used up; the
+        // referee's shard retries later
+        return Err("Referral bonus unavailable: minting allowance exhausted".to_string());
+    }
+    stats.rewarded_count += 1;
+    stats.daily_rewarded += 1;
+    stats.total_earned += bonus;
+    REFERRER_STATS.with(|s| s.borrow_mut().insert(referrer, stats));
+
+    Ok(bonus)
+}
+
+/// Point referrals made by a recovered account at its new principal
+///
+/// Referral records are keyed by referee, so every record on the shard is
+/// checked. Returns the number of records updated.
+pub fn rekey_referrer(old_referrer: Principal, new_referrer: Principal) -> u64 {
+    REFERRALS.with(|r| {
+        let mut map = r.borrow_mut();
+        let moved: Vec<(Principal, ReferralRecord)> = map.iter()
+            .filter(|(_, record)| record.referrer == old_referrer)
+            .collect();
+        let count = moved.len() as u64;
+        for (referee, mut record) in moved {
+            record.referrer = new_referrer;
+            map.insert(referee, record);
+        }
+        count
+    })
+}
+
+/// Stable deterministic hash for answer verification (must match Learning Engine)
+pub fn stable_hash(data: &[u8]) -> [u8; 32] {
+    let mut hash: u64 = 5381;
+    for b in data {
+        hash = ((hash << 5).wrapping_add(hash)).wrapping_add(*b as u64);
+    }
+    let b = hash.to_le_bytes();
+    let mut res = [0u8; 32];
+    for i in 0..8 {
+        res[i] = b[i];
+    }
+    // Fill the rest with a pattern to make it 32 bytes
+    for i in 8..32 {
+        res[i] = res[i % 8];
+    }
+    res
+}
+
+// ============================================================================
+// ACCOUNT RECOVERY
+// ============================================================================
+
+/// True while an account is being migrated to a new principal
+pub fn is_recovery_executing(user: Principal) -> bool {
+    PENDING_RECOVERIES.with(|r| {
+        r.borrow().get(&user).is_some_and(|req| req.status == RecoveryStatus::Executing)
+    })
+}
+
+/// Move the user's archived entries to the new principal on every archive in the chain
+async fn rekey_archive_chain(old_user: Principal, new_user: Principal) -> Result<(), String> {
+    let mut current = ARCHIVE_CANISTER_ID.with(|id| *id.borrow().get());
+    let mut depth = 0;
+
+    while current != Principal::anonymous() && depth < MAX_ARCHIVE_CHAIN_DEPTH {
+        for _ in 0..MAX_ARCHIVE_REKEY_ROUNDS {
+            let result: Result<(Result<u64, String>,), _> = ic_cdk::call(
+                current,
+                "rekey_user",
+                (old_user, new_user)
+            ).await;
+            match result {
+                Ok((Ok(0),)) => break,
+                Ok((Ok(_),)) => continue,
+                Ok((Err(msg),)) => return Err(format!("Archive rejected rekey: {}", msg)),
+                Err((code, msg)) => return Err(format!("Archive call failed: {:?} {}", code, msg)),
+            }
+        }
+
+        let (stats,): (ArchiveStats,) = ic_cdk::call(current, "get_stats", ())
+            .await
+            .map_err(|(code, msg)| format!("Archive call failed: {:?} {}", code, msg))?;
+        current = stats.next_archive.unwrap_or(Principal::anonymous());
+        depth += 1;
+    }
+
+    Ok(())
+}
+
+/// Re-key every piece of per-user shard state from `old_user` to `new_user`
+///
+/// Runs synchronously so no message can observe a half-migrated account.
+pub fn rekey_user_state(old_user: Principal, new_user: Principal) {
+    let Some(profile) = USER_PROFILES.with(|p| p.borrow_mut().remove(&old_user)) else {
+        return;
+    };
+
+    // Local transactions (archived ones are re-keyed on the archive chain)
+    USER_TRANSACTIONS.with(|t| {
+        let mut map = t.borrow_mut();
+        for index in profile.archived_transaction_count..profile.transaction_count {
+            if let Some(tx) = map.remove(&TransactionKey { user: old_user, index }) {
+                map.insert(TransactionKey { user: new_user, index }, tx);
+            }
+        }
+    });
+    USER_PROFILES.with(|p| p.borrow_mut().insert(new_user, profile));
+
+    COMPLETED_QUIZZES.with(|q| rekey_user_quiz_entries(&mut q.borrow_mut(), old_user, new_user));
+
+    if let Some(stats) = USER_TIME_STATS.with(|s| s.borrow_mut().remove(&old_user)) {
+        USER_TIME_STATS.with(|s| s.borrow_mut().insert(new_user, stats));
+    }
+    if let Some(usage) = ROLLING_USAGE.with(|r| r.borrow_mut().remove(&old_user)) {
+        ROLLING_USAGE.with(|r| r.borrow_mut().insert(new_user, usage));
+    }
+    if let Some(streak) = USER_STREAKS.with(|s| s.borrow_mut().remove(&old_user)) {
+        USER_STREAKS.with(|s| s.borrow_mut().insert(new_user, streak));
+    }
+    if let Some(badges) = USER_BADGES.with(|b| b.borrow_mut().remove(&old_user)) {
+        USER_BADGES.with(|b| b.borrow_mut().insert(new_user, badges));
+    }
+    if let Some(referral) = REFERRALS.with(|r| r.borrow_mut().remove(&old_user)) {
+        REFERRALS.with(|r| r.borrow_mut().insert(new_user, referral));
+    }
+    if let Some(pending) = PENDING_REFERRALS.with(|p| p.borrow_mut().remove(&old_user)) {
+        PENDING_REFERRALS.with(|p| p.borrow_mut().insert(new_user, pending));
+    }
+    if let Some(stats) = REFERRER_STATS.with(|s| s.borrow_mut().remove(&old_user)) {
+        REFERRER_STATS.with(|s| s.borrow_mut().insert(new_user, stats));
+    }
+    if let Some(config) = RECOVERY_CONFIGS.with(|c| c.borrow_mut().remove(&old_user)) {
+        RECOVERY_CONFIGS.with(|c| c.borrow_mut().insert(new_user, config));
+    }
+
+    // Linked principals do not follow the account: the old key may have been
+    // compromised, so anything it linked is dropped (hub entries are removed
+    // by finalize_recovery_internal before this runs)
+    if let Some(linked) = LINKED_OF_CANONICAL.with(|l| l.borrow_mut().remove(&old_user)) {
+        PRINCIPAL_LINKS.with(|l| {
+            let mut map = l.borrow_mut();
+            for link in linked.0 {
+                map.remove(&link.principal);
+            }
+        });
+    }
+    LINK_CHALLENGES.with(|c| c.borrow_mut().remove(&old_user));
+
+    if let Some(record) = ERASURE_RECORDS.with(|e| e.borrow_mut().remove(&old_user)) {
+        ERASURE_RECORDS.with(|e| e.borrow_mut().insert(new_user, record));
+    }
+    if let Some(period) = USER_SUBSCRIPTIONS.with(|s| s.borrow_mut().remove(&old_user)) {
+        USER_SUBSCRIPTIONS.with(|s| s.borrow_mut().insert(new_user, period));
+    }
+    PENDING_BOUNTY_CLAIMS.with(|c| rekey_user_quiz_entries(&mut c.borrow_mut(), old_user, new_user));
+    APPLIED_EXTENSIONS.with(|a| {
+        let mut map = a.borrow_mut();
+        let entries: Vec<(UserExtensionKey, u64)> = map
+            .range(UserExtensionKey { user: old_user, extension_id: String::new() }..)
+            .take_while(|(k, _)| k.user == old_user)
+            .collect();
+        for (key, applied_at) in entries {
+            map.remove(&key);
+            map.insert(UserExtensionKey { user: new_user, extension_id: key.extension_id }, applied_at);
+        }
+    });
+
+    // Quiz scores and the certificate index follow the account; issued
+    // certificates keep naming the principal they were signed for
+    QUIZ_SCORES.with(|q| rekey_user_quiz_entries(&mut q.borrow_mut(), old_user, new_user));
+    REVIEW_CARDS.with(|r| rekey_user_quiz_entries(&mut r.borrow_mut(), old_user, new_user));
+    PRACTICE_RECORDS.with(|p| rekey_user_quiz_entries(&mut p.borrow_mut(), old_user, new_user));
+    USER_CERTIFICATES.with(|c| {
+        let mut map = c.borrow_mut();
+        let entries: Vec<(UserCertificateKey, String)> = map
+            .range(UserCertificateKey { user: old_user, course_id: String::new() }..)
+            .take_while(|(k, _)| k.user == old_user)
+            .collect();
+        for (key, id) in entries {
+            map.remove(&key);
+            map.insert(UserCertificateKey { user: new_user, course_id: key.course_id }, id);
+        }
+    });
+
+    rekey_user_notifications(old_user, new_user);
+
+    // Outgoing transfer records (all settled: recovery waits for pending ones)
+    OUTGOING_TRANSFERS.with(|t| {
+        let mut map = t.borrow_mut();
+        let entries: Vec<(UserTransferKey, StakedTransfer)> = map
+            .range(UserTransferKey { user: old_user, idempotency_key: String::new() }..)
+            .take_while(|(k, _)| k.user == old_user)
+            .collect();
+        for (key, transfer) in entries {
+            map.remove(&key);
+            map.insert(UserTransferKey { user: new_user, idempotency_key: key.idempotency_key }, transfer);
+        }
+    });
+}
+
+/// Execute an approved, time-locked recovery
+///
+/// Order matters for retries: the archive re-key and hub update are both
+/// idempotent, and the local re-key only runs once both have succeeded.
+pub async fn finalize_recovery_internal(account: Principal) -> Result<Principal, String> {
+    let now = ic_cdk::api::time();
+    let mut request = PENDING_RECOVERIES.with(|r| r.borrow().get(&account))
+        .ok_or("No pending recovery for this account")?;
+
+    if request.status == RecoveryStatus::Executing {
+        return Err("Recovery is already being executed".to_string());
+    }
+    if now < request.executable_at {
+        return Err(format!("Recovery is time-locked for another {} seconds", (request.executable_at - now) / 1_000_000_000));
+    }
+    let config = RECOVERY_CONFIGS.with(|c| c.borrow().get(&account))
+        .ok_or("Account has no recovery guardians")?;
+    let valid_approvals = request.approvals.iter().filter(|a| config.guardians.contains(a)).count();
+    if valid_approvals < config.threshold as usize {
+        return Err(format!("Recovery needs {} guardian approvals, has {}", config.threshold, valid_approvals));
+    }
+    if USER_PROFILES.with(|p| p.borrow().contains_key(&request.new_principal)) {
+        return Err("New principal is already registered on this shard".to_string());
+    }
+    if is_linked_principal(request.new_principal) {
+        return Err("New principal is linked to an existing account".to_string());
+    }
+    if has_unsettled_transfers(account) {
+        return Err("Account has transfers in flight; retry them before finalizing".to_string());
+    }
+
+    request.status = RecoveryStatus::Executing;
+    PENDING_RECOVERIES.with(|r| r.borrow_mut().insert(account, request.clone()));
+    let new_principal = request.new_principal;
+
+    let result = async {
+        rekey_archive_chain(account, new_principal).await?;
+
+        let linked = LINKED_OF_CANONICAL.with(|l| l.borrow().get(&account)).unwrap_or_default();
+        for link in linked.0 {
+            unregister_linked_principal_at_hub(link.principal).await?;
+        }
+
+        let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+        let hub_result: Result<(Result<(), String>,), _> = ic_cdk::call(
+            staking_hub_id,
+            "migrate_user_location",
+            (account, new_principal)
+        ).await;
+        match hub_result {
+            Ok((Ok(()),)) => Ok(()),
+            Ok((Err(msg),)) => Err(format!("Hub rejected migration: {}", msg)),
+            Err((code, msg)) => Err(format!("Hub call failed: {:?} {}", code, msg)),
+        }
+    }.await;
+
+    if let Err(e) = result {
+        // Back to Pending so the recovery can be retried (or cancelled)
+        request.status = RecoveryStatus::Pending;
+        PENDING_RECOVERIES.with(|r| r.borrow_mut().insert(account, request));
+        return Err(e);
+    }
+
+    rekey_user_state(account, new_principal);
+    PENDING_RECOVERIES.with(|r| r.borrow_mut().remove(&account));
+    // Soulbound credentials cannot be transferred: burn and re-mint
+    spawn_credential_migration(account, new_principal);
+
+    Ok(new_principal)
+}
+
+// ============================================================================
+// COURSE PROGRESS
+// ============================================================================
+
+/// Every unit the user has passed (their keys are contiguous)
+pub fn completed_units(user: Principal) -> Vec<String> {
+    COMPLETED_QUIZZES.with(|q| {
+        user_quiz_keys(&q.borrow(), user).into_iter().map(|k| k.unit_id).collect()
+    })
+}
+
+/// Prerequisite units of a quiz the user has not passed yet
+pub fn missing_prerequisites(user: Principal, cache: &QuizCacheData) -> Vec<String> {
+    cache.prerequisites.iter().flatten()
+        .filter(|unit_id| !COMPLETED_QUIZZES.with(|q| q.borrow().contains_key(&UserQuizKey { user, unit_id: (*unit_id).clone() })))
+        .cloned()
+        .collect()
+}
+
+/// Refuse a quiz whose prerequisites are not met
+pub fn check_prerequisites(user: Principal, cache: &QuizCacheData) -> Result<(), String> {
+    let missing = missing_prerequisites(user, cache);
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let shown: Vec<&str> = missing.iter().take(MAX_LISTED_PREREQUISITES).map(|id| id.as_str()).collect();
+    let more = missing.len() - shown.len();
+    Err(if more > 0 {
+        format!("Prerequisites not met: pass {} and {} more first", shown.join(", "), more)
+    } else {
+        format!("Prerequisites not met: pass {} first", shown.join(", "))
+    })
+}
+
+/// Aggregate a user's completed quizzes over a content outline
+///
+/// The user's completions are read once (their keys are contiguous), then
+/// totals are rolled up from the leaves: the outline lists parents before
+/// children, so walking it backwards visits every child before its parent.
+pub fn compute_course_progress(user: Principal, root_id: String, outline: Vec<ContentOutlineNode>) -> CourseProgress {
+    let completed: std::collections::HashSet<String> = completed_units(user).into_iter().collect();
+
+    let position: std::collections::HashMap<String, usize> = outline.iter()
+        .enumerate()
+        .map(|(i, node)| (node.id.clone(), i))
+        .collect();
+
+    let mut totals = vec![0u64; outline.len()];
+    let mut done = vec![0u64; outline.len()];
+    for (i, node) in outline.iter().enumerate().rev() {
+        if node.has_quiz {
+            totals[i] += 1;
+            if completed.contains(&node.id) {
+                done[i] += 1;
+            }
+        }
+        if i == 0 {
+            continue;
+        }
+        if let Some(&parent) = node.parent_id.as_ref().and_then(|p| position.get(p)) {
+            totals[parent] += totals[i];
+            done[parent] += done[i];
+        }
+    }
+
+    let nodes = outline.into_iter()
+        .enumerate()
+        .map(|(i, node)| NodeProgress {
+            completed: node.has_quiz.then(|| completed.contains(&node.id)),
+            completed_quizzes: done[i],
+            total_quizzes: totals[i],
+            percent: (done[i] * 100).checked_div(totals[i]).unwrap_or(0) as u8,
+            id: node.id,
+            parent_id: node.parent_id,
+            order: node.order,
+            display_type: node.display_type,
+            title: node.title,
+            has_quiz: node.has_quiz,
+        })
+        .collect();
+
+    CourseProgress { root_id, nodes }
+}
+
+// ============================================================================
+// LINKED PRINCIPALS
+// ============================================================================
+
+/// Resolve a principal to the canonical principal that owns its profile
+/// (unlinked principals resolve to themselves)
+pub fn resolve_account(principal: Principal) -> Principal {
+    PRINCIPAL_LINKS.with(|l| l.borrow().get(&principal)).unwrap_or(principal)
+}
+
+pub fn is_linked_principal(principal: Principal) -> bool {
+    PRINCIPAL_LINKS.with(|l| l.borrow().contains_key(&principal))
+}
+
+/// Generate a hex-encoded 32-byte link token from the management canister's randomness
+pub async fn generate_link_token() -> Result<String, String> {
+    let (bytes,) = ic_cdk::api::management_canister::main::raw_rand().await
+        .map_err(|(code, msg)| format!("Randomness call failed: {:?} {}", code, msg))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Remove a secondary principal from the hub registry
+pub async fn unregister_linked_principal_at_hub(secondary: Principal) -> Result<(), String> {
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    let result: Result<(Result<(), String>,), _> = ic_cdk::call(
+        staking_hub_id,
+        "unregister_linked_principal",
+        (secondary,)
+    ).await;
+    match result {
+        Ok((Ok(()),)) => Ok(()),
+        Ok((Err(msg),)) => Err(format!("Hub rejected unlink: {}", msg)),
+        Err((code, msg)) => Err(format!("Hub call failed: {:?} {}", code, msg)),
+    }
+}
+
+/// Drop a secondary principal from the local link tables
+pub fn remove_local_link(canonical: Principal, secondary: Principal) {
+    PRINCIPAL_LINKS.with(|l| l.borrow_mut().remove(&secondary));
+    LINKED_OF_CANONICAL.with(|l| {
+        let mut map = l.borrow_mut();
+        let mut list = map.get(&canonical).unwrap_or_default();
+        list.0.retain(|p| p.principal != secondary);
+        if list.0.is_empty() {
+            map.remove(&canonical);
+        } else {
+            map.insert(canonical, list);
+        }
+    });
+}
+
+// ============================================================================
+// DATA EXPORT & ERASURE
+// ============================================================================
+
+pub fn is_erased(user: Principal) -> bool {
+    ERASURE_RECORDS.with(|e| e.borrow().contains_key(&user))
+}
+
+/// Assemble everything held about `user` on this shard and its archive chain,
+/// plus their verification record from the KYC manager
+///
+/// Archived transactions are collected newest first; if there are more than
+/// MAX_EXPORT_ARCHIVED_TRANSACTIONS the oldest are left out and
+/// `history_cursor` points get_transaction_history at them.
+pub async fn export_user_data_internal(user: Principal) -> Result<UserDataExport, String> {
+    let profile = USER_PROFILES.with(|p| p.borrow().get(&user)).ok_or("User not registered")?;
+
+    let mut transactions: Vec<TransactionHistoryEntry> = USER_TRANSACTIONS.with(|t| {
+        let map = t.borrow();
+        (profile.archived_transaction_count..profile.transaction_count)
+            .filter_map(|sequence| {
+                map.get(&TransactionKey { user, index: sequence }).map(|tx| TransactionHistoryEntry {
+                    sequence,
+                    timestamp: tx.timestamp,
+                    tx_type: tx.tx_type,
+                    amount: tx.amount,
+                    archived: false,
+                })
+            })
+            .collect()
+    });
+
+    let archive_id = ARCHIVE_CANISTER_ID.with(|id| *id.borrow().get());
+    let mut cursor = profile.archived_transaction_count;
+    let mut exported: u64 = 0;
+    if archive_id != Principal::anonymous() {
+        while cursor > 0 && exported < MAX_EXPORT_ARCHIVED_TRANSACTIONS {
+            let chunk_start = cursor.saturating_sub(ARCHIVE_FETCH_CHUNK);
+            for tx in fetch_archived_range(user, archive_id, chunk_start, cursor).await? {
+                if let Some(tx_type) = TransactionType::from_archive_label(&tx.transaction_type) {
+                    transactions.push(TransactionHistoryEntry {
+                        sequence: tx.sequence,
+                        timestamp: tx.timestamp,
+                        tx_type,
+                        amount: tx.amount,
+                        archived: true,
+                    });
+                }
+            }
+            exported += cursor - chunk_start;
+            cursor = chunk_start;
+        }
+    }
+    transactions.sort_by_key(|tx| tx.sequence);
+
+    let completed_quizzes = completed_units(user);
+    let streak = USER_STREAKS.with(|s| s.borrow().get(&user)).unwrap_or_default();
+
+    let kyc_manager_id = KYC_MANAGER_ID.with(|id| *id.borrow().get());
+    let kyc = if kyc_manager_id == Principal::anonymous() {
+        None
+    } else {
+        let (kyc,): (Option<KycStatus>,) = ic_cdk::call(kyc_manager_id, "get_user_kyc_status", (user,))
+            .await
+            .map_err(|(code, msg)| format!("KYC manager call failed: {:?} {}", code, msg))?;
+        kyc
+    };
+
+    Ok(UserDataExport {
+        user,
+        exported_at: ic_cdk::api::time(),
+        profile,
+        kyc,
+        time_stats: USER_TIME_STATS.with(|s| s.borrow().get(&user)),
+        rolling_usage: ROLLING_USAGE.with(|r| r.borrow().get(&user)),
+        completed_quizzes,
+        transactions,
+        history_cursor: if cursor > 0 && archive_id != Principal::anonymous() { Some(cursor) } else { None },
+        achievements: UserAchievements {
+            current_streak: effective_current_streak(&streak, get_current_day()),
+            longest_streak: streak.longest_streak,
+            total_quizzes_passed: streak.total_quizzes_passed,
+            badges: USER_BADGES.with(|b| b.borrow().get(&user)).unwrap_or_default().0,
+        },
+        referral: ReferralInfo {
+            referred_by: REFERRALS.with(|r| r.borrow().get(&user)),
+            referrer_stats: REFERRER_STATS.with(|s| s.borrow().get(&user)).unwrap_or_default(),
+        },
+        linked_principals: LINKED_OF_CANONICAL.with(|l| l.borrow().get(&user)).unwrap_or_default().0,
+        recovery: RecoveryInfo {
+            config: RECOVERY_CONFIGS.with(|c| c.borrow().get(&user)),
+            pending: PENDING_RECOVERIES.with(|r| r.borrow().get(&user)),
+        },
+        subscription: USER_SUBSCRIPTIONS.with(|s| s.borrow().get(&user)),
+        subscription_extensions: APPLIED_EXTENSIONS.with(|a| {
+            a.borrow()
+                .range(UserExtensionKey { user, extension_id: String::new() }..)
+                .take_while(|(k, _)| k.user == user)
+                .map(|(k, _)| k.extension_id)
+                .collect()
+        }),
+        quiz_scores: QUIZ_SCORES.with(|q| {
+            user_quiz_entries(&q.borrow(), user)
+                .map(|(k, score)| (k.unit_id, score))
+                .collect()
+        }),
+        practice_records: user_practice_records(user),
+        review_cards: user_review_cards(user),
+        certificates: user_certificates(user),
+        outgoing_transfers: user_outgoing_transfers(user),
+        notifications: user_notifications(user),
+        notification_preferences: NOTIFICATION_PREFERENCES.with(|p| p.borrow().get(&user)).unwrap_or_default(),
+        erasure: ERASURE_RECORDS.with(|e| e.borrow().get(&user)),
+    })
+}
+
+/// Ask every archive in the chain to redact the user's metadata
+async fn redact_archive_chain(user: Principal) -> Result<(), String> {
+    let mut current = ARCHIVE_CANISTER_ID.with(|id| *id.borrow().get());
+    let mut depth = 0;
+
+    while current != Principal::anonymous() && depth < MAX_ARCHIVE_CHAIN_DEPTH {
+        for _ in 0..MAX_ARCHIVE_REKEY_ROUNDS {
+            let result: Result<(Result<u64, String>,), _> = ic_cdk::call(
+                current,
+                "redact_user",
+                (user,)
+            ).await;
+            match result {
+                Ok((Ok(0),)) => break,
+                Ok((Ok(_),)) => continue,
+                Ok((Err(msg),)) => return Err(format!("Archive rejected redaction: {}", msg)),
+                Err((code, msg)) => return Err(format!("Archive call failed: {:?} {}", code, msg)),
+            }
+        }
+
+        let (stats,): (ArchiveStats,) = ic_cdk::call(current, "get_stats", ())
+            .await
+            .map_err(|(code, msg)| format!("Archive call failed: {:?} {}", code, msg))?;
+        current = stats.next_archive.unwrap_or(Principal::anonymous());
+        depth += 1;
+    }
+
+    Ok(())
+}
+
+/// Wipe a user's personal data and leave a tombstone
+///
+/// The profile stays (PII cleared) together with its balance and transaction
+/// amounts, which supply accounting depends on. Idempotent: calling it again
+/// retries whatever did not complete (linked principals, credential burns,
+/// archive redaction).
+pub async fn erase_user_data_internal(user: Principal) -> Result<ErasureRecord, String> {
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&user)) {
+        return Err("User not registered".to_string());
+    }
+    if is_recovery_executing(user) {
+        return Err("Account is being migrated to a new principal".to_string());
+    }
+
+    // 1. Linked principals (hub first, so a failure leaves them intact)
+    let linked = LINKED_OF_CANONICAL.with(|l| l.borrow().get(&user)).unwrap_or_default();
+    for link in linked.0 {
+        unregister_linked_principal_at_hub(link.principal).await?;
+        remove_local_link(user, link.principal);
+    }
+
+    // 2. Local wipe (re-read the profile after the awaits)
+    let mut profile = USER_PROFILES.with(|p| p.borrow().get(&user)).ok_or("User not registered")?;
+    profile.email = String::new();
+    profile.name = String::new();
+    profile.education = String::new();
+    profile.gender = String::new();
+    profile.verification_tier = VerificationTier::None;
+    profile.is_subscribed = false;
+    USER_PROFILES.with(|p| p.borrow_mut().insert(user, profile));
+
+    let mut record = ERASURE_RECORDS.with(|e| e.borrow().get(&user)).unwrap_or(ErasureRecord {
+        erased_at: ic_cdk::api::time(),
+        archive_redacted: false,
+    });
+    ERASURE_RECORDS.with(|e| e.borrow_mut().insert(user, record.clone()));
+
+    COMPLETED_QUIZZES.with(|q| remove_user_quiz_entries(&mut q.borrow_mut(), user));
+    USER_TIME_STATS.with(|s| s.borrow_mut().remove(&user));
+    ROLLING_USAGE.with(|r| r.borrow_mut().remove(&user));
+    USER_STREAKS.with(|s| s.borrow_mut().remove(&user));
+    USER_BADGES.with(|b| b.borrow_mut().remove(&user));
+    REFERRALS.with(|r| r.borrow_mut().remove(&user));
+    PENDING_REFERRALS.with(|p| p.borrow_mut().remove(&user));
+    REFERRER_STATS.with(|s| s.borrow_mut().remove(&user));
+    RECOVERY_CONFIGS.with(|c| c.borrow_mut().remove(&user));
+    PENDING_RECOVERIES.with(|r| r.borrow_mut().remove(&user));
+    LINK_CHALLENGES.with(|c| c.borrow_mut().remove(&user));
+    USER_SUBSCRIPTIONS.with(|s| s.borrow_mut().remove(&user));
+    PENDING_BOUNTY_CLAIMS.with(|c| remove_user_quiz_entries(&mut c.borrow_mut(), user));
+    APPLIED_EXTENSIONS.with(|a| {
+        let mut map = a.borrow_mut();
+        let keys: Vec<UserExtensionKey> = map
+            .range(UserExtensionKey { user, extension_id: String::new() }..)
+            .take_while(|(k, _)| k.user == user)
+            .map(|(k, _)| k)
+            .collect();
+        for key in keys {
+            map.remove(&key);
+        }
+    });
+    remove_user_notifications(user);
+    QUIZ_SCORES.with(|q| remove_user_quiz_entries(&mut q.borrow_mut(), user));
+    REVIEW_CARDS.with(|r| remove_user_quiz_entries(&mut r.borrow_mut(), user));
+    PRACTICE_RECORDS.with(|p| remove_user_quiz_entries(&mut p.borrow_mut(), user));
+    // Certificates are revoked (verification stops returning them)
+    let certificate_keys: Vec<(UserCertificateKey, String)> = USER_CERTIFICATES.with(|c| {
+        c.borrow()
+            .range(UserCertificateKey { user, course_id: String::new() }..)
+            .take_while(|(k, _)| k.user == user)
+            .collect()
+    });
+    for (key, certificate_id) in certificate_keys {
+        CERTIFICATES.with(|c| c.borrow_mut().remove(&certificate_id));
+        USER_CERTIFICATES.with(|c| c.borrow_mut().remove(&key));
+    }
+
+    // 3. Soulbound credentials (burning is idempotent at the collection)
+    revoke_credentials_at_collection(user).await?;
+
+    // 4. Archive chain
+    if !record.archive_redacted {
+        redact_archive_chain(user).await?;
+        record.archive_redacted = true;
+        ERASURE_RECORDS.with(|e| e.borrow_mut().insert(user, record.clone()));
+    }
+
+    Ok(record)
+}
+
+// ============================================================================
+// SUBSCRIPTIONS
+// ============================================================================
+
+/// Whether the user's subscription is active at `now`
+pub fn is_subscription_active(user: Principal, now: u64) -> bool {
+    USER_SUBSCRIPTIONS.with(|s| s.borrow().get(&user)).is_some_and(|period| period.is_active(now))
+}
+
+/// Apply a paid period pushed by the subscription manager
+///
+/// Extensions stack on the current period (or start now if it has lapsed).
+/// Re-pushing the last applied extension is a no-op, so retries are safe.
+pub fn extend_subscription_internal(user: Principal, extension: SubscriptionExtension) -> Result<SubscriptionPeriod, String> {
+    if extension.extension_id.is_empty() || extension.extension_id.len() > MAX_EXTENSION_ID_LEN {
+        return Err(format!("Extension id must be 1-{} characters", MAX_EXTENSION_ID_LEN));
+    }
+    if extension.duration_ns == 0 {
+        return Err("Extension duration must be positive".to_string());
+    }
+    let mut profile = USER_PROFILES.with(|p| p.borrow().get(&user)).ok_or("User not found in this shard")?;
+
+    let now = ic_cdk::api::time();
+    let existing = USER_SUBSCRIPTIONS.with(|s| s.borrow().get(&user));
+    let extension_key = UserExtensionKey { user, extension_id: extension.extension_id.clone() };
+    if let Some(period) = &existing {
+        // Any extension applied before, not just the last one, is a no-op
+        let applied = APPLIED_EXTENSIONS.with(|a| a.borrow().contains_key(&extension_key));
+        if applied || period.last_extension_id == extension.extension_id {
+            return Ok(period.clone());
+        }
+    }
+
+    let period = match existing {
+        Some(mut period) if period.expires_at > now => {
+            period.expires_at = period.expires_at.saturating_add(extension.duration_ns);
+            period.plan_id = extension.plan_id;
+            period.auto_renew = extension.auto_renew;
+            period.last_extension_id = extension.extension_id;
+            period.updated_at = now;
+            period.expiry_reminded_at = None;
+            period
+        }
+        _ => SubscriptionPeriod {
+            plan_id: extension.plan_id,
+            started_at: now,
+            expires_at: now.saturating_add(extension.duration_ns),
+            auto_renew: extension.auto_renew,
+            last_extension_id: extension.extension_id,
+            updated_at: now,
+            expiry_reminded_at: None,
+        },
+    };
+    USER_SUBSCRIPTIONS.with(|s| s.borrow_mut().insert(user, period.clone()));
+    APPLIED_EXTENSIONS.with(|a| a.borrow_mut().insert(extension_key, now));
+
+    profile.is_subscribed = true;
+    USER_PROFILES.with(|p| p.borrow_mut().insert(user, profile));
+
+    Ok(period)
+}
+
+/// End a user's subscription immediately
+pub fn end_subscription(user: Principal) {
+    let now = ic_cdk::api::time();
+    USER_SUBSCRIPTIONS.with(|s| {
+        let mut map = s.borrow_mut();
+        if let Some(mut period) = map.get(&user) {
+            period.expires_at = period.expires_at.min(now);
+            period.auto_renew = false;
+            period.updated_at = now;
+            map.insert(user, period);
+        }
+    });
+    USER_PROFILES.with(|p| {
+        let mut map = p.borrow_mut();
+        if let Some(mut profile) = map.get(&user) {
+            profile.is_subscribed = false;
+            map.insert(user, profile);
+        }
+    });
+}
+
+/// Next slice of subscription periods for the sweep, continuing after the
+/// persisted cursor and wrapping to the start once the end is reached
+///
+/// Quota checks read the period itself, so a late downgrade only delays the flag.
+pub fn next_subscription_batch() -> Vec<(Principal, SubscriptionPeriod)> {
+    let cursor = SUBSCRIPTION_SWEEP_CURSOR.with(|c| *c.borrow().get());
+    let batch: Vec<(Principal, SubscriptionPeriod)> = USER_SUBSCRIPTIONS.with(|s| {
+        let map = s.borrow();
+        if cursor == Principal::anonymous() {
+            map.iter().take(MAX_SUBSCRIPTIONS_SCANNED_PER_SWEEP).collect()
+        } else {
+            map.range((std::ops::Bound::Excluded(cursor), std::ops::Bound::Unbounded))
+                .take(MAX_SUBSCRIPTIONS_SCANNED_PER_SWEEP)
+                .collect()
+        }
+    });
+
+    let next = if batch.len() < MAX_SUBSCRIPTIONS_SCANNED_PER_SWEEP {
+        Principal::anonymous()
+    } else {
+        batch.last().map(|(user, _)| *user).unwrap_or(Principal::anonymous())
+    };
+    SUBSCRIPTION_SWEEP_CURSOR.with(|c| c.borrow_mut().set(next).expect("Failed to advance subscription sweep cursor"));
+    batch
+}
+
+/// Downgrade users in `batch` whose subscription period has lapsed (timer callback)
+/// Returns the number of users downgraded
+pub fn downgrade_expired_subscriptions(batch: &[(Principal, SubscriptionPeriod)]) -> u64 {
+    let now = ic_cdk::api::time();
+    let mut downgraded = 0;
+    USER_PROFILES.with(|p| {
+        let mut map = p.borrow_mut();
+        for (user, period) in batch {
+            if period.is_active(now) {
+                continue;
+            }
+            if let Some(mut profile) = map.get(user) {
+                if profile.is_subscribed {
+                    profile.is_subscribed = false;
+                    map.insert(*user, profile);
+                    downgraded += 1;
+                }
+            }
+        }
+    });
+    downgraded
+}
+
+/// Give profiles subscribed before periods existed a final fixed period
+/// (their bare flag would otherwise never expire)
+pub fn migrate_legacy_subscriptions() {
+    let now = ic_cdk::api::time();
+    let legacy: Vec<Principal> = USER_PROFILES.with(|p| {
+        USER_SUBSCRIPTIONS.with(|s| {
+            let periods = s.borrow();
+            p.borrow()
+                .iter()
+                .filter(|(user, profile)| profile.is_subscribed && !periods.contains_key(user))
+                .map(|(user, _)| user)
+                .collect()
+        })
+    });
+
+    USER_SUBSCRIPTIONS.with(|s| {
+        let mut map = s.borrow_mut();
+        for user in legacy {
+            map.insert(user, SubscriptionPeriod {
+                plan_id: "legacy".to_string(),
+                started_at: now,
+                expires_at: now + MANUAL_SUBSCRIPTION_PERIOD_NS,
+                auto_renew: false,
+                last_extension_id: String::new(),
+                updated_at: now,
+                expiry_reminded_at: None,
+            });
+        }
+    });
+}
+
+/// Manual on/off switch (admin corrections and the legacy manager call):
+/// activating grants a fixed period, deactivating ends the current one
+pub fn set_subscription_manual(user: Principal, active: bool) -> Result<(), String> {
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&user)) {
+        return Err("User not found in this shard".to_string());
+    }
+    if active {
+        extend_subscription_internal(user, SubscriptionExtension {
+            extension_id: format!("manual-{}", ic_cdk::api::time()),
+            plan_id: "manual".to_string(),
+            duration_ns: MANUAL_SUBSCRIPTION_PERIOD_NS,
+            auto_renew: false,
+        })?;
+    } else {
+        end_subscription(user);
+    }
+    Ok(())
+}
+
+// ============================================================================
+// COMPLETION CERTIFICATES
+// ============================================================================
+
+fn certificate_key_id() -> EcdsaKeyId {
+    EcdsaKeyId {
+        curve: EcdsaCurve::Secp256k1,
+        name: CERTIFICATE_KEY_NAME.with(|k| k.borrow().get().clone()),
+    }
+}
+
+/// Public key that verifies certificates issued by this shard
+pub async fn certificate_signing_key() -> Result<CertificateSigningKey, String> {
+    let key_id = certificate_key_id();
+    let (response,) = ecdsa_public_key(EcdsaPublicKeyArgument {
+        canister_id: None,
+        derivation_path: vec![CERTIFICATE_DERIVATION_PATH.to_vec()],
+        key_id: key_id.clone(),
+    }).await.map_err(|(code, msg)| format!("Public key call failed: {:?} {}", code, msg))?;
+
+    Ok(CertificateSigningKey {
+        key_name: key_id.name,
+        public_key: response.public_key,
+        derivation_path: vec![CERTIFICATE_DERIVATION_PATH.to_vec()],
+    })
+}
+
+/// Summarize the recorded scores of a set of passed quizzes
+/// Returns the summary and the latest recorded pass time, if any
+fn summarize_scores(user: Principal, quiz_ids: &[String]) -> (ScoreSummary, Option<u64>) {
+    let mut summary = ScoreSummary {
+        quizzes_passed: quiz_ids.len() as u64,
+        scored_quizzes: 0,
+        correct_answers: 0,
+        total_questions: 0,
+        average_percent: 0,
+    };
+    let mut last_passed_at = None;
+
+    QUIZ_SCORES.with(|q| {
+        let scores = q.borrow();
+        for unit_id in quiz_ids {
+            if let Some(score) = scores.get(&UserQuizKey { user, unit_id: unit_id.clone() }) {
+                summary.scored_quizzes += 1;
+                summary.correct_answers += score.correct;
+                summary.total_questions += score.total;
+                last_passed_at = last_passed_at.max(Some(score.passed_at));
+            }
+        }
+    });
+    summary.average_percent = (summary.correct_answers * 100).checked_div(summary.total_questions).unwrap_or(0) as u8;
+
+    (summary, last_passed_at)
+}
+
+/// Issue (or return the existing) certificate for a completed course
+///
+/// The certificate id is reserved before signing so concurrent claims cannot
+/// issue twice; the reservation is released if signing fails.
+pub async fn issue_certificate_internal(user: Principal, course_id: String) -> Result<CompletionCertificate, String> {
+    let index_key = UserCertificateKey { user, course_id: course_id.clone() };
+    if let Some(certificate_id) = USER_CERTIFICATES.with(|c| c.borrow().get(&index_key)) {
+        return CERTIFICATES.with(|c| c.borrow().get(&certificate_id))
+            .ok_or("Certificate issuance already in progress".to_string());
+    }
+
+    // 1. Course tree from the learning engine
+    let learning_content_id = LEARNING_CONTENT_ID.with(|id| *id.borrow().get());
+    let (outline,): (Result<Vec<ContentOutlineNode>, String>,) = ic_cdk::call(
+        learning_content_id,
+        "get_subtree_outline",
+        (course_id.clone(),)
+    ).await.map_err(|(code, msg)| format!("Learning engine call failed: {:?} {}", code, msg))?;
+    let outline = outline?;
+    let root = outline.first().ok_or("Course not found")?.clone();
+
+    // 2. Every quiz in the course must be passed
+    let quiz_ids: Vec<String> = outline.into_iter().filter(|n| n.has_quiz).map(|n| n.id).collect();
+    if quiz_ids.is_empty() {
+        return Err("Course has no quizzes".to_string());
+    }
+    let remaining = quiz_ids.iter()
+        .filter(|unit_id| !COMPLETED_QUIZZES.with(|q| q.borrow().contains_key(&UserQuizKey { user, unit_id: (*unit_id).clone() })))
+        .count();
+    if remaining > 0 {
+        return Err(format!("Course not completed: {} of {} quizzes remaining", remaining, quiz_ids.len()));
+    }
+
+    // 3. Reserve the id (re-check after the await)
+    if USER_CERTIFICATES.with(|c| c.borrow().contains_key(&index_key)) {
+        return Err("Certificate issuance already in progress".to_string());
+    }
+    let sequence = CERTIFICATE_COUNT.with(|c| {
+        let mut cell = c.borrow_mut();
+        let next = *cell.get() + 1;
+        cell.set(next).expect("Failed to update certificate count");
+        next
+    });
+    let issuer = ic_cdk::id();
+    let certificate_id = format!("{}-{}", issuer, sequence);
+    USER_CERTIFICATES.with(|c| c.borrow_mut().insert(index_key.clone(), certificate_id.clone()));
+
+    // 4. Sign
+    let now = ic_cdk::api::time();
+    let (score, last_passed_at) = summarize_scores(user, &quiz_ids);
+    let payload = CertificatePayload {
+        certificate_id: certificate_id.clone(),
+        issuer,
+        learner: user,
+        course_id,
+        course_title: root.title,
+        course_version: root.version,
+        completed_at: last_passed_at.unwrap_or(now),
+        issued_at: now,
+        score,
+    };
+    let signed_payload = Encode!(&payload).map_err(|e| e.to_string())?;
+    let key_id = certificate_key_id();
+
+    let signed = sign_with_ecdsa(SignWithEcdsaArgument {
+        message_hash: Sha256::digest(&signed_payload).to_vec(),
+        derivation_path: vec![CERTIFICATE_DERIVATION_PATH.to_vec()],
+        key_id: key_id.clone(),
+    }).await;
+    let signature = match signed {
+        Ok((response,)) => response.signature,
+        Err((code, msg)) => {
+            USER_CERTIFICATES.with(|c| c.borrow_mut().remove(&index_key));
+            return Err(format!("Signing failed: {:?} {}", code, msg));
+        }
+    };
+
+    let certificate = CompletionCertificate {
+        payload,
+        signed_payload,
+        signature,
+        key_name: key_id.name,
+    };
+    CERTIFICATES.with(|c| c.borrow_mut().insert(certificate_id, certificate.clone()));
+
+    Ok(certificate)
+}
+
+/// All certificates issued to a user on this shard
+pub fn user_certificates(user: Principal) -> Vec<CompletionCertificate> {
+    let ids: Vec<String> = USER_CERTIFICATES.with(|c| {
+        c.borrow()
+            .range(UserCertificateKey { user, course_id: String::new() }..)
+            .take_while(|(k, _)| k.user == user)
+            .map(|(_, id)| id)
+            .collect()
+    });
+    CERTIFICATES.with(|c| {
+        let map = c.borrow();
+        ids.iter().filter_map(|id| map.get(id)).collect()
+    })
+}
+
+// ============================================================================
+// SOULBOUND CREDENTIALS
+// ============================================================================
+
+/// Mint request for an earned badge (the badge definition supplies the text)
+pub fn badge_credential_args(user: Principal, badge: &EarnedBadge) -> Option<MintCredentialArgs> {
+    let definition = BADGE_DEFINITIONS.with(|d| d.borrow().get(&badge.badge_id))?;
+    let content_node_id = match &definition.rule {
+        BadgeRule::SubtreeCompleted { root_id, .. } => Some(root_id.clone()),
+        _ => None,
+    };
+
+    Some(MintCredentialArgs {
+        owner: user,
+        kind: CredentialKind::Badge,
+        reference: badge.badge_id.clone(),
+        name: definition.name,
+        description: definition.description,
+        content_node_id,
+        attributes: vec![
+            ("badge_id".to_string(), CredentialValue::Text(badge.badge_id.clone())),
+            ("earned_at".to_string(), CredentialValue::Nat(badge.earned_at.into())),
+            ("bonus_awarded".to_string(), CredentialValue::Nat(badge.bonus_awarded.into())),
+        ],
+    })
+}
+
+/// Mint request for a completion certificate
+pub fn certificate_credential_args(certificate: &CompletionCertificate) -> MintCredentialArgs {
+    let payload = &certificate.payload;
+    MintCredentialArgs {
+        owner: payload.learner,
+        kind: CredentialKind::Certificate,
+        reference: payload.certificate_id.clone(),
+        name: payload.course_title.clone(),
+        description: format!("Completed the course \"{}\"", payload.course_title),
+        content_node_id: Some(payload.course_id.clone()),
+        attributes: vec![
+            ("certificate_id".to_string(), CredentialValue::Text(payload.certificate_id.clone())),
+            ("course_version".to_string(), CredentialValue::Nat(payload.course_version.into())),
+            ("completed_at".to_string(), CredentialValue::Nat(payload.completed_at.into())),
+            ("issued_at".to_string(), CredentialValue::Nat(payload.issued_at.into())),
+            ("quizzes_passed".to_string(), CredentialValue::Nat(payload.score.quizzes_passed.into())),
+            ("correct_answers".to_string(), CredentialValue::Nat(payload.score.correct_answers.into())),
+            ("total_questions".to_string(), CredentialValue::Nat(payload.score.total_questions.into())),
+            ("average_percent".to_string(), CredentialValue::Nat(payload.score.average_percent.into())),
+        ],
+    }
+}
+
+/// Every credential a user currently holds on this shard
+fn user_credential_args(user: Principal) -> Vec<MintCredentialArgs> {
+    let badges = USER_BADGES.with(|b| b.borrow().get(&user)).unwrap_or_default();
+    let mut args: Vec<MintCredentialArgs> = badges.0.iter()
+        .filter_map(|badge| badge_credential_args(user, badge))
+        .collect();
+    args.extend(user_certificates(user).iter().map(certificate_credential_args));
+    args
+}
+
+/// Mint one credential; Ok(None) when no collection is configured
+pub async fn mint_credential_at_collection(args: MintCredentialArgs) -> Result<Option<candid::Nat>, String> {
+    let collection_id = CREDENTIALS_COLLECTION_ID.with(|id| *id.borrow().get());
+    if collection_id == Principal::anonymous() {
+        return Ok(None);
+    }
+
+    let (result,): (Result<candid::Nat, String>,) = ic_cdk::call(
+        collection_id,
+        "mint_credential",
+        (args,)
+    ).await.map_err(|(code, msg)| format!("Credentials call failed: {:?} {}", code, msg))?;
+    result.map(Some)
+}
+
+/// Mint in the background; the learner can retry with sync_my_credentials
+pub fn spawn_credential_mints(mints: Vec<MintCredentialArgs>) {
+    if mints.is_empty() || CREDENTIALS_COLLECTION_ID.with(|id| *id.borrow().get()) == Principal::anonymous() {
+        return;
+    }
+    ic_cdk::spawn(async move {
+        for args in mints {
+            let _ = mint_credential_at_collection(args).await;
+        }
+    });
+}
+
+/// (Re-)mint every badge and certificate of a user. Minting is idempotent at
+/// the collection, so this only fills gaps. Returns the number of credentials held.
+pub async fn sync_user_credentials(user: Principal) -> Result<u64, String> {
+    if CREDENTIALS_COLLECTION_ID.with(|id| *id.borrow().get()) == Principal::anonymous() {
+        return Err("Credentials collection not configured".to_string());
+    }
+
+    let mut count = 0;
+    for args in user_credential_args(user) {
+        if mint_credential_at_collection(args).await?.is_some() {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Burn every credential this shard minted for `owner`
+pub async fn revoke_credentials_at_collection(owner: Principal) -> Result<u64, String> {
+    let collection_id = CREDENTIALS_COLLECTION_ID.with(|id| *id.borrow().get());
+    if collection_id == Principal::anonymous() {
+        return Ok(0);
+    }
+
+    let (result,): (Result<u64, String>,) = ic_cdk::call(
+        collection_id,
+        "revoke_credentials",
+        (owner,)
+    ).await.map_err(|(code, msg)| format!("Credentials call failed: {:?} {}", code, msg))?;
+    result
+}
+
+/// After a recovery, move credentials from the old principal to the new one
+pub fn spawn_credential_migration(old_user: Principal, new_user: Principal) {
+    if CREDENTIALS_COLLECTION_ID.with(|id| *id.borrow().get()) == Principal::anonymous() {
+        return;
+    }
+    ic_cdk::spawn(async move {
+        if revoke_credentials_at_collection(old_user).await.is_ok() {
+            let _ = sync_user_credentials(new_user).await;
+        }
+    });
+}
+
+// ============================================================================
+// PEER-TO-PEER TRANSFERS
+// ============================================================================
+
+/// Append a transfer (or unstake) entry to the user's log and adjust the
+/// balance. Transfers move existing stake, so supply stats and allowance are
+/// untouched. The caller persists `profile`.
+fn apply_transfer_entry(user: Principal, profile: &mut UserProfile, amount: u64, tx_type: TransactionType) {
+    match tx_type {
+        TransactionType::TransferOut | TransactionType::Unstake => profile.staked_balance -= amount,
+        _ => profile.staked_balance += amount,
+    }
+    let tx_index = profile.transaction_count;
+    profile.transaction_count += 1;
+    USER_TRANSACTIONS.with(|t| t.borrow_mut().insert(
+        TransactionKey { user, index: tx_index },
+        TransactionRecord {
+            timestamp: now(),
+            tx_type,
+            amount,
+        }
+    ));
+}
+
+/// Debit an unstake before the hub is asked to pay it out: the balance, an
+/// Unstake entry and the pending supply delta
+pub fn debit_unstake(user: Principal, amount: u64) -> Result<(), String> {
+    let mut profile = USER_PROFILES.with(|p| p.borrow().get(&user)).ok_or("User not registered")?;
+    if profile.staked_balance < amount {
+        return Err(format!("Insufficient balance. Available: {}", profile.staked_balance));
+    }
+
+    apply_transfer_entry(user, &mut profile, amount, TransactionType::Unstake);
+    USER_PROFILES.with(|p| p.borrow_mut().insert(user, profile));
+    PENDING_STATS.with(|s| {
+        let mut cell = s.borrow_mut();
+        let mut stats = cell.get().clone();
+        stats.staked_delta -= amount as i64;
+        cell.set(stats).expect("Failed to update pending stats");
+    });
+    Ok(())
+}
+
+/// Return an unstake the hub did not pay out
+///
+/// The hub may have credited transfers, bounties or referral bonuses to this
+/// user while the unstake was awaiting it, so the profile is re-read and the
+/// amount added back with an UnstakeRefund entry; the Unstake entry stays.
+pub fn refund_unstake(user: Principal, amount: u64) {
+    if let Some(mut profile) = USER_PROFILES.with(|p| p.borrow().get(&user)) {
+        apply_transfer_entry(user, &mut profile, amount, TransactionType::UnstakeRefund);
+        USER_PROFILES.with(|p| p.borrow_mut().insert(user, profile));
+    }
+    PENDING_STATS.with(|s| {
+        let mut cell = s.borrow_mut();
+        let mut stats = cell.get().clone();
+        stats.staked_delta += amount as i64;
+        cell.set(stats).expect("Failed to rollback pending stats");
+    });
+}
+
+/// Check that `user` can receive a transfer on this shard
+fn ensure_can_receive(user: Principal) -> Result<(), String> {
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&user)) {
+        return Err("Recipient not registered".to_string());
+    }
+    if is_erased(user) {
+        return Err("Recipient account has been erased".to_string());
+    }
+    if is_recovery_executing(user) {
+        return Err("Recipient account is being migrated to a new principal".to_string());
+    }
+    Ok(())
+}
+
+/// Whether the user has an outgoing transfer that is not settled yet
+pub fn has_unsettled_transfers(user: Principal) -> bool {
+    OUTGOING_TRANSFERS.with(|t| {
+        t.borrow()
+            .range(UserTransferKey { user, idempotency_key: String::new() }..)
+            .take_while(|(k, _)| k.user == user)
+            .any(|(_, transfer)| matches!(transfer.status, StakedTransferStatus::Pending | StakedTransferStatus::Unresolved))
+    })
+}
+
+pub fn user_outgoing_transfers(user: Principal) -> Vec<StakedTransfer> {
+    OUTGOING_TRANSFERS.with(|t| {
+        t.borrow()
+            .range(UserTransferKey { user, idempotency_key: String::new() }..)
+            .take_while(|(k, _)| k.user == user)
+            .map(|(_, transfer)| transfer)
+            .collect()
+    })
+}
+
+/// Phase 1: debit the sender and record the transfer.
+/// Recipients on this shard are credited immediately; others are routed through the hub.
+pub async fn transfer_staked_internal(user: Principal, args: StakedTransferArgs) -> Result<StakedTransfer, String> {
+    if args.idempotency_key.is_empty() || args.idempotency_key.len() > MAX_TRANSFER_IDEMPOTENCY_KEY_LEN {
+        return Err(format!("Idempotency key must be 1-{} bytes", MAX_TRANSFER_IDEMPOTENCY_KEY_LEN));
+    }
+    if args.memo.as_ref().is_some_and(|m| m.len() > MAX_TRANSFER_MEMO_LEN) {
+        return Err(format!("Memo is limited to {} bytes", MAX_TRANSFER_MEMO_LEN));
+    }
+
+    let key = UserTransferKey { user, idempotency_key: args.idempotency_key.clone() };
+    if let Some(existing) = OUTGOING_TRANSFERS.with(|t| t.borrow().get(&key)) {
+        if existing.to != args.to || existing.amount != args.amount {
+            return Err("Idempotency key was already used for a different transfer".to_string());
+        }
+        return Ok(existing);
+    }
+
+    if args.amount == 0 {
+        return Err("Amount must be greater than 0".to_string());
+    }
+    if args.to == Principal::anonymous() {
+        return Err("Cannot transfer to the anonymous principal".to_string());
+    }
+    let local_recipient = resolve_account(args.to);
+    if local_recipient == user {
+        return Err("Cannot transfer to yourself".to_string());
+    }
+    let is_local = USER_PROFILES.with(|p| p.borrow().contains_key(&local_recipient));
+    if is_local {
+        ensure_can_receive(local_recipient)?;
+    }
+
+    let mut profile = USER_PROFILES.with(|p| p.borrow().get(&user)).ok_or("User not registered")?;
+    if profile.staked_balance < args.amount {
+        return Err(format!("Insufficient balance. Available: {}", profile.staked_balance));
+    }
+
+    // Debit (committed before any await)
+    apply_transfer_entry(user, &mut profile, args.amount, TransactionType::TransferOut);
+    USER_PROFILES.with(|p| p.borrow_mut().insert(user, profile));
+
+    let now = ic_cdk::api::time();
+    let mut transfer = StakedTransfer {
+        transfer_id: format!("{}-{}-{}", ic_cdk::id(), user, args.idempotency_key),
+        idempotency_key: args.idempotency_key,
+        from: user,
+        to: args.to,
+        amount: args.amount,
+        memo: args.memo,
+        created_at: now,
+        updated_at: now,
+        status: StakedTransferStatus::Pending,
+    };
+
+    if is_local {
+        let mut recipient = USER_PROFILES.with(|p| p.borrow().get(&local_recipient)).ok_or("Recipient not registered")?;
+        apply_transfer_entry(local_recipient, &mut recipient, transfer.amount, TransactionType::TransferIn);
+        USER_PROFILES.with(|p| p.borrow_mut().insert(local_recipient, recipient));
+        INCOMING_TRANSFERS.with(|t| t.borrow_mut().insert(transfer.transfer_id.clone(), IncomingTransfer {
+            from: user,
+            to: local_recipient,
+            amount: transfer.amount,
+            credited_at: now,
+        }));
+        transfer.status = StakedTransferStatus::Completed;
+        OUTGOING_TRANSFERS.with(|t| t.borrow_mut().insert(key, transfer.clone()));
+        return Ok(transfer);
+    }
+
+    OUTGOING_TRANSFERS.with(|t| t.borrow_mut().insert(key.clone(), transfer));
+    route_staked_transfer(key).await
+}
+
+/// Phase 2: ask the hub to credit the recipient's shard, then settle locally.
+/// The hub and the destination deduplicate on transfer_id, so this is safe to repeat.
+async fn route_staked_transfer(key: UserTransferKey) -> Result<StakedTransfer, String> {
+    let mut transfer = OUTGOING_TRANSFERS.with(|t| t.borrow().get(&key)).ok_or("Transfer not found")?;
+    transfer.status = StakedTransferStatus::Pending;
+    transfer.updated_at = ic_cdk::api::time();
+    OUTGOING_TRANSFERS.with(|t| t.borrow_mut().insert(key.clone(), transfer.clone()));
+
+    let request = StakedTransferRequest {
+        transfer_id: transfer.transfer_id.clone(),
+        from: key.user,
+        to: transfer.to,
+        amount: transfer.amount,
+        memo: transfer.memo.clone(),
+    };
+    let staking_hub_id = STAKING_HUB_ID.with(|id| *id.borrow().get());
+    let result: Result<(Result<(), String>,), _> = ic_cdk::call(
+        staking_hub_id,
+        "route_staked_transfer",
+        (request,)
+    ).await;
+
+    transfer.updated_at = ic_cdk::api::time();
+    transfer.status = match result {
+        Ok((Ok(()),)) => StakedTransferStatus::Completed,
+        Ok((Err(reason),)) => {
+            // Definitive failure: refund the sender (re-read after the await)
+            let mut profile = USER_PROFILES.with(|p| p.borrow().get(&key.user)).ok_or("User not registered")?;
+            apply_transfer_entry(key.user, &mut profile, transfer.amount, TransactionType::TransferRefund);
+            USER_PROFILES.with(|p| p.borrow_mut().insert(key.user, profile));
+            StakedTransferStatus::Refunded { reason }
+        }
+        // The hub may or may not have routed it: keep the debit until a retry settles it
+        Err(_) => StakedTransferStatus::Unresolved,
+    };
+    OUTGOING_TRANSFERS.with(|t| t.borrow_mut().insert(key, transfer.clone()));
+
+    Ok(transfer)
+}
+
+/// Settle an Unresolved (or interrupted Pending) transfer
+pub async fn retry_staked_transfer_internal(user: Principal, idempotency_key: String) -> Result<StakedTransfer, String> {
+    let key = UserTransferKey { user, idempotency_key };
+    let transfer = OUTGOING_TRANSFERS.with(|t| t.borrow().get(&key)).ok_or("Transfer not found")?;
+
+    match transfer.status {
+        StakedTransferStatus::Completed | StakedTransferStatus::Refunded { .. } => Ok(transfer),
+        StakedTransferStatus::Pending
+            if ic_cdk::api::time().saturating_sub(transfer.updated_at) < STAKED_TRANSFER_RETRY_AFTER_NS =>
+        {
+            Err("Transfer is still being processed".to_string())
+        }
+        _ => route_staked_transfer(key).await,
+    }
+}
+
+/// Destination side of a hub-routed transfer. Idempotent per transfer_id.
+pub fn credit_incoming_transfer(request: StakedTransferRequest) -> Result<(), String> {
+    if INCOMING_TRANSFERS.with(|t| t.borrow().contains_key(&request.transfer_id)) {
+        return Ok(());
+    }
+    if request.amount == 0 {
+        return Err("Amount must be greater than 0".to_string());
+    }
+
+    let recipient = resolve_account(request.to);
+    ensure_can_receive(recipient)?;
+
+    let mut profile = USER_PROFILES.with(|p| p.borrow().get(&recipient)).ok_or("Recipient not registered")?;
+    apply_transfer_entry(recipient, &mut profile, request.amount, TransactionType::TransferIn);
+    USER_PROFILES.with(|p| p.borrow_mut().insert(recipient, profile));
+    INCOMING_TRANSFERS.with(|t| t.borrow_mut().insert(request.transfer_id, IncomingTransfer {
+        from: request.from,
+        to: recipient,
+        amount: request.amount,
+        credited_at: now(),
+    }));
+
+    Ok(())
+}
+
+// ============================================================================
+// NOTIFICATIONS
+// ============================================================================
+
+pub fn validate_notification(input: &NotificationInput) -> Result<(), String> {
+    if input.title.is_empty() || input.title.len() > MAX_NOTIFICATION_TITLE_LEN {
+        return Err(format!("Title must be 1-{} bytes", MAX_NOTIFICATION_TITLE_LEN));
+    }
+    if input.body.len() > MAX_NOTIFICATION_BODY_LEN {
+        return Err(format!("Body is limited to {} bytes", MAX_NOTIFICATION_BODY_LEN));
+    }
+    if input.link.as_ref().is_some_and(|l| l.len() > MAX_NOTIFICATION_LINK_LEN) {
+        return Err(format!("Link is limited to {} bytes", MAX_NOTIFICATION_LINK_LEN));
+    }
+    Ok(())
+}
+
+/// Store a notification in the user's inbox, dropping the oldest beyond the cap.
+/// Returns the new id, or None if the user is unknown, erased or muted the category.
+pub fn deliver_notification(user: Principal, source: Principal, input: &NotificationInput) -> Option<u64> {
+    if !USER_PROFILES.with(|p| p.borrow().contains_key(&user)) || is_erased(user) {
+        return None;
+    }
+    let muted = NOTIFICATION_PREFERENCES.with(|p| p.borrow().get(&user))
+        .is_some_and(|prefs| prefs.muted.contains(&input.category));
+    if muted {
+        return None;
+    }
+
+    let mut inbox = NOTIFICATION_INBOXES.with(|i| i.borrow().get(&user)).unwrap_or_default();
+    let id = inbox.next_id;
+    inbox.next_id += 1;
+    inbox.unread_count += 1;
+
+    NOTIFICATIONS.with(|n| {
+        let mut map = n.borrow_mut();
+        map.insert(NotificationKey { user, id }, Notification {
+            id,
+            category: input.category,
+            title: input.title.clone(),
+            body: input.body.clone(),
+            link: input.link.clone(),
+            source,
+            created_at: ic_cdk::api::time(),
+            read: false,
+        });
+
+        while inbox.next_id - inbox.oldest_id > MAX_NOTIFICATIONS_PER_USER {
+            if let Some(dropped) = map.remove(&NotificationKey { user, id: inbox.oldest_id }) {
+                if !dropped.read {
+                    inbox.unread_count = inbox.unread_count.saturating_sub(1);
+                }
+            }
+            inbox.oldest_id += 1;
+        }
+    });
+    NOTIFICATION_INBOXES.with(|i| i.borrow_mut().insert(user, inbox));
+
+    Some(id)
+}
+
+/// Deliver to several users (linked wallets resolve to their account).
+/// Returns the number of inboxes the notification was stored in.
+pub fn notify_users(source: Principal, users: Vec<Principal>, input: &NotificationInput) -> u64 {
+    let mut delivered = 0;
+    for user in users {
+        if deliver_notification(resolve_account(user), source, input).is_some() {
+            delivered += 1;
+        }
+    }
+    delivered
+}
+
+/// Newest-first page of notifications with ids below `before`
+pub fn notification_page(user: Principal, before: Option<u64>, limit: Option<u32>, unread_only: bool) -> NotificationPage {
+    let inbox = NOTIFICATION_INBOXES.with(|i| i.borrow().get(&user)).unwrap_or_default();
+    let limit = limit.unwrap_or(DEFAULT_NOTIFICATION_PAGE_SIZE).clamp(1, MAX_NOTIFICATION_PAGE_SIZE) as usize;
+    let end = before.unwrap_or(inbox.next_id).min(inbox.next_id);
+
+    let mut notifications = Vec::new();
+    let mut next_cursor = None;
+    NOTIFICATIONS.with(|n| {
+        let map = n.borrow();
+        for id in (inbox.oldest_id..end).rev() {
+            if notifications.len() == limit {
+                next_cursor = notifications.last().map(|last: &Notification| last.id);
+                break;
+            }
+            if let Some(notification) = map.get(&NotificationKey { user, id }) {
+                if !unread_only || !notification.read {
+                    notifications.push(notification);
+                }
+            }
+        }
+    });
+
+    NotificationPage {
+        notifications,
+        next_cursor,
+        unread_count: inbox.unread_count,
+    }
+}
+
+/// Mark the given notifications (or all, if `ids` is None) as read
+/// Returns the number of notifications that changed
+pub fn mark_notifications_read_internal(user: Principal, ids: Option<Vec<u64>>) -> u64 {
+    let Some(mut inbox) = NOTIFICATION_INBOXES.with(|i| i.borrow().get(&user)) else {
+        return 0;
+    };
+    let ids = ids.unwrap_or_else(|| (inbox.oldest_id..inbox.next_id).collect());
+
+    let mut changed = 0;
+    NOTIFICATIONS.with(|n| {
+        let mut map = n.borrow_mut();
+        for id in ids {
+            let key = NotificationKey { user, id };
+            if let Some(mut notification) = map.get(&key) {
+                if !notification.read {
+                    notification.read = true;
+                    map.insert(key, notification);
+                    changed += 1;
+                }
+            }
+        }
+    });
+
+    inbox.unread_count = inbox.unread_count.saturating_sub(changed);
+    NOTIFICATION_INBOXES.with(|i| i.borrow_mut().insert(user, inbox));
+    changed
+}
+
+/// All retained notifications of a user, oldest first
+pub fn user_notifications(user: Principal) -> Vec<Notification> {
+    let inbox = NOTIFICATION_INBOXES.with(|i| i.borrow().get(&user)).unwrap_or_default();
+    NOTIFICATIONS.with(|n| {
+        let map = n.borrow();
+        (inbox.oldest_id..inbox.next_id)
+            .filter_map(|id| map.get(&NotificationKey { user, id }))
+            .collect()
+    })
+}
+
+/// Drop a user's inbox and preferences (erasure)
+pub fn remove_user_notifications(user: Principal) {
+    if let Some(inbox) = NOTIFICATION_INBOXES.with(|i| i.borrow_mut().remove(&user)) {
+        NOTIFICATIONS.with(|n| {
+            let mut map = n.borrow_mut();
+            for id in inbox.oldest_id..inbox.next_id {
+                map.remove(&NotificationKey { user, id });
+            }
+        });
+    }
+    NOTIFICATION_PREFERENCES.with(|p| p.borrow_mut().remove(&user));
+}
+
+/// Move a user's inbox and preferences to a new principal (account recovery)
+pub fn rekey_user_notifications(old_user: Principal, new_user: Principal) {
+    if let Some(inbox) = NOTIFICATION_INBOXES.with(|i| i.borrow_mut().remove(&old_user)) {
+        NOTIFICATIONS.with(|n| {
+            let mut map = n.borrow_mut();
+            for id in inbox.oldest_id..inbox.next_id {
+                if let Some(notification) = map.remove(&NotificationKey { user: old_user, id }) {
+                    map.insert(NotificationKey { user: new_user, id }, notification);
+                }
+            }
+        });
+        NOTIFICATION_INBOXES.with(|i| i.borrow_mut().insert(new_user, inbox));
+    }
+    if let Some(prefs) = NOTIFICATION_PREFERENCES.with(|p| p.borrow_mut().remove(&old_user)) {
+        NOTIFICATION_PREFERENCES.with(|p| p.borrow_mut().insert(new_user, prefs));
+    }
+}
+
+/// Remind users in `batch` whose non-renewing subscription expires soon (timer callback)
+/// Returns the number of reminders sent
+pub fn send_subscription_expiry_reminders(batch: &[(Principal, SubscriptionPeriod)]) -> u64 {
+    let now = ic_cdk::api::time();
+    let due: Vec<(Principal, SubscriptionPeriod)> = batch.iter()
+        .filter(|(_, period)| {
+            !period.auto_renew
+                && period.expiry_reminded_at.is_none()
+                && period.expires_at > now
+                && period.expires_at - now <= SUBSCRIPTION_EXPIRY_REMINDER_NS
+        })
+        .cloned()
+        .collect();
+
+    let self_id = ic_cdk::id();
+    let mut sent = 0;
+    for (user, mut period) in due {
+        let hours_left = (period.expires_at - now) / (60 * 60 * 1_000_000_000);
+        let input = NotificationInput {
+            category: NotificationCategory::Subscription,
+            title: "Subscription expiring soon".to_string(),
+            body: format!("Your subscription expires in {} hours and will not renew automatically.", hours_left),
+            link: None,
+        };
+        if deliver_notification(user, self_id, &input).is_some() {
+            sent += 1;
+        }
+        period.expiry_reminded_at = Some(now);
+        USER_SUBSCRIPTIONS.with(|s| s.borrow_mut().insert(user, period));
+    }
+    sent
+}
+
+pub fn enqueue_content_notice(notice: ContentUpdateNotice) {
+    CONTENT_NOTICE_QUEUE.with(|q| {
+        let mut queue = q.borrow_mut();
+        let next = queue.last_key_value().map(|(k, _)| k + 1).unwrap_or(0);
+        queue.insert(next, PendingContentNotice { notice, cursor: None });
+    });
+}
+
+/// Fan the oldest queued content update out to the users it concerns
+/// (passed its quiz or hold a certificate for it), a bounded number of users per call.
+/// Returns the number of notifications delivered.
+pub fn process_content_notices() -> u64 {
+    let Some((seq, mut pending)) = CONTENT_NOTICE_QUEUE.with(|q| q.borrow().first_key_value()) else {
+        return 0;
+    };
+
+    let start = match pending.cursor {
+        Some(cursor) => std::ops::Bound::Excluded(cursor),
+        None => std::ops::Bound::Unbounded,
+    };
+    let users: Vec<Principal> = USER_PROFILES.with(|p| {
+        p.borrow()
+            .range((start, std::ops::Bound::Unbounded))
+            .take(CONTENT_NOTICE_USERS_PER_SWEEP)
+            .map(|(user, _)| user)
+            .collect()
+    });
+
+    let notice = &pending.notice;
+    let input = NotificationInput {
+        category: NotificationCategory::Content,
+        title: "Course content updated".to_string(),
+        body: format!("\"{}\" was updated to version {}.", notice.title, notice.version),
+        link: Some(notice.content_id.clone()),
+    };
+    let self_id = ic_cdk::id();
+    let mut delivered = 0;
+    for user in users.iter() {
+        let concerned = COMPLETED_QUIZZES.with(|q| {
+            q.borrow().contains_key(&UserQuizKey { user: *user, unit_id: notice.content_id.clone() })
+        }) || USER_CERTIFICATES.with(|c| {
+            c.borrow().contains_key(&UserCertificateKey { user: *user, course_id: notice.content_id.clone() })
+        });
+        if concerned && deliver_notification(*user, self_id, &input).is_some() {
+            delivered += 1;
+        }
+    }
+
+    CONTENT_NOTICE_QUEUE.with(|q| {
+        let mut queue = q.borrow_mut();
+        if users.len() < CONTENT_NOTICE_USERS_PER_SWEEP {
+            queue.remove(&seq);
+        } else {
+            pending.cursor = users.last().copied();
+            queue.insert(seq, pending);
+        }
+    });
+
+    delivered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clock seen by `now` in unit tests
+    pub const TEST_NOW: u64 = 20_000 * DAY_NS;
+
+    #[test]
+    fn answer_matches_numeric_ranges_inclusively() {
+        let range = AnswerCommitment::Numeric { min: -5, max: 5 };
+        assert!(answer_matches(&range, &QuizAnswer::Numeric(-5)));
+        assert!(answer_matches(&range, &QuizAnswer::Numeric(5)));
+        assert!(!answer_matches(&range, &QuizAnswer::Numeric(6)));
+        assert!(!answer_matches(&range, &QuizAnswer::ShortText("5".to_string())));
+    }
+
+    #[test]
+    fn answer_matches_hashed_commitments() {
+        let single = AnswerCommitment::SingleChoice(stable_hash(&2u8.to_le_bytes()));
+        assert!(answer_matches(&single, &QuizAnswer::SingleChoice(2)));
+        assert!(!answer_matches(&single, &QuizAnswer::SingleChoice(3)));
+
+        let multi = AnswerCommitment::MultiSelect(stable_hash(&[0, 2]));
+        assert!(answer_matches(&multi, &QuizAnswer::MultiSelect(vec![2, 0, 0])));
+
+        let truth = AnswerCommitment::TrueFalse(stable_hash(&[0]));
+        assert!(answer_matches(&truth, &QuizAnswer::TrueFalse(false)));
+        assert!(answer_matches(&truth, &QuizAnswer::SingleChoice(0)));
+
+        let text = AnswerCommitment::ShortText(vec![stable_hash(b"paris")]);
+        assert!(answer_matches(&text, &QuizAnswer::ShortText("Paris.".to_string())));
+        assert!(!answer_matches(&text, &QuizAnswer::ShortText("x".repeat(MAX_TEXT_ANSWER_LEN + 1))));
+    }
+
+    #[test]
+    fn score_answers_checks_each_question_against_its_commitment() {
+        let cache = QuizCacheData {
+            content_id: "unit_1".to_string(),
+            answer_hashes: vec![stable_hash(&1u8.to_le_bytes()), stable_hash(&0u8.to_le_bytes())],
+            question_count: 2,
+            version: 1,
+            commitments: Some(vec![
+                AnswerCommitment::SingleChoice(stable_hash(&1u8.to_le_bytes())),
+                AnswerCommitment::Numeric { min: 9, max: 11 },
+            ]),
+            draw_count: None,
+            prerequisites: None,
+        };
+        let user = Principal::from_slice(&[1; 29]);
+        let answers = [QuizAnswer::SingleChoice(1), QuizAnswer::Numeric(12)];
+        assert_eq!(score_answers(&cache, user, &answers), Some(vec![true, false]));
+        assert_eq!(score_answers(&cache, user, &answers[..1]), None);
+    }
+
+    #[test]
+    fn review_quality_rounds_the_score_to_a_grade() {
+        assert_eq!(review_quality(0, 10), 0);
+        assert_eq!(review_quality(5, 10), 3);
+        assert_eq!(review_quality(7, 10), 4);
+        assert_eq!(review_quality(8, 10), 4);
+        assert_eq!(review_quality(10, 10), 5);
+        assert_eq!(review_quality(12, 10), 5);
+        assert_eq!(review_quality(0, 0), 0);
+    }
+
+    #[test]
+    fn sm2_step_grows_the_interval_on_success() {
+        let card = sm2_step(new_review_card(100, 101), 5, 101);
+        assert_eq!((card.repetitions, card.interval_days, card.ease_milli), (2, 6, 2_600));
+        assert_eq!(card.due_day, 107);
+        assert_eq!(card.last_review_day, 101);
+
+        // 6 days x 2.6, rounded up
+        let card = sm2_step(card, 5, 107);
+        assert_eq!((card.repetitions, card.interval_days, card.ease_milli), (3, 16, 2_700));
+        assert_eq!(card.due_day, 123);
+
+        // Quality 4 keeps the ease, 3 lowers it but still passes
+        let card = sm2_step(card, 4, 123);
+        assert_eq!((card.interval_days, card.ease_milli), (44, 2_700));
+        let card = sm2_step(card, 3, 167);
+        assert_eq!((card.repetitions, card.ease_milli), (5, 2_560));
+    }
+
+    #[test]
+    fn sm2_step_restarts_a_lapsed_card() {
+        let card = sm2_step(sm2_step(new_review_card(100, 101), 5, 101), 2, 107);
+        assert_eq!((card.repetitions, card.interval_days, card.lapses), (0, 1, 1));
+        assert_eq!(card.ease_milli, 2_600 - 320);
+        assert_eq!(card.due_day, 108);
+
+        // The next success starts the 1, 6, ... progression again
+        let card = sm2_step(card, 5, 108);
+        assert_eq!((card.repetitions, card.interval_days, card.lapses), (1, 1, 1));
+    }
+
+    #[test]
+    fn sm2_step_bounds_the_ease_and_interval() {
+        let mut card = new_review_card(0, 1);
+        for day in 1..10 {
+            card = sm2_step(card, 0, day);
+        }
+        assert_eq!(card.ease_milli, MIN_EASE_MILLI);
+        assert_eq!(card.lapses, 9);
+
+        let card = ReviewCard { interval_days: 300, repetitions: 5, ..new_review_card(0, 0) };
+        assert_eq!(sm2_step(card, 5, 0).interval_days, MAX_REVIEW_INTERVAL_DAYS);
+    }
+
+    #[test]
+    fn add_to_tier_accumulates_and_prunes_old_buckets() {
+        let mut buckets = Vec::new();
+        add_to_tier(&mut buckets, 10, 3, 1, 5);
+        add_to_tier(&mut buckets, 10, 3, 1, 5);
+        assert_eq!(buckets.len(), 1);
+        assert_eq!((buckets[0].quizzes, buckets[0].earnings), (2, 10));
+
+        add_to_tier(&mut buckets, 12, 3, 1, 1);
+        assert_eq!(buckets.iter().map(|b| b.index).collect::<Vec<_>>(), vec![10, 12]);
+
+        // Bucket 10 is no longer among the 3 most recent (11, 12, 13)
+        add_to_tier(&mut buckets, 13, 3, 1, 1);
+        assert_eq!(buckets.iter().map(|b| b.index).collect::<Vec<_>>(), vec![12, 13]);
+    }
+
+    #[test]
+    fn rolling_usage_rolls_over_each_window() {
+        let user = Principal::from_slice(&[2; 29]);
+        let start = 1_000 * DAY_NS + 30 * 60 * 1_000_000_000;
+        record_rolling_usage(user, start, 1, 100);
+
+        let stats = rolling_usage_stats(user, start + DAY_NS);
+        assert_eq!((stats.daily_quizzes, stats.daily_earnings), (1, 100));
+
+        // The attempt's hour no longer overlaps the last 24h
+        let stats = rolling_usage_stats(user, start + DAY_NS + HOUR_NS);
+        assert_eq!((stats.daily_quizzes, stats.daily_earnings), (0, 0));
+        assert_eq!((stats.weekly_quizzes, stats.weekly_earnings), (1, 100));
+
+        let stats = rolling_usage_stats(user, start + 8 * DAY_NS);
+        assert_eq!(stats.weekly_earnings, 0);
+        assert_eq!(stats.monthly_earnings, 100);
+
+        let stats = rolling_usage_stats(user, start + 31 * DAY_NS);
+        assert_eq!(stats.monthly_earnings, 0);
+        assert_eq!(stats.yearly_earnings, 100);
+
+        let stats = rolling_usage_stats(user, start + 373 * DAY_NS);
+        assert_eq!((stats.yearly_quizzes, stats.yearly_earnings), (0, 0));
+    }
+
+    #[test]
+    fn rolling_usage_sums_attempts_across_buckets() {
+        let user = Principal::from_slice(&[3; 29]);
+        let start = 2_000 * DAY_NS;
+        record_rolling_usage(user, start, 1, 10);
+        record_rolling_usage(user, start + 5 * HOUR_NS, 1, 20);
+        record_rolling_usage(user, start + 20 * HOUR_NS, 0, 5);
+
+        let stats = rolling_usage_stats(user, start + 20 * HOUR_NS);
+        assert_eq!((stats.daily_quizzes, stats.daily_earnings), (2, 35));
+
+        let stats = rolling_usage_stats(user, start + 26 * HOUR_NS);
+        assert_eq!((stats.daily_quizzes, stats.daily_earnings), (1, 25));
+    }
+
+    #[test]
+    fn user_quiz_helpers_touch_only_that_users_keys() {
+        let (old_user, new_user, other) = (
+            Principal::from_slice(&[9; 29]),
+            Principal::from_slice(&[10; 29]),
+            Principal::from_slice(&[11; 29]),
+        );
+        COMPLETED_QUIZZES.with(|q| {
+            let mut map = q.borrow_mut();
+            for (user, unit_id) in [(old_user, "a"), (old_user, "b"), (other, "a")] {
+                map.insert(UserQuizKey { user, unit_id: unit_id.to_string() }, true);
+            }
+        });
+
+        COMPLETED_QUIZZES.with(|q| rekey_user_quiz_entries(&mut q.borrow_mut(), old_user, new_user));
+        assert!(completed_units(old_user).is_empty());
+        assert_eq!(completed_units(new_user), vec!["a".to_string(), "b".to_string()]);
+
+        COMPLETED_QUIZZES.with(|q| remove_user_quiz_entries(&mut q.borrow_mut(), new_user));
+        assert_eq!(count_completed_quizzes(new_user), 0);
+        assert_eq!(completed_units(other), vec!["a".to_string()]);
+    }
+
+    pub fn register(user: Principal, staked_balance: u64) {
+        USER_PROFILES.with(|p| p.borrow_mut().insert(user, UserProfile {
+            email: String::new(),
+            name: String::new(),
+            education: String::new(),
+            gender: String::new(),
+            verification_tier: VerificationTier::None,
+            staked_balance,
+            transaction_count: 0,
+            archived_transaction_count: 0,
+            is_subscribed: false,
+        }));
+    }
+
+    pub fn transaction_types(user: Principal) -> Vec<TransactionType> {
+        let count = USER_PROFILES.with(|p| p.borrow().get(&user)).map_or(0, |profile| profile.transaction_count);
+        USER_TRANSACTIONS.with(|t| {
+            let t = t.borrow();
+            (0..count).map(|index| t.get(&TransactionKey { user, index }).expect("entry recorded").tx_type).collect()
+        })
+    }
+
+    fn staked_balance(user: Principal) -> u64 {
+        USER_PROFILES.with(|p| p.borrow().get(&user)).map_or(0, |profile| profile.staked_balance)
+    }
+
+    fn pending_staked_delta() -> i64 {
+        PENDING_STATS.with(|s| s.borrow().get().staked_delta)
+    }
+
+    #[test]
+    fn refunded_unstake_keeps_a_transfer_credited_during_the_hub_call() {
+        let user = Principal::from_slice(&[4; 29]);
+        register(user, 100);
+
+        debit_unstake(user, 60).unwrap();
+        assert_eq!(staked_balance(user), 40);
+        assert_eq!(pending_staked_delta(), -60);
+
+        // The hub routes a transfer to the user while process_unstake is pending
+        credit_incoming_transfer(StakedTransferRequest {
+            transfer_id: "t1".to_string(),
+            from: Principal::from_slice(&[5; 29]),
+            to: user,
+            amount: 25,
+            memo: None,
+        }).unwrap();
+
+        refund_unstake(user, 60);
+        assert_eq!(staked_balance(user), 125);
+        assert_eq!(pending_staked_delta(), 0);
+        assert!(matches!(
+            transaction_types(user)[..],
+            [TransactionType::Unstake, TransactionType::TransferIn, TransactionType::UnstakeRefund]
+        ));
+    }
+
+    #[test]
+    fn refunded_unstake_keeps_a_bounty_credited_during_the_hub_call() {
+        let user = Principal::from_slice(&[7; 29]);
+        register(user, 50);
+
+        debit_unstake(user, 50).unwrap();
+        credit_bounty_reward(user, 30).unwrap();
+        refund_unstake(user, 50);
+
+        assert_eq!(staked_balance(user), 80);
+        assert_eq!(pending_staked_delta(), 0);
+        assert!(matches!(
+            transaction_types(user)[..],
+            [TransactionType::Unstake, TransactionType::BountyReward, TransactionType::UnstakeRefund]
+        ));
+    }
+
+    #[test]
+    fn refunded_unstake_keeps_a_referral_bonus_credited_during_the_hub_call() {
+        let user = Principal::from_slice(&[8; 29]);
+        register(user, 50);
+        MINTING_ALLOWANCE.with(|a| a.borrow_mut().set(REFERRAL_BONUS_AMOUNT).unwrap());
+
+        debit_unstake(user, 50).unwrap();
+        assert_eq!(credit_referrer(user), Ok(REFERRAL_BONUS_AMOUNT));
+        refund_unstake(user, 50);
+
+        assert_eq!(staked_balance(user), 50 + REFERRAL_BONUS_AMOUNT);
+        assert_eq!(pending_staked_delta(), REFERRAL_BONUS_AMOUNT as i64);
+        assert!(matches!(
+            transaction_types(user)[..],
+            [TransactionType::Unstake, TransactionType::ReferralBonus, TransactionType::UnstakeRefund]
+        ));
+    }
+
+    #[test]
+    fn rekey_referrer_updates_only_the_recovered_referrers_records() {
+        let (old_referrer, new_referrer, other) = (
+            Principal::from_slice(&[12; 29]),
+            Principal::from_slice(&[13; 29]),
+            Principal::from_slice(&[14; 29]),
+        );
+        let record = |referrer| ReferralRecord {
+            referrer,
+            registered_at: 0,
+            quizzes_passed: 0,
+            status: ReferralStatus::Qualifying,
+            referee_bonus: 0,
+            referrer_bonus: 0,
+        };
+        let referees: Vec<Principal> = (15..18).map(|b| Principal::from_slice(&[b; 29])).collect();
+        REFERRALS.with(|r| {
+            let mut map = r.borrow_mut();
+            map.insert(referees[0], record(old_referrer));
+            map.insert(referees[1], record(other));
+            map.insert(referees[2], record(old_referrer));
+        });
+
+        assert_eq!(rekey_referrer(old_referrer, new_referrer), 2);
+        let referrers: Vec<Principal> = REFERRALS.with(|r| {
+            referees.iter().map(|referee| r.borrow().get(referee).unwrap().referrer).collect()
+        });
+        assert_eq!(referrers, vec![new_referrer, other, new_referrer]);
+    }
+
+    #[test]
+    fn debit_unstake_rejects_more_than_the_staked_balance() {
+        let user = Principal::from_slice(&[6; 29]);
+        register(user, 10);
+        assert!(debit_unstake(user, 11).is_err());
+        assert_eq!(staked_balance(user), 10);
+        assert!(transaction_types(user).is_empty());
     }
-    res
 }
@@ -3,6 +3,7 @@ use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, StableCell};
 use std::cell::RefCell;
 use candid::Principal;
 use crate::types::*;
+use crate::constants::DEFAULT_CERTIFICATE_KEY_NAME;
 
 pub type Memory = VirtualMemory<DefaultMemoryImpl>;
 
@@ -108,7 +109,7 @@ thread_local! {
     );
 
     /// Principal ID of the subscription manager canister
-    /// Only this canister is allowed to flip the is_subscribed flag
+    /// Only this canister is allowed to extend or end subscription periods
     pub static SUBSCRIPTION_MANAGER_ID: RefCell<StableCell<Principal, Memory>> = RefCell::new(
         StableCell::init(
             MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(11))),
@@ -124,4 +125,320 @@ thread_local! {
             Principal::anonymous()
         ).unwrap()
     );
+
+    /// Principal ID of the soulbound credentials NFT collection
+    /// Anonymous until the hub broadcasts one; minting is skipped meanwhile
+    pub static CREDENTIALS_COLLECTION_ID: RefCell<StableCell<Principal, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(30))),
+            Principal::anonymous()
+        ).unwrap()
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Streaks & Achievements
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Badge definitions pushed by the staking hub: badge id -> definition
+    pub static BADGE_DEFINITIONS: RefCell<StableBTreeMap<String, BadgeDefinition, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(13)))
+        )
+    );
+
+    /// Map of user Principal -> LearningStreak
+    pub static USER_STREAKS: RefCell<StableBTreeMap<Principal, LearningStreak, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(14)))
+        )
+    );
+
+    /// Map of user Principal -> badges earned
+    pub static USER_BADGES: RefCell<StableBTreeMap<Principal, EarnedBadgeList, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(15)))
+        )
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Referrals
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Map of referee Principal -> ReferralRecord
+    pub static REFERRALS: RefCell<StableBTreeMap<Principal, ReferralRecord, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(16)))
+        )
+    );
+
+    /// Map of referrer Principal -> ReferrerStats (anti-abuse caps)
+    pub static REFERRER_STATS: RefCell<StableBTreeMap<Principal, ReferrerStats, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(17)))
+        )
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Account Recovery
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Map of user Principal -> RecoveryConfig (guardians)
+    pub static RECOVERY_CONFIGS: RefCell<StableBTreeMap<Principal, RecoveryConfig, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(18)))
+        )
+    );
+
+    /// Map of account Principal -> pending RecoveryRequest
+    pub static PENDING_RECOVERIES: RefCell<StableBTreeMap<Principal, RecoveryRequest, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(19)))
+        )
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Linked Principals
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Map of secondary Principal -> canonical Principal (owner of the profile)
+    pub static PRINCIPAL_LINKS: RefCell<StableBTreeMap<Principal, Principal, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(20)))
+        )
+    );
+
+    /// Map of canonical Principal -> secondary principals
+    pub static LINKED_OF_CANONICAL: RefCell<StableBTreeMap<Principal, LinkedPrincipalList, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(21)))
+        )
+    );
+
+    /// Map of canonical Principal -> outstanding LinkChallenge (one at a time)
+    pub static LINK_CHALLENGES: RefCell<StableBTreeMap<Principal, LinkChallenge, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(22)))
+        )
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Data Erasure
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Map of user Principal -> ErasureRecord (tombstone of erased profiles)
+    pub static ERASURE_RECORDS: RefCell<StableBTreeMap<Principal, ErasureRecord, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(23)))
+        )
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Subscriptions
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Map of user Principal -> SubscriptionPeriod
+    /// UserProfile.is_subscribed mirrors whether this period is active
+    pub static USER_SUBSCRIPTIONS: RefCell<StableBTreeMap<Principal, SubscriptionPeriod, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(24)))
+        )
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Completion Certificates
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Map of (user, unit_id) -> QuizScore of the passing attempt
+    pub static QUIZ_SCORES: RefCell<StableBTreeMap<UserQuizKey, QuizScore, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(25)))
+        )
+    );
+
+    /// Map of certificate id -> CompletionCertificate (public verification)
+    pub static CERTIFICATES: RefCell<StableBTreeMap<String, CompletionCertificate, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(26)))
+        )
+    );
+
+    /// Map of (user, course_id) -> certificate id
+    /// Inserted before signing, so an id without a certificate means issuance is in flight
+    pub static USER_CERTIFICATES: RefCell<StableBTreeMap<UserCertificateKey, String, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(27)))
+        )
+    );
+
+    /// Counter used to generate certificate ids
+    pub static CERTIFICATE_COUNT: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(28))),
+            0
+        ).unwrap()
+    );
+
+    /// Threshold ECDSA key used to sign certificates
+    pub static CERTIFICATE_KEY_NAME: RefCell<StableCell<String, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(29))),
+            DEFAULT_CERTIFICATE_KEY_NAME.to_string()
+        ).unwrap()
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Peer-to-peer Transfers
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Outgoing transfers: (sender, idempotency key) -> StakedTransfer
+    pub static OUTGOING_TRANSFERS: RefCell<StableBTreeMap<UserTransferKey, StakedTransfer, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(31)))
+        )
+    );
+
+    /// Transfers credited on this shard: transfer id -> IncomingTransfer
+    pub static INCOMING_TRANSFERS: RefCell<StableBTreeMap<String, IncomingTransfer, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(32)))
+        )
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Notifications
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Map of (user, id) -> Notification
+    pub static NOTIFICATIONS: RefCell<StableBTreeMap<NotificationKey, Notification, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(33)))
+        )
+    );
+
+    /// Map of user -> inbox bookkeeping (id range, unread count)
+    pub static NOTIFICATION_INBOXES: RefCell<StableBTreeMap<Principal, NotificationInbox, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(34)))
+        )
+    );
+
+    /// Map of user -> NotificationPreferences (absent = everything on)
+    pub static NOTIFICATION_PREFERENCES: RefCell<StableBTreeMap<Principal, NotificationPreferences, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(35)))
+        )
+    );
+
+    /// Content updates still being fanned out, in arrival order
+    pub static CONTENT_NOTICE_QUEUE: RefCell<StableBTreeMap<u64, PendingContentNotice, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(36)))
+        )
+    );
+
+    // ─────────────────────────────────────────────────────────────────────
+    // Audit Log
+    // ─────────────────────────────────────────────────────────────────────
+
+    /// Local audit entries: id -> AuditEntry (ids below AUDIT_ARCHIVED_COUNT live in the archive)
+    pub static AUDIT_LOG: RefCell<StableBTreeMap<u64, AuditEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(37)))
+        )
+    );
+
+    /// Total audit entries ever recorded (next id)
+    pub static AUDIT_COUNT: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(38))),
+            0
+        ).unwrap()
+    );
+
+    /// Number of oldest audit entries moved to the archive
+    pub static AUDIT_ARCHIVED_COUNT: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(39))),
+            0
+        ).unwrap()
+    );
+
+    /// Map of user -> bucketed usage history (Rolling limit mode)
+    pub static ROLLING_USAGE: RefCell<StableBTreeMap<Principal, RollingUsage, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(40)))
+        )
+    );
+
+    /// Map of (user, unit_id) -> PracticeRecord (practice attempts never mint)
+    pub static PRACTICE_RECORDS: RefCell<StableBTreeMap<UserQuizKey, PracticeRecord, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(41)))
+        )
+    );
+
+    /// Map of (user, unit_id) -> ReviewCard of a passed unit (spaced repetition)
+    pub static REVIEW_CARDS: RefCell<StableBTreeMap<UserQuizKey, ReviewCard, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(42)))
+        )
+    );
+
+    /// Map of unit_id -> UnitRewardRule pushed by the hub (absent = 1x, no bounty)
+    pub static UNIT_REWARD_RULES: RefCell<StableBTreeMap<String, UnitRewardRule, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(43)))
+        )
+    );
+
+    /// Translated quiz caches: (unit_id, locale) -> QuizCacheData, only for
+    /// locales listed in QUIZ_LOCALES (fallbacks use QUIZ_CACHE)
+    pub static LOCALIZED_QUIZ_CACHE: RefCell<StableBTreeMap<LocalizedQuizKey, QuizCacheData, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(44)))
+        )
+    );
+
+    /// Subscription extensions applied per user: (user, extension_id) -> time applied
+    pub static APPLIED_EXTENSIONS: RefCell<StableBTreeMap<UserExtensionKey, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(45)))
+        )
+    );
+
+    /// Last user checked by the subscription sweep (anonymous = start from the first)
+    pub static SUBSCRIPTION_SWEEP_CURSOR: RefCell<StableCell<Principal, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(46))),
+            Principal::anonymous()
+        ).unwrap()
+    );
+
+    /// Locales with a translated quiz per unit: unit_id -> QuizLocales
+    /// (absent = not fetched yet; replaced by the Hub when translations change)
+    pub static QUIZ_LOCALES: RefCell<StableBTreeMap<String, QuizLocales, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(47)))
+        )
+    );
+
+    /// Bounty claims not yet acknowledged by the Hub: (user, unit_id) -> time queued
+    pub static PENDING_BOUNTY_CLAIMS: RefCell<StableBTreeMap<UserQuizKey, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(48)))
+        )
+    );
+
+    /// Referrals awaiting a settlement retry: referee -> (time queued, time of the next attempt)
+    pub static PENDING_REFERRALS: RefCell<StableBTreeMap<Principal, (u64, u64), Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(49)))
+        )
+    );
+
+    /// Last bounty claim retried by the sweep (None = start from the first; resets on upgrade)
+    pub static BOUNTY_CLAIM_CURSOR: RefCell<Option<UserQuizKey>> = const { RefCell::new(None) };
+
+    /// Last pending referral visited by the sweep (None = start from the first; resets on upgrade)
+    pub static REFERRAL_SWEEP_CURSOR: RefCell<Option<Principal>> = const { RefCell::new(None) };
 }
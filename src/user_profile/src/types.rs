@@ -1,6 +1,7 @@
-use candid::{CandidType, Deserialize, Principal, Encode, Decode};
+use candid::{CandidType, Deserialize, Principal, Encode, Decode, Nat};
 use ic_stable_structures::{storable::Bound, Storable};
 use std::borrow::Cow;
+use crate::constants::{DEFAULT_REWARD_MULTIPLIER_BPS, SUBSCRIPTION_RENEWAL_GRACE_NS};
 
 /// Arguments passed during canister initialization
 #[derive(CandidType, Deserialize, Clone)]
@@ -21,6 +22,16 @@ pub enum VerificationTier {
     KYC,        // 2: Full Legal KYC (Passport/AML)
 }
 
+/// A user's verification record held by the KYC manager
+/// (mirrors kyc_canister::KycStatus)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct KycStatus {
+    pub user: Principal,
+    pub tier: VerificationTier,
+    pub verified_at: u64,
+    pub provider: String,
+}
+
 /// User profile containing personal info and staking state
 /// 
 /// Simplified version without interest/tier system.
@@ -81,12 +92,47 @@ pub struct UserProfileUpdate {
 }
 
 /// Types of transactions that can be recorded
-#[derive(CandidType, Deserialize, Clone, Debug)]
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
 pub enum TransactionType {
     /// Tokens earned from completing quizzes
     QuizReward,
     /// Tokens withdrawn from staking
     Unstake,
+    /// One-off bonus paid when an achievement badge is earned
+    BadgeBonus,
+    /// Bonus paid to referrer and referee once the referee qualifies
+    ReferralBonus,
+    /// Staked balance sent to another learner
+    TransferOut,
+    /// Staked balance received from another learner
+    TransferIn,
+    /// Outgoing transfer returned after the destination rejected it
+    TransferRefund,
+    /// Small reward for passing a due spaced-repetition review
+    ReviewReward,
+    /// Sponsor-funded reward paid from a bounty escrow (not minted)
+    BountyReward,
+    /// Unstake returned to the staked balance after the hub rejected it
+    UnstakeRefund,
+}
+
+impl TransactionType {
+    /// Parse the label stored by the archive canister (the variant's Debug name)
+    pub fn from_archive_label(label: &str) -> Option<Self> {
+        match label {
+            "QuizReward" => Some(Self::QuizReward),
+            "Unstake" => Some(Self::Unstake),
+            "BadgeBonus" => Some(Self::BadgeBonus),
+            "ReferralBonus" => Some(Self::ReferralBonus),
+            "TransferOut" => Some(Self::TransferOut),
+            "TransferIn" => Some(Self::TransferIn),
+            "TransferRefund" => Some(Self::TransferRefund),
+            "ReviewReward" => Some(Self::ReviewReward),
+            "BountyReward" => Some(Self::BountyReward),
+            "UnstakeRefund" => Some(Self::UnstakeRefund),
+            _ => None,
+        }
+    }
 }
 
 /// A record of a single transaction for a user
@@ -96,7 +142,7 @@ pub enum TransactionType {
 pub struct TransactionRecord {
     /// When the transaction occurred (nanoseconds since epoch)
     pub timestamp: u64,
-    /// Type of transaction (QuizReward or Unstake)
+    /// Type of transaction (QuizReward, Unstake, bonuses)
     pub tx_type: TransactionType,
     /// Amount of tokens involved (in e8s = 1/100,000,000 of a token)
     pub amount: u64,
@@ -111,8 +157,10 @@ impl Storable for TransactionRecord {
         Decode!(bytes.as_ref(), Self).expect("Failed to decode TransactionRecord")
     }
 
+    // The Candid type table lists every TransactionType variant, so each new
+    // variant grows every record by a few bytes
     const BOUND: Bound = Bound::Bounded {
-        max_size: 100,
+        max_size: 200,
         is_fixed_size: false,
     };
 }
@@ -160,6 +208,67 @@ impl Storable for UserQuizKey {
     };
 }
 
+/// A unit's quiz served for a requested locale (mirrors learning_engine::LocalizedQuizData)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LocalizedQuizData {
+    /// Locale of the translated quiz served; None = the unit's own quiz
+    pub locale: Option<String>,
+    /// Every locale with a translated quiz of this unit
+    pub locales: Vec<String>,
+    pub cache: QuizCacheData,
+}
+
+/// Locales with a translated quiz of one unit (bounded by the learning
+/// engine's MAX_TRANSLATIONS_PER_NODE)
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct QuizLocales(pub Vec<String>);
+
+impl Storable for QuizLocales {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode QuizLocales")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 1024,
+        is_fixed_size: false,
+    };
+}
+
+/// Key of a translated quiz cache: (unit_id, locale), encoded as
+/// `unit_id 0x00 locale` so a unit's locales are contiguous
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LocalizedQuizKey {
+    pub unit_id: String,
+    pub locale: String,
+}
+
+impl Storable for LocalizedQuizKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(self.unit_id.len() + 1 + self.locale.len());
+        bytes.extend_from_slice(self.unit_id.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(self.locale.as_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let split = bytes.iter().position(|b| *b == 0).expect("Failed to decode LocalizedQuizKey");
+        Self {
+            unit_id: String::from_utf8(bytes[..split].to_vec()).expect("Failed to decode LocalizedQuizKey"),
+            locale: String::from_utf8(bytes[split + 1..].to_vec()).expect("Failed to decode LocalizedQuizKey"),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 300,
+        is_fixed_size: false,
+    };
+}
+
 /// Pending statistics to be synced with the staking hub
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct PendingStats {
@@ -204,6 +313,48 @@ impl Default for TokenLimits {
     }
 }
 
+/// Global emission budget snapshot reported by the staking hub
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EmissionBudgetStatus {
+    pub daily_budget: u64,
+    pub weekly_budget: u64,
+    pub granted_last_24h: u64,
+    pub granted_last_7d: u64,
+    pub remaining_daily: u64,
+    pub remaining_weekly: u64,
+    pub registered_shards: u64,
+    pub per_shard_daily_cap: u64,
+}
+
+/// Bound that limits this shard's allowance, as reported by the staking hub
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AllowanceLimit {
+    MaxSupply,
+    WeeklyBudget,
+    DailyBudget,
+    ShardShare,
+}
+
+/// This shard's view of the emission budget, reported by the staking hub
+/// Used to explain why the hub refused to refill the minting allowance
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ShardEmissionStatus {
+    pub budget: EmissionBudgetStatus,
+    pub shard_granted_last_24h: u64,
+    pub shard_daily_limit: u64,
+    pub available: u64,
+    pub limited_by: AllowanceLimit,
+}
+
+/// How the period token limits are measured
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LimitMode {
+    /// Counters reset on UTC day, week (Sunday), month and year boundaries
+    Calendar,
+    /// Usage over the last 24h, 7d, 30d and 365d (see RollingUsage)
+    Rolling,
+}
+
 /// Cached quiz configuration - stored locally to avoid inter-canister calls
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct TokenLimitsConfig {
@@ -213,6 +364,8 @@ pub struct TokenLimitsConfig {
     pub regular_limits: TokenLimits,
     pub subscribed_limits: TokenLimits,
     pub version: u64,
+    /// None means Calendar (configs stored before limit modes existed)
+    pub limit_mode: Option<LimitMode>,
 }
 
 impl Default for TokenLimitsConfig {
@@ -229,6 +382,7 @@ impl Default for TokenLimitsConfig {
                 max_yearly_tokens: 40_000_000_000, // 400 GHC
             },
             version: 0,
+            limit_mode: None,
         }
     }
 }
@@ -255,6 +409,12 @@ pub struct QuizCacheData {
     pub answer_hashes: Vec<[u8; 32]>,
     pub question_count: u8,
     pub version: u64,
+    /// One commitment per question; None when every question is single-choice
+    pub commitments: Option<Vec<AnswerCommitment>>,
+    /// Questions drawn per learner from the pool (None = all, in order)
+    pub draw_count: Option<u8>,
+    /// Quiz units that must be passed before this one (None = ungated)
+    pub prerequisites: Option<Vec<String>>,
 }
 
 impl Storable for QuizCacheData {
@@ -267,11 +427,43 @@ impl Storable for QuizCacheData {
     }
 
     const BOUND: Bound = Bound::Bounded {
-        max_size: 1000, 
+        max_size: 100_000, // Matches the learning engine's QUIZ_INDEX
         is_fixed_size: false,
     };
 }
 
+/// Per-question answer commitment (hashes use stable_hash of the canonical
+/// answer bytes; numeric ranges are stored as they are)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum AnswerCommitment {
+    SingleChoice([u8; 32]),
+    /// Hash of the sorted, deduplicated option indexes
+    MultiSelect([u8; 32]),
+    /// Hash of 1 (true) or 0 (false)
+    TrueFalse([u8; 32]),
+    /// Inclusive range, scaled by 10^decimals
+    Numeric { min: i64, max: i64 },
+    /// Hash of the option indexes in order
+    Ordering([u8; 32]),
+    /// Hashes of the normalized accepted answers
+    ShortText(Vec<[u8; 32]>),
+}
+
+/// A submitted answer to one question
+///
+/// Quiz payloads are either one byte per question (single choice; 0/1 for
+/// true/false) or a Candid-encoded vec of QuizAnswer.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum QuizAnswer {
+    SingleChoice(u8),
+    MultiSelect(Vec<u8>),
+    TrueFalse(bool),
+    /// Scaled by 10^decimals of the question
+    Numeric(i64),
+    Ordering(Vec<u8>),
+    ShortText(String),
+}
+
 /// Comprehensive time-based statistics for a user
 #[derive(CandidType, Deserialize, Clone, Debug)]
 pub struct UserTimeStats {
@@ -311,6 +503,65 @@ impl Default for UserTimeStats {
     }
 }
 
+/// Quiz attempts and earnings recorded in one time bucket
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UsageBucket {
+    /// Bucket number since epoch (time / bucket length)
+    pub index: u64,
+    pub quizzes: u16,
+    pub earnings: u64,
+}
+
+/// Bucketed usage history backing the Rolling limit mode
+///
+/// Every attempt is added to all three tiers. A window counts every bucket
+/// that overlaps it, so it can only over-count: by under an hour for 24h,
+/// a day for 7d/30d and a week for 365d.
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct RollingUsage {
+    pub hourly: Vec<UsageBucket>,
+    pub daily: Vec<UsageBucket>,
+    pub weekly: Vec<UsageBucket>,
+}
+
+impl Storable for RollingUsage {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode RollingUsage")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 4096,
+        is_fixed_size: false,
+    };
+}
+
+/// A user's quiz usage and remaining quota under the configured limit mode
+///
+/// Carries the same counters as UserTimeStats; in Rolling mode they cover the
+/// last 24h/7d/30d/365d instead of the current calendar periods.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UserQuotaStats {
+    pub last_active_day: u64,
+    pub daily_quizzes: u8,
+    pub daily_earnings: u64,
+    pub weekly_quizzes: u8,
+    pub weekly_earnings: u64,
+    pub monthly_quizzes: u8,
+    pub monthly_earnings: u64,
+    pub yearly_quizzes: u16,
+    pub yearly_earnings: u64,
+    pub limit_mode: LimitMode,
+    pub remaining_daily_attempts: u8,
+    pub remaining_daily_tokens: u64,
+    pub remaining_weekly_tokens: u64,
+    pub remaining_monthly_tokens: u64,
+    pub remaining_yearly_tokens: u64,
+}
+
 impl Storable for UserTimeStats {
     fn to_bytes(&self) -> Cow<'_, [u8]> {
         Cow::Owned(Encode!(self).unwrap())
@@ -380,3 +631,1240 @@ pub struct TransactionToArchive {
     pub amount: u64,
     pub metadata: String,
 }
+
+/// Archived transaction as returned by the archive canister
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArchivedTransaction {
+    pub sequence: u64,
+    pub timestamp: u64,
+    pub transaction_type: String,
+    pub amount: u64,
+    pub metadata: String,
+    pub archived_at: u64,
+}
+
+/// Slice of archived transactions plus the next archive in the chain
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArchivedRange {
+    pub transactions: Vec<ArchivedTransaction>,
+    pub next_archive: Option<Principal>,
+}
+
+/// Request for a page of transaction history (newest first)
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct TransactionHistoryRequest {
+    /// `next_cursor` from the previous page; None starts at the newest transaction
+    pub cursor: Option<u64>,
+    /// Max entries to return (0 = default page size)
+    pub limit: u32,
+    /// Only include these transaction types (None = all)
+    pub tx_types: Option<Vec<TransactionType>>,
+    /// Only include transactions at or after this time (nanoseconds)
+    pub from_timestamp: Option<u64>,
+    /// Only include transactions at or before this time (nanoseconds)
+    pub to_timestamp: Option<u64>,
+}
+
+/// A transaction in the unified history, wherever it is stored
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TransactionHistoryEntry {
+    /// Position in the user's history (0 = first transaction ever)
+    pub sequence: u64,
+    pub timestamp: u64,
+    pub tx_type: TransactionType,
+    pub amount: u64,
+    /// True if served from the archive chain
+    pub archived: bool,
+}
+
+/// Page of transaction history spanning local storage and the archive chain
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TransactionHistoryPage {
+    pub entries: Vec<TransactionHistoryEntry>,
+    /// Pass back as `cursor` to continue; None when the history is exhausted
+    pub next_cursor: Option<u64>,
+    /// All transactions ever recorded for the user, ignoring the request's
+    /// type and time filters (local_count + archived_count)
+    pub unfiltered_count: u64,
+    /// Transactions still held by this shard (unfiltered)
+    pub local_count: u64,
+    /// Transactions moved to the archive chain (unfiltered)
+    pub archived_count: u64,
+}
+
+// ============================================================================
+// STREAKS & ACHIEVEMENTS
+// ============================================================================
+
+/// Condition a user must meet to earn a badge (mirrors staking_hub::BadgeRule)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum BadgeRule {
+    /// Lifetime number of passed quizzes (1 = "first quiz")
+    QuizzesCompleted { count: u64 },
+    /// Consecutive days with at least one passed quiz
+    StreakDays { days: u64 },
+    /// Every quiz unit under a content node has been passed
+    SubtreeCompleted { root_id: String, unit_ids: Vec<String> },
+}
+
+/// Achievement definition pushed by the staking hub (mirrors staking_hub::BadgeDefinition)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct BadgeDefinition {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub rule: BadgeRule,
+    /// One-off bonus paid when earned, clamped by the user's token limits (in e8s)
+    pub bonus_reward: u64,
+    /// Inactive badges are no longer awarded; already earned ones are kept
+    pub active: bool,
+}
+
+impl Storable for BadgeDefinition {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode BadgeDefinition")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 48_000,
+        is_fixed_size: false,
+    };
+}
+
+/// Reward rule of a quiz unit pushed by the staking hub
+/// (mirrors staking_hub::UnitRewardRule; absent = 1x, no bounty)
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub struct UnitRewardRule {
+    /// Multiplier applied to the quiz reward (10,000 BPS = 1x)
+    pub multiplier_bps: u32,
+    /// Whether an open sponsor bounty covers the unit
+    pub has_bounty: bool,
+}
+
+impl Default for UnitRewardRule {
+    fn default() -> Self {
+        Self { multiplier_bps: DEFAULT_REWARD_MULTIPLIER_BPS, has_bounty: false }
+    }
+}
+
+impl Storable for UnitRewardRule {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode UnitRewardRule")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 100,
+        is_fixed_size: false,
+    };
+}
+
+/// Daily learning streak of a user
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct LearningStreak {
+    /// Consecutive days with a passed quiz, ending at last_streak_day
+    pub current_streak: u64,
+    /// Best streak ever reached
+    pub longest_streak: u64,
+    /// Last day (days since epoch) a quiz was passed
+    pub last_streak_day: u64,
+    /// Lifetime number of passed quizzes
+    pub total_quizzes_passed: u64,
+}
+
+impl Storable for LearningStreak {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode LearningStreak")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 100,
+        is_fixed_size: false,
+    };
+}
+
+/// A badge earned by a user
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct EarnedBadge {
+    pub badge_id: String,
+    /// When the badge was earned (nanoseconds since epoch)
+    pub earned_at: u64,
+    /// Bonus actually paid after clamping to token limits (in e8s)
+    pub bonus_awarded: u64,
+}
+
+/// All badges earned by a user
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct EarnedBadgeList(pub Vec<EarnedBadge>);
+
+impl Storable for EarnedBadgeList {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode EarnedBadgeList")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 16_000,
+        is_fixed_size: false,
+    };
+}
+
+/// Streak and badge overview for a user
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UserAchievements {
+    /// Current streak; 0 if the user missed yesterday and has not played today
+    pub current_streak: u64,
+    pub longest_streak: u64,
+    pub total_quizzes_passed: u64,
+    pub badges: Vec<EarnedBadge>,
+}
+
+// ============================================================================
+// REFERRALS
+// ============================================================================
+
+/// Lifecycle of a referral, tracked on the referee's shard
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum ReferralStatus {
+    /// Referee has not yet passed enough quizzes
+    Qualifying,
+    /// Bonus settlement with the referrer's shard is in flight
+    Processing,
+    /// Settlement failed (e.g. the referrer's daily cap) and is retried by
+    /// the referral sweep
+    Pending,
+    /// Both bonuses have been paid
+    Rewarded,
+    /// Settlement kept failing until REFERRAL_SETTLEMENT_EXPIRY_NS passed
+    Rejected,
+}
+
+/// Who referred a user and how far the referral has progressed
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReferralRecord {
+    pub referrer: Principal,
+    /// When the referee registered (nanoseconds since epoch)
+    pub registered_at: u64,
+    /// Quizzes passed by the referee since registering
+    pub quizzes_passed: u32,
+    pub status: ReferralStatus,
+    /// Bonus paid to the referee (in e8s)
+    pub referee_bonus: u64,
+    /// Bonus paid to the referrer (in e8s)
+    pub referrer_bonus: u64,
+}
+
+impl Storable for ReferralRecord {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode ReferralRecord")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 200,
+        is_fixed_size: false,
+    };
+}
+
+/// Referral rewards earned by a referrer, tracked on the referrer's shard
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct ReferrerStats {
+    /// Referrals that have been rewarded (lifetime)
+    pub rewarded_count: u64,
+    /// Referrals rewarded on `last_reward_day`
+    pub daily_rewarded: u64,
+    pub last_reward_day: u64,
+    /// Total referral bonus earned (in e8s)
+    pub total_earned: u64,
+}
+
+impl Storable for ReferrerStats {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode ReferrerStats")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 100,
+        is_fixed_size: false,
+    };
+}
+
+/// Referral overview for a user
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReferralInfo {
+    /// Set if this user registered with a referral code
+    pub referred_by: Option<ReferralRecord>,
+    /// Rewards earned by referring others
+    pub referrer_stats: ReferrerStats,
+}
+
+// ============================================================================
+// ACCOUNT RECOVERY
+// ============================================================================
+
+/// Guardians allowed to move an account to a new principal
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RecoveryConfig {
+    /// Recovery principals (backup identities or trusted people)
+    pub guardians: Vec<Principal>,
+    /// Guardian approvals required before a recovery can be finalized
+    pub threshold: u8,
+    pub updated_at: u64,
+}
+
+impl Storable for RecoveryConfig {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode RecoveryConfig")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 500,
+        is_fixed_size: false,
+    };
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum RecoveryStatus {
+    /// Collecting approvals / waiting for the time lock
+    Pending,
+    /// Migration in progress (blocks cancellation and concurrent finalization)
+    Executing,
+}
+
+/// A pending request to move an account to a new principal
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RecoveryRequest {
+    pub new_principal: Principal,
+    pub initiated_by: Principal,
+    /// Guardians that approved (including the initiator)
+    pub approvals: Vec<Principal>,
+    pub created_at: u64,
+    /// Earliest time the recovery can be finalized
+    pub executable_at: u64,
+    pub status: RecoveryStatus,
+}
+
+impl Storable for RecoveryRequest {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode RecoveryRequest")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 600,
+        is_fixed_size: false,
+    };
+}
+
+/// Recovery setup and any pending request for an account
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct RecoveryInfo {
+    pub config: Option<RecoveryConfig>,
+    pub pending: Option<RecoveryRequest>,
+}
+
+// ============================================================================
+// COURSE PROGRESS
+// ============================================================================
+
+/// Tree metadata of a content node (mirrors learning_engine::ContentOutlineNode)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ContentOutlineNode {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub order: u32,
+    pub display_type: String,
+    pub title: String,
+    pub has_quiz: bool,
+    /// Content version of the node
+    pub version: u64,
+}
+
+/// Whether a node is open to a learner (mirrors learning_engine::NodeUnlockStatus)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NodeUnlockStatus {
+    pub id: String,
+    pub unlocked: bool,
+    /// Quiz units still to pass
+    pub missing: Vec<String>,
+}
+
+/// Completion of one node's subtree
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NodeProgress {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub order: u32,
+    pub display_type: String,
+    pub title: String,
+    pub has_quiz: bool,
+    /// Set for quiz-bearing nodes: whether this node's own quiz was passed
+    pub completed: Option<bool>,
+    /// Quiz-bearing nodes in the subtree (including this node) that were passed
+    pub completed_quizzes: u64,
+    /// Quiz-bearing nodes in the subtree (including this node)
+    pub total_quizzes: u64,
+    /// completed_quizzes / total_quizzes, rounded down (0 when there are no quizzes)
+    pub percent: u8,
+}
+
+/// Per-node progress of a user through a content subtree
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CourseProgress {
+    pub root_id: String,
+    /// Parents before children, siblings in content order; the first entry is the root
+    pub nodes: Vec<NodeProgress>,
+}
+
+// ============================================================================
+// COMPLETION CERTIFICATES
+// ============================================================================
+
+/// Score of a passed quiz (recorded since certificates were introduced)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct QuizScore {
+    pub correct: u64,
+    pub total: u64,
+    pub passed_at: u64,
+}
+
+impl Storable for QuizScore {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode QuizScore")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 60,
+        is_fixed_size: false,
+    };
+}
+
+/// Outcome of a practice attempt (nothing is minted, counted or completed)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PracticeResult {
+    pub unit_id: String,
+    /// Per-question correctness, in question order
+    pub results: Vec<bool>,
+    pub correct: u64,
+    pub total: u64,
+    /// Whether this attempt would have passed submit_quiz
+    pub would_pass: bool,
+    pub history: PracticeRecord,
+    /// Set when the attempt counted as a review of a passed unit that was due
+    pub review: Option<ReviewOutcome>,
+}
+
+/// Practice history of one unit, kept apart from QUIZ_SCORES
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PracticeRecord {
+    pub attempts: u32,
+    pub last_correct: u64,
+    pub best_correct: u64,
+    pub total: u64,
+    /// Per-question correctness of the latest attempt
+    pub last_results: Vec<bool>,
+    pub last_practiced_at: u64,
+}
+
+impl Storable for PracticeRecord {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode PracticeRecord")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 600, // up to 255 questions (question_count is a u8)
+        is_fixed_size: false,
+    };
+}
+
+/// SM-2 review state of a passed unit
+///
+/// Stored as 16 fixed bytes so a shard at SHARD_HARD_LIMIT users with dozens
+/// of passed units each stays within a few hundred MiB of stable memory.
+#[derive(CandidType, Deserialize, Clone, Copy, Debug)]
+pub struct ReviewCard {
+    /// Ease factor x1000 (never below MIN_EASE_MILLI)
+    pub ease_milli: u16,
+    pub interval_days: u16,
+    /// Consecutive successful reviews (the pass counts as the first)
+    pub repetitions: u16,
+    pub lapses: u16,
+    /// Day index (days since epoch) the unit is next due
+    pub due_day: u32,
+    pub last_review_day: u32,
+}
+
+impl Storable for ReviewCard {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let mut bytes = Vec::with_capacity(16);
+        bytes.extend_from_slice(&self.ease_milli.to_le_bytes());
+        bytes.extend_from_slice(&self.interval_days.to_le_bytes());
+        bytes.extend_from_slice(&self.repetitions.to_le_bytes());
+        bytes.extend_from_slice(&self.lapses.to_le_bytes());
+        bytes.extend_from_slice(&self.due_day.to_le_bytes());
+        bytes.extend_from_slice(&self.last_review_day.to_le_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        Self {
+            ease_milli: u16_at(0),
+            interval_days: u16_at(2),
+            repetitions: u16_at(4),
+            lapses: u16_at(6),
+            due_day: u32_at(8),
+            last_review_day: u32_at(12),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 16,
+        is_fixed_size: true,
+    };
+}
+
+/// Result of a due review (see practice_quiz)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ReviewOutcome {
+    /// SM-2 quality 0-5 derived from the score
+    pub quality: u8,
+    pub card: ReviewCard,
+    /// Review reward actually credited (clamped to the token limits)
+    pub reward: u64,
+}
+
+/// A passed unit that is due for review
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct DueReview {
+    pub unit_id: String,
+    pub card: ReviewCard,
+    pub overdue_days: u32,
+}
+
+/// Score summary over the quizzes of a course
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ScoreSummary {
+    pub quizzes_passed: u64,
+    /// Passed quizzes with a recorded score (older passes have none)
+    pub scored_quizzes: u64,
+    pub correct_answers: u64,
+    pub total_questions: u64,
+    /// correct_answers / total_questions, rounded down
+    pub average_percent: u8,
+}
+
+/// The signed statement of a completion certificate
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CertificatePayload {
+    pub certificate_id: String,
+    /// Shard canister that issued (and signed) the certificate
+    pub issuer: Principal,
+    pub learner: Principal,
+    /// Root content node of the course
+    pub course_id: String,
+    pub course_title: String,
+    /// Content version of the root node at issuance
+    pub course_version: u64,
+    pub completed_at: u64,
+    pub issued_at: u64,
+    pub score: ScoreSummary,
+}
+
+/// A course completion certificate
+///
+/// `signature` is a threshold ECDSA (secp256k1) signature by the issuing
+/// shard over SHA-256(`signed_payload`), where `signed_payload` is the Candid
+/// encoding of `payload`. Verifiers check it against the key returned by
+/// get_certificate_signing_key and decode `signed_payload` themselves.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CompletionCertificate {
+    pub payload: CertificatePayload,
+    pub signed_payload: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub key_name: String,
+}
+
+impl Storable for CompletionCertificate {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode CompletionCertificate")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 2000,
+        is_fixed_size: false,
+    };
+}
+
+/// Key of a user's certificate for a course (keys for a user are contiguous)
+#[derive(CandidType, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UserCertificateKey {
+    pub user: Principal,
+    pub course_id: String,
+}
+
+impl Storable for UserCertificateKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode UserCertificateKey")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 100,
+        is_fixed_size: false,
+    };
+}
+
+/// Public key that verifies this shard's certificates
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct CertificateSigningKey {
+    pub key_name: String,
+    /// SEC1-encoded (compressed) secp256k1 public key
+    pub public_key: Vec<u8>,
+    pub derivation_path: Vec<Vec<u8>>,
+}
+
+// ============================================================================
+// PEER-TO-PEER TRANSFERS
+// ============================================================================
+
+/// Arguments of transfer_staked
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct StakedTransferArgs {
+    pub to: Principal,
+    pub amount: u64,
+    /// Chosen by the sender; retrying with the same key never sends twice
+    pub idempotency_key: String,
+    pub memo: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq)]
+pub enum StakedTransferStatus {
+    /// Debited, waiting for the hub to credit the recipient
+    Pending,
+    /// The hub could not be reached; retry_staked_transfer resolves it
+    Unresolved,
+    Completed,
+    /// The destination rejected the transfer and the sender was refunded
+    Refunded { reason: String },
+}
+
+/// An outgoing transfer, as seen by the sender's shard
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct StakedTransfer {
+    pub transfer_id: String,
+    pub idempotency_key: String,
+    pub from: Principal,
+    pub to: Principal,
+    pub amount: u64,
+    pub memo: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub status: StakedTransferStatus,
+}
+
+impl Storable for StakedTransfer {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode StakedTransfer")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 1000,
+        is_fixed_size: false,
+    };
+}
+
+/// Key of an outgoing transfer: (sender, idempotency key); a sender's keys are contiguous
+#[derive(CandidType, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UserTransferKey {
+    pub user: Principal,
+    pub idempotency_key: String,
+}
+
+impl Storable for UserTransferKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode UserTransferKey")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 200,
+        is_fixed_size: false,
+    };
+}
+
+/// Credit request routed by the staking hub (mirrors staking_hub::StakedTransferRequest)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct StakedTransferRequest {
+    pub transfer_id: String,
+    pub from: Principal,
+    pub to: Principal,
+    pub amount: u64,
+    pub memo: Option<String>,
+}
+
+/// A transfer credited on this shard (deduplicates hub retries)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct IncomingTransfer {
+    pub from: Principal,
+    pub to: Principal,
+    pub amount: u64,
+    pub credited_at: u64,
+}
+
+impl Storable for IncomingTransfer {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode IncomingTransfer")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 200,
+        is_fixed_size: false,
+    };
+}
+
+// ============================================================================
+// NOTIFICATIONS
+// ============================================================================
+
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum NotificationCategory {
+    /// Proposals the user supported or voted on
+    Governance,
+    /// Subscription renewals and upcoming expiry
+    Subscription,
+    /// KYC verification decisions
+    Kyc,
+    /// Updates to content the user has completed
+    Content,
+}
+
+/// Message posted by another canister through internal_notify
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NotificationInput {
+    pub category: NotificationCategory,
+    pub title: String,
+    pub body: String,
+    /// Frontend reference, e.g. "proposal:12" or a content node id
+    pub link: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct Notification {
+    /// Per-user sequence number (newer notifications have larger ids)
+    pub id: u64,
+    pub category: NotificationCategory,
+    pub title: String,
+    pub body: String,
+    pub link: Option<String>,
+    /// Canister that posted the notification
+    pub source: Principal,
+    pub created_at: u64,
+    pub read: bool,
+}
+
+impl Storable for Notification {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode Notification")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 2000,
+        is_fixed_size: false,
+    };
+}
+
+/// Composite key for notification storage: (user principal, id)
+#[derive(CandidType, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NotificationKey {
+    pub user: Principal,
+    pub id: u64,
+}
+
+impl Storable for NotificationKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode NotificationKey")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 100,
+        is_fixed_size: false,
+    };
+}
+
+/// Bookkeeping of a user's inbox; ids oldest_id..next_id are retained
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct NotificationInbox {
+    pub next_id: u64,
+    pub oldest_id: u64,
+    pub unread_count: u64,
+}
+
+impl Storable for NotificationInbox {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode NotificationInbox")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 100,
+        is_fixed_size: false,
+    };
+}
+
+/// Per-user notification settings (all categories are on by default)
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct NotificationPreferences {
+    /// Categories that are dropped instead of delivered
+    pub muted: Vec<NotificationCategory>,
+}
+
+impl Storable for NotificationPreferences {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode NotificationPreferences")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 200,
+        is_fixed_size: false,
+    };
+}
+
+/// A page of notifications, newest first
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct NotificationPage {
+    pub notifications: Vec<Notification>,
+    /// Pass as `before` to fetch the next (older) page
+    pub next_cursor: Option<u64>,
+    pub unread_count: u64,
+}
+
+/// Content update broadcast by the hub (mirrors staking_hub::ContentUpdateNotice)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ContentUpdateNotice {
+    pub content_id: String,
+    pub title: String,
+    pub version: u64,
+}
+
+/// A content update being fanned out to this shard's users
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct PendingContentNotice {
+    pub notice: ContentUpdateNotice,
+    /// Last user processed (sweep resumes after it)
+    pub cursor: Option<Principal>,
+}
+
+impl Storable for PendingContentNotice {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode PendingContentNotice")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 1000,
+        is_fixed_size: false,
+    };
+}
+
+// ============================================================================
+// SOULBOUND CREDENTIALS (mirrors credentials_nft types)
+// ============================================================================
+
+#[derive(CandidType, Deserialize, Clone, Copy, Debug)]
+pub enum CredentialKind {
+    Badge,
+    Certificate,
+}
+
+/// ICRC-3 style metadata value (subset used by this shard)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub enum CredentialValue {
+    Nat(Nat),
+    Text(String),
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct MintCredentialArgs {
+    pub owner: Principal,
+    pub kind: CredentialKind,
+    /// Badge id or certificate id
+    pub reference: String,
+    pub name: String,
+    pub description: String,
+    pub content_node_id: Option<String>,
+    pub attributes: Vec<(String, CredentialValue)>,
+}
+
+// ============================================================================
+// LINKED PRINCIPALS
+// ============================================================================
+
+/// A secondary principal (e.g. NFID or Plug) attached to a canonical profile
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LinkedPrincipal {
+    pub principal: Principal,
+    pub linked_at: u64,
+}
+
+/// Secondary principals of a canonical profile
+#[derive(CandidType, Deserialize, Clone, Debug, Default)]
+pub struct LinkedPrincipalList(pub Vec<LinkedPrincipal>);
+
+impl Storable for LinkedPrincipalList {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode LinkedPrincipalList")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 600,
+        is_fixed_size: false,
+    };
+}
+
+/// Outstanding link handshake issued by a canonical principal
+///
+/// The canonical principal names the secondary up front, and the link only
+/// completes when that secondary presents the token from its own session,
+/// so both identities authenticate against the same challenge.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct LinkChallenge {
+    /// Hex-encoded random token
+    pub token: String,
+    pub secondary: Principal,
+    pub expires_at: u64,
+}
+
+impl Storable for LinkChallenge {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode LinkChallenge")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 200,
+        is_fixed_size: false,
+    };
+}
+
+/// Canonical principal and every principal linked to it
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AccountLinks {
+    pub canonical: Principal,
+    pub linked: Vec<LinkedPrincipal>,
+}
+
+// ============================================================================
+// SUBSCRIPTIONS
+// ============================================================================
+
+/// Paid subscription period of a user (source of truth for subscribed limits)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SubscriptionPeriod {
+    pub plan_id: String,
+    pub started_at: u64,
+    pub expires_at: u64,
+    /// Billing will try to renew; the period stays active through a short grace window
+    pub auto_renew: bool,
+    /// Id of the last applied extension (every applied id is kept in APPLIED_EXTENSIONS)
+    pub last_extension_id: String,
+    pub updated_at: u64,
+    /// Set once the expiry reminder for the current expires_at was sent
+    pub expiry_reminded_at: Option<u64>,
+}
+
+impl SubscriptionPeriod {
+    pub fn is_active(&self, now: u64) -> bool {
+        let grace = if self.auto_renew { SUBSCRIPTION_RENEWAL_GRACE_NS } else { 0 };
+        now < self.expires_at.saturating_add(grace)
+    }
+}
+
+impl Storable for SubscriptionPeriod {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode SubscriptionPeriod")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 400,
+        is_fixed_size: false,
+    };
+}
+
+/// A paid period pushed by the subscription manager
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct SubscriptionExtension {
+    /// Payment / invoice id, unique per paid period
+    pub extension_id: String,
+    pub plan_id: String,
+    pub duration_ns: u64,
+    pub auto_renew: bool,
+}
+
+/// An extension applied to a user's subscription (re-pushed ids are no-ops),
+/// encoded as `len(user) user extension_id` so a user's ids are contiguous
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UserExtensionKey {
+    pub user: Principal,
+    pub extension_id: String,
+}
+
+impl Storable for UserExtensionKey {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        let user = self.user.as_slice();
+        let mut bytes = Vec::with_capacity(1 + user.len() + self.extension_id.len());
+        bytes.push(user.len() as u8);
+        bytes.extend_from_slice(user);
+        bytes.extend_from_slice(self.extension_id.as_bytes());
+        Cow::Owned(bytes)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        let user_end = 1 + bytes[0] as usize;
+        Self {
+            user: Principal::from_slice(&bytes[1..user_end]),
+            extension_id: String::from_utf8(bytes[user_end..].to_vec()).expect("Failed to decode UserExtensionKey"),
+        }
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 200,
+        is_fixed_size: false,
+    };
+}
+
+// ============================================================================
+// AUDIT LOG
+// ============================================================================
+
+/// Immutable record of a privileged (admin_* / internal_*) mutation
+///
+/// `before` and `after` are debug renderings of the affected state, cut to
+/// MAX_AUDIT_VALUE_LEN. Ids are sequential; the oldest are moved to the archive.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AuditEntry {
+    pub id: u64,
+    pub timestamp: u64,
+    pub caller: Principal,
+    pub method: String,
+    /// Affected user, or this canister for configuration changes
+    pub target: Principal,
+    pub before: String,
+    pub after: String,
+}
+
+impl Storable for AuditEntry {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode AuditEntry")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 2048,
+        is_fixed_size: false,
+    };
+}
+
+/// Audit entry in the archive canister's input format
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AuditEntryToArchive {
+    pub id: u64,
+    pub timestamp: u64,
+    pub caller: Principal,
+    pub method: String,
+    pub target: Principal,
+    pub before: String,
+    pub after: String,
+}
+
+/// Archived audit entry as returned by the archive canister
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArchivedAuditEntry {
+    pub id: u64,
+    pub timestamp: u64,
+    pub caller: Principal,
+    pub method: String,
+    pub target: Principal,
+    pub before: String,
+    pub after: String,
+    pub archived_at: u64,
+}
+
+/// Slice of archived audit entries plus the next archive in the chain
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArchivedAuditRange {
+    pub entries: Vec<ArchivedAuditEntry>,
+    pub next_archive: Option<Principal>,
+}
+
+/// One page of the audit log, newest first
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditEntry>,
+    /// Pass as `before` to fetch the next (older) page
+    pub next_cursor: Option<u64>,
+    pub total_count: u64,
+    pub archived_count: u64,
+}
+
+// ============================================================================
+// DATA EXPORT & ERASURE
+// ============================================================================
+
+/// Tombstone left after a user's personal data was erased
+///
+/// The profile itself is kept (with PII wiped) so balances and transaction
+/// amounts still add up for supply accounting.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ErasureRecord {
+    pub erased_at: u64,
+    /// False until every archive in the chain has redacted the user's metadata
+    pub archive_redacted: bool,
+}
+
+impl Storable for ErasureRecord {
+    fn to_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).expect("Failed to decode ErasureRecord")
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 50,
+        is_fixed_size: false,
+    };
+}
+
+/// Everything the shard and its archives hold about a user
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct UserDataExport {
+    pub user: Principal,
+    pub exported_at: u64,
+    /// Includes the KYC verification tier and subscription status
+    pub profile: UserProfile,
+    /// Full verification record from the KYC manager (None if never verified
+    /// or no KYC manager is configured)
+    pub kyc: Option<KycStatus>,
+    pub time_stats: Option<UserTimeStats>,
+    pub rolling_usage: Option<RollingUsage>,
+    pub completed_quizzes: Vec<String>,
+    /// Local and archived transactions, oldest first
+    pub transactions: Vec<TransactionHistoryEntry>,
+    /// Set if the archived history was too long for one export; continue
+    /// with get_transaction_history using this as the cursor
+    pub history_cursor: Option<u64>,
+    pub achievements: UserAchievements,
+    pub referral: ReferralInfo,
+    pub linked_principals: Vec<LinkedPrincipal>,
+    pub recovery: RecoveryInfo,
+    pub subscription: Option<SubscriptionPeriod>,
+    /// Payment / invoice ids applied to the subscription
+    pub subscription_extensions: Vec<String>,
+    pub quiz_scores: Vec<(String, QuizScore)>,
+    pub practice_records: Vec<(String, PracticeRecord)>,
+    pub review_cards: Vec<(String, ReviewCard)>,
+    pub certificates: Vec<CompletionCertificate>,
+    pub outgoing_transfers: Vec<StakedTransfer>,
+    pub notifications: Vec<Notification>,
+    pub notification_preferences: NotificationPreferences,
+    pub erasure: Option<ErasureRecord>,
+}
+
+/// Archive statistics (mirrors archive_canister::ArchiveStats)
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct ArchiveStats {
+    pub parent_shard: Principal,
+    pub entry_count: u64,
+    pub size_bytes: u64,
+    pub is_full: bool,
+    pub next_archive: Option<Principal>,
+}